@@ -0,0 +1,81 @@
+/**
+ * Copyright 2022 pyke.io
+ *           2019-2021 Tauri Programme within The Commons Conservancy
+ *                     [https://tauri.studio/]
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/**
+ * Launch the application automatically when the user logs in.
+ *
+ * This package is also accessible with `window.Millennium.autostart` when `build > withGlobalMillennium` is set to true
+ * in the Millennium config.
+ *
+ * The APIs must be allowlisted in `Millennium.toml`:
+ * ```toml
+ * [millennium.allowlist.autostart]
+ * all = true # enable all autostart APIs
+ * enable = true
+ * disable = true
+ * isEnabled = true
+ * # arguments passed to the application when it is launched at login
+ * args = [ "--minimized" ]
+ * ```
+ *
+ * @module
+ */
+
+import { invokeMillenniumCommand } from './_internal';
+
+/**
+ * Registers the application to be launched when the user logs in.
+ *
+ * @returns A promise indicating the success or failure of the operation.
+ */
+export async function enable(): Promise<void> {
+	return invokeMillenniumCommand({
+		__millenniumModule: 'Autostart',
+		message: {
+			cmd: 'enable'
+		}
+	});
+}
+
+/**
+ * Unregisters the application from being launched when the user logs in.
+ *
+ * @returns A promise indicating the success or failure of the operation.
+ */
+export async function disable(): Promise<void> {
+	return invokeMillenniumCommand({
+		__millenniumModule: 'Autostart',
+		message: {
+			cmd: 'disable'
+		}
+	});
+}
+
+/**
+ * Checks whether the application is registered to be launched when the user logs in.
+ *
+ * @returns A promise that resolves to `true` if the application is launched at login.
+ */
+export async function isEnabled(): Promise<boolean> {
+	return invokeMillenniumCommand<boolean>({
+		__millenniumModule: 'Autostart',
+		message: {
+			cmd: 'isEnabled'
+		}
+	});
+}
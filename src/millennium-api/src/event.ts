@@ -120,3 +120,41 @@ export async function once<T>(event: EventName, windowLabel: string | null, hand
 		unlisten(event, eventData.id).catch(() => {});
 	});
 }
+
+/**
+ * Maps the names of typed events to their payload types.
+ *
+ * The map is empty by default, and is augmented by the TypeScript bindings generated with
+ * `millennium::typescript::Bindings` for the events implementing `TypedEvent` on the Rust side.
+ */
+export interface EventMap {}
+
+export type TypedEventName = keyof EventMap & string;
+
+/**
+ * Emits a typed event to the backend.
+ *
+ * @param event The name of an event declared in the {@link EventMap}.
+ * @param windowLabel The label of the window to which the event is sent. If null, the event is sent to all windows.
+ */
+export async function emitTyped<K extends TypedEventName>(event: K, windowLabel: WindowLabel | null, payload: EventMap[K]): Promise<void> {
+	return emit(event, windowLabel, payload);
+}
+
+/**
+ * Listen to a typed event from the backend.
+ *
+ * @param event The name of an event declared in the {@link EventMap}.
+ */
+export async function listenTyped<K extends TypedEventName>(event: K, windowLabel: string | null, handler: EventCallback<EventMap[K]>): Promise<Unlistener> {
+	return listen<EventMap[K]>(event, windowLabel, handler);
+}
+
+/**
+ * Listen to a one-off typed event from the backend.
+ *
+ * @param event The name of an event declared in the {@link EventMap}.
+ */
+export async function onceTyped<K extends TypedEventName>(event: K, windowLabel: string | null, handler: EventCallback<EventMap[K]>): Promise<Unlistener> {
+	return once<EventMap[K]>(event, windowLabel, handler);
+}
@@ -24,6 +24,7 @@
  * [millennium.allowlist.http]
  * all = true # enable all HTTP APIs
  * request = true # enable all request APIs
+ * websocket = true # enable the WebSocket API
  * ```
  *
  * ## Security
@@ -39,12 +40,21 @@
  */
 
 import { invokeMillenniumCommand } from './_internal';
+import { listen } from './event';
+import type { Unlistener } from './event';
 
 export interface Duration {
 	secs: number;
 	nanos: number;
 }
 
+interface ClientIdentity {
+	/** Path of the PKCS #12 archive. */
+	path: string;
+	/** Password of the archive. */
+	password?: string;
+}
+
 interface ClientOptions {
 	/**
 	 * Defines the maximum number of redirects the client should follow.
@@ -52,6 +62,21 @@ interface ClientOptions {
 	 */
 	maxRedirections?: number;
 	connectTimeout?: number | Duration;
+	/** Headers sent with every request, unless the request sets them itself. */
+	defaultHeaders?: Record<string, string>;
+	/** Whether to persist cookies in the app data directory and send them back on matching requests. */
+	cookies?: boolean;
+	/** Whether to cache `GET` responses in the app cache directory, revalidating them with their `ETag` or `Last-Modified` headers. */
+	cache?: boolean;
+	/** Paths of PEM-encoded root certificates to trust in addition to the system ones. */
+	rootCertificates?: string[];
+	/**
+	 * The PKCS #12 archive holding the client certificate to present to servers.
+	 *
+	 * Only supported when the app is built with the `reqwest-client` feature of the `millennium` crate.
+	 * With the default HTTP backend, {@link createClient} rejects when this is set.
+	 */
+	identity?: ClientIdentity;
 }
 
 export enum ResponseType {
@@ -166,6 +191,39 @@ export interface HttpOptions {
 export type RequestOptions = Omit<HttpOptions, 'method' | 'url'>;
 export type FetchOptions = Omit<HttpOptions, 'url'>;
 
+export interface Progress {
+	/** Number of bytes transferred, including the bytes of a resumed download that were already on disk. */
+	transferred: number;
+	/** Total number of bytes, if known. */
+	total: number | null;
+}
+
+export type ProgressHandler = (progress: Progress) => void;
+
+export interface DownloadOptions extends Omit<FetchOptions, 'body' | 'responseType'> {
+	/** Whether to resume the download with a `Range` request if the file already exists. */
+	resume?: boolean;
+	/** The checksum the downloaded file must match. The file is removed if it doesn't. */
+	checksum?: {
+		algorithm: 'sha256' | 'sha512';
+		/** The hex-encoded hash. */
+		value: string;
+	};
+	onProgress?: ProgressHandler;
+}
+
+export interface UploadOptions extends Omit<FetchOptions, 'body'> {
+	/** The name of the form field of the file. Defaults to `file`. */
+	field?: string;
+	/** The file name sent to the server. Defaults to the name of the file. */
+	fileName?: string;
+	/** The MIME type of the file. Defaults to `application/octet-stream`. */
+	mime?: string;
+	/** Text fields sent along with the file. */
+	fields?: Record<string, string>;
+	onProgress?: ProgressHandler;
+}
+
 interface IResponse<T> {
 	url: string;
 	status: number;
@@ -197,6 +255,38 @@ export class Response<T> {
 	}
 }
 
+/** @internal */
+function parseResponse<T>(res: IResponse<T>, jsonResponse: boolean): Response<T> {
+	const response = new Response(res);
+	if (jsonResponse)
+		try {
+			// @ts-expect-error
+			response.data = JSON.parse(response.data as unknown as string);
+		} catch (e) {
+			if (response.ok && (response.data as unknown as string) === '')
+				// @ts-expect-error
+				response.data = {};
+			else if (response.ok)
+				throw new Error(`Failed to parse response body (\`${response.data}\`): ${e}\nTry setting the responseType to a different type if the API doesn't return JSON.`);
+		}
+
+	return response;
+}
+
+/** @internal */
+async function withProgress<T>(onProgress: ProgressHandler | undefined, transfer: (id: number) => Promise<T>): Promise<T> {
+	const id = window.crypto.getRandomValues(new Uint32Array(1))[0];
+	// listen before starting the transfer so no progress is missed
+	const unlisten = onProgress
+		? await listen<Progress>(`millennium://http/progress/${id}`, null, event => onProgress(event.payload))
+		: null;
+	try {
+		return await transfer(id);
+	} finally {
+		unlisten?.();
+	}
+}
+
 class Client {
 	public constructor(public readonly id: number) {}
 
@@ -225,20 +315,57 @@ class Client {
 				options
 			}
 		});
-		const response = new Response(res);
-		if (jsonResponse)
-			try {
-				// @ts-expect-error
-				response.data = JSON.parse(response.data as unknown as string);
-			} catch (e) {
-				if (response.ok && (response.data as unknown as string) === '')
-					// @ts-expect-error
-					response.data = {};
-				else if (response.ok)
-					throw new Error(`Failed to parse response body (\`${response.data}\`): ${e}\nTry setting the responseType to a different type if the API doesn't return JSON.`);
+		return parseResponse(res, jsonResponse);
+	}
+
+	/**
+	 * Downloads the response of a GET request to a file, without loading it into memory.
+	 * The path must be allowed in the `fs` scope configuration.
+	 *
+	 * @param url The request URL.
+	 * @param path The path of the downloaded file.
+	 * @param options Request and download configuration.
+	 */
+	public async download(url: string, path: string, options?: DownloadOptions): Promise<void> {
+		const { resume, checksum, onProgress, ...request } = options ?? {};
+		return withProgress(onProgress, id => invokeMillenniumCommand({
+			__millenniumModule: 'Http',
+			message: {
+				cmd: 'download',
+				client: this.id,
+				id,
+				options: { ...request, method: request.method ?? 'GET', url },
+				path,
+				download: { resume, checksum }
 			}
+		}));
+	}
+
+	/**
+	 * Uploads a file as a `multipart/form-data` POST request, streaming it from disk.
+	 * The path must be allowed in the `fs` scope configuration.
+	 *
+	 * @param url The request URL.
+	 * @param path The path of the uploaded file.
+	 * @param options Request and upload configuration.
+	 */
+	public async upload<T>(url: string, path: string, options?: UploadOptions): Promise<Response<T>> {
+		const { field, fileName, mime, fields, onProgress, ...request } = options ?? {};
+		const jsonResponse = !request.responseType || request.responseType === ResponseType.JSON;
+		if (jsonResponse)
+			request.responseType = ResponseType.TEXT;
 
-		return response;
+		const res = await withProgress(onProgress, id => invokeMillenniumCommand<IResponse<T>>({
+			__millenniumModule: 'Http',
+			message: {
+				cmd: 'upload',
+				client: this.id,
+				id,
+				options: { ...request, method: request.method ?? 'POST', url },
+				upload: { path, field, fileName, mime, fields }
+			}
+		}));
+		return parseResponse(res, jsonResponse);
 	}
 
 	/** Makes a GET request. */
@@ -296,4 +423,111 @@ export async function fetch<T>(url: string, options?: FetchOptions): Promise<Res
 	return defaultClient.request({ ...options, url, method: options?.method ?? 'GET' });
 }
 
+export interface WebSocketConnectOptions {
+	/** Headers of the handshake request. */
+	headers?: Record<string, string>;
+}
+
+export interface CloseFrame {
+	/** The status code of the close. */
+	code: number;
+	/** The reason of the close. */
+	reason: string;
+}
+
+export type WebSocketMessage =
+	| { type: 'Text', data: string }
+	| { type: 'Binary', data: number[] }
+	| { type: 'Ping', data: number[] }
+	| { type: 'Pong', data: number[] }
+	| { type: 'Close', data: CloseFrame | null };
+
+export type WebSocketMessageHandler = (message: WebSocketMessage) => void;
+
+export class WebSocket {
+	private readonly listeners: WebSocketMessageHandler[] = [];
+
+	private constructor(public readonly id: number, private unlisten: Unlistener | null = null) {}
+
+	/**
+	 * Connects to a WebSocket server. The URL must be allowed in the `http` scope configuration.
+	 *
+	 * @param url The server URL, with the `ws` or `wss` scheme.
+	 * @param options Connection configuration.
+	 */
+	public static async connect(url: string, options?: WebSocketConnectOptions): Promise<WebSocket> {
+		const id = window.crypto.getRandomValues(new Uint32Array(1))[0];
+		const socket = new WebSocket(id);
+		// listen before connecting so no message is missed
+		socket.unlisten = await listen<WebSocketMessage>(`millennium://websocket/${id}`, null, event => {
+			if (event.payload.type === 'Close')
+				socket.unlisten?.();
+
+			for (const listener of socket.listeners)
+				listener(event.payload);
+		});
+
+		try {
+			await invokeMillenniumCommand({
+				__millenniumModule: 'Http',
+				message: {
+					cmd: 'websocketConnect',
+					id,
+					url,
+					options
+				}
+			});
+		} catch (e) {
+			socket.unlisten();
+			throw e;
+		}
+
+		return socket;
+	}
+
+	/**
+	 * Adds a listener for the messages received from the server.
+	 * A `Close` message is received once the connection is closed.
+	 */
+	public addListener(handler: WebSocketMessageHandler): void {
+		this.listeners.push(handler);
+	}
+
+	/**
+	 * Sends a message to the server.
+	 *
+	 * @param message A string is sent as a text message, and a byte array as a binary message.
+	 */
+	public async send(message: WebSocketMessage | string | number[]): Promise<void> {
+		return invokeMillenniumCommand({
+			__millenniumModule: 'Http',
+			message: {
+				cmd: 'websocketSend',
+				id: this.id,
+				message: typeof message === 'string'
+					? { type: 'Text', data: message }
+					: Array.isArray(message)
+						? { type: 'Binary', data: message }
+						: message
+			}
+		});
+	}
+
+	/**
+	 * Starts the close handshake.
+	 *
+	 * @param frame The close status code and reason.
+	 */
+	public async close(frame?: CloseFrame): Promise<void> {
+		return invokeMillenniumCommand({
+			__millenniumModule: 'Http',
+			message: {
+				cmd: 'websocketClose',
+				id: this.id,
+				frame
+			}
+		});
+	}
+}
+
 export type { Client };
@@ -17,6 +17,7 @@
  */
 
 export * as app from './app';
+export * as autostart from './autostart';
 export * as commandLine from './cli';
 export * as clipboard from './clipboard';
 export * as dialog from './dialog';
@@ -24,12 +25,14 @@ export * as event from './event';
 export * as fs from './fs';
 export * as globalShortcut from './globalShortcut';
 export * as http from './http';
+export * as log from './log';
 export * as millennium from './millennium';
 export * as notification from './notification';
 export * as os from './os';
 export * as path from './path';
 export * as platform from './platform';
 export * as process from './process';
+export * as secrets from './secrets';
 export * as shell from './shell';
 export * as updater from './updater';
 export * as window from './window';
@@ -47,6 +50,7 @@ export type { Event, EventCallback, EventName, Unlistener } from './event';
 export { BaseDirectory, BinaryFileContents } from './fs';
 export type { ShortcutHandler } from './globalShortcut';
 export type { Duration, Body as HttpBody, Client as HttpClient, FetchOptions, HttpOptions, HttpVerb, RequestOptions, Response as HttpResponse, ResponseType } from './http';
+export type { RecordPayload as LogRecordPayload } from './log';
 export type { NotificationOptions, NotificationPermissionStatus } from './notification';
 export type { UpdateManifest, UpdateResult, UpdateStatus } from './updater';
 export { LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize, UserAttentionType, WebviewWindow } from './window';
@@ -0,0 +1,127 @@
+/**
+ * Copyright 2022 pyke.io
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/**
+ * Send log messages to the application's logger and print the application's logs in the devtools console.
+ *
+ * This package is also accessible with `window.Millennium.log` when `build > withGlobalMillennium` is set to true
+ * in the Millennium config.
+ *
+ * The `log` Cargo feature must be enabled and the logging plugin must be registered:
+ * ```rust
+ * millennium::Builder::default()
+ *     .plugin(millennium::log::Builder::new().build())
+ * ```
+ *
+ * @module
+ */
+
+import type { Unlistener } from './event';
+import { listen } from './event';
+import { invoke } from './millennium';
+
+export const enum LogLevel {
+	Trace = 1,
+	Debug,
+	Info,
+	Warn,
+	Error
+}
+
+export interface RecordPayload {
+	level: LogLevel;
+	/** The Rust module that logged the message. */
+	target: string;
+	message: string;
+}
+
+async function log(level: LogLevel, message: string): Promise<void> {
+	return invoke('plugin:log|log', {
+		level,
+		message,
+		location: window.location.href
+	});
+}
+
+/**
+ * Logs a message at the trace level.
+ */
+export async function trace(message: string): Promise<void> {
+	return log(LogLevel.Trace, message);
+}
+
+/**
+ * Logs a message at the debug level.
+ */
+export async function debug(message: string): Promise<void> {
+	return log(LogLevel.Debug, message);
+}
+
+/**
+ * Logs a message at the info level.
+ */
+export async function info(message: string): Promise<void> {
+	return log(LogLevel.Info, message);
+}
+
+/**
+ * Logs a message at the warn level.
+ */
+export async function warn(message: string): Promise<void> {
+	return log(LogLevel.Warn, message);
+}
+
+/**
+ * Logs a message at the error level.
+ */
+export async function error(message: string): Promise<void> {
+	return log(LogLevel.Error, message);
+}
+
+/**
+ * Listens to messages logged by the application with the `Webview` log target.
+ *
+ * @returns A promise resolving to a function to stop listening.
+ */
+export async function attachLogger(handler: (record: RecordPayload) => void): Promise<Unlistener> {
+	return listen<RecordPayload>('log://log', null, event => handler(event.payload));
+}
+
+/**
+ * Prints messages logged by the application with the `Webview` log target in the devtools console.
+ *
+ * @returns A promise resolving to a function to stop printing messages.
+ */
+export async function attachConsole(): Promise<Unlistener> {
+	return attachLogger(({ level, target, message }) => {
+		const line = `[${target}] ${message}`;
+		switch (level) {
+			case LogLevel.Trace:
+			case LogLevel.Debug:
+				console.debug(line);
+				break;
+			case LogLevel.Info:
+				console.info(line);
+				break;
+			case LogLevel.Warn:
+				console.warn(line);
+				break;
+			case LogLevel.Error:
+				console.error(line);
+				break;
+		}
+	});
+}
@@ -0,0 +1,100 @@
+/**
+ * Copyright 2022 pyke.io
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/**
+ * Securely store credentials such as OAuth refresh tokens.
+ *
+ * Secrets are stored with the Secret Service (GNOME Keyring, KWallet...) on Linux, and in an encrypted file in the app
+ * data directory when no Secret Service is available.
+ *
+ * This package is also accessible with `window.Millennium.secrets` when `build > withGlobalMillennium` is set to true
+ * in the Millennium config.
+ *
+ * The APIs must be allowlisted in `Millennium.toml`:
+ * ```toml
+ * [millennium.allowlist.secrets]
+ * all = true # enable all secrets APIs
+ * get = true
+ * set = true
+ * delete = true
+ * ```
+ *
+ * @module
+ */
+
+import { invokeMillenniumCommand } from './_internal';
+
+/**
+ * Reads the secret stored for the given service and account.
+ *
+ * @example
+ * ```typescript
+ * import { getSecret } from '@pyke/millennium-api/secrets';
+ * const token = await getSecret('api.example.com', 'user@example.com');
+ * ```
+ *
+ * @param service The service the secret is used for, e.g. the API host.
+ * @param account The account the secret belongs to.
+ * @returns A promise that resolves to the secret, or `null` if no secret is stored.
+ */
+export async function getSecret(service: string, account: string): Promise<string | null> {
+	return invokeMillenniumCommand<string | null>({
+		__millenniumModule: 'Secrets',
+		message: {
+			cmd: 'get',
+			service,
+			account
+		}
+	});
+}
+
+/**
+ * Stores a secret for the given service and account, replacing the existing one.
+ *
+ * @param service The service the secret is used for, e.g. the API host.
+ * @param account The account the secret belongs to.
+ * @param secret The secret to store.
+ * @returns A promise indicating the success or failure of the operation.
+ */
+export async function setSecret(service: string, account: string, secret: string): Promise<void> {
+	return invokeMillenniumCommand({
+		__millenniumModule: 'Secrets',
+		message: {
+			cmd: 'set',
+			service,
+			account,
+			secret
+		}
+	});
+}
+
+/**
+ * Deletes the secret stored for the given service and account.
+ *
+ * @param service The service the secret is used for, e.g. the API host.
+ * @param account The account the secret belongs to.
+ * @returns A promise that resolves to `false` if no secret was stored.
+ */
+export async function deleteSecret(service: string, account: string): Promise<boolean> {
+	return invokeMillenniumCommand<boolean>({
+		__millenniumModule: 'Secrets',
+		message: {
+			cmd: 'delete',
+			service,
+			account
+		}
+	});
+}
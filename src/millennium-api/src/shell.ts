@@ -47,7 +47,7 @@
  * ### Restricting access to the [[Command | `Command`]] APIs
  *
  * The `shell` allowlist object has a `scope` field that defines an array of CLIs that can be used.
- * Each CLI is a configuration object `{ name: string, cmd: string, sidecar?: bool, args?: boolean | Arg[] }`.
+ * Each CLI is a configuration object `{ name: string, cmd: string, sidecar?: bool, args?: boolean | Arg[], restrictions?: Restrictions }`.
  *
  * - `name`: the unique identifier of the command, passed to the [[Command.constructor | Command constructor]].
  * If it's a sidecar, this must be the value defined under `millennium > bundle > externalBin` in the Millennium
@@ -59,6 +59,12 @@
  * 	- `false` means that no arguments are allowed.
  * 	- otherwise an array can be configured. Each item is either a string representing the fixed argument value or a
  * 	  `{ validator: string }` that defines a regex validating the argument value.
+ * - `restrictions`: limits applied to the spawned process, enforced regardless of the options passed to the [[Command]].
+ * 	- `timeout`: kills the process after the given number of seconds.
+ * 	- `maxMemory` and `maxCpuTime`: limit the virtual memory (in bytes) and CPU time (in seconds) of the process on Unix.
+ * 	- `env`: the only environment variables the process can inherit or be given. Defaults to none.
+ * 	- `cwd`: a directory the working directory of the process must be inside of.
+ * 	- `sandbox`: `none`, `noNetwork` or `strict`, the latter also denying dangerous system calls. Linux only.
  *
  * #### Example scope configuration
  *
@@ -98,6 +104,19 @@ interface SpawnOptions {
 	env?: { [key: string]: string };
 	/** Character encoding for stdout/stderr */
 	encoding?: string;
+	/**
+	 * Spawns the program in a pseudo-terminal of the given size, so it behaves as if run from an interactive terminal.
+	 * Its output is then emitted as raw bytes by the `data` event of the {@link Command}, with `stderr` merged into it.
+	 *
+	 * Requires the `process-pty` feature of the `millennium` crate.
+	 */
+	pty?: PtySize;
+}
+
+/** The size of a pseudo-terminal, in characters. */
+interface PtySize {
+	rows: number;
+	cols: number;
 }
 
 interface InternalSpawnOptions extends SpawnOptions {
@@ -231,6 +250,22 @@ class Child {
 		});
 	}
 
+	/**
+	 * Resizes the pseudo-terminal of a child spawned with the `pty` option.
+	 *
+	 * @returns A Promise indicating the success or failure of the operation.
+	 */
+	public async resize(rows: number, cols: number): Promise<void> {
+		return await invokeMillenniumCommand<void>({
+			__millenniumModule: 'Shell',
+			message: {
+				cmd: 'resizePty',
+				pid: this.pid,
+				size: { rows, cols }
+			}
+		});
+	}
+
 	/**
 	 * Kills the child process.
 	 *
@@ -248,14 +283,15 @@ class Child {
 }
 
 /**
- * The entry point for spawning child processes. It emits `close` and `error` events.
+ * The entry point for spawning child processes. It emits `close` and `error` events, and `data` events with the raw
+ * output of commands spawned in a pseudo-terminal.
  *
  * @example
  * ```typescript
  *
  * ```
  */
-export class Command extends EventEmitter<'close' | 'error'> {
+export class Command extends EventEmitter<'close' | 'error' | 'data'> {
 	private readonly args: string[];
 	protected options: InternalSpawnOptions;
 	public readonly stdout = new EventEmitter<'data'>();
@@ -307,6 +343,8 @@ export class Command extends EventEmitter<'close' | 'error'> {
 					this.stdout.emit('data', payload); break;
 				case 'Stderr':
 					this.stderr.emit('data', payload); break;
+				case 'Data':
+					this.emit('data', new Uint8Array(payload)); break;
 			}
 		};
 		const pid = await execute(eventHandler, this.program, this.args, this.options);
@@ -362,6 +400,7 @@ interface TerminatedPayload {
 type CommandEvent =
 	| Event<'Stdout', string>
 	| Event<'Stderr', string>
+	| Event<'Data', number[]>
 	| Event<'Terminated', TerminatedPayload>
 	| Event<'Error', string>;
 
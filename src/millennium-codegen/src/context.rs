@@ -492,6 +492,11 @@ fn get_allowed_clis(root: &TokenStream, scope: &ShellAllowlistScope) -> TokenStr
 			let command = scope.command.to_string_lossy();
 			let command = quote!(::std::path::PathBuf::from(#command));
 
+			let restrictions = match &scope.restrictions {
+				Some(restrictions) => quote!(::std::option::Option::Some(#restrictions)),
+				None => quote!(::std::option::Option::None)
+			};
+
 			let args = match &scope.args {
 				ShellAllowedArgs::Flag(true) => quote!(::std::option::Option::None),
 				ShellAllowedArgs::Flag(false) => quote!(::std::option::Option::Some(::std::vec![])),
@@ -531,7 +536,8 @@ fn get_allowed_clis(root: &TokenStream, scope: &ShellAllowlistScope) -> TokenStr
 					#root::scope::ShellScopeAllowedCommand {
 						command: #command,
 						args: #args,
-						sidecar: #sidecar
+						sidecar: #sidecar,
+						restrictions: #restrictions
 					}
 				)
 			)
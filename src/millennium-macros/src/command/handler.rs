@@ -72,3 +72,36 @@ impl From<Handler> for proc_macro::TokenStream {
 		.into()
 	}
 }
+
+/// The items parsed from [`generate_bindings!`](crate::generate_bindings).
+pub struct Bindings {
+	descriptions: Vec<Path>
+}
+
+impl Parse for Bindings {
+	fn parse(input: &ParseBuffer<'_>) -> syn::Result<Self> {
+		let paths = input.parse_terminated::<Path, Token![,]>(Path::parse)?;
+
+		// set the paths to the functions describing the commands
+		let descriptions = paths
+			.into_iter()
+			.map(|mut path| {
+				let last = super::path_to_command(&mut path);
+				last.ident = super::format_command_description(&last.ident);
+				path
+			})
+			.collect();
+
+		Ok(Self { descriptions })
+	}
+}
+
+impl From<Bindings> for proc_macro::TokenStream {
+	fn from(Bindings { descriptions }: Bindings) -> Self {
+		quote::quote!(
+			::millennium::typescript::Bindings::new()
+				#(.command(#descriptions::<::millennium::typescript::private::Describer>()))*
+		)
+		.into()
+	}
+}
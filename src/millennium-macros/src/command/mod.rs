@@ -17,7 +17,10 @@
 use proc_macro2::Ident;
 use syn::{Path, PathSegment};
 
-pub use self::{handler::Handler, wrapper::wrapper};
+pub use self::{
+	handler::{Bindings, Handler},
+	wrapper::wrapper
+};
 
 mod handler;
 mod wrapper;
@@ -27,6 +30,11 @@ fn format_command_wrapper(function: &Ident) -> Ident {
 	quote::format_ident!("__cmd__{}", function)
 }
 
+/// The autogenerated ident of the function describing the command's signature.
+fn format_command_description(function: &Ident) -> Ident {
+	quote::format_ident!("__cmd_ts__{}", function)
+}
+
 /// This function will panic if the passed [`syn::Path`] does not have any
 /// segments.
 fn path_to_command(path: &mut Path) -> &mut PathSegment {
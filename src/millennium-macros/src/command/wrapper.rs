@@ -17,13 +17,14 @@
 use heck::{ToLowerCamelCase, ToSnakeCase};
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::{format_ident, quote};
+use proc_macro2::TokenTree;
+use quote::{format_ident, quote, ToTokens};
 use syn::{
 	ext::IdentExt,
 	parse::{Parse, ParseStream},
-	parse_macro_input,
+	parse_macro_input, parse_quote,
 	spanned::Spanned,
-	FnArg, Ident, ItemFn, Lit, Meta, Pat, Token, Visibility
+	FnArg, GenericArgument, GenericParam, Ident, ItemFn, Lit, Meta, Pat, PathArguments, PathSegment, ReturnType, Token, Type, Visibility
 };
 
 struct WrapperAttributes {
@@ -98,6 +99,7 @@ struct Invoke {
 pub fn wrapper(attributes: TokenStream, item: TokenStream) -> TokenStream {
 	let function = parse_macro_input!(item as ItemFn);
 	let wrapper = super::format_command_wrapper(&function.sig.ident);
+	let description = super::format_command_description(&function.sig.ident);
 	let visibility = &function.vis;
 
 	// macros used with `pub use my_macro;` need to be exported with
@@ -114,7 +116,9 @@ pub fn wrapper(attributes: TokenStream, item: TokenStream) -> TokenStream {
 
 	// body to the command wrapper or a `compile_error!` of an error occurred while
 	// parsing it.
-	let body = syn::parse::<WrapperAttributes>(attributes)
+	let attributes = syn::parse::<WrapperAttributes>(attributes);
+	let argument_case = attributes.as_ref().map_or(ArgumentCase::Camel, |attrs| attrs.argument_case);
+	let body = attributes
 		.map(|mut attrs| {
 			if function.sig.asyncness.is_some() {
 				attrs.execution_context = ExecutionContext::Async;
@@ -127,6 +131,9 @@ pub fn wrapper(attributes: TokenStream, item: TokenStream) -> TokenStream {
 		})
 		.unwrap_or_else(syn::Error::into_compile_error);
 
+	// the signature of the command, used by `generate_bindings!`
+	let describe_body = describe(&function, argument_case).unwrap_or_else(syn::Error::into_compile_error);
+
 	let Invoke { message, resolver } = invoke;
 
 	// Rely on rust 2018 edition to allow importing a macro from a path.
@@ -151,6 +158,11 @@ pub fn wrapper(attributes: TokenStream, item: TokenStream) -> TokenStream {
 		// allow the macro to be resolved with the same path as the command function
 		#[allow(unused_imports)]
 		#visibility use #wrapper;
+
+		#[doc(hidden)]
+		#[allow(dead_code)]
+		#visibility fn #description<__MillenniumD>() -> ::millennium::typescript::Command
+		#describe_body
 	)
 	.into()
 }
@@ -209,6 +221,19 @@ fn parse_args(function: &ItemFn, message: &Ident, case: ArgumentCase) -> syn::Re
 
 /// Transform a [`FnArg`] into a command argument.
 fn parse_arg(command: &Ident, arg: &FnArg, message: &Ident, case: ArgumentCase) -> syn::Result<TokenStream2> {
+	let key = arg_key(arg, case)?;
+
+	Ok(quote!(::millennium::command::CommandArg::from_command(
+		::millennium::command::CommandItem {
+			name: stringify!(#command),
+			key: #key,
+			message: &#message,
+		}
+	)))
+}
+
+/// The key of a command argument in the invoke payload.
+fn arg_key(arg: &FnArg, case: ArgumentCase) -> syn::Result<String> {
 	// we have no use for self arguments
 	let mut arg = match arg {
 		FnArg::Typed(arg) => arg.pat.as_ref().clone(),
@@ -239,11 +264,115 @@ fn parse_arg(command: &Ident, arg: &FnArg, message: &Ident, case: ArgumentCase)
 		}
 	}
 
-	Ok(quote!(::millennium::command::CommandArg::from_command(
-		::millennium::command::CommandItem {
-			name: stringify!(#command),
-			key: #key,
-			message: &#message,
+	Ok(key)
+}
+
+/// Generates the where clause and body of the function describing the
+/// arguments and return type of a command.
+///
+/// The types are bounded on a generic parameter instead of being used
+/// directly, so they only need to implement `TsType` when the bindings of the
+/// command are generated.
+fn describe(function: &ItemFn, case: ArgumentCase) -> syn::Result<TokenStream2> {
+	let generics: Vec<String> = function
+		.sig
+		.generics
+		.params
+		.iter()
+		.map(|param| match param {
+			GenericParam::Type(param) => param.ident.to_string(),
+			GenericParam::Lifetime(param) => param.lifetime.ident.to_string(),
+			GenericParam::Const(param) => param.ident.to_string()
+		})
+		.collect();
+
+	let mut bounds = Vec::new();
+	let mut steps = Vec::new();
+	for arg in &function.sig.inputs {
+		let key = arg_key(arg, case)?;
+		let ty = match arg {
+			FnArg::Typed(arg) => arg.ty.as_ref(),
+			FnArg::Receiver(_) => unreachable!("self arguments are rejected by arg_key")
+		};
+		// arguments that are not read from the payload
+		if key.is_empty() || matches!(last_segment(ty), Some(s) if s.ident == "Window" || s.ident == "AppHandle" || s.ident == "State") {
+			continue;
 		}
-	)))
+
+		let method = match last_segment(ty) {
+			Some(s) if s.ident == "Option" => quote!(optional_arg),
+			_ => quote!(arg)
+		};
+		steps.push(match describable(ty, &generics) {
+			Some(ty) => {
+				bounds.push(ty.clone());
+				quote!(let command = <__MillenniumD as ::millennium::typescript::private::Describe<#ty>>::#method(command, #key);)
+			}
+			None => quote!(let command = command.#method::<::millennium::typescript::private::Unknown>(#key);)
+		});
+	}
+
+	let returns = match &function.sig.output {
+		ReturnType::Default => Some(parse_quote!(())),
+		ReturnType::Type(_, ty) => describable(result_ok(ty), &generics)
+	};
+	steps.push(match returns {
+		Some(ty) => {
+			bounds.push(ty.clone());
+			quote!(let command = <__MillenniumD as ::millennium::typescript::private::Describe<#ty>>::returns(command);)
+		}
+		None => quote!(let command = command.returns::<::millennium::typescript::private::Unknown>();)
+	});
+
+	let command = &function.sig.ident;
+	Ok(quote! {
+		where #(__MillenniumD: ::millennium::typescript::private::Describe<#bounds>,)*
+		{
+			let command = ::millennium::typescript::Command::new(stringify!(#command));
+			#(#steps)*
+			command
+		}
+	})
+}
+
+/// The last path segment of a type, ignoring references.
+fn last_segment(ty: &Type) -> Option<&PathSegment> {
+	match ty {
+		Type::Reference(r) => last_segment(&r.elem),
+		Type::Path(p) => p.path.segments.last(),
+		_ => None
+	}
+}
+
+/// The `T` of a `Result<T, E>`, or the type itself.
+fn result_ok(ty: &Type) -> &Type {
+	if let Some(segment) = last_segment(ty).filter(|s| s.ident == "Result") {
+		if let PathArguments::AngleBracketed(args) = &segment.arguments {
+			if let Some(GenericArgument::Type(ok)) = args.args.first() {
+				return ok;
+			}
+		}
+	}
+	ty
+}
+
+/// Strips the references from a type so it can be used outside of the
+/// function signature, or returns `None` if the type depends on the generic
+/// parameters of the function or may borrow data.
+fn describable(mut ty: &Type, generics: &[String]) -> Option<Type> {
+	while let Type::Reference(r) = ty {
+		ty = &r.elem;
+	}
+
+	fn is_describable(tokens: TokenStream2, generics: &[String]) -> bool {
+		tokens.into_iter().all(|token| match token {
+			TokenTree::Group(group) => is_describable(group.stream(), generics),
+			// references and lifetimes can not be elided outside of the signature
+			TokenTree::Punct(punct) => punct.as_char() != '&' && punct.as_char() != '\'',
+			TokenTree::Ident(ident) => !(ident == "impl" || ident == "Self" || ident == "Cow" || generics.iter().any(|g| ident == g)),
+			TokenTree::Literal(_) => true
+		})
+	}
+
+	if is_describable(ty.to_token_stream(), generics) { Some(ty.clone()) } else { None }
 }
@@ -0,0 +1,50 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use heck::ToKebabCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, DeriveInput, Lit, Meta, NestedMeta};
+
+pub fn derive_typed_event(input: DeriveInput) -> syn::Result<TokenStream> {
+	let mut name = None;
+	for attr in input.attrs.iter().filter(|a| a.path.is_ident("event")) {
+		let list = match attr.parse_meta()? {
+			Meta::List(list) => list,
+			meta => return Err(syn::Error::new(meta.span(), "expected `#[event(name = \"...\")]`"))
+		};
+		for nested in list.nested {
+			match nested {
+				NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => match nv.lit {
+					Lit::Str(s) => name = Some((s.value(), s.span())),
+					lit => return Err(syn::Error::new(lit.span(), "expected a string literal"))
+				},
+				nested => return Err(syn::Error::new(nested.span(), "unknown `event` attribute"))
+			}
+		}
+	}
+
+	let (name, span) = name.unwrap_or_else(|| (input.ident.to_string().to_kebab_case(), input.ident.span()));
+	if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '/' || c == ':' || c == '_') {
+		return Err(syn::Error::new(span, "Event name must include only alphanumeric characters, `-`, `/`, `:` and `_`."));
+	}
+
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::millennium::TypedEvent for #ident #ty_generics #where_clause {
+			const NAME: &'static str = #name;
+		}
+	})
+}
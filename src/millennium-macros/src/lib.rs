@@ -23,7 +23,9 @@ use crate::context::ContextItems;
 
 mod command;
 mod command_module;
+mod event;
 mod runtime;
+mod typescript;
 
 #[macro_use]
 mod context;
@@ -67,6 +69,34 @@ pub fn generate_handler(item: TokenStream) -> TokenStream {
 	parse_macro_input!(item as command::Handler).into()
 }
 
+/// Accepts a list of command functions. Creates a
+/// `::millennium::typescript::Bindings` declaring the commands, their argument
+/// and return types, and a function invoking each of them.
+///
+/// The argument and return types of the commands must implement
+/// `::millennium::typescript::TsType`. Types that depend on the generic
+/// parameters of a command are declared as `unknown`.
+///
+/// # Examples
+/// ```rust,ignore
+/// use millennium_macros::{command, generate_bindings, TsType};
+/// #[derive(serde::Deserialize, TsType)]
+/// struct Greeting {
+/// 	name: String
+/// }
+/// #[command]
+/// fn greet(greeting: Greeting) -> String {
+/// 	format!("Hello, {}!", greeting.name)
+/// }
+/// fn main() {
+/// 	let _bindings = generate_bindings![greet];
+/// }
+/// ```
+#[proc_macro]
+pub fn generate_bindings(item: TokenStream) -> TokenStream {
+	parse_macro_input!(item as command::Bindings).into()
+}
+
 /// Reads a Millennium config file and generates a `::millennium::Context` based
 /// on the content.
 ///
@@ -119,6 +149,41 @@ pub fn command_enum(_: TokenStream, input: TokenStream) -> TokenStream {
 	command_module::generate_command_enum(input)
 }
 
+/// Implements `millennium::typescript::TsType` for a struct or an enum,
+/// describing the TypeScript type of its serialized value.
+///
+/// The `rename`, `rename_all`, `tag`, `content`, `untagged`, `skip`,
+/// `flatten`, `default` and `skip_serializing_if` serde attributes are taken
+/// into account. Fields marked with `default` or `skip_serializing_if` are
+/// declared as optional.
+#[proc_macro_derive(TsType, attributes(serde))]
+pub fn derive_ts_type(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	typescript::derive_ts_type(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Implements `millennium::TypedEvent` for a type, associating it with an
+/// event name.
+///
+/// The event name is set with `#[event(name = "...")]` and defaults to the
+/// kebab-cased type name.
+///
+/// # Examples
+/// ```rust,ignore
+/// #[derive(Clone, Serialize, Deserialize, TsType, TypedEvent)]
+/// #[serde(rename_all = "camelCase")]
+/// #[event(name = "download-progress")]
+/// struct DownloadProgress {
+/// 	downloaded: u64,
+/// 	total_size: Option<u64>
+/// }
+/// ```
+#[proc_macro_derive(TypedEvent, attributes(event))]
+pub fn derive_typed_event(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	event::derive_typed_event(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
 #[doc(hidden)]
 #[proc_macro_attribute]
 pub fn module_command_handler(attributes: TokenStream, input: TokenStream) -> TokenStream {
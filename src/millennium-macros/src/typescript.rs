@@ -0,0 +1,282 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use heck::{ToKebabCase, ToLowerCamelCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta, Type};
+
+/// The serde attributes that affect the shape of the serialized value.
+#[derive(Default)]
+struct SerdeAttrs {
+	rename: Option<String>,
+	rename_all: Option<String>,
+	tag: Option<String>,
+	content: Option<String>,
+	untagged: bool,
+	skip: bool,
+	optional: bool,
+	flatten: bool
+}
+
+impl SerdeAttrs {
+	fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+		let mut parsed = Self::default();
+		for attr in attrs.iter().filter(|a| a.path.is_ident("serde")) {
+			let list = match attr.parse_meta()? {
+				Meta::List(list) => list,
+				_ => continue
+			};
+			for nested in list.nested {
+				match nested {
+					NestedMeta::Meta(Meta::NameValue(nv)) => {
+						let value = match &nv.lit {
+							Lit::Str(s) => s.value(),
+							_ => continue
+						};
+						if nv.path.is_ident("rename") {
+							parsed.rename = Some(value);
+						} else if nv.path.is_ident("rename_all") {
+							parsed.rename_all = Some(value);
+						} else if nv.path.is_ident("tag") {
+							parsed.tag = Some(value);
+						} else if nv.path.is_ident("content") {
+							parsed.content = Some(value);
+						} else if nv.path.is_ident("skip_serializing_if") || nv.path.is_ident("default") {
+							parsed.optional = true;
+						}
+					}
+					NestedMeta::Meta(Meta::Path(path)) => {
+						if path.is_ident("untagged") {
+							parsed.untagged = true;
+						} else if path.is_ident("skip") || path.is_ident("skip_serializing") {
+							parsed.skip = true;
+						} else if path.is_ident("default") {
+							parsed.optional = true;
+						} else if path.is_ident("flatten") {
+							parsed.flatten = true;
+						}
+					}
+					_ => {}
+				}
+			}
+		}
+		Ok(parsed)
+	}
+}
+
+/// Applies a serde `rename_all` rule to a field or variant name.
+fn rename(name: &str, rule: Option<&str>) -> String {
+	match rule {
+		Some("lowercase") => name.to_lowercase(),
+		Some("UPPERCASE") => name.to_uppercase(),
+		Some("PascalCase") => name.to_upper_camel_case(),
+		Some("camelCase") => name.to_lower_camel_case(),
+		Some("snake_case") => name.to_snake_case(),
+		Some("SCREAMING_SNAKE_CASE") => name.to_shouty_snake_case(),
+		Some("kebab-case") => name.to_kebab_case(),
+		Some("SCREAMING-KEBAB-CASE") => name.to_shouty_kebab_case(),
+		_ => name.to_string()
+	}
+}
+
+/// Formats an object key, quoting it if it is not a valid identifier.
+fn key(name: &str) -> String {
+	let mut chars = name.chars();
+	let starts_like_ident = chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == '$');
+	if starts_like_ident && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+		name.to_string()
+	} else {
+		format!("{name:?}")
+	}
+}
+
+fn ts_type(ty: &Type) -> TokenStream {
+	quote!(<#ty as ::millennium::typescript::TsType>::ts_type())
+}
+
+/// Generates an expression that evaluates to the members of an object type,
+/// e.g. `["a: string", "b?: number | null"]`, followed by the types it
+/// intersects with because of flattened fields.
+fn object_members(fields: &syn::FieldsNamed, rename_all: Option<&str>, types: &mut Vec<Type>) -> syn::Result<(TokenStream, TokenStream)> {
+	let mut members = Vec::new();
+	let mut flattened = Vec::new();
+	for field in &fields.named {
+		let attrs = SerdeAttrs::parse(&field.attrs)?;
+		if attrs.skip {
+			continue;
+		}
+		types.push(field.ty.clone());
+		let ty = ts_type(&field.ty);
+		if attrs.flatten {
+			flattened.push(ty);
+			continue;
+		}
+		let name = attrs
+			.rename
+			.unwrap_or_else(|| rename(&field.ident.as_ref().unwrap().to_string(), rename_all));
+		let name = format!("{}{}", key(&name), if attrs.optional { "?" } else { "" });
+		members.push(quote!(format!("{}: {}", #name, #ty)));
+	}
+	let members = quote!({
+		let members: Vec<String> = vec![#(#members),*];
+		members
+	});
+	let flattened = quote!({
+		let flattened: Vec<String> = vec![#(#flattened),*];
+		flattened
+	});
+	Ok((members, flattened))
+}
+
+/// Generates an expression that evaluates to an inline object type.
+fn inline_object(fields: &syn::FieldsNamed, rename_all: Option<&str>, leading: Option<String>, types: &mut Vec<Type>) -> syn::Result<TokenStream> {
+	let (members, flattened) = object_members(fields, rename_all, types)?;
+	let leading = leading.into_iter();
+	Ok(quote!({
+		let mut members: Vec<String> = vec![#(#leading.to_string()),*];
+		members.extend(#members);
+		let mut ty = if members.is_empty() { "Record<string, never>".to_string() } else { format!("{{ {} }}", members.join("; ")) };
+		for flattened in #flattened {
+			ty = format!("{} & {}", ty, flattened);
+		}
+		ty
+	}))
+}
+
+/// Generates an expression that evaluates to a tuple type.
+fn tuple(fields: &syn::FieldsUnnamed, types: &mut Vec<Type>) -> TokenStream {
+	types.extend(fields.unnamed.iter().map(|f| f.ty.clone()));
+	if fields.unnamed.len() == 1 {
+		return ts_type(&fields.unnamed[0].ty);
+	}
+	let elements = fields.unnamed.iter().map(|f| ts_type(&f.ty));
+	quote!(format!("[{}]", [#(#elements),*].join(", ")))
+}
+
+pub fn derive_ts_type(input: DeriveInput) -> syn::Result<TokenStream> {
+	if input.generics.type_params().next().is_some() || input.generics.const_params().next().is_some() {
+		return Err(syn::Error::new(input.generics.span(), "`TsType` can not be derived for generic types"));
+	}
+
+	let ident = &input.ident;
+	let container = SerdeAttrs::parse(&input.attrs)?;
+	let name = container.rename.clone().unwrap_or_else(|| ident.to_string());
+	let rename_all = container.rename_all.as_deref();
+	let mut types = Vec::new();
+
+	let declaration = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => {
+				let (members, flattened) = object_members(fields, rename_all, &mut types)?;
+				quote!({
+					let mut body = String::from("{\n");
+					for member in #members {
+						body.push_str(&format!("\t{};\n", member));
+					}
+					body.push('}');
+					let flattened = #flattened;
+					if flattened.is_empty() {
+						format!("export interface {} {}", #name, body)
+					} else {
+						format!("export type {} = {} & {};", #name, body, flattened.join(" & "))
+					}
+				})
+			}
+			Fields::Unnamed(fields) => {
+				let ty = tuple(fields, &mut types);
+				quote!(format!("export type {} = {};", #name, #ty))
+			}
+			Fields::Unit => quote!(format!("export type {} = null;", #name))
+		},
+		Data::Enum(data) => {
+			let mut variants = Vec::new();
+			for variant in &data.variants {
+				let attrs = SerdeAttrs::parse(&variant.attrs)?;
+				if attrs.skip {
+					continue;
+				}
+				let variant_name = attrs.rename.clone().unwrap_or_else(|| rename(&variant.ident.to_string(), rename_all));
+				let tag_value = format!("{variant_name:?}");
+				let field_rename_all = attrs.rename_all.as_deref();
+
+				let variant = if container.untagged {
+					match &variant.fields {
+						Fields::Named(fields) => inline_object(fields, field_rename_all, None, &mut types)?,
+						Fields::Unnamed(fields) => tuple(fields, &mut types),
+						Fields::Unit => quote!("null".to_string())
+					}
+				} else if let Some(tag) = &container.tag {
+					let tag_member = format!("{}: {}", key(tag), tag_value);
+					match (&variant.fields, &container.content) {
+						(Fields::Unit, _) => quote!(format!("{{ {} }}", #tag_member)),
+						(Fields::Named(fields), None) => inline_object(fields, field_rename_all, Some(tag_member), &mut types)?,
+						(Fields::Unnamed(fields), None) => {
+							let ty = tuple(fields, &mut types);
+							quote!(format!("{{ {} }} & {}", #tag_member, #ty))
+						}
+						(fields, Some(content)) => {
+							let ty = match fields {
+								Fields::Named(fields) => inline_object(fields, field_rename_all, None, &mut types)?,
+								Fields::Unnamed(fields) => tuple(fields, &mut types),
+								Fields::Unit => unreachable!()
+							};
+							let content = key(content);
+							quote!(format!("{{ {}; {}: {} }}", #tag_member, #content, #ty))
+						}
+					}
+				} else {
+					match &variant.fields {
+						Fields::Unit => quote!(#tag_value.to_string()),
+						Fields::Named(fields) => {
+							let ty = inline_object(fields, field_rename_all, None, &mut types)?;
+							let key = key(&variant_name);
+							quote!(format!("{{ {}: {} }}", #key, #ty))
+						}
+						Fields::Unnamed(fields) => {
+							let ty = tuple(fields, &mut types);
+							let key = key(&variant_name);
+							quote!(format!("{{ {}: {} }}", #key, #ty))
+						}
+					}
+				};
+				variants.push(variant);
+			}
+			quote!({
+				let variants: Vec<String> = vec![#(#variants),*];
+				format!("export type {} = {};", #name, if variants.is_empty() { "never".to_string() } else { variants.join(" | ") })
+			})
+		}
+		Data::Union(_) => return Err(syn::Error::new(input.span(), "`TsType` can not be derived for unions"))
+	};
+
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::millennium::typescript::TsType for #ident #ty_generics #where_clause {
+			fn ts_type() -> String {
+				#name.to_string()
+			}
+
+			fn ts_declarations(declarations: &mut ::millennium::typescript::Declarations) {
+				if declarations.contains(#name) {
+					return;
+				}
+				// reserve the name first so recursive types terminate
+				declarations.insert(#name, String::new());
+				#(<#types as ::millennium::typescript::TsType>::ts_declarations(declarations);)*
+				declarations.insert(#name, #declaration);
+			}
+		}
+	})
+}
@@ -95,6 +95,10 @@ pub enum BundleType {
 	App,
 	/// Apple Disk Image (.dmg) for macOS.
 	Dmg,
+	/// Flatpak manifest and bundle (.flatpak) for Linux.
+	Flatpak,
+	/// Arch Linux package (.pkg.tar.zst).
+	Pacman,
 	/// Millennium updater bundle.
 	Updater
 }
@@ -111,6 +115,8 @@ impl Display for BundleType {
 				Self::Nsis => "nsis",
 				Self::App => "app",
 				Self::Dmg => "dmg",
+				Self::Flatpak => "flatpak",
+				Self::Pacman => "pacman",
 				Self::Updater => "updater"
 			}
 		)
@@ -139,6 +145,8 @@ impl<'de> Deserialize<'de> for BundleType {
 			"nsis" => Ok(Self::Nsis),
 			"app" => Ok(Self::App),
 			"dmg" => Ok(Self::Dmg),
+			"flatpak" => Ok(Self::Flatpak),
+			"pacman" => Ok(Self::Pacman),
 			"updater" => Ok(Self::Updater),
 			_ => Err(D::Error::custom(format!("unknown bundle target '{s}'")))
 		}
@@ -258,6 +266,7 @@ impl BundleTarget {
 }
 
 /// Configuration for AppImage bundles.
+#[skip_serializing_none]
 #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
@@ -265,7 +274,67 @@ pub struct AppImageConfig {
 	/// Include additional gstreamer dependencies needed for audio and video playback.
 	/// This increases the bundle size by ~15-35MB depending on your build system.
 	#[serde(default, alias = "bundle-media-framework")]
-	pub bundle_media_framework: bool
+	pub bundle_media_framework: bool,
+	/// The path to the AppImage type 2 runtime prepended to the image, relative to the configuration file.
+	///
+	/// Defaults to the `MILLENNIUM_APPIMAGE_RUNTIME` environment variable, then to `runtime-<arch>` in the
+	/// `millennium` cache directory. The runtime is never downloaded.
+	pub runtime: Option<PathBuf>,
+	/// Additional shared libraries to bundle in `usr/lib`, relative to the configuration file.
+	/// Their dependencies are bundled too.
+	#[serde(default)]
+	pub libraries: Vec<PathBuf>,
+	/// The update information embedded in the AppImage, e.g.
+	/// `gh-releases-zsync|pykeio|millennium|latest|*_amd64.AppImage.zsync`.
+	///
+	/// When a zsync transport is used, a `.zsync` file is generated next to the AppImage.
+	#[serde(alias = "update-information")]
+	pub update_information: Option<String>
+}
+
+fn default_flatpak_runtime() -> String {
+	"org.gnome.Platform".into()
+}
+
+fn default_flatpak_runtime_version() -> String {
+	"43".into()
+}
+
+fn default_flatpak_sdk() -> String {
+	"org.gnome.Sdk".into()
+}
+
+/// Configuration for Flatpak bundles.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FlatpakConfig {
+	/// The runtime the application runs on. It must provide WebKitGTK.
+	#[serde(default = "default_flatpak_runtime")]
+	pub runtime: String,
+	/// The version of the runtime and of the SDK.
+	#[serde(default = "default_flatpak_runtime_version", alias = "runtime-version")]
+	pub runtime_version: String,
+	/// The SDK used to build the application.
+	#[serde(default = "default_flatpak_sdk")]
+	pub sdk: String,
+	/// Additional sandbox permissions, e.g. `--filesystem=xdg-download`.
+	///
+	/// They are added to the permissions derived from the allowlist: network access for the HTTP APIs and the updater,
+	/// filesystem access for the filesystem scope, and D-Bus access for notifications, secrets and the system tray.
+	#[serde(default, alias = "finish-args")]
+	pub finish_args: Vec<String>
+}
+
+impl Default for FlatpakConfig {
+	fn default() -> Self {
+		Self {
+			runtime: default_flatpak_runtime(),
+			runtime_version: default_flatpak_runtime_version(),
+			sdk: default_flatpak_sdk(),
+			finish_args: Vec::new()
+		}
+	}
 }
 
 /// Configuration for Debian (.deb) bundles.
@@ -281,6 +350,99 @@ pub struct DebConfig {
 	pub files: HashMap<PathBuf, PathBuf>
 }
 
+/// Configuration for Arch Linux (.pkg.tar.zst) bundles.
+#[skip_serializing_none]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PacmanConfig {
+	/// The list of pacman dependencies your application relies on.
+	pub depends: Option<Vec<String>>,
+	/// The list of optional dependencies, in the `package: reason` format.
+	#[serde(alias = "optional-depends")]
+	pub optional_depends: Option<Vec<String>>
+}
+
+/// The intensity of a content rating attribute.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum AppStreamContentRating {
+	/// The application has no such content.
+	None,
+	/// The application has mild instances of the content.
+	Mild,
+	/// The application has moderate instances of the content.
+	Moderate,
+	/// The application has intense instances of the content.
+	Intense
+}
+
+impl Display for AppStreamContentRating {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{}",
+			match self {
+				Self::None => "none",
+				Self::Mild => "mild",
+				Self::Moderate => "moderate",
+				Self::Intense => "intense"
+			}
+		)
+	}
+}
+
+/// A screenshot shown by software centers.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AppStreamScreenshot {
+	/// The URL of the image. It must be publicly reachable over HTTP(S).
+	pub url: String,
+	/// A short description of what the screenshot shows.
+	pub caption: Option<String>
+}
+
+/// Configuration for the AppStream metainfo software centers like GNOME Software and KDE Discover use to list the
+/// application.
+#[skip_serializing_none]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AppStreamConfig {
+	/// A one-line summary of the application. Defaults to the bundle's short description.
+	pub summary: Option<String>,
+	/// The description of the application. Paragraphs are separated by blank lines and lines starting with `- ` form
+	/// lists. Defaults to the bundle's long description.
+	pub description: Option<String>,
+	/// The screenshots of the application. The first one is the default.
+	#[serde(default)]
+	pub screenshots: Vec<AppStreamScreenshot>,
+	/// The [OARS 1.1](https://hughsie.github.io/oars/) content rating, e.g. `{ "social-chat": "moderate" }`.
+	/// Attributes that are not set are rated `none`.
+	#[serde(default, alias = "content-rating")]
+	pub content_rating: HashMap<String, AppStreamContentRating>,
+	/// The SPDX license expression of the application, e.g. `MIT OR Apache-2.0`.
+	#[serde(alias = "project-license")]
+	pub project_license: Option<String>,
+	/// The path to a changelog in the [Keep a Changelog](https://keepachangelog.com/) format, which the releases and
+	/// their notes are read from. Releases need a `YYYY-MM-DD` date, except the one being bundled, which defaults to
+	/// the `SOURCE_DATE_EPOCH` or the current date.
+	pub changelog: Option<PathBuf>
+}
+
+/// Configuration for all Linux bundles.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct LinuxConfig {
+	/// The AppStream metainfo of the application, included in every Linux bundle.
+	#[serde(default)]
+	pub appstream: AppStreamConfig
+}
+
 fn de_minimum_system_version<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
 	D: Deserializer<'de>
@@ -614,7 +776,7 @@ pub struct BundleConfig {
 	/// Whether Millennium should handle bundling your application or just output the executable.
 	#[serde(default)]
 	pub active: bool,
-	/// The bundle targets to build. Currently supports `["deb", "appimage", "msi", "nsis", "app", "dmg", "updater"]` or
+	/// The bundle targets to build. Currently supports `["deb", "appimage", "flatpak", "pacman", "msi", "nsis", "app", "dmg", "updater"]` or
 	/// "all" to build all targets.
 	#[serde(default)]
 	pub targets: BundleTarget,
@@ -657,9 +819,18 @@ pub struct BundleConfig {
 	/// Configuration for AppImage bundles.
 	#[serde(default)]
 	pub appimage: AppImageConfig,
+	/// Configuration for Flatpak bundles.
+	#[serde(default)]
+	pub flatpak: FlatpakConfig,
 	/// Configuration for the Debian bundle.
 	#[serde(default)]
 	pub deb: DebConfig,
+	/// Configuration for the Arch Linux bundle.
+	#[serde(default)]
+	pub pacman: PacmanConfig,
+	/// Configuration shared by all Linux bundles.
+	#[serde(default)]
+	pub linux: LinuxConfig,
 	/// Configuration for the macOS bundles.
 	#[serde(rename = "macOS", default)]
 	pub macos: MacConfig,
@@ -1606,7 +1777,11 @@ pub struct ShellAllowedCommand {
 
 	/// If this command is a sidecar command.
 	#[serde(default)]
-	pub sidecar: bool
+	pub sidecar: bool,
+
+	/// Restrictions applied to the spawned process.
+	#[serde(default)]
+	pub restrictions: Option<ShellCommandRestrictions>
 }
 
 impl<'de> Deserialize<'de> for ShellAllowedCommand {
@@ -1622,7 +1797,9 @@ impl<'de> Deserialize<'de> for ShellAllowedCommand {
 			#[serde(default)]
 			args: ShellAllowedArgs,
 			#[serde(default)]
-			sidecar: bool
+			sidecar: bool,
+			#[serde(default)]
+			restrictions: Option<ShellCommandRestrictions>
 		}
 
 		let config = InnerShellAllowedCommand::deserialize(deserializer)?;
@@ -1634,11 +1811,72 @@ impl<'de> Deserialize<'de> for ShellAllowedCommand {
 			name: config.name,
 			command: config.command.unwrap_or_default(),
 			args: config.args,
-			sidecar: config.sidecar
+			sidecar: config.sidecar,
+			restrictions: config.restrictions
 		})
 	}
 }
 
+/// Restrictions applied to a process spawned by the webview API.
+///
+/// Once restrictions are configured, the process no longer inherits the
+/// environment of the app: only the variables listed in `env` are passed on.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ShellCommandRestrictions {
+	/// Kills the process if it is still running after this many seconds.
+	pub timeout: Option<u64>,
+
+	/// The maximum size of the virtual memory of the process, in bytes.
+	/// Only supported on Unix.
+	pub max_memory: Option<u64>,
+
+	/// The maximum CPU time the process can use, in seconds.
+	/// Only supported on Unix.
+	pub max_cpu_time: Option<u64>,
+
+	/// The environment variables the process can inherit from the app or be
+	/// given by the webview API.
+	#[serde(default)]
+	pub env: Vec<String>,
+
+	/// A directory the working directory of the process must be inside of. It
+	/// is also the default working directory of the process.
+	///
+	/// It can start with the same variables as `cmd`.
+	pub cwd: Option<PathBuf>,
+
+	/// The sandbox the process is spawned in.
+	#[serde(default)]
+	pub sandbox: ShellSandboxProfile
+}
+
+/// A sandbox profile for processes spawned by the webview API.
+///
+/// Sandboxes other than `none` are only supported on Linux, where they rely on
+/// unprivileged user namespaces and seccomp.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum ShellSandboxProfile {
+	/// The process is not sandboxed.
+	None,
+	/// The process runs in its own network namespace, without network access.
+	NoNetwork,
+	/// Like `noNetwork`, and the process is denied system calls used to debug
+	/// other processes, load kernel modules, mount filesystems or create and
+	/// switch namespaces.
+	Strict
+}
+
+impl Default for ShellSandboxProfile {
+	fn default() -> Self {
+		Self::None
+	}
+}
+
 /// A set of command arguments allowed to be executed by the webview API.
 ///
 /// A value of `true` will allow any arguments to be passed to the command.
@@ -1832,6 +2070,83 @@ impl Allowlist for DialogAllowlistConfig {
 	}
 }
 
+/// An URL allowed by the HTTP API scope.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(untagged)]
+pub enum HttpScopeEntry {
+	/// A URL glob pattern, allowing any method and header.
+	Url(Url),
+	/// A URL glob pattern with restrictions on the requests.
+	#[serde(rename_all = "camelCase")]
+	Rule {
+		/// The URL glob pattern.
+		url: Url,
+		/// The allowed HTTP methods. All methods are allowed if not set.
+		#[serde(default)]
+		methods: Option<Vec<String>>,
+		/// The request headers the webview is allowed to set. Other headers are removed from the requests.
+		/// All headers are allowed if not set.
+		#[serde(default)]
+		headers: Option<Vec<String>>,
+		/// Headers set on every request, replacing the headers set by the webview.
+		#[serde(default)]
+		forced_headers: HashMap<String, String>
+	}
+}
+
+impl<'de> Deserialize<'de> for HttpScopeEntry {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>
+	{
+		// `deny_unknown_fields` only applies to containers, so rules are parsed as a struct to reject typos such as `method`
+		#[derive(Deserialize)]
+		#[serde(rename_all = "camelCase", deny_unknown_fields)]
+		struct InnerHttpScopeRule {
+			url: Url,
+			#[serde(default)]
+			methods: Option<Vec<String>>,
+			#[serde(default)]
+			headers: Option<Vec<String>>,
+			#[serde(default)]
+			forced_headers: HashMap<String, String>
+		}
+
+		#[derive(Deserialize)]
+		#[serde(untagged)]
+		enum InnerHttpScopeEntry {
+			Url(Url),
+			Rule(InnerHttpScopeRule)
+		}
+
+		match InnerHttpScopeEntry::deserialize(deserializer)? {
+			InnerHttpScopeEntry::Url(url) => Ok(Self::Url(url)),
+			InnerHttpScopeEntry::Rule(InnerHttpScopeRule {
+				url,
+				methods,
+				headers,
+				forced_headers
+			}) => Ok(Self::Rule {
+				url,
+				methods,
+				headers,
+				forced_headers
+			})
+		}
+	}
+}
+
+impl HttpScopeEntry {
+	/// The URL glob pattern of this entry.
+	pub fn url(&self) -> &Url {
+		match self {
+			Self::Url(url) => url,
+			Self::Rule { url, .. } => url
+		}
+	}
+}
+
 /// HTTP API scope definition.
 /// It is a list of URLs that can be accessed by the webview when using the HTTP
 /// APIs. The scoped URL is matched against the request URL using a glob
@@ -1842,10 +2157,50 @@ impl Allowlist for DialogAllowlistConfig {
 /// - "https://**": allows all HTTPS urls
 /// - "https://*.github.com/pykeio/millennium": allows any subdomain of "github.com" with the "pykeio/millennium" path
 /// - "https://myapi.service.com/users/*": allows access to any URLs that begins with "https://myapi.service.com/users/"
+/// - `{ url = "https://myapi.service.com/**", methods = [ "GET" ], forcedHeaders = { "X-Client" = "app" } }`: allows
+///   GET requests to the API, always sending the `X-Client` header
 #[allow(rustdoc::bare_urls)]
-#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
-pub struct HttpAllowlistScope(pub Vec<Url>);
+#[serde(untagged)]
+pub enum HttpAllowlistScope {
+	/// A list of URLs that are allowed by this scope.
+	AllowedUrls(Vec<HttpScopeEntry>),
+	/// A complete scope configuration.
+	Scope {
+		/// A list of URLs that are allowed by this scope.
+		#[serde(default)]
+		allow: Vec<HttpScopeEntry>,
+		/// A list of URL glob patterns that are not allowed by this scope.
+		/// This has precedence over the [`Self::Scope::allow`] list.
+		#[serde(default)]
+		deny: Vec<Url>
+	}
+}
+
+impl Default for HttpAllowlistScope {
+	fn default() -> Self {
+		Self::AllowedUrls(Vec::new())
+	}
+}
+
+impl HttpAllowlistScope {
+	/// Returns the list of allowed URLs for this scope.
+	pub fn allowed_urls(&self) -> &Vec<HttpScopeEntry> {
+		match self {
+			Self::AllowedUrls(urls) => urls,
+			Self::Scope { allow, .. } => allow
+		}
+	}
+
+	/// Returns the list of denied URLs for this scope.
+	pub fn forbidden_urls(&self) -> Option<&Vec<Url>> {
+		match self {
+			Self::AllowedUrls(_) => None,
+			Self::Scope { deny, .. } => Some(deny)
+		}
+	}
+}
 
 /// Allowlist for the HTTP APIs.
 #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -1860,7 +2215,10 @@ pub struct HttpAllowlistConfig {
 	pub all: bool,
 	/// Allows making HTTP requests.
 	#[serde(default)]
-	pub request: bool
+	pub request: bool,
+	/// Allows connecting to WebSocket servers.
+	#[serde(default)]
+	pub websocket: bool
 }
 
 impl Allowlist for HttpAllowlistConfig {
@@ -1868,7 +2226,8 @@ impl Allowlist for HttpAllowlistConfig {
 		let allowlist = Self {
 			scope: Default::default(),
 			all: false,
-			request: true
+			request: true,
+			websocket: true
 		};
 		let mut features = allowlist.to_features();
 		features.push("http-all");
@@ -1881,6 +2240,7 @@ impl Allowlist for HttpAllowlistConfig {
 		} else {
 			let mut features = Vec::new();
 			check_feature!(self, features, request, "http-request");
+			check_feature!(self, features, websocket, "http-websocket");
 			features
 		}
 	}
@@ -2143,6 +2503,101 @@ impl Allowlist for AppAllowlistConfig {
 	}
 }
 
+/// Allowlist for the autostart APIs.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct AutostartAllowlistConfig {
+	/// Use this flag to enable all autostart APIs.
+	#[serde(default)]
+	pub all: bool,
+	/// Enables the autostart's `enable` API.
+	#[serde(default)]
+	pub enable: bool,
+	/// Enables the autostart's `disable` API.
+	#[serde(default)]
+	pub disable: bool,
+	/// Enables the autostart's `isEnabled` API.
+	#[serde(default, alias = "is-enabled")]
+	pub is_enabled: bool,
+	/// Extra arguments passed to the application when it is launched at login,
+	/// e.g. `["--minimized"]`.
+	#[serde(default)]
+	pub args: Vec<String>
+}
+
+impl Allowlist for AutostartAllowlistConfig {
+	fn all_features() -> Vec<&'static str> {
+		let allowlist = Self {
+			all: false,
+			enable: true,
+			disable: true,
+			is_enabled: true,
+			args: Vec::new()
+		};
+		let mut features = allowlist.to_features();
+		features.push("autostart-all");
+		features
+	}
+
+	fn to_features(&self) -> Vec<&'static str> {
+		if self.all {
+			vec!["autostart-all"]
+		} else {
+			let mut features = Vec::new();
+			check_feature!(self, features, enable, "autostart-enable");
+			check_feature!(self, features, disable, "autostart-disable");
+			check_feature!(self, features, is_enabled, "autostart-is-enabled");
+			features
+		}
+	}
+}
+
+/// Allowlist for the secrets APIs.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SecretsAllowlistConfig {
+	/// Use this flag to enable all secrets APIs.
+	#[serde(default)]
+	pub all: bool,
+	/// Enables the secrets' `get` API.
+	#[serde(default)]
+	pub get: bool,
+	/// Enables the secrets' `set` API.
+	#[serde(default)]
+	pub set: bool,
+	/// Enables the secrets' `delete` API.
+	#[serde(default)]
+	pub delete: bool
+}
+
+impl Allowlist for SecretsAllowlistConfig {
+	fn all_features() -> Vec<&'static str> {
+		let allowlist = Self {
+			all: false,
+			get: true,
+			set: true,
+			delete: true
+		};
+		let mut features = allowlist.to_features();
+		features.push("secrets-all");
+		features
+	}
+
+	fn to_features(&self) -> Vec<&'static str> {
+		if self.all {
+			vec!["secrets-all"]
+		} else {
+			let mut features = Vec::new();
+			check_feature!(self, features, get, "secrets-get");
+			check_feature!(self, features, set, "secrets-set");
+			check_feature!(self, features, delete, "secrets-delete");
+			features
+		}
+	}
+}
+
 /// Allowlist configuration.
 #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
 #[cfg_attr(feature = "schema", derive(JsonSchema))]
@@ -2189,7 +2644,13 @@ pub struct AllowlistConfig {
 	pub clipboard: ClipboardAllowlistConfig,
 	/// App APIs allowlist.
 	#[serde(default)]
-	pub app: AppAllowlistConfig
+	pub app: AppAllowlistConfig,
+	/// Autostart APIs allowlist.
+	#[serde(default)]
+	pub autostart: AutostartAllowlistConfig,
+	/// Secrets APIs allowlist.
+	#[serde(default)]
+	pub secrets: SecretsAllowlistConfig
 }
 
 impl Allowlist for AllowlistConfig {
@@ -2208,6 +2669,8 @@ impl Allowlist for AllowlistConfig {
 		features.extend(ProcessAllowlistConfig::all_features());
 		features.extend(ClipboardAllowlistConfig::all_features());
 		features.extend(AppAllowlistConfig::all_features());
+		features.extend(AutostartAllowlistConfig::all_features());
+		features.extend(SecretsAllowlistConfig::all_features());
 		features
 	}
 
@@ -2229,6 +2692,8 @@ impl Allowlist for AllowlistConfig {
 			features.extend(self.process.to_features());
 			features.extend(self.clipboard.to_features());
 			features.extend(self.app.to_features());
+			features.extend(self.autostart.to_features());
+			features.extend(self.secrets.to_features());
 			features
 		}
 	}
@@ -2437,6 +2902,18 @@ pub struct UpdaterWindowsConfig {
 	pub install_mode: WindowsUpdateInstallMode
 }
 
+/// A public key trusted to sign updates.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct UpdaterKey {
+	/// The public key, as generated by `millennium signer generate`.
+	pub pubkey: String,
+	/// The date and time, in RFC 3339 format, after which signatures from this key are rejected.
+	pub expires: Option<String>
+}
+
 /// The Updater configuration object.
 #[skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Clone, Serialize)]
@@ -2466,6 +2943,16 @@ pub struct UpdaterConfig {
 	/// Signature public key.
 	#[serde(default)] // use default just so the schema doesn't flag it as required
 	pub pubkey: String,
+	/// Additional public keys trusted to sign updates, which can expire.
+	///
+	/// To rotate keys, add the new key here and ship a release signed by the old key. Releases can also carry
+	/// signatures for several keys, and introduce new keys endorsed by a trusted key in their `keys` field.
+	#[serde(default, alias = "trusted-keys")]
+	pub trusted_keys: Vec<UpdaterKey>,
+	/// Public keys which are never trusted, even when they are listed in `pubkey` or `trustedKeys` or introduced by a
+	/// release.
+	#[serde(default, alias = "revoked-keys")]
+	pub revoked_keys: Vec<String>,
 	/// The Windows configuration for the updater.
 	#[serde(default)]
 	pub windows: UpdaterWindowsConfig
@@ -2484,13 +2971,17 @@ impl<'de> Deserialize<'de> for UpdaterConfig {
 			dialog: bool,
 			endpoints: Option<Vec<UpdaterEndpoint>>,
 			pubkey: Option<String>,
+			#[serde(default, alias = "trusted-keys")]
+			trusted_keys: Vec<UpdaterKey>,
+			#[serde(default, alias = "revoked-keys")]
+			revoked_keys: Vec<String>,
 			#[serde(default)]
 			windows: UpdaterWindowsConfig
 		}
 
 		let config = InnerUpdaterConfig::deserialize(deserializer)?;
 
-		if config.active && config.pubkey.is_none() {
+		if config.active && config.pubkey.is_none() && config.trusted_keys.is_empty() {
 			return Err(DeError::custom("The updater `pubkey` configuration is required."));
 		}
 
@@ -2499,6 +2990,8 @@ impl<'de> Deserialize<'de> for UpdaterConfig {
 			dialog: config.dialog,
 			endpoints: config.endpoints,
 			pubkey: config.pubkey.unwrap_or_default(),
+			trusted_keys: config.trusted_keys,
+			revoked_keys: config.revoked_keys,
 			windows: config.windows
 		})
 	}
@@ -2511,6 +3004,8 @@ impl Default for UpdaterConfig {
 			dialog: default_dialog(),
 			endpoints: None,
 			pubkey: "".into(),
+			trusted_keys: Vec::new(),
+			revoked_keys: Vec::new(),
 			windows: Default::default()
 		}
 	}
@@ -2603,6 +3098,75 @@ pub enum HookCommand {
 	}
 }
 
+/// A rule forwarding the requests under a path to another server.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DevServerProxy {
+	/// The path prefix of the requests to forward, e.g. `/api`.
+	pub path: String,
+	/// The URL of the server the requests are forwarded to, e.g. `http://localhost:3000`.
+	pub target: Url,
+	/// Replaces the path prefix when forwarding, e.g. `""` forwards `/api/users` to `/users`.
+	pub rewrite: Option<String>
+}
+
+fn default_dev_server_host() -> String {
+	"127.0.0.1".into()
+}
+
+fn default_dev_server_port() -> u16 {
+	40874
+}
+
+fn default_dev_server_debounce() -> u64 {
+	1000
+}
+
+fn default_dev_server_spa_fallback() -> bool {
+	true
+}
+
+/// The built-in development server, used when `devPath` is a directory.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(JsonSchema))]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DevServerConfig {
+	/// The address the server listens on. Use `0.0.0.0` to make it reachable from other devices.
+	#[serde(default = "default_dev_server_host")]
+	pub host: String,
+	/// The port the server listens on.
+	#[serde(default = "default_dev_server_port")]
+	pub port: u16,
+	/// Whether requests for unknown routes are answered with the root `index.html`, so client-side routers using the
+	/// history API work. Missing files with an extension, e.g. `/app.js`, still return 404.
+	#[serde(default = "default_dev_server_spa_fallback", alias = "spa-fallback")]
+	pub spa_fallback: bool,
+	/// How long to wait for changes to settle before reloading, in milliseconds.
+	#[serde(default = "default_dev_server_debounce")]
+	pub debounce: u64,
+	/// Patterns in the gitignore format of the files that don't trigger a reload when changed.
+	#[serde(default)]
+	pub ignore: Vec<String>,
+	/// Rules forwarding requests to other servers, e.g. an API backend. The first matching rule is used.
+	#[serde(default)]
+	pub proxy: Vec<DevServerProxy>
+}
+
+impl Default for DevServerConfig {
+	fn default() -> Self {
+		Self {
+			host: default_dev_server_host(),
+			port: default_dev_server_port(),
+			spa_fallback: default_dev_server_spa_fallback(),
+			debounce: default_dev_server_debounce(),
+			ignore: Vec::new(),
+			proxy: Vec::new()
+		}
+	}
+}
+
 /// The Build configuration object.
 #[skip_serializing_none]
 #[derive(Debug, PartialEq, Eq, Clone, Deserialize, Serialize)]
@@ -2620,6 +3184,10 @@ pub struct BuildConfig {
 	/// for examples on how to set up a dev server.
 	#[serde(default = "default_dev_path", alias = "dev-path")]
 	pub dev_path: AppUrl,
+	/// The configuration of the built-in development server, which serves `devPath` with live reloading when it is a
+	/// directory.
+	#[serde(default, alias = "dev-server")]
+	pub dev_server: DevServerConfig,
 	/// The path to the application assets or URL to load in production.
 	///
 	/// When a path relative to the configuration file is provided,
@@ -2660,7 +3228,14 @@ pub struct BuildConfig {
 	/// Whether we should inject the Millennium API as `window.Millennium`
 	/// or not.
 	#[serde(default, alias = "with-global-millennium")]
-	pub with_global_millennium: bool
+	pub with_global_millennium: bool,
+	/// The path of the TypeScript bindings generated from the commands, events
+	/// and types passed to `Builder::typescript_bindings`, relative to the
+	/// configuration file.
+	///
+	/// The bindings are regenerated every time the application starts during
+	/// `millennium dev`.
+	pub bindings: Option<PathBuf>
 }
 
 impl Default for BuildConfig {
@@ -2668,12 +3243,14 @@ impl Default for BuildConfig {
 		Self {
 			runner: None,
 			dev_path: default_dev_path(),
+			dev_server: Default::default(),
 			dist_dir: default_dist_dir(),
 			before_dev_command: None,
 			before_build_command: None,
 			before_bundle_command: None,
 			features: None,
-			with_global_millennium: false
+			with_global_millennium: false,
+			bindings: None
 		}
 	}
 }
@@ -2816,12 +3393,14 @@ fn default_build() -> BuildConfig {
 	BuildConfig {
 		runner: None,
 		dev_path: default_dev_path(),
+		dev_server: Default::default(),
 		dist_dir: default_dist_dir(),
 		before_dev_command: None,
 		before_build_command: None,
 		before_bundle_command: None,
 		features: None,
-		with_global_millennium: false
+		with_global_millennium: false,
+		bindings: None
 	}
 }
 
@@ -3250,7 +3829,10 @@ mod build {
 			let short_description = quote!(None);
 			let long_description = quote!(None);
 			let appimage = quote!(Default::default());
+			let flatpak = quote!(Default::default());
 			let deb = quote!(Default::default());
+			let pacman = quote!(Default::default());
+			let linux = quote!(Default::default());
 			let macos = quote!(Default::default());
 			let external_bin = opt_vec_str_lit(self.external_bin.as_ref());
 			let windows = &self.windows;
@@ -3269,7 +3851,10 @@ mod build {
 				short_description,
 				long_description,
 				appimage,
+				flatpak,
 				deb,
+				pacman,
+				linux,
 				macos,
 				external_bin,
 				windows
@@ -3299,22 +3884,26 @@ mod build {
 			let dist_dir = &self.dist_dir;
 			let with_global_millennium = self.with_global_millennium;
 			let runner = quote!(None);
+			let dev_server = quote!(Default::default());
 			let before_dev_command = quote!(None);
 			let before_build_command = quote!(None);
 			let before_bundle_command = quote!(None);
 			let features = quote!(None);
+			let bindings = quote!(None);
 
 			literal_struct!(
 				tokens,
 				BuildConfig,
 				runner,
 				dev_path,
+				dev_server,
 				dist_dir,
 				with_global_millennium,
 				before_dev_command,
 				before_build_command,
 				before_bundle_command,
-				features
+				features,
+				bindings
 			);
 		}
 	}
@@ -3339,6 +3928,14 @@ mod build {
 		}
 	}
 
+	impl ToTokens for UpdaterKey {
+		fn to_tokens(&self, tokens: &mut TokenStream) {
+			let pubkey = str_lit(&self.pubkey);
+			let expires = opt_str_lit(self.expires.as_ref());
+			literal_struct!(tokens, UpdaterKey, pubkey, expires);
+		}
+	}
+
 	impl ToTokens for UpdaterConfig {
 		fn to_tokens(&self, tokens: &mut TokenStream) {
 			let active = self.active;
@@ -3355,9 +3952,11 @@ mod build {
 					})
 					.as_ref()
 			);
+			let trusted_keys = vec_lit(&self.trusted_keys, identity);
+			let revoked_keys = vec_lit(&self.revoked_keys, str_lit);
 			let windows = &self.windows;
 
-			literal_struct!(tokens, UpdaterConfig, active, dialog, pubkey, endpoints, windows);
+			literal_struct!(tokens, UpdaterConfig, active, dialog, pubkey, endpoints, trusted_keys, revoked_keys, windows);
 		}
 	}
 
@@ -3464,10 +4063,46 @@ mod build {
 		}
 	}
 
+	impl ToTokens for HttpScopeEntry {
+		fn to_tokens(&self, tokens: &mut TokenStream) {
+			let prefix = quote! { ::millennium::utils::config::HttpScopeEntry };
+
+			tokens.append_all(match self {
+				Self::Url(url) => {
+					let url = url_lit(url);
+					quote! { #prefix::Url(#url) }
+				}
+				Self::Rule {
+					url,
+					methods,
+					headers,
+					forced_headers
+				} => {
+					let url = url_lit(url);
+					let methods = opt_vec_str_lit(methods.as_ref());
+					let headers = opt_vec_str_lit(headers.as_ref());
+					let forced_headers = map_lit(quote! { ::std::collections::HashMap }, forced_headers, str_lit, str_lit);
+					quote! { #prefix::Rule { url: #url, methods: #methods, headers: #headers, forced_headers: #forced_headers } }
+				}
+			});
+		}
+	}
+
 	impl ToTokens for HttpAllowlistScope {
 		fn to_tokens(&self, tokens: &mut TokenStream) {
-			let allowed_urls = vec_lit(&self.0, url_lit);
-			tokens.append_all(quote! { ::millennium::utils::config::HttpAllowlistScope(#allowed_urls) })
+			let prefix = quote! { ::millennium::utils::config::HttpAllowlistScope };
+
+			tokens.append_all(match self {
+				Self::AllowedUrls(allow) => {
+					let allowed_urls = vec_lit(allow, identity);
+					quote! { #prefix::AllowedUrls(#allowed_urls) }
+				}
+				Self::Scope { allow, deny } => {
+					let allow = vec_lit(allow, identity);
+					let deny = vec_lit(deny, url_lit);
+					quote! { #prefix::Scope { allow: #allow, deny: #deny } }
+				}
+			});
 		}
 	}
 
@@ -3484,8 +4119,34 @@ mod build {
 			let command = path_buf_lit(&self.command);
 			let args = &self.args;
 			let sidecar = &self.sidecar;
+			let restrictions = opt_lit(self.restrictions.as_ref());
 
-			literal_struct!(tokens, ShellAllowedCommand, name, command, args, sidecar);
+			literal_struct!(tokens, ShellAllowedCommand, name, command, args, sidecar, restrictions);
+		}
+	}
+
+	impl ToTokens for ShellCommandRestrictions {
+		fn to_tokens(&self, tokens: &mut TokenStream) {
+			let timeout = opt_lit(self.timeout.as_ref());
+			let max_memory = opt_lit(self.max_memory.as_ref());
+			let max_cpu_time = opt_lit(self.max_cpu_time.as_ref());
+			let env = vec_lit(&self.env, str_lit);
+			let cwd = opt_lit(self.cwd.as_ref().map(path_buf_lit).as_ref());
+			let sandbox = &self.sandbox;
+
+			literal_struct!(tokens, ShellCommandRestrictions, timeout, max_memory, max_cpu_time, env, cwd, sandbox);
+		}
+	}
+
+	impl ToTokens for ShellSandboxProfile {
+		fn to_tokens(&self, tokens: &mut TokenStream) {
+			let prefix = quote! { ::millennium::utils::config::ShellSandboxProfile };
+
+			tokens.append_all(match self {
+				Self::None => quote! { #prefix::None },
+				Self::NoNetwork => quote! { #prefix::NoNetwork },
+				Self::Strict => quote! { #prefix::Strict }
+			})
 		}
 	}
 
@@ -3545,15 +4206,23 @@ mod build {
 		}
 	}
 
+	impl ToTokens for AutostartAllowlistConfig {
+		fn to_tokens(&self, tokens: &mut TokenStream) {
+			let args = vec_lit(&self.args, str_lit);
+			tokens.append_all(quote! { ::millennium::utils::config::AutostartAllowlistConfig { args: #args, ..Default::default() } })
+		}
+	}
+
 	impl ToTokens for AllowlistConfig {
 		fn to_tokens(&self, tokens: &mut TokenStream) {
 			let fs = &self.fs;
 			let protocol = &self.protocol;
 			let http = &self.http;
 			let shell = &self.shell;
-			tokens.append_all(
-				quote! { ::millennium::utils::config::AllowlistConfig { fs: #fs, protocol: #protocol, http: #http, shell: #shell, ..Default::default() } }
-			)
+			let autostart = &self.autostart;
+			tokens.append_all(quote! {
+				::millennium::utils::config::AllowlistConfig { fs: #fs, protocol: #protocol, http: #http, shell: #shell, autostart: #autostart, ..Default::default() }
+			})
 		}
 	}
 
@@ -3640,7 +4309,10 @@ mod test {
 				short_description: None,
 				long_description: None,
 				appimage: Default::default(),
+				flatpak: Default::default(),
 				deb: Default::default(),
+				pacman: Default::default(),
+				linux: Default::default(),
 				macos: Default::default(),
 				external_bin: None,
 				windows: Default::default()
@@ -3651,6 +4323,8 @@ mod test {
 				dialog: true,
 				pubkey: "".into(),
 				endpoints: None,
+				trusted_keys: Vec::new(),
+				revoked_keys: Vec::new(),
 				windows: Default::default()
 			},
 			security: SecurityConfig {
@@ -3668,12 +4342,14 @@ mod test {
 		let build = BuildConfig {
 			runner: None,
 			dev_path: AppUrl::Url(WindowUrl::External(Url::parse("http://localhost:8080").unwrap())),
+			dev_server: Default::default(),
 			dist_dir: AppUrl::Url(WindowUrl::App("../dist".into())),
 			before_dev_command: None,
 			before_build_command: None,
 			before_bundle_command: None,
 			features: None,
-			with_global_millennium: false
+			with_global_millennium: false,
+			bindings: None
 		};
 
 		// test the configs
@@ -3684,4 +4360,23 @@ mod test {
 		assert_eq!(d_path, AppUrl::Url(WindowUrl::External(Url::parse("http://localhost:8080").unwrap())));
 		assert_eq!(d_windows, millennium.windows);
 	}
+
+	#[test]
+	fn test_http_scope_rules() {
+		let entry: HttpScopeEntry =
+			serde_json::from_str(r#"{ "url": "https://api.example.com/**", "methods": ["GET"], "forcedHeaders": { "X-Client": "app" } }"#).unwrap();
+		assert_eq!(
+			entry,
+			HttpScopeEntry::Rule {
+				url: "https://api.example.com/**".parse().unwrap(),
+				methods: Some(vec!["GET".into()]),
+				headers: None,
+				forced_headers: HashMap::from([("X-Client".into(), "app".into())])
+			}
+		);
+
+		// a typo must not silently allow every method or header
+		assert!(serde_json::from_str::<HttpScopeEntry>(r#"{ "url": "https://api.example.com/**", "method": ["GET"] }"#).is_err());
+		assert!(serde_json::from_str::<HttpScopeEntry>(r#"{ "url": "https://api.example.com/**", "header": ["Accept"] }"#).is_err());
+	}
 }
@@ -102,16 +102,18 @@ fn main() {
 		alias_module("dialog", &["open", "save", "message", "ask", "confirm"], api_all);
 	}
 
-	alias_module("http", &["request"], api_all);
+	alias_module("http", &["request", "websocket"], api_all);
 
 	alias("cli", has_feature("cli"));
 
 	if !mobile {
 		alias_module("notification", &[], api_all);
 		alias_module("global-shortcut", &[], api_all);
+		alias_module("autostart", &["enable", "disable", "is-enabled"], api_all);
 	}
 	alias_module("os", &[], api_all);
 	alias_module("path", &[], api_all);
+	alias_module("secrets", &["get", "set", "delete"], api_all);
 
 	alias_module("protocol", &["asset"], api_all);
 
@@ -0,0 +1,44 @@
+/**
+ * Copyright 2022 pyke.io
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// <reference path="./types.d.ts" />
+
+// Forwards `console.*` calls to the `log` plugin, keeping the original console output.
+const levels = { trace: 1, debug: 2, log: 3, info: 3, warn: 4, error: 5 };
+
+function stringify(value) {
+	if (typeof value === 'string')
+		return value;
+	if (value instanceof Error)
+		return value.stack || value.toString();
+	try {
+		return JSON.stringify(value);
+	} catch (_) {
+		return String(value);
+	}
+}
+
+for (const [method, level] of Object.entries(levels)) {
+	const original = console[method];
+	console[method] = function (...args) {
+		original.apply(console, args);
+		window.__MILLENNIUM_INVOKE__('plugin:log|log', {
+			level,
+			message: args.map(stringify).join(' '),
+			location: window.location.href
+		}).catch(() => {});
+	};
+}
@@ -0,0 +1,329 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(clippy::tabs_in_doc_comments)]
+
+//! Launch the application automatically when the user logs in.
+//!
+//! On Linux, this is implemented with an [XDG autostart] entry written to
+//! `$XDG_CONFIG_HOME/autostart/<identifier>.desktop`.
+//!
+//! [XDG autostart]: https://specifications.freedesktop.org/autostart-spec/autostart-spec-latest.html
+
+use std::path::PathBuf;
+
+use crate::{Config, Env, PackageInfo};
+
+/// An autostart entry for an application.
+///
+/// # Examples
+/// ```rust,no_run
+/// use millennium::{api::autostart::AutoLaunch, Manager};
+/// millennium::Builder::default().setup(|app| {
+/// 	let auto_launch = AutoLaunch::for_app(&app.config(), app.package_info(), &app.env())?.args(["--minimized"]);
+/// 	if !auto_launch.is_enabled()? {
+/// 		auto_launch.enable()?;
+/// 	}
+/// 	Ok(())
+/// });
+/// ```
+#[derive(Debug, Clone)]
+pub struct AutoLaunch {
+	/// The application identifier, used as the name of the autostart entry.
+	identifier: String,
+	/// The application name.
+	name: String,
+	/// The application description.
+	description: Option<String>,
+	/// The path to the program to launch.
+	program: PathBuf,
+	/// The arguments passed to the program.
+	args: Vec<String>
+}
+
+impl AutoLaunch {
+	/// Creates an autostart entry that launches `program` with the given
+	/// identifier and display name.
+	pub fn new(identifier: impl Into<String>, name: impl Into<String>, program: impl Into<PathBuf>) -> Self {
+		Self {
+			identifier: identifier.into(),
+			name: name.into(),
+			description: None,
+			program: program.into(),
+			args: Vec::new()
+		}
+	}
+
+	/// Creates an autostart entry for the running application, using the
+	/// `bundle > identifier` config and the package info.
+	///
+	/// When running as an AppImage, the entry launches the AppImage file
+	/// instead of the mounted binary.
+	pub fn for_app(config: &Config, package_info: &PackageInfo, env: &Env) -> crate::api::Result<Self> {
+		let bundle = &config.millennium.bundle;
+		if bundle.identifier.is_empty() {
+			return Err(crate::api::Error::Autostart("the `bundle > identifier` config is not set".into()));
+		}
+
+		#[cfg(target_os = "linux")]
+		let program = match &env.appimage {
+			Some(appimage) => PathBuf::from(appimage),
+			None => crate::utils::platform::current_exe()?
+		};
+		#[cfg(not(target_os = "linux"))]
+		let program = {
+			let _ = env;
+			crate::utils::platform::current_exe()?
+		};
+
+		let mut auto_launch = Self::new(&bundle.identifier, &package_info.name, program);
+		auto_launch.description = bundle
+			.short_description
+			.clone()
+			.or_else(|| Some(package_info.description.to_string()).filter(|d| !d.is_empty()));
+		Ok(auto_launch)
+	}
+
+	/// Sets the description shown by the desktop environment's startup
+	/// applications settings.
+	#[must_use]
+	pub fn description(mut self, description: impl Into<String>) -> Self {
+		self.description = Some(description.into());
+		self
+	}
+
+	/// Appends an argument passed to the application when it is launched at
+	/// login.
+	#[must_use]
+	pub fn arg(mut self, arg: impl Into<String>) -> Self {
+		self.args.push(arg.into());
+		self
+	}
+
+	/// Appends arguments passed to the application when it is launched at
+	/// login.
+	#[must_use]
+	pub fn args<I, S>(mut self, args: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>
+	{
+		self.args.extend(args.into_iter().map(Into::into));
+		self
+	}
+
+	/// Registers the application to be launched at login.
+	///
+	/// Overwrites any existing entry, so this can be used to update the
+	/// launch arguments.
+	pub fn enable(&self) -> crate::api::Result<()> {
+		imp::enable(self)
+	}
+
+	/// Unregisters the application from being launched at login.
+	///
+	/// Does nothing if the application was not registered.
+	pub fn disable(&self) -> crate::api::Result<()> {
+		imp::disable(self)
+	}
+
+	/// Checks whether the application is registered to be launched at login.
+	pub fn is_enabled(&self) -> crate::api::Result<bool> {
+		imp::is_enabled(self)
+	}
+}
+
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+mod imp {
+	use std::{
+		fs,
+		io::ErrorKind,
+		path::{Path, PathBuf}
+	};
+
+	use super::AutoLaunch;
+
+	fn autostart_dir() -> crate::api::Result<PathBuf> {
+		crate::api::path::config_dir()
+			.map(|dir| dir.join("autostart"))
+			.ok_or_else(|| crate::api::Error::Autostart("could not resolve the user config directory".into()))
+	}
+
+	/// Returns the path of the `.desktop` entry for the given application in
+	/// the given autostart directory.
+	pub fn entry_path(dir: &Path, auto_launch: &AutoLaunch) -> PathBuf {
+		dir.join(format!("{}.desktop", auto_launch.identifier))
+	}
+
+	/// Quotes an argument of the `Exec` key as described in the desktop entry
+	/// specification.
+	///
+	/// The result still has to go through [`escape_value`], which doubles the
+	/// backslashes added here.
+	fn quote_exec_arg(arg: &str) -> String {
+		let arg = arg.replace('%', "%%");
+		let needs_quoting = arg.is_empty()
+			|| arg
+				.chars()
+				.any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '>' | '<' | '~' | '|' | '&' | ';' | '$' | '*' | '?' | '#' | '(' | ')' | '`'));
+		if needs_quoting {
+			let mut quoted = String::with_capacity(arg.len() + 2);
+			quoted.push('"');
+			for c in arg.chars() {
+				if matches!(c, '"' | '`' | '$' | '\\') {
+					quoted.push('\\');
+				}
+				quoted.push(c);
+			}
+			quoted.push('"');
+			quoted
+		} else {
+			arg
+		}
+	}
+
+	/// Escapes a value of a `string` or `localestring` key.
+	fn escape_value(value: &str) -> String {
+		value.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t").replace('\r', "\\r")
+	}
+
+	pub fn desktop_entry(auto_launch: &AutoLaunch) -> String {
+		let exec = std::iter::once(auto_launch.program.to_string_lossy().into_owned())
+			.chain(auto_launch.args.iter().cloned())
+			.map(|arg| quote_exec_arg(&arg))
+			.collect::<Vec<_>>()
+			.join(" ");
+
+		let mut entry = String::from("[Desktop Entry]\nType=Application\nVersion=1.0\n");
+		entry.push_str(&format!("Name={}\n", escape_value(&auto_launch.name)));
+		if let Some(description) = &auto_launch.description {
+			entry.push_str(&format!("Comment={}\n", escape_value(description)));
+		}
+		entry.push_str(&format!("Exec={}\n", escape_value(&exec)));
+		entry.push_str("Terminal=false\nX-GNOME-Autostart-enabled=true\n");
+		entry
+	}
+
+	pub fn enable(auto_launch: &AutoLaunch) -> crate::api::Result<()> {
+		enable_in(&autostart_dir()?, auto_launch)
+	}
+
+	pub fn disable(auto_launch: &AutoLaunch) -> crate::api::Result<()> {
+		disable_in(&autostart_dir()?, auto_launch)
+	}
+
+	pub fn is_enabled(auto_launch: &AutoLaunch) -> crate::api::Result<bool> {
+		is_enabled_in(&autostart_dir()?, auto_launch)
+	}
+
+	pub fn enable_in(dir: &Path, auto_launch: &AutoLaunch) -> crate::api::Result<()> {
+		fs::create_dir_all(dir)?;
+		fs::write(entry_path(dir, auto_launch), desktop_entry(auto_launch))?;
+		Ok(())
+	}
+
+	pub fn disable_in(dir: &Path, auto_launch: &AutoLaunch) -> crate::api::Result<()> {
+		match fs::remove_file(entry_path(dir, auto_launch)) {
+			Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+			_ => Ok(())
+		}
+	}
+
+	pub fn is_enabled_in(dir: &Path, auto_launch: &AutoLaunch) -> crate::api::Result<bool> {
+		let contents = match fs::read_to_string(entry_path(dir, auto_launch)) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+			Err(e) => return Err(e.into())
+		};
+		// the entry may have been disabled by the user through the desktop environment's settings
+		let disabled = contents
+			.lines()
+			.map(|line| line.split_once('=').map(|(k, v)| (k.trim(), v.trim())))
+			.any(|kv| matches!(kv, Some(("Hidden", "true")) | Some(("X-GNOME-Autostart-enabled", "false"))));
+		Ok(!disabled)
+	}
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd")))]
+mod imp {
+	use super::AutoLaunch;
+
+	fn unsupported() -> crate::api::Error {
+		crate::api::Error::Autostart("launching at login is not supported on this platform yet".into())
+	}
+
+	pub fn enable(_auto_launch: &AutoLaunch) -> crate::api::Result<()> {
+		Err(unsupported())
+	}
+
+	pub fn disable(_auto_launch: &AutoLaunch) -> crate::api::Result<()> {
+		Err(unsupported())
+	}
+
+	pub fn is_enabled(_auto_launch: &AutoLaunch) -> crate::api::Result<bool> {
+		Err(unsupported())
+	}
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+	use super::{
+		imp::{disable_in, enable_in, entry_path, is_enabled_in},
+		AutoLaunch
+	};
+
+	#[test]
+	fn xdg_autostart_entry() {
+		let dir = tempfile::tempdir().unwrap();
+		let dir = dir.path().join("autostart");
+
+		let auto_launch = AutoLaunch::new("com.millennium.test", "Test App", "/opt/Test App/test")
+			.description("A test\napplication")
+			.args(["--minimized", "100%", "a \"quoted\" $arg", "C:\\path"]);
+		let entry_path = entry_path(&dir, &auto_launch);
+		assert_eq!(entry_path, dir.join("com.millennium.test.desktop"));
+
+		assert!(!is_enabled_in(&dir, &auto_launch).unwrap());
+		// disabling an entry that does not exist is not an error
+		disable_in(&dir, &auto_launch).unwrap();
+
+		enable_in(&dir, &auto_launch).unwrap();
+		assert!(is_enabled_in(&dir, &auto_launch).unwrap());
+		assert_eq!(
+			std::fs::read_to_string(&entry_path).unwrap(),
+			r#"[Desktop Entry]
+Type=Application
+Version=1.0
+Name=Test App
+Comment=A test\napplication
+Exec="/opt/Test App/test" --minimized 100%% "a \\"quoted\\" \\$arg" "C:\\\\path"
+Terminal=false
+X-GNOME-Autostart-enabled=true
+"#
+		);
+
+		// entries disabled from the desktop environment's settings are reported as disabled
+		let contents = std::fs::read_to_string(&entry_path)
+			.unwrap()
+			.replace("X-GNOME-Autostart-enabled=true", "X-GNOME-Autostart-enabled=false");
+		std::fs::write(&entry_path, contents).unwrap();
+		assert!(!is_enabled_in(&dir, &auto_launch).unwrap());
+
+		enable_in(&dir, &auto_launch).unwrap();
+		assert!(is_enabled_in(&dir, &auto_launch).unwrap());
+		disable_in(&dir, &auto_launch).unwrap();
+		assert!(!is_enabled_in(&dir, &auto_launch).unwrap());
+		assert!(!entry_path.exists());
+	}
+}
@@ -22,8 +22,18 @@
 //! closures to give results back. This is particularly useful when running
 //! dialogs from the main thread. When using on asynchronous contexts such as
 //! async commands, the [`blocking`] APIs are recommended.
+//!
+//! On Linux, file dialogs can be shown through the XDG desktop portal instead
+//! of GTK, which lets sandboxed (Flatpak, Snap) apps reach the user's files.
+//! See [`FileDialogBackend`].
 
 pub use nonblocking::*;
+#[cfg(target_os = "linux")]
+#[cfg_attr(doc_cfg, doc(cfg(target_os = "linux")))]
+pub use portal::{set_file_dialog_backend, FileDialogBackend};
+
+#[cfg(target_os = "linux")]
+mod portal;
 
 #[cfg(not(target_os = "linux"))]
 macro_rules! run_dialog {
@@ -50,9 +60,10 @@ macro_rules! run_dialog {
 
 #[cfg(not(target_os = "linux"))]
 macro_rules! run_file_dialog {
-	($e:expr, $h: ident) => {{
+	($builder:expr, $method:ident, $h: ident) => {{
+		let dialog = $builder.dialog;
 		std::thread::spawn(move || {
-			let response = crate::async_runtime::block_on($e);
+			let response = crate::async_runtime::block_on(dialog.$method());
 			$h(response);
 		});
 	}};
@@ -60,11 +71,29 @@ macro_rules! run_file_dialog {
 
 #[cfg(target_os = "linux")]
 macro_rules! run_file_dialog {
-	($e:expr, $h: ident) => {{
+	($builder:expr, $method:ident, $h: ident) => {{
+		let builder = $builder;
 		std::thread::spawn(move || {
+			if builder.portal.is_enabled() {
+				match builder.portal.$method() {
+					Ok(response) => {
+						$h(response);
+						return;
+					}
+					// only fall back when the portal didn't show anything, so the user isn't asked twice
+					Err(e @ crate::api::dialog::portal::PortalError::Unavailable(_)) => tracing::warn!("{}, falling back to GTK", e),
+					Err(e) => {
+						tracing::warn!("{}", e);
+						$h(None);
+						return;
+					}
+				}
+			}
+
+			let dialog = builder.dialog;
 			let context = glib::MainContext::default();
 			context.invoke_with_priority(glib::PRIORITY_HIGH, move || {
-				let response = $e;
+				let response = dialog.$method();
 				$h(response);
 			});
 		});
@@ -72,12 +101,12 @@ macro_rules! run_file_dialog {
 }
 
 macro_rules! run_dialog_sync {
-	($e:expr) => {{
+	($builder:expr, $method:ident) => {{
 		let (tx, rx) = sync_channel(0);
 		let cb = move |response| {
 			tx.send(response).unwrap();
 		};
-		run_file_dialog!($e, cb);
+		run_file_dialog!($builder, $method, cb);
 		rx.recv().unwrap()
 	}};
 }
@@ -94,7 +123,11 @@ macro_rules! file_dialog_builder {
 		/// Constructs file picker dialogs that can select single/multiple files or
 		/// directories.
 		#[derive(Debug, Default)]
-		pub struct FileDialogBuilder(FileDialog);
+		pub struct FileDialogBuilder {
+			dialog: FileDialog,
+			#[cfg(target_os = "linux")]
+			portal: super::portal::FileChooser
+		}
 
 		impl FileDialogBuilder {
 			/// Gets the default file dialog builder.
@@ -106,35 +139,59 @@ macro_rules! file_dialog_builder {
 			/// extensions
 			#[must_use]
 			pub fn add_filter(mut self, name: impl AsRef<str>, extensions: &[&str]) -> Self {
-				self.0 = self.0.add_filter(name.as_ref(), extensions);
+				#[cfg(target_os = "linux")]
+				self.portal.add_filter(name.as_ref(), extensions);
+				self.dialog = self.dialog.add_filter(name.as_ref(), extensions);
 				self
 			}
 
 			/// Set starting directory of the dialog.
 			#[must_use]
 			pub fn set_directory<P: AsRef<Path>>(mut self, directory: P) -> Self {
-				self.0 = self.0.set_directory(directory);
+				#[cfg(target_os = "linux")]
+				self.portal.set_directory(directory.as_ref());
+				self.dialog = self.dialog.set_directory(directory);
 				self
 			}
 
 			/// Set starting file name of the dialog.
+			///
+			/// ## Platform-specific
+			///
+			/// - **Linux**: Only used by save dialogs when shown through the XDG desktop portal.
 			#[must_use]
 			pub fn set_file_name(mut self, file_name: &str) -> Self {
-				self.0 = self.0.set_file_name(file_name);
+				#[cfg(target_os = "linux")]
+				self.portal.set_file_name(file_name);
+				self.dialog = self.dialog.set_file_name(file_name);
 				self
 			}
 
 			/// Sets the parent window of the dialog.
 			#[must_use]
 			pub fn set_parent<W: raw_window_handle::HasRawWindowHandle>(mut self, parent: &W) -> Self {
-				self.0 = self.0.set_parent(parent);
+				#[cfg(target_os = "linux")]
+				self.portal.set_parent(parent.raw_window_handle());
+				self.dialog = self.dialog.set_parent(parent);
 				self
 			}
 
 			/// Set the title of the dialog.
 			#[must_use]
 			pub fn set_title(mut self, title: &str) -> Self {
-				self.0 = self.0.set_title(title);
+				#[cfg(target_os = "linux")]
+				self.portal.set_title(title);
+				self.dialog = self.dialog.set_title(title);
+				self
+			}
+
+			/// Sets the backend showing the dialog, overriding the one set with
+			/// [`crate::api::dialog::set_file_dialog_backend`].
+			#[cfg(target_os = "linux")]
+			#[cfg_attr(doc_cfg, doc(cfg(target_os = "linux")))]
+			#[must_use]
+			pub fn set_backend(mut self, backend: crate::api::dialog::FileDialogBackend) -> Self {
+				self.portal.set_backend(backend);
 				self
 			}
 		}
@@ -265,7 +322,7 @@ pub mod blocking {
 		/// ```
 		pub fn pick_file(self) -> Option<PathBuf> {
 			#[allow(clippy::let_and_return)]
-			let response = run_dialog_sync!(self.0.pick_file());
+			let response = run_dialog_sync!(self, pick_file);
 			#[cfg(not(target_os = "linux"))]
 			let response = response.map(|p| p.path().to_path_buf());
 			response
@@ -288,7 +345,7 @@ pub mod blocking {
 		/// ```
 		pub fn pick_files(self) -> Option<Vec<PathBuf>> {
 			#[allow(clippy::let_and_return)]
-			let response = run_dialog_sync!(self.0.pick_files());
+			let response = run_dialog_sync!(self, pick_files);
 			#[cfg(not(target_os = "linux"))]
 			let response = response.map(|paths| paths.into_iter().map(|p| p.path().to_path_buf()).collect());
 			response
@@ -311,7 +368,7 @@ pub mod blocking {
 		/// ```
 		pub fn pick_folder(self) -> Option<PathBuf> {
 			#[allow(clippy::let_and_return)]
-			let response = run_dialog_sync!(self.0.pick_folder());
+			let response = run_dialog_sync!(self, pick_folder);
 			#[cfg(not(target_os = "linux"))]
 			let response = response.map(|p| p.path().to_path_buf());
 			response
@@ -333,7 +390,7 @@ pub mod blocking {
 		/// ```
 		pub fn pick_folders(self) -> Option<Vec<PathBuf>> {
 			#[allow(clippy::let_and_return)]
-			let response = run_dialog_sync!(self.0.pick_folders());
+			let response = run_dialog_sync!(self, pick_folders);
 			#[cfg(not(target_os = "linux"))]
 			let response = response.map(|paths| paths.into_iter().map(|p| p.path().to_path_buf()).collect());
 			response
@@ -356,7 +413,7 @@ pub mod blocking {
 		/// ```
 		pub fn save_file(self) -> Option<PathBuf> {
 			#[allow(clippy::let_and_return)]
-			let response = run_dialog_sync!(self.0.save_file());
+			let response = run_dialog_sync!(self, save_file);
 			#[cfg(not(target_os = "linux"))]
 			let response = response.map(|p| p.path().to_path_buf());
 			response
@@ -487,7 +544,7 @@ mod nonblocking {
 		pub fn pick_file<F: FnOnce(Option<PathBuf>) + Send + 'static>(self, f: F) {
 			#[cfg(not(target_os = "linux"))]
 			let f = |path: Option<rfd::FileHandle>| f(path.map(|p| p.path().to_path_buf()));
-			run_file_dialog!(self.0.pick_file(), f)
+			run_file_dialog!(self, pick_file, f)
 		}
 
 		/// Shows the dialog to select multiple files.
@@ -512,7 +569,7 @@ mod nonblocking {
 		pub fn pick_files<F: FnOnce(Option<Vec<PathBuf>>) + Send + 'static>(self, f: F) {
 			#[cfg(not(target_os = "linux"))]
 			let f = |paths: Option<Vec<rfd::FileHandle>>| f(paths.map(|list| list.into_iter().map(|p| p.path().to_path_buf()).collect()));
-			run_file_dialog!(self.0.pick_files(), f)
+			run_file_dialog!(self, pick_files, f)
 		}
 
 		/// Shows the dialog to select a single folder.
@@ -537,7 +594,7 @@ mod nonblocking {
 		pub fn pick_folder<F: FnOnce(Option<PathBuf>) + Send + 'static>(self, f: F) {
 			#[cfg(not(target_os = "linux"))]
 			let f = |path: Option<rfd::FileHandle>| f(path.map(|p| p.path().to_path_buf()));
-			run_file_dialog!(self.0.pick_folder(), f)
+			run_file_dialog!(self, pick_folder, f)
 		}
 
 		/// Shows the dialog to select multiple folders.
@@ -561,7 +618,7 @@ mod nonblocking {
 		pub fn pick_folders<F: FnOnce(Option<Vec<PathBuf>>) + Send + 'static>(self, f: F) {
 			#[cfg(not(target_os = "linux"))]
 			let f = |paths: Option<Vec<rfd::FileHandle>>| f(paths.map(|list| list.into_iter().map(|p| p.path().to_path_buf()).collect()));
-			run_file_dialog!(self.0.pick_folders(), f)
+			run_file_dialog!(self, pick_folders, f)
 		}
 
 		/// Shows the dialog to save a file.
@@ -587,7 +644,7 @@ mod nonblocking {
 		pub fn save_file<F: FnOnce(Option<PathBuf>) + Send + 'static>(self, f: F) {
 			#[cfg(not(target_os = "linux"))]
 			let f = |path: Option<rfd::FileHandle>| f(path.map(|p| p.path().to_path_buf()));
-			run_file_dialog!(self.0.save_file(), f)
+			run_file_dialog!(self, save_file, f)
 		}
 	}
 
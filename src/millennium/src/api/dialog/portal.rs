@@ -0,0 +1,407 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! File dialogs shown through the [XDG desktop portal](https://flatpak.github.io/xdg-desktop-portal/docs/doc-org.freedesktop.portal.FileChooser.html),
+//! which can reach the user's files from sandboxed (Flatpak, Snap) installs.
+
+use std::{
+	collections::HashMap,
+	fmt,
+	os::unix::ffi::OsStrExt,
+	path::{Path, PathBuf},
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Mutex
+	}
+};
+
+use once_cell::sync::Lazy;
+use raw_window_handle::RawWindowHandle;
+use zbus::{
+	blocking::{Connection, Proxy},
+	zvariant::{OwnedObjectPath, OwnedValue, Value}
+};
+
+const DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const FILE_CHOOSER_INTERFACE: &str = "org.freedesktop.portal.FileChooser";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// A filter as transferred over D-Bus: its name, and a list of glob (`0`) or
+/// MIME type (`1`) patterns.
+type Filter = (String, Vec<(u32, String)>);
+
+/// Why a file dialog was not shown through the portal.
+#[derive(Debug)]
+pub(crate) enum PortalError {
+	/// The portal did not accept the request, e.g. because there is no
+	/// session bus or no portal implements the `FileChooser` interface. The
+	/// dialog can be shown by another backend.
+	Unavailable(zbus::Error),
+	/// The portal accepted the request but failed to show the dialog.
+	Failed(String)
+}
+
+impl fmt::Display for PortalError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unavailable(e) => write!(f, "the XDG desktop portal is not available: {e}"),
+			Self::Failed(e) => write!(f, "the XDG desktop portal failed to show the file dialog: {e}")
+		}
+	}
+}
+
+type Result<T> = std::result::Result<T, PortalError>;
+
+/// The backend showing file dialogs on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileDialogBackend {
+	/// Uses the XDG desktop portal when the app runs in a Flatpak or Snap
+	/// sandbox or `GTK_USE_PORTAL=1` is set, and GTK otherwise.
+	Auto,
+	/// Uses the GTK file chooser.
+	Gtk,
+	/// Uses the XDG desktop portal.
+	Portal
+}
+
+impl Default for FileDialogBackend {
+	fn default() -> Self {
+		Self::Auto
+	}
+}
+
+impl FileDialogBackend {
+	fn uses_portal(self) -> bool {
+		match self {
+			Self::Auto => {
+				Path::new("/.flatpak-info").exists() || std::env::var_os("SNAP").is_some() || std::env::var_os("GTK_USE_PORTAL").map_or(false, |v| v == "1")
+			}
+			Self::Gtk => false,
+			Self::Portal => true
+		}
+	}
+}
+
+static BACKEND: Lazy<Mutex<FileDialogBackend>> = Lazy::new(Default::default);
+
+/// Sets the backend of the file dialogs that don't set one with
+/// `FileDialogBuilder::set_backend`.
+///
+/// The portal falls back to GTK when it is not available.
+pub fn set_file_dialog_backend(backend: FileDialogBackend) {
+	*BACKEND.lock().unwrap() = backend;
+}
+
+/// The options of a file dialog shown through the portal.
+#[derive(Debug, Default)]
+pub(crate) struct FileChooser {
+	backend: Option<FileDialogBackend>,
+	parent_window: String,
+	title: String,
+	filters: Vec<Filter>,
+	current_folder: Option<PathBuf>,
+	current_name: Option<String>,
+	/// The bus the portal is reached on instead of the session bus.
+	#[cfg(test)]
+	bus_address: Option<String>
+}
+
+impl FileChooser {
+	pub(crate) fn set_backend(&mut self, backend: FileDialogBackend) {
+		self.backend.replace(backend);
+	}
+
+	/// Whether the dialog should be shown through the portal.
+	pub(crate) fn is_enabled(&self) -> bool {
+		self.backend.unwrap_or_else(|| *BACKEND.lock().unwrap()).uses_portal()
+	}
+
+	pub(crate) fn add_filter(&mut self, name: &str, extensions: &[&str]) {
+		let patterns = extensions.iter().map(|extension| (0, format!("*.{extension}"))).collect();
+		self.filters.push((name.into(), patterns));
+	}
+
+	pub(crate) fn set_directory(&mut self, directory: &Path) {
+		self.current_folder.replace(directory.to_path_buf());
+	}
+
+	pub(crate) fn set_file_name(&mut self, file_name: &str) {
+		self.current_name.replace(file_name.into());
+	}
+
+	/// Sets the parent window. Only X11 windows can be identified without
+	/// exporting a handle through the compositor.
+	pub(crate) fn set_parent(&mut self, parent: RawWindowHandle) {
+		if let RawWindowHandle::Xlib(handle) = parent {
+			self.parent_window = format!("x11:{:x}", handle.window);
+		}
+	}
+
+	pub(crate) fn set_title(&mut self, title: &str) {
+		self.title = title.into();
+	}
+
+	pub(crate) fn pick_file(&self) -> Result<Option<PathBuf>> {
+		Ok(self.open(false, false)?.and_then(|paths| paths.into_iter().next()))
+	}
+
+	pub(crate) fn pick_files(&self) -> Result<Option<Vec<PathBuf>>> {
+		self.open(true, false)
+	}
+
+	pub(crate) fn pick_folder(&self) -> Result<Option<PathBuf>> {
+		Ok(self.open(false, true)?.and_then(|paths| paths.into_iter().next()))
+	}
+
+	pub(crate) fn pick_folders(&self) -> Result<Option<Vec<PathBuf>>> {
+		self.open(true, true)
+	}
+
+	pub(crate) fn save_file(&self) -> Result<Option<PathBuf>> {
+		let mut options = self.options();
+		if let Some(current_name) = &self.current_name {
+			options.insert("current_name", Value::from(current_name.as_str()));
+		}
+		Ok(self.request("SaveFile", options)?.and_then(|paths| paths.into_iter().next()))
+	}
+
+	fn open(&self, multiple: bool, directory: bool) -> Result<Option<Vec<PathBuf>>> {
+		let mut options = self.options();
+		options.insert("multiple", Value::from(multiple));
+		options.insert("directory", Value::from(directory));
+		self.request("OpenFile", options)
+	}
+
+	fn options(&self) -> HashMap<&'static str, Value<'_>> {
+		let mut options = HashMap::from([("modal", Value::from(true))]);
+		if !self.filters.is_empty() {
+			options.insert("filters", Value::new(self.filters.clone()));
+		}
+		if let Some(current_folder) = &self.current_folder {
+			// the portal expects a nul-terminated byte string
+			let mut folder = current_folder.as_os_str().as_bytes().to_vec();
+			folder.push(0);
+			options.insert("current_folder", Value::from(folder));
+		}
+		options
+	}
+
+	fn connect(&self) -> zbus::Result<Connection> {
+		#[cfg(test)]
+		if let Some(address) = &self.bus_address {
+			return zbus::blocking::ConnectionBuilder::address(address.as_str())?.build();
+		}
+		Connection::session()
+	}
+
+	/// Calls a `FileChooser` method and waits for the user to answer, returning
+	/// `None` if the dialog was cancelled.
+	fn request(&self, method: &str, mut options: HashMap<&'static str, Value<'_>>) -> Result<Option<Vec<PathBuf>>> {
+		static TOKEN: AtomicUsize = AtomicUsize::new(0);
+
+		let connection = self.connect().map_err(PortalError::Unavailable)?;
+		let token = format!("millennium{}", TOKEN.fetch_add(1, Ordering::Relaxed));
+		let sender = connection
+			.unique_name()
+			.ok_or_else(|| PortalError::Unavailable(zbus::Error::Failure("the bus connection has no unique name".into())))?;
+		// subscribe to the response before making the request, so it can't be missed
+		let expected = request_path(sender.as_str(), &token);
+		let mut responses = Proxy::new(&connection, DESTINATION, expected.as_str(), REQUEST_INTERFACE)
+			.and_then(|proxy| proxy.receive_signal("Response"))
+			.map_err(PortalError::Unavailable)?;
+
+		options.insert("handle_token", Value::from(token.as_str()));
+		let handle: OwnedObjectPath = Proxy::new(&connection, DESTINATION, PORTAL_PATH, FILE_CHOOSER_INTERFACE)
+			.and_then(|proxy| proxy.call(method, &(self.parent_window.as_str(), self.title.as_str(), options)))
+			.map_err(PortalError::Unavailable)?;
+		// portals older than version 0.9 ignore the token
+		if handle.as_str() != expected {
+			responses = Proxy::new(&connection, DESTINATION, handle.as_str(), REQUEST_INTERFACE)
+				.and_then(|proxy| proxy.receive_signal("Response"))
+				.map_err(|e| PortalError::Failed(e.to_string()))?;
+		}
+
+		let message = responses
+			.next()
+			.ok_or_else(|| PortalError::Failed("the request was closed without a response".into()))?;
+		let (response, results): (u32, HashMap<String, OwnedValue>) = message.body().map_err(|e| PortalError::Failed(e.to_string()))?;
+		match response {
+			0 => Ok(Some(selected_paths(&results))),
+			1 => Ok(None),
+			_ => Err(PortalError::Failed("the request was ended unexpectedly".into()))
+		}
+	}
+}
+
+/// The object path of the request with the given token, made by the
+/// connection with the given unique name.
+fn request_path(sender: &str, token: &str) -> String {
+	format!("{PORTAL_PATH}/request/{}/{token}", sender.trim_start_matches(':').replace('.', "_"))
+}
+
+/// Reads the paths of the `file://` URIs selected by the user.
+fn selected_paths(results: &HashMap<String, OwnedValue>) -> Vec<PathBuf> {
+	match results.get("uris").map(|uris| &**uris) {
+		Some(Value::Array(uris)) => uris
+			.get()
+			.iter()
+			.filter_map(|uri| match uri {
+				Value::Str(uri) => url::Url::parse(uri.as_str()).ok()?.to_file_path().ok(),
+				_ => None
+			})
+			.collect(),
+		_ => Vec::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::HashMap,
+		path::{Path, PathBuf},
+		sync::mpsc::{channel, Receiver},
+		thread
+	};
+
+	use zbus::{
+		blocking::MessageIterator,
+		names::BusName,
+		zvariant::{OwnedObjectPath, OwnedValue, Value},
+		MessageType
+	};
+
+	use super::{request_path, selected_paths, FileChooser, PortalError, DESTINATION, FILE_CHOOSER_INTERFACE, REQUEST_INTERFACE};
+	use crate::test_utils::PrivateBus;
+
+	/// A `FileChooser` call received by [`serve_portal`]: the method, the parent window, the title and the options.
+	type Request = (String, String, String, HashMap<String, OwnedValue>);
+
+	/// Serves a stub `FileChooser` portal on the bus, answering the requests in order with the given response codes and
+	/// selected URIs.
+	fn serve_portal(bus: &PrivateBus, responses: Vec<(u32, &'static [&'static str])>) -> Receiver<Request> {
+		let connection = bus
+			.connection()
+			.name(DESTINATION)
+			.unwrap()
+			.build()
+			.unwrap();
+		let (tx, rx) = channel();
+		thread::spawn(move || {
+			let mut responses = responses.into_iter();
+			for message in MessageIterator::from(&connection) {
+				let message = match message {
+					Ok(message) => message,
+					Err(_) => break
+				};
+				if message.message_type() != MessageType::MethodCall || message.interface().map_or(true, |i| i.as_str() != FILE_CHOOSER_INTERFACE) {
+					continue;
+				}
+
+				let method = message.member().unwrap().to_string();
+				let (parent_window, title, options): (String, String, HashMap<String, OwnedValue>) = message.body().unwrap();
+				let token: String = options["handle_token"].clone().try_into().unwrap();
+				let sender = message.header().unwrap().sender().unwrap().unwrap().to_string();
+				let handle = OwnedObjectPath::try_from(request_path(&sender, &token)).unwrap();
+				connection.reply(&message, &handle).unwrap();
+
+				let (response, uris) = responses.next().expect("unexpected request");
+				let results = HashMap::from([("uris", Value::from(uris.to_vec()))]);
+				connection
+					.emit_signal(None::<BusName<'_>>, handle.as_str(), REQUEST_INTERFACE, "Response", &(response, results))
+					.unwrap();
+				tx.send((method, parent_window, title, options)).unwrap();
+			}
+		});
+		rx
+	}
+
+	fn file_chooser(bus: &PrivateBus) -> FileChooser {
+		FileChooser {
+			bus_address: Some(bus.address().into()),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn request_path_from_unique_name() {
+		assert_eq!(request_path(":1.42", "millennium0"), "/org/freedesktop/portal/desktop/request/1_42/millennium0");
+	}
+
+	#[test]
+	fn decodes_selected_uris() {
+		let uris = Value::from(vec!["file:///home/user/My%20Files/a.txt", "file:///tmp", "https://example.com/b.txt"]);
+		let results = HashMap::from([("uris".to_string(), OwnedValue::from(uris))]);
+		assert_eq!(selected_paths(&results), [PathBuf::from("/home/user/My Files/a.txt"), PathBuf::from("/tmp")]);
+		assert!(selected_paths(&HashMap::new()).is_empty());
+	}
+
+	#[test]
+	#[ignore = "requires dbus-daemon"]
+	fn shows_dialogs_through_the_portal() {
+		let bus = PrivateBus::start();
+		let requests = serve_portal(&bus, vec![(0, &["file:///tmp/a.txt", "file:///tmp/My%20Files/b.md"][..]), (0, &["file:///home/user/new.txt"][..])]);
+
+		let mut chooser = file_chooser(&bus);
+		chooser.set_title("Open files");
+		chooser.add_filter("Text", &["txt", "md"]);
+		chooser.set_directory(Path::new("/tmp"));
+		assert_eq!(chooser.pick_files().unwrap(), Some(vec![PathBuf::from("/tmp/a.txt"), PathBuf::from("/tmp/My Files/b.md")]));
+
+		let (method, parent_window, title, options) = requests.recv().unwrap();
+		assert_eq!((method.as_str(), parent_window.as_str(), title.as_str()), ("OpenFile", "", "Open files"));
+		assert_eq!(*options["modal"], Value::from(true));
+		assert_eq!(*options["multiple"], Value::from(true));
+		assert_eq!(*options["directory"], Value::from(false));
+		assert_eq!(*options["current_folder"], Value::from(b"/tmp\0".to_vec()));
+		let filters = vec![("Text".to_string(), vec![(0u32, "*.txt".to_string()), (0, "*.md".to_string())])];
+		assert_eq!(*options["filters"], Value::new(filters));
+
+		let mut chooser = file_chooser(&bus);
+		chooser.set_file_name("new.txt");
+		assert_eq!(chooser.save_file().unwrap(), Some(PathBuf::from("/home/user/new.txt")));
+
+		let (method, _, _, options) = requests.recv().unwrap();
+		assert_eq!(method, "SaveFile");
+		assert_eq!(*options["current_name"], Value::from("new.txt"));
+		assert!(!options.contains_key("multiple"));
+	}
+
+	#[test]
+	#[ignore = "requires dbus-daemon"]
+	fn reports_cancelled_and_failed_requests() {
+		let bus = PrivateBus::start();
+		let _requests = serve_portal(&bus, vec![(1, &[][..]), (2, &[][..])]);
+
+		let chooser = file_chooser(&bus);
+		assert_eq!(chooser.pick_folder().unwrap(), None);
+		assert!(matches!(chooser.pick_folders(), Err(PortalError::Failed(_))));
+	}
+
+	#[test]
+	fn reports_unavailable_portals() {
+		let chooser = FileChooser {
+			bus_address: Some("unix:path=/nonexistent/bus".into()),
+			..Default::default()
+		};
+		assert!(matches!(chooser.pick_file(), Err(PortalError::Unavailable(_))));
+	}
+
+	#[test]
+	#[ignore = "requires dbus-daemon"]
+	fn reports_buses_without_a_portal() {
+		let bus = PrivateBus::start();
+		assert!(matches!(file_chooser(&bus).pick_file(), Err(PortalError::Unavailable(_))));
+	}
+}
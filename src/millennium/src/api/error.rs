@@ -46,8 +46,8 @@ pub enum Error {
 	#[error(transparent)]
 	HttpMethod(#[from] http::method::InvalidMethod),
 	/// Invalid HTTP header value.
-	#[cfg(feature = "reqwest-client")]
-	#[cfg_attr(doc_cfg, doc(cfg(feature = "reqwest-client")))]
+	#[cfg(any(feature = "reqwest-client", feature = "websocket-api"))]
+	#[cfg_attr(doc_cfg, doc(cfg(any(feature = "reqwest-client", feature = "websocket-api"))))]
 	#[error(transparent)]
 	HttpHeaderValue(#[from] http::header::InvalidHeaderValue),
 	/// Invalid HTTP header value.
@@ -56,6 +56,31 @@ pub enum Error {
 	/// Failed to serialize header value as string.
 	#[error(transparent)]
 	Utf8(#[from] std::string::FromUtf8Error),
+	/// The HTTP request failed.
+	#[cfg(feature = "http-api")]
+	#[error("request failed with status {0}")]
+	HttpStatus(http::StatusCode),
+	/// The server did not resume a download at the requested offset.
+	#[cfg(feature = "http-api")]
+	#[error("the server responded with an unexpected `Content-Range`")]
+	InvalidContentRange,
+	/// The checksum of a downloaded file did not match.
+	#[cfg(feature = "http-api")]
+	#[error("checksum mismatch: expected {expected}, got {actual}")]
+	ChecksumMismatch {
+		/// The expected checksum.
+		expected: String,
+		/// The checksum of the downloaded file.
+		actual: String
+	},
+	/// The cookie jar could not be read or written.
+	#[cfg(feature = "http-api")]
+	#[error("cookie jar error: {0}")]
+	CookieJar(String),
+	/// The TLS configuration of the HTTP client is invalid.
+	#[cfg(feature = "http-api")]
+	#[error("TLS error: {0}")]
+	Tls(String),
 	/// HTTP form to must be an object.
 	#[error("http form must be an object")]
 	InvalidHttpForm,
@@ -97,12 +122,30 @@ pub enum Error {
 	/// Shell error.
 	#[error("shell error: {0}")]
 	Shell(String),
+	/// Autostart error.
+	#[error("autostart error: {0}")]
+	Autostart(String),
+	/// Secrets error.
+	#[error("secrets error: {0}")]
+	Secrets(String),
 	/// Unknown program name.
 	#[error("unknown program name: {0}")]
 	UnknownProgramName(String),
 	/// HTTP error.
 	#[error(transparent)]
-	Http(#[from] http::Error)
+	Http(#[from] http::Error),
+	/// WebSocket error.
+	#[cfg(feature = "websocket-api")]
+	#[cfg_attr(doc_cfg, doc(cfg(feature = "websocket-api")))]
+	#[error("WebSocket error: {0}")]
+	WebSocket(Box<tokio_tungstenite::tungstenite::Error>)
+}
+
+#[cfg(feature = "websocket-api")]
+impl From<tokio_tungstenite::tungstenite::Error> for Error {
+	fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+		Self::WebSocket(Box::new(error))
+	}
 }
 
 #[cfg(feature = "cli")]
@@ -18,10 +18,22 @@
 
 //! Types and functions related to HTTP request.
 
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+mod cache;
+mod cookies;
+
+use std::{
+	collections::HashMap,
+	fmt::Write as _,
+	fs::{File, OpenOptions},
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::Duration
+};
 
 #[cfg(not(feature = "reqwest-client"))]
 pub use attohttpc::header;
+use cache::{Cache, CachedResponse};
+use cookies::CookieJar;
 use header::{HeaderName, HeaderValue};
 use http::Method;
 pub use http::StatusCode;
@@ -50,6 +62,16 @@ fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Op
 	}
 }
 
+/// A client certificate used to authenticate to servers requiring mutual TLS.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientIdentity {
+	/// Path of the PKCS #12 archive containing the certificate chain and its private key.
+	pub path: PathBuf,
+	/// Password of the archive.
+	#[serde(default)]
+	pub password: String
+}
+
 /// The builder of [`Client`].
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -58,7 +80,24 @@ pub struct ClientBuilder {
 	pub max_redirections: Option<usize>,
 	/// Connect timeout in seconds for the request.
 	#[serde(deserialize_with = "deserialize_duration", default)]
-	pub connect_timeout: Option<Duration>
+	pub connect_timeout: Option<Duration>,
+	/// Headers sent with every request, unless the request sets them itself.
+	pub default_headers: Option<HeaderMap>,
+	/// Paths of PEM-encoded root certificates to trust in addition to the system ones.
+	#[serde(default)]
+	pub root_certificates: Vec<PathBuf>,
+	/// The client certificate to present to servers.
+	///
+	/// Only supported with the `reqwest-client` feature; the default attohttpc
+	/// backend can't present client certificates, so [`ClientBuilder::build`]
+	/// fails when this is set.
+	pub identity: Option<ClientIdentity>,
+	/// Path of the file cookies are persisted in. Cookies are ignored if not set.
+	#[serde(skip)]
+	pub cookie_jar: Option<PathBuf>,
+	/// Directory `GET` responses are cached in. Responses are not cached if not set.
+	#[serde(skip)]
+	pub cache_dir: Option<PathBuf>
 }
 
 impl ClientBuilder {
@@ -81,17 +120,91 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Sets the headers sent with every request.
+	#[must_use]
+	pub fn default_headers(mut self, headers: header::HeaderMap) -> Self {
+		self.default_headers.replace(HeaderMap(headers));
+		self
+	}
+
+	/// Trusts the PEM-encoded root certificate at the given path.
+	#[must_use]
+	pub fn root_certificate(mut self, path: impl Into<PathBuf>) -> Self {
+		self.root_certificates.push(path.into());
+		self
+	}
+
+	/// Sets the PKCS #12 archive holding the client certificate.
+	///
+	/// Client certificates require the `reqwest-client` feature. Without it,
+	/// [`ClientBuilder::build`] returns an [`Error::Tls`](crate::api::Error::Tls).
+	#[must_use]
+	pub fn identity(mut self, path: impl Into<PathBuf>, password: impl Into<String>) -> Self {
+		self.identity.replace(ClientIdentity {
+			path: path.into(),
+			password: password.into()
+		});
+		self
+	}
+
+	/// Persists cookies in the given file and sends them back on matching requests.
+	#[must_use]
+	pub fn cookie_jar(mut self, path: impl Into<PathBuf>) -> Self {
+		self.cookie_jar.replace(path.into());
+		self
+	}
+
+	/// Caches `GET` responses in the given directory, revalidating them with the server.
+	#[must_use]
+	pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+		self.cache_dir.replace(dir.into());
+		self
+	}
+
 	/// Builds the Client.
-	#[cfg(not(feature = "reqwest-client"))]
 	pub fn build(self) -> crate::api::Result<Client> {
-		Ok(Client(self))
+		let cookies = self.cookie_jar.clone().map(CookieJar::shared).transpose()?;
+		let cache = self.cache_dir.clone().map(Cache::new);
+		let default_headers = self.default_headers.clone().map(|headers| headers.0).unwrap_or_default();
+		Ok(Client {
+			inner: self.build_inner(cookies.as_ref())?,
+			default_headers,
+			cookies,
+			cache
+		})
+	}
+
+	#[cfg(not(feature = "reqwest-client"))]
+	fn build_inner(self, _cookies: Option<&Arc<CookieJar>>) -> crate::api::Result<ClientInner> {
+		if self.identity.is_some() {
+			return Err(crate::api::Error::Tls("client certificates require the `reqwest-client` feature".into()));
+		}
+
+		let root_certificates = self
+			.root_certificates
+			.iter()
+			.map(|path| {
+				let pem = std::fs::read(path)?;
+				native_tls::Certificate::from_pem(&pem).map_err(|e| crate::api::Error::Tls(e.to_string()))?;
+				Ok::<_, crate::api::Error>(pem)
+			})
+			.collect::<crate::api::Result<_>>()?;
+
+		Ok(ClientInner {
+			max_redirections: self.max_redirections,
+			root_certificates
+		})
 	}
 
-	/// Builds the Client.
 	#[cfg(feature = "reqwest-client")]
-	pub fn build(self) -> crate::api::Result<Client> {
+	fn build_inner(self, cookies: Option<&Arc<CookieJar>>) -> crate::api::Result<ClientInner> {
 		let mut client_builder = reqwest::Client::builder();
 
+		// reqwest sends and stores the cookies of every redirection
+		if let Some(cookies) = cookies {
+			client_builder = client_builder.cookie_provider(cookies.clone());
+		}
+
 		if let Some(max_redirections) = self.max_redirections {
 			client_builder = client_builder.redirect(if max_redirections == 0 {
 				reqwest::redirect::Policy::none()
@@ -104,22 +217,37 @@ impl ClientBuilder {
 			client_builder = client_builder.connect_timeout(connect_timeout);
 		}
 
-		let client = client_builder.build()?;
-		Ok(Client(client))
+		for path in &self.root_certificates {
+			client_builder = client_builder.add_root_certificate(reqwest::Certificate::from_pem(&std::fs::read(path)?)?);
+		}
+
+		if let Some(identity) = &self.identity {
+			client_builder = client_builder.identity(reqwest::Identity::from_pkcs12_der(&std::fs::read(&identity.path)?, &identity.password)?);
+		}
+
+		Ok(client_builder.build()?)
 	}
 }
 
-/// The HTTP client based on [`reqwest`].
 #[cfg(feature = "reqwest-client")]
+type ClientInner = reqwest::Client;
+
+#[cfg(not(feature = "reqwest-client"))]
 #[derive(Debug, Clone)]
-pub struct Client(reqwest::Client);
+struct ClientInner {
+	max_redirections: Option<usize>,
+	root_certificates: Vec<Vec<u8>>
+}
 
 /// The HTTP client.
-#[cfg(not(feature = "reqwest-client"))]
 #[derive(Debug, Clone)]
-pub struct Client(ClientBuilder);
+pub struct Client {
+	inner: ClientInner,
+	default_headers: header::HeaderMap,
+	cookies: Option<Arc<CookieJar>>,
+	cache: Option<Cache>
+}
 
-#[cfg(not(feature = "reqwest-client"))]
 impl Client {
 	/// Executes an HTTP request.
 	///
@@ -141,22 +269,76 @@ impl Client {
 	/// 	}
 	/// }
 	/// ```
-	pub async fn send(&self, mut request: HttpRequestBuilder) -> crate::api::Result<Response> {
-		let method = Method::from_bytes(request.method.to_uppercase().as_bytes())?;
+	pub async fn send(&self, request: HttpRequestBuilder) -> crate::api::Result<Response> {
+		self.send_with_cache(request, true).await
+	}
 
-		let mut request_builder = attohttpc::RequestBuilder::try_new(method, &request.url)?;
+	/// Executes an HTTP request, serving it from the cache when `use_cache` is set and the client has one.
+	async fn send_with_cache(&self, mut request: HttpRequestBuilder, use_cache: bool) -> crate::api::Result<Response> {
+		let url = request.full_url();
+		let response_type = request.response_type.clone().unwrap_or(ResponseType::Json);
+		let is_get = request.method.eq_ignore_ascii_case("GET");
+
+		let headers = &mut request.headers.get_or_insert_with(Default::default).0;
+		self.prepare_headers(&url, headers);
+
+		let cache = self
+			.cache
+			.as_ref()
+			.filter(|_| use_cache && is_get && !headers.contains_key(header::RANGE));
+		let cached = cache.and_then(|cache| cache.get(&url));
+		if let Some(cached) = &cached {
+			cached.add_validators(headers);
+		}
 
-		if let Some(query) = request.query {
-			request_builder = request_builder.params(&query);
+		let response = self.send_request(request).await?;
+
+		let cache = match cache {
+			Some(cache) => cache,
+			None => return Ok(response)
+		};
+		if let Some(cached) = cached.filter(|_| response.status() == StatusCode::NOT_MODIFIED) {
+			return Ok(Response(response_type, ResponseBody::Cached(cached)));
+		}
+		if !CachedResponse::is_cacheable(response.status(), response.headers()) {
+			return Ok(response);
 		}
 
-		if let Some(headers) = &request.headers {
-			for (name, value) in headers.0.iter() {
-				request_builder = request_builder.header(name, value);
+		let status = response.status();
+		let headers = response.headers().clone();
+		let cached = CachedResponse {
+			url,
+			status,
+			headers,
+			body: response.bytes().await?.data
+		};
+		// the cache is best-effort: failing to write it must not fail the request
+		let _ = cache.put(&cached);
+		Ok(Response(response_type, ResponseBody::Cached(cached)))
+	}
+
+	/// Adds the default headers and, with attohttpc, the cookies of the jar to the headers of a request.
+	#[allow(unused_variables)]
+	fn prepare_headers(&self, url: &Url, headers: &mut header::HeaderMap) {
+		for (name, value) in &self.default_headers {
+			if !headers.contains_key(name) {
+				headers.insert(name.clone(), value.clone());
 			}
 		}
+		#[cfg(not(feature = "reqwest-client"))]
+		if let Some(cookies) = &self.cookies {
+			cookies.add_request_cookies(url, headers);
+		}
+	}
+}
 
-		if let Some(max_redirections) = self.0.max_redirections {
+#[cfg(not(feature = "reqwest-client"))]
+impl Client {
+	/// Creates an [`attohttpc::RequestBuilder`] honoring the redirection and certificate options of the client.
+	fn request_builder(&self, method: Method, url: &Url) -> crate::api::Result<attohttpc::RequestBuilder> {
+		let mut request_builder = attohttpc::RequestBuilder::try_new(method, url)?;
+
+		if let Some(max_redirections) = self.inner.max_redirections {
 			if max_redirections == 0 {
 				request_builder = request_builder.follow_redirects(false);
 			} else {
@@ -164,6 +346,69 @@ impl Client {
 			}
 		}
 
+		for pem in &self.inner.root_certificates {
+			let certificate = native_tls::Certificate::from_pem(pem).map_err(|e| crate::api::Error::Tls(e.to_string()))?;
+			request_builder = request_builder.add_root_certificate(certificate);
+		}
+
+		Ok(request_builder)
+	}
+
+	fn store_cookies(&self, url: &Url, headers: &header::HeaderMap) -> crate::api::Result<()> {
+		if let Some(cookies) = &self.cookies {
+			cookies.store_response_cookies(url, headers)?;
+		}
+		Ok(())
+	}
+
+	/// Sends a request. With a cookie jar, redirections are followed here rather than by attohttpc, so the cookies
+	/// set by every hop are stored and sent.
+	async fn send_request(&self, mut request: HttpRequestBuilder) -> crate::api::Result<Response> {
+		let cookies = match &self.cookies {
+			Some(cookies) => cookies,
+			None => return self.send_once(request).await
+		};
+
+		let max_redirections = self.inner.max_redirections.unwrap_or(DEFAULT_MAX_REDIRECTIONS);
+		let mut redirections = 0;
+		loop {
+			let next = request.clone();
+			let response = self.send_once(request).await?;
+			cookies.store_response_cookies(response.url(), response.headers())?;
+
+			let location = response
+				.headers()
+				.get(header::LOCATION)
+				.and_then(|location| location.to_str().ok())
+				.filter(|_| response.status().is_redirection() && redirections < max_redirections);
+			let url = match location {
+				Some(location) => response.url().join(location)?,
+				None => return Ok(response)
+			};
+			redirections += 1;
+			request = redirect(next, response.status(), url);
+			cookies.add_request_cookies(&request.url, &mut request.headers.get_or_insert_with(Default::default).0);
+		}
+	}
+
+	async fn send_once(&self, mut request: HttpRequestBuilder) -> crate::api::Result<Response> {
+		let method = Method::from_bytes(request.method.to_uppercase().as_bytes())?;
+
+		let mut request_builder = self.request_builder(method, &request.url)?;
+		if self.cookies.is_some() {
+			request_builder = request_builder.follow_redirects(false);
+		}
+
+		if let Some(query) = request.query {
+			request_builder = request_builder.params(&query);
+		}
+
+		if let Some(headers) = &request.headers {
+			for (name, value) in headers.0.iter() {
+				request_builder = request_builder.header(name, value);
+			}
+		}
+
 		if let Some(timeout) = request.timeout {
 			request_builder = request_builder.timeout(timeout);
 		}
@@ -232,19 +477,44 @@ impl Client {
 			request_builder.send()?
 		};
 
-		Ok(Response(request.response_type.unwrap_or(ResponseType::Json), response, request.url))
+		Ok(Response(request.response_type.unwrap_or(ResponseType::Json), ResponseBody::Network(response, request.url)))
+	}
+}
+
+/// The maximum number of redirections attohttpc follows by default.
+#[cfg(not(feature = "reqwest-client"))]
+const DEFAULT_MAX_REDIRECTIONS: usize = 5;
+
+/// Turns a request into the request following a redirection to the given URL.
+#[cfg(not(feature = "reqwest-client"))]
+fn redirect(mut request: HttpRequestBuilder, status: StatusCode, url: Url) -> HttpRequestBuilder {
+	let headers = &mut request.headers.get_or_insert_with(Default::default).0;
+	let is_head = request.method.eq_ignore_ascii_case("HEAD");
+	let is_post = request.method.eq_ignore_ascii_case("POST");
+	// https://fetch.spec.whatwg.org/#http-redirect-fetch
+	if (status == StatusCode::SEE_OTHER && !is_head) || ((status == StatusCode::MOVED_PERMANENTLY || status == StatusCode::FOUND) && is_post) {
+		request.method = "GET".into();
+		request.body = None;
+		headers.remove(header::CONTENT_TYPE);
+		headers.remove(header::CONTENT_LENGTH);
 	}
+	if url.origin() != request.url.origin() {
+		headers.remove(header::AUTHORIZATION);
+	}
+	// the cookies are added back from the jar for the new URL
+	headers.remove(header::COOKIE);
+	request.url = url;
+	// the query is part of the redirection URL
+	request.query = None;
+	request
 }
 
 #[cfg(feature = "reqwest-client")]
 impl Client {
-	/// Executes an HTTP request
-	///
-	/// # Examples
-	pub async fn send(&self, request: HttpRequestBuilder) -> crate::api::Result<Response> {
+	async fn send_request(&self, request: HttpRequestBuilder) -> crate::api::Result<Response> {
 		let method = Method::from_bytes(request.method.to_uppercase().as_bytes())?;
 
-		let mut request_builder = self.0.request(method, request.url.as_str());
+		let mut request_builder = self.inner.request(method, request.url.as_str());
 
 		if let Some(query) = request.query {
 			request_builder = request_builder.query(&query);
@@ -315,9 +585,9 @@ impl Client {
 
 		let http_request = request_builder.build()?;
 
-		let response = self.0.execute(http_request).await?;
+		let response = self.inner.execute(http_request).await?;
 
-		Ok(Response(request.response_type.unwrap_or(ResponseType::Json), response))
+		Ok(Response(request.response_type.unwrap_or(ResponseType::Json), ResponseBody::Network(response)))
 	}
 }
 
@@ -357,7 +627,7 @@ impl TryFrom<FilePart> for Vec<u8> {
 }
 
 /// [`FormBody`] data types.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
 #[non_exhaustive]
 pub enum FormPart {
@@ -378,7 +648,7 @@ pub enum FormPart {
 }
 
 /// Form body definition.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct FormBody(pub(crate) HashMap<String, FormPart>);
 
 impl FormBody {
@@ -389,7 +659,7 @@ impl FormBody {
 }
 
 /// A body for the request.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(tag = "type", content = "payload")]
 #[non_exhaustive]
 pub enum Body {
@@ -404,8 +674,8 @@ pub enum Body {
 }
 
 /// A set of HTTP headers.
-#[derive(Debug, Default)]
-pub struct HeaderMap(header::HeaderMap);
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMap(pub(crate) header::HeaderMap);
 
 impl<'de> Deserialize<'de> for HeaderMap {
 	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -442,7 +712,7 @@ impl<'de> Deserialize<'de> for HeaderMap {
 /// 	}
 /// }
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpRequestBuilder {
 	/// The request method (GET, POST, PUT, DELETE, PATCH, HEAD, OPTIONS,
@@ -526,43 +796,92 @@ impl HttpRequestBuilder {
 		self.response_type = Some(response_type);
 		self
 	}
+
+	/// The URL with the query parameters appended, in a stable order.
+	fn full_url(&self) -> Url {
+		let mut url = self.url.clone();
+		if let Some(query) = &self.query {
+			let mut params: Vec<_> = query.iter().collect();
+			params.sort();
+			url.query_pairs_mut().extend_pairs(params);
+		}
+		url
+	}
 }
 
 /// The HTTP response.
-#[cfg(feature = "reqwest-client")]
 #[derive(Debug)]
-pub struct Response(ResponseType, reqwest::Response);
-/// The HTTP response.
-#[cfg(not(feature = "reqwest-client"))]
+pub struct Response(ResponseType, ResponseBody);
+
 #[derive(Debug)]
-pub struct Response(ResponseType, attohttpc::Response, Url);
+enum ResponseBody {
+	/// A response received from the server.
+	#[cfg(feature = "reqwest-client")]
+	Network(reqwest::Response),
+	/// A response received from the server.
+	#[cfg(not(feature = "reqwest-client"))]
+	Network(attohttpc::Response, Url),
+	/// A response served from the cache of the client.
+	Cached(CachedResponse)
+}
 
 impl Response {
 	/// Get the [`StatusCode`] of the response.
 	pub fn status(&self) -> StatusCode {
-		self.1.status()
+		match &self.1 {
+			#[cfg(feature = "reqwest-client")]
+			ResponseBody::Network(response) => response.status(),
+			#[cfg(not(feature = "reqwest-client"))]
+			ResponseBody::Network(response, _) => response.status(),
+			ResponseBody::Cached(response) => response.status
+		}
 	}
 
 	/// Get the headers of the response.
 	pub fn headers(&self) -> &header::HeaderMap {
-		self.1.headers()
+		match &self.1 {
+			#[cfg(feature = "reqwest-client")]
+			ResponseBody::Network(response) => response.headers(),
+			#[cfg(not(feature = "reqwest-client"))]
+			ResponseBody::Network(response, _) => response.headers(),
+			ResponseBody::Cached(response) => &response.headers
+		}
+	}
+
+	/// Get the URL of the response.
+	pub fn url(&self) -> &Url {
+		match &self.1 {
+			#[cfg(feature = "reqwest-client")]
+			ResponseBody::Network(response) => response.url(),
+			#[cfg(not(feature = "reqwest-client"))]
+			ResponseBody::Network(_, url) => url,
+			ResponseBody::Cached(response) => &response.url
+		}
 	}
 
 	/// Reads the response as raw bytes.
 	pub async fn bytes(self) -> crate::api::Result<RawResponse> {
 		let status = self.status().as_u16();
-		#[cfg(feature = "reqwest-client")]
-		let data = self.1.bytes().await?.to_vec();
-		#[cfg(not(feature = "reqwest-client"))]
-		let data = self.1.bytes()?;
+		let data = match self.1 {
+			#[cfg(feature = "reqwest-client")]
+			ResponseBody::Network(response) => response.bytes().await?.to_vec(),
+			#[cfg(not(feature = "reqwest-client"))]
+			ResponseBody::Network(response, _) => response.bytes()?,
+			ResponseBody::Cached(response) => response.body
+		};
 		Ok(RawResponse { status, data })
 	}
 
 	#[cfg(not(feature = "reqwest-client"))]
 	#[allow(dead_code)]
-	pub(crate) fn reader(self) -> attohttpc::ResponseReader {
-		let (_, _, reader) = self.1.split();
-		reader
+	pub(crate) fn reader(self) -> Box<dyn std::io::Read + Send> {
+		match self.1 {
+			ResponseBody::Network(response, _) => {
+				let (_, _, reader) = response.split();
+				Box::new(reader)
+			}
+			ResponseBody::Cached(response) => Box::new(std::io::Cursor::new(response.body))
+		}
 	}
 
 	/// Convert the response body into a stream of [`bytes::Bytes`].
@@ -585,48 +904,53 @@ impl Response {
 	/// ```
 	#[cfg(feature = "reqwest-client")]
 	#[allow(dead_code)]
-	pub(crate) fn bytes_stream(self) -> impl futures_util::Stream<Item = crate::api::Result<bytes::Bytes>> {
+	pub(crate) fn bytes_stream(self) -> futures_util::stream::BoxStream<'static, crate::api::Result<bytes::Bytes>> {
 		use futures_util::StreamExt;
-		self.1.bytes_stream().map(|res| res.map_err(Into::into))
+		match self.1 {
+			ResponseBody::Network(response) => response.bytes_stream().map(|res| res.map_err(Into::into)).boxed(),
+			ResponseBody::Cached(response) => futures_util::stream::once(async move { Ok(bytes::Bytes::from(response.body)) }).boxed()
+		}
 	}
 
 	/// Reads the response.
 	///
 	/// Note that the body is serialized to a [`Value`].
 	pub async fn read(self) -> crate::api::Result<ResponseData> {
-		#[cfg(feature = "reqwest-client")]
-		let url = self.1.url().clone();
-		#[cfg(not(feature = "reqwest-client"))]
-		let url = self.2;
+		let url = self.url().clone();
 
 		let mut headers = HashMap::new();
 		let mut raw_headers = HashMap::new();
-		for (name, value) in self.1.headers() {
+		for (name, value) in self.headers() {
 			headers.insert(name.as_str().to_string(), String::from_utf8(value.as_bytes().to_vec())?);
 			raw_headers.insert(
 				name.as_str().to_string(),
-				self.1
-					.headers()
+				self.headers()
 					.get_all(name)
 					.into_iter()
 					.map(|v| String::from_utf8(v.as_bytes().to_vec()).map_err(Into::into))
 					.collect::<crate::api::Result<Vec<String>>>()?
 			);
 		}
-		let status = self.1.status().as_u16();
-
-		#[cfg(feature = "reqwest-client")]
-		let data = match self.0 {
-			ResponseType::Json => self.1.json().await?,
-			ResponseType::Text => Value::String(self.1.text().await?),
-			ResponseType::Binary => serde_json::to_value(&self.1.bytes().await?)?
-		};
+		let status = self.status().as_u16();
 
-		#[cfg(not(feature = "reqwest-client"))]
-		let data = match self.0 {
-			ResponseType::Json => self.1.json()?,
-			ResponseType::Text => Value::String(self.1.text()?),
-			ResponseType::Binary => serde_json::to_value(self.1.bytes()?)?
+		let data = match self.1 {
+			#[cfg(feature = "reqwest-client")]
+			ResponseBody::Network(response) => match self.0 {
+				ResponseType::Json => response.json().await?,
+				ResponseType::Text => Value::String(response.text().await?),
+				ResponseType::Binary => serde_json::to_value(&response.bytes().await?)?
+			},
+			#[cfg(not(feature = "reqwest-client"))]
+			ResponseBody::Network(response, _) => match self.0 {
+				ResponseType::Json => response.json()?,
+				ResponseType::Text => Value::String(response.text()?),
+				ResponseType::Binary => serde_json::to_value(response.bytes()?)?
+			},
+			ResponseBody::Cached(response) => match self.0 {
+				ResponseType::Json => serde_json::from_slice(&response.body)?,
+				ResponseType::Text => Value::String(String::from_utf8_lossy(&response.body).into_owned()),
+				ResponseType::Binary => serde_json::to_value(&response.body)?
+			}
 		};
 
 		Ok(ResponseData {
@@ -666,18 +990,691 @@ pub struct ResponseData {
 	pub data: Value
 }
 
+/// The size of the chunks files are uploaded in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The progress of a download or an upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+	/// Number of bytes transferred, including the bytes of a resumed download that were already on disk.
+	pub transferred: u64,
+	/// Total number of bytes, if known.
+	pub total: Option<u64>
+}
+
+/// The hash algorithm of a [`Checksum`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ChecksumAlgorithm {
+	/// SHA-256.
+	Sha256,
+	/// SHA-512.
+	Sha512
+}
+
+/// The expected checksum of a downloaded file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Checksum {
+	/// The hash algorithm.
+	pub algorithm: ChecksumAlgorithm,
+	/// The hex-encoded hash.
+	pub value: String
+}
+
+impl Checksum {
+	/// Verifies the checksum of a file.
+	fn verify(&self, path: &Path) -> crate::api::Result<()> {
+		fn digest<D: sha2::Digest + std::io::Write>(path: &Path) -> crate::api::Result<String> {
+			let mut hasher = D::new();
+			std::io::copy(&mut File::open(path)?, &mut hasher)?;
+			Ok(hasher.finalize().iter().fold(String::new(), |mut hex, byte| {
+				let _ = write!(hex, "{byte:02x}");
+				hex
+			}))
+		}
+
+		let actual = match self.algorithm {
+			ChecksumAlgorithm::Sha256 => digest::<sha2::Sha256>(path)?,
+			ChecksumAlgorithm::Sha512 => digest::<sha2::Sha512>(path)?
+		};
+		if actual.eq_ignore_ascii_case(&self.value) {
+			Ok(())
+		} else {
+			Err(crate::api::Error::ChecksumMismatch { expected: self.value.clone(), actual })
+		}
+	}
+}
+
+/// The options of [`Client::download`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadOptions {
+	/// Whether to resume the download with a `Range` request if the file already exists.
+	#[serde(default)]
+	pub resume: bool,
+	/// The checksum the downloaded file must match. The file is removed if it doesn't.
+	pub checksum: Option<Checksum>
+}
+
+impl DownloadOptions {
+	/// Creates the default options.
+	pub fn new() -> Self {
+		Default::default()
+	}
+
+	/// Sets whether to resume the download if the file already exists.
+	#[must_use]
+	pub fn resume(mut self, resume: bool) -> Self {
+		self.resume = resume;
+		self
+	}
+
+	/// Sets the checksum the downloaded file must match.
+	#[must_use]
+	pub fn checksum(mut self, algorithm: ChecksumAlgorithm, value: impl Into<String>) -> Self {
+		self.checksum.replace(Checksum { algorithm, value: value.into() });
+		self
+	}
+}
+
+/// Parses the start offset and the total length of a `Content-Range` header.
+fn parse_content_range(value: &HeaderValue) -> Option<(u64, Option<u64>)> {
+	let (range, total) = value.to_str().ok()?.strip_prefix("bytes ")?.split_once('/')?;
+	let (start, _) = range.split_once('-')?;
+	let total = if total == "*" { None } else { Some(total.parse().ok()?) };
+	Some((start.parse().ok()?, total))
+}
+
+impl Client {
+	/// Downloads the response of a request to a file, calling `on_progress` as chunks are written.
+	///
+	/// If [`DownloadOptions::resume`] is set and the file exists, only the missing bytes are requested, and the
+	/// download starts over if the server does not support ranges.
+	///
+	/// # Examples
+	///
+	/// ```rust,no_run
+	/// use millennium::api::http::{ChecksumAlgorithm, ClientBuilder, DownloadOptions, HttpRequestBuilder};
+	/// async fn run() -> millennium::api::Result<()> {
+	/// 	let client = ClientBuilder::new().build()?;
+	/// 	let request = HttpRequestBuilder::new("GET", "https://example.com/dataset.tar.gz")?;
+	/// 	let options = DownloadOptions::new().resume(true).checksum(ChecksumAlgorithm::Sha256, "9f86d081...");
+	/// 	client
+	/// 		.download(request, "dataset.tar.gz", options, |progress| {
+	/// 			println!("{}/{:?}", progress.transferred, progress.total)
+	/// 		})
+	/// 		.await
+	/// }
+	/// ```
+	pub async fn download<F: FnMut(Progress)>(
+		&self,
+		mut request: HttpRequestBuilder,
+		path: impl AsRef<Path>,
+		options: DownloadOptions,
+		mut on_progress: F
+	) -> crate::api::Result<()> {
+		let path = path.as_ref();
+		let offset = if options.resume { std::fs::metadata(path).map(|m| m.len()).unwrap_or_default() } else { 0 };
+		if offset > 0 {
+			request = request.header(header::RANGE, format!("bytes={offset}-"))?;
+		}
+		// ranges must refer to the bytes written to disk
+		if !request.headers.as_ref().map_or(false, |h| h.0.contains_key(header::ACCEPT_ENCODING)) {
+			request = request.header(header::ACCEPT_ENCODING, "identity")?;
+		}
+
+		let response = self.send_with_cache(request, false).await?;
+		let status = response.status();
+		let content_length: Option<u64> = response
+			.headers()
+			.get(header::CONTENT_LENGTH)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.parse().ok());
+		let (file, mut transferred, total) = if offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE {
+			// the file was already complete
+			on_progress(Progress {
+				transferred: offset,
+				total: Some(offset)
+			});
+			(None, offset, Some(offset))
+		} else if offset > 0 && status == StatusCode::PARTIAL_CONTENT {
+			match response.headers().get(header::CONTENT_RANGE).and_then(parse_content_range) {
+				Some((start, total)) if start == offset => (Some(OpenOptions::new().append(true).open(path)?), offset, total),
+				_ => return Err(crate::api::Error::InvalidContentRange)
+			}
+		} else if status.is_success() {
+			(Some(File::create(path)?), 0, content_length)
+		} else {
+			return Err(crate::api::Error::HttpStatus(status));
+		};
+
+		if let Some(file) = file {
+			#[cfg(feature = "reqwest-client")]
+			{
+				use futures_util::StreamExt;
+				use tokio::io::AsyncWriteExt;
+				let mut file = tokio::fs::File::from_std(file);
+				let mut stream = response.bytes_stream();
+				while let Some(chunk) = stream.next().await {
+					let chunk = chunk?;
+					file.write_all(&chunk).await?;
+					transferred += chunk.len() as u64;
+					on_progress(Progress { transferred, total });
+				}
+				file.sync_all().await?;
+			}
+			#[cfg(not(feature = "reqwest-client"))]
+			{
+				use std::io::{Read, Write};
+				// attohttpc reads block, so the body is written on a blocking thread which reports its progress back
+				let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+				let mut reader = response.reader();
+				let writer = tokio::task::spawn_blocking(move || {
+					let mut file = file;
+					let mut buf = vec![0; CHUNK_SIZE];
+					loop {
+						let read = reader.read(&mut buf)?;
+						if read == 0 {
+							break;
+						}
+						file.write_all(&buf[..read])?;
+						let _ = progress_tx.send(read as u64);
+					}
+					file.sync_all()
+				});
+				while let Some(read) = progress_rx.recv().await {
+					transferred += read;
+					on_progress(Progress { transferred, total });
+				}
+				writer.await.map_err(std::io::Error::from)??;
+			}
+
+			// the last event must mark the transfer as done even if the server did not send its length
+			if total.is_none() {
+				on_progress(Progress {
+					transferred,
+					total: Some(transferred)
+				});
+			}
+		}
+
+		if let Some(checksum) = &options.checksum {
+			if let Err(e) = checksum.verify(path) {
+				let _ = std::fs::remove_file(path);
+				return Err(e);
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A file uploaded as a `multipart/form-data` request by [`Client::upload`].
+///
+/// The file is streamed from disk, so it is never loaded into memory.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultipartUpload {
+	/// The path of the file.
+	pub path: PathBuf,
+	/// The name of the form field of the file. Defaults to `file`.
+	#[serde(default = "default_upload_field")]
+	pub field: String,
+	/// The file name sent to the server. Defaults to the name of the file.
+	pub file_name: Option<String>,
+	/// The MIME type of the file. Defaults to `application/octet-stream`.
+	pub mime: Option<String>,
+	/// Text fields sent along with the file.
+	#[serde(default)]
+	pub fields: HashMap<String, String>
+}
+
+fn default_upload_field() -> String {
+	"file".into()
+}
+
+impl MultipartUpload {
+	/// Creates an upload of the given file.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self {
+			path: path.into(),
+			field: default_upload_field(),
+			file_name: None,
+			mime: None,
+			fields: Default::default()
+		}
+	}
+
+	/// Sets the name of the form field of the file.
+	#[must_use]
+	pub fn field(mut self, field: impl Into<String>) -> Self {
+		self.field = field.into();
+		self
+	}
+
+	/// Sets the file name sent to the server.
+	#[must_use]
+	pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+		self.file_name.replace(file_name.into());
+		self
+	}
+
+	/// Sets the MIME type of the file.
+	#[must_use]
+	pub fn mime(mut self, mime: impl Into<String>) -> Self {
+		self.mime.replace(mime.into());
+		self
+	}
+
+	/// Adds a text field.
+	#[must_use]
+	pub fn text(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+		self.fields.insert(name.into(), value.into());
+		self
+	}
+
+	fn encode(self) -> crate::api::Result<MultipartBody> {
+		// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#multipart-form-data
+		fn escape(name: &str) -> String {
+			name.replace('"', "%22").replace('\r', "%0D").replace('\n', "%0A")
+		}
+
+		let boundary = format!("----MillenniumBoundary{:032x}", rand::random::<u128>());
+		let file_len = std::fs::metadata(&self.path)?.len();
+		let file_name = self
+			.file_name
+			.or_else(|| self.path.file_name().map(|name| name.to_string_lossy().into_owned()))
+			.unwrap_or_default();
+
+		let mut head = String::new();
+		for (name, value) in &self.fields {
+			let _ = write!(head, "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{value}\r\n", escape(name));
+		}
+		let _ = write!(
+			head,
+			"--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+			escape(&self.field),
+			escape(&file_name),
+			self.mime.as_deref().unwrap_or("application/octet-stream")
+		);
+
+		Ok(MultipartBody {
+			content_type: format!("multipart/form-data; boundary={boundary}"),
+			head: head.into_bytes(),
+			path: self.path,
+			file_len,
+			tail: format!("\r\n--{boundary}--\r\n").into_bytes()
+		})
+	}
+}
+
+/// An encoded [`MultipartUpload`], with the file contents between `head` and `tail`.
+struct MultipartBody {
+	content_type: String,
+	head: Vec<u8>,
+	path: PathBuf,
+	file_len: u64,
+	tail: Vec<u8>
+}
+
+impl MultipartBody {
+	fn len(&self) -> u64 {
+		(self.head.len() + self.tail.len()) as u64 + self.file_len
+	}
+}
+
+#[cfg(not(feature = "reqwest-client"))]
+struct ProgressBody<F> {
+	body: MultipartBody,
+	on_progress: F
+}
+
+#[cfg(not(feature = "reqwest-client"))]
+impl<F: FnMut(Progress)> attohttpc::body::Body for ProgressBody<F> {
+	fn kind(&mut self) -> std::io::Result<attohttpc::body::BodyKind> {
+		Ok(attohttpc::body::BodyKind::KnownLength(self.body.len()))
+	}
+
+	fn write<W: std::io::Write>(&mut self, mut writer: W) -> std::io::Result<()> {
+		use std::io::Read;
+		let Self { body, on_progress } = self;
+		let total = Some(body.len());
+		let mut transferred = 0;
+		let mut write = |chunk: &[u8]| {
+			writer.write_all(chunk)?;
+			transferred += chunk.len() as u64;
+			on_progress(Progress { transferred, total });
+			Ok::<_, std::io::Error>(())
+		};
+
+		write(&body.head)?;
+		let mut file = File::open(&body.path)?.take(body.file_len);
+		let mut buf = vec![0; CHUNK_SIZE];
+		loop {
+			let read = file.read(&mut buf)?;
+			if read == 0 {
+				break;
+			}
+			write(&buf[..read])?;
+		}
+		write(&body.tail)
+	}
+}
+
+#[cfg(not(feature = "reqwest-client"))]
+impl Client {
+	/// Uploads a file as a `multipart/form-data` request, calling `on_progress` as chunks are sent.
+	///
+	/// The body of the request is replaced by the upload.
+	pub async fn upload<F: FnMut(Progress) + Send + 'static>(
+		&self,
+		mut request: HttpRequestBuilder,
+		upload: MultipartUpload,
+		on_progress: F
+	) -> crate::api::Result<Response> {
+		let body = upload.encode()?;
+		self.prepare_headers(&request.url, &mut request.headers.get_or_insert_with(Default::default).0);
+		let method = Method::from_bytes(request.method.to_uppercase().as_bytes())?;
+
+		let mut request_builder = self.request_builder(method, &request.url)?;
+
+		if let Some(query) = request.query {
+			request_builder = request_builder.params(&query);
+		}
+
+		if let Some(headers) = &request.headers {
+			for (name, value) in headers.0.iter() {
+				request_builder = request_builder.header(name, value);
+			}
+		}
+
+		if let Some(timeout) = request.timeout {
+			request_builder = request_builder.timeout(timeout);
+		}
+
+		let response = request_builder
+			.try_header(header::CONTENT_TYPE, body.content_type.as_str())?
+			.body(ProgressBody { body, on_progress })
+			.send()?;
+		self.store_cookies(&request.url, response.headers())?;
+
+		Ok(Response(request.response_type.unwrap_or(ResponseType::Json), ResponseBody::Network(response, request.url)))
+	}
+}
+
+#[cfg(feature = "reqwest-client")]
+impl Client {
+	/// Uploads a file as a `multipart/form-data` request, calling `on_progress` as chunks are sent.
+	///
+	/// The body of the request is replaced by the upload.
+	pub async fn upload<F: FnMut(Progress) + Send + 'static>(
+		&self,
+		mut request: HttpRequestBuilder,
+		upload: MultipartUpload,
+		on_progress: F
+	) -> crate::api::Result<Response> {
+		use futures_util::{stream, StreamExt, TryStreamExt};
+		use tokio::io::AsyncReadExt;
+
+		let body = upload.encode()?;
+		self.prepare_headers(&request.url, &mut request.headers.get_or_insert_with(Default::default).0);
+		let total = body.len();
+		let file = tokio::fs::File::open(&body.path).await?.take(body.file_len);
+		let file = stream::try_unfold(file, |mut file| async move {
+			let mut buf = vec![0; CHUNK_SIZE];
+			let read = file.read(&mut buf).await?;
+			buf.truncate(read);
+			Ok::<_, std::io::Error>((read > 0).then(|| (bytes::Bytes::from(buf), file)))
+		});
+		let (head, tail) = (bytes::Bytes::from(body.head), bytes::Bytes::from(body.tail));
+		// the body stream must be `Sync`
+		let on_progress = std::sync::Mutex::new(on_progress);
+		let mut transferred = 0;
+		let stream = stream::once(async move { Ok(head) })
+			.chain(file)
+			.chain(stream::once(async move { Ok(tail) }))
+			.inspect_ok(move |chunk| {
+				transferred += chunk.len() as u64;
+				(on_progress.get_mut().unwrap())(Progress { transferred, total: Some(total) });
+			});
+
+		let method = Method::from_bytes(request.method.to_uppercase().as_bytes())?;
+
+		let mut request_builder = self.inner.request(method, request.url.as_str());
+
+		if let Some(query) = request.query {
+			request_builder = request_builder.query(&query);
+		}
+
+		if let Some(timeout) = request.timeout {
+			request_builder = request_builder.timeout(timeout);
+		}
+
+		if let Some(headers) = request.headers {
+			request_builder = request_builder.headers(headers.0);
+		}
+
+		let response = request_builder
+			.header(header::CONTENT_TYPE, body.content_type)
+			.header(header::CONTENT_LENGTH, total)
+			.body(reqwest::Body::wrap_stream(stream))
+			.send()
+			.await?;
+
+		Ok(Response(request.response_type.unwrap_or(ResponseType::Json), ResponseBody::Network(response)))
+	}
+}
+
 #[cfg(test)]
 mod test {
+	use mockito::Matcher;
 	use quickcheck::{Arbitrary, Gen};
 
-	use super::ClientBuilder;
+	use super::{header, parse_content_range, ChecksumAlgorithm, ClientBuilder, DownloadOptions, HttpRequestBuilder, MultipartUpload, Progress, ResponseType};
+	use crate::api::Error;
 
 	impl Arbitrary for ClientBuilder {
 		fn arbitrary(g: &mut Gen) -> Self {
 			Self {
 				max_redirections: Option::arbitrary(g),
-				connect_timeout: Option::arbitrary(g)
+				connect_timeout: Option::arbitrary(g),
+				..Default::default()
 			}
 		}
 	}
+
+	#[test]
+	fn parses_content_ranges() {
+		assert_eq!(parse_content_range(&"bytes 4-9/10".parse().unwrap()), Some((4, Some(10))));
+		assert_eq!(parse_content_range(&"bytes 4-9/*".parse().unwrap()), Some((4, None)));
+		assert_eq!(parse_content_range(&"bytes */10".parse().unwrap()), None);
+	}
+
+	#[tokio::test]
+	async fn resumes_downloads() {
+		let _mock = mockito::mock("GET", "/resume")
+			.match_header("range", "bytes=4-")
+			.with_status(206)
+			.with_header("content-range", "bytes 4-9/10")
+			.with_body("456789")
+			.create();
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("file");
+		std::fs::write(&path, "0123").unwrap();
+
+		let client = ClientBuilder::new().build().unwrap();
+		let request = HttpRequestBuilder::new("GET", format!("{}/resume", mockito::server_url())).unwrap();
+		let options = DownloadOptions::new()
+			.resume(true)
+			.checksum(ChecksumAlgorithm::Sha256, "84d89877f0d4041efb6bf91a16f0248f2fd573e6af05c19f96bedb9f882f7882");
+		let mut progress = Vec::new();
+		client.download(request, &path, options, |p| progress.push(p)).await.unwrap();
+
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "0123456789");
+		assert_eq!(progress.last(), Some(&Progress { transferred: 10, total: Some(10) }));
+	}
+
+	#[tokio::test]
+	async fn completes_downloads_of_unknown_length() {
+		use std::io::Write;
+		let _mock = mockito::mock("GET", "/chunked")
+			.with_body_from_fn(|w| w.write_all(b"0123456789"))
+			.create();
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("file");
+
+		let client = ClientBuilder::new().build().unwrap();
+		let request = HttpRequestBuilder::new("GET", format!("{}/chunked", mockito::server_url())).unwrap();
+		let mut progress = Vec::new();
+		client
+			.download(request, &path, DownloadOptions::new(), |p| progress.push(p))
+			.await
+			.unwrap();
+
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "0123456789");
+		assert!(progress[..progress.len() - 1].iter().all(|p| p.total.is_none()));
+		assert_eq!(progress.last(), Some(&Progress { transferred: 10, total: Some(10) }));
+	}
+
+	#[tokio::test]
+	async fn removes_downloads_failing_checksum() {
+		let _mock = mockito::mock("GET", "/corrupted").with_body("0123456789").create();
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("file");
+
+		let client = ClientBuilder::new().build().unwrap();
+		let request = HttpRequestBuilder::new("GET", format!("{}/corrupted", mockito::server_url())).unwrap();
+		let options = DownloadOptions::new().checksum(ChecksumAlgorithm::Sha256, "0".repeat(64));
+		let result = client.download(request, &path, options, |_| {}).await;
+
+		assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+		assert!(!path.exists());
+	}
+
+	#[tokio::test]
+	async fn uploads_multipart_files() {
+		let _mock = mockito::mock("POST", "/upload")
+			.match_header("content-type", Matcher::Regex("^multipart/form-data; boundary=".into()))
+			.match_body(Matcher::AllOf(vec![
+				Matcher::Regex("name=\"kind\"\r\n\r\ndataset\r\n".into()),
+				Matcher::Regex("name=\"upload\"; filename=\"data.txt\"\r\nContent-Type: text/plain\r\n\r\nhello\r\n".into())
+			]))
+			.with_body("{}")
+			.create();
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("data.txt");
+		std::fs::write(&path, "hello").unwrap();
+
+		let client = ClientBuilder::new().build().unwrap();
+		let request = HttpRequestBuilder::new("POST", format!("{}/upload", mockito::server_url())).unwrap();
+		let upload = MultipartUpload::new(&path).field("upload").mime("text/plain").text("kind", "dataset");
+		let (tx, rx) = std::sync::mpsc::channel();
+		let response = client.upload(request, upload, move |p| tx.send(p).unwrap()).await.unwrap();
+
+		assert_eq!(response.status(), 200);
+		let progress: Vec<Progress> = rx.try_iter().collect();
+		let last = progress.last().unwrap();
+		assert_eq!(Some(last.transferred), last.total);
+	}
+
+	#[test]
+	fn validates_tls_options() {
+		let dir = tempfile::tempdir().unwrap();
+		let invalid = dir.path().join("invalid.pem");
+		std::fs::write(&invalid, "not a certificate").unwrap();
+
+		assert!(ClientBuilder::new().root_certificate("test/api/root.pem").build().is_ok());
+		assert!(ClientBuilder::new().root_certificate(&invalid).build().is_err());
+		assert!(ClientBuilder::new().root_certificate(dir.path().join("missing.pem")).build().is_err());
+		#[cfg(not(feature = "reqwest-client"))]
+		assert!(matches!(ClientBuilder::new().identity(&invalid, "").build(), Err(Error::Tls(_))));
+		#[cfg(feature = "reqwest-client")]
+		assert!(ClientBuilder::new().identity(&invalid, "").build().is_err());
+	}
+
+	#[tokio::test]
+	async fn caches_responses_by_query() {
+		// a request revalidating the response of another page would be served the wrong body
+		let _not_modified = mockito::mock("GET", "/items")
+			.match_query(Matcher::Any)
+			.match_header("if-none-match", Matcher::Any)
+			.with_status(304)
+			.create();
+		let dir = tempfile::tempdir().unwrap();
+		let client = ClientBuilder::new().cache_dir(dir.path()).build().unwrap();
+		for page in ["1", "2"] {
+			let _mock = mockito::mock("GET", "/items")
+				.match_query(Matcher::UrlEncoded("page".into(), page.into()))
+				.match_header("if-none-match", Matcher::Missing)
+				.with_header("etag", &format!("\"{page}\""))
+				.with_body(page)
+				.create();
+			let request = HttpRequestBuilder::new("GET", format!("{}/items", mockito::server_url()))
+				.unwrap()
+				.query([("page".to_string(), page.to_string())].into())
+				.response_type(ResponseType::Text);
+			let response = client.send(request).await.unwrap().read().await.unwrap();
+			assert_eq!(response.data, page);
+		}
+	}
+
+	#[tokio::test]
+	async fn stores_cookies_of_redirections() {
+		let _login = mockito::mock("POST", "/login")
+			.with_status(303)
+			.with_header("set-cookie", "session=1; Path=/")
+			.with_header("location", "/home")
+			.create();
+		let home = mockito::mock("GET", "/home")
+			.match_header("cookie", "session=1")
+			.with_body("home")
+			.create();
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("cookies.json");
+
+		let client = ClientBuilder::new().cookie_jar(&path).build().unwrap();
+		let request = HttpRequestBuilder::new("POST", format!("{}/login", mockito::server_url()))
+			.unwrap()
+			.response_type(ResponseType::Text);
+		let response = client.send(request).await.unwrap().read().await.unwrap();
+
+		assert_eq!(response.status, 200);
+		assert_eq!(response.data, "home");
+		home.assert();
+	}
+
+	#[tokio::test]
+	async fn revalidates_cached_responses() {
+		let _not_modified = mockito::mock("GET", "/cached")
+			.match_header("x-app", "test")
+			.match_header("if-none-match", "\"v1\"")
+			.with_status(304)
+			.create();
+		let fresh = mockito::mock("GET", "/cached")
+			.match_header("x-app", "test")
+			.match_header("if-none-match", Matcher::Missing)
+			.with_header("etag", "\"v1\"")
+			.with_body("cached body")
+			.expect(1)
+			.create();
+		let dir = tempfile::tempdir().unwrap();
+
+		let mut default_headers = header::HeaderMap::new();
+		default_headers.insert("x-app", "test".parse().unwrap());
+		let client = ClientBuilder::new()
+			.default_headers(default_headers)
+			.cache_dir(dir.path())
+			.build()
+			.unwrap();
+		for _ in 0..2 {
+			let request = HttpRequestBuilder::new("GET", format!("{}/cached", mockito::server_url()))
+				.unwrap()
+				.response_type(ResponseType::Text);
+			let response = client.send(request).await.unwrap().read().await.unwrap();
+			assert_eq!(response.status, 200);
+			assert_eq!(response.data, "cached body");
+		}
+		fresh.assert();
+	}
 }
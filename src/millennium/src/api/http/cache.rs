@@ -0,0 +1,175 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::header::{self, HeaderMap, HeaderName, HeaderValue};
+
+/// A disk cache of `GET` responses, revalidated with their `ETag` and `Last-Modified` headers.
+#[derive(Debug, Clone)]
+pub(crate) struct Cache {
+	dir: PathBuf
+}
+
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+	url: Url,
+	status: u16,
+	headers: Vec<(String, String)>
+}
+
+/// A response read from or stored in the [`Cache`].
+#[derive(Debug)]
+pub(crate) struct CachedResponse {
+	pub(crate) url: Url,
+	pub(crate) status: StatusCode,
+	pub(crate) headers: HeaderMap,
+	pub(crate) body: Vec<u8>
+}
+
+impl CachedResponse {
+	/// Whether a response can be cached, i.e. it is successful, can be revalidated, doesn't forbid caching and doesn't
+	/// vary with the request headers, as the cache is only keyed by URL.
+	pub(crate) fn is_cacheable(status: StatusCode, headers: &HeaderMap) -> bool {
+		status == StatusCode::OK
+			&& (headers.contains_key(header::ETAG) || headers.contains_key(header::LAST_MODIFIED))
+			&& !headers.contains_key(header::VARY)
+			&& !headers
+				.get_all(header::CACHE_CONTROL)
+				.iter()
+				.filter_map(|value| value.to_str().ok())
+				.any(|value| value.to_ascii_lowercase().contains("no-store"))
+	}
+
+	/// Adds the conditional headers revalidating this response to a request, unless they are already set.
+	pub(crate) fn add_validators(&self, headers: &mut HeaderMap) {
+		for (validator, condition) in [(header::ETAG, header::IF_NONE_MATCH), (header::LAST_MODIFIED, header::IF_MODIFIED_SINCE)] {
+			if let Some(value) = self.headers.get(&validator) {
+				if !headers.contains_key(&condition) {
+					headers.insert(condition, value.clone());
+				}
+			}
+		}
+	}
+}
+
+impl Cache {
+	pub(crate) fn new(dir: PathBuf) -> Self {
+		Self { dir }
+	}
+
+	fn paths(&self, url: &Url) -> (PathBuf, PathBuf) {
+		let key = Sha256::digest(url.as_str().as_bytes()).iter().fold(String::new(), |mut hex, byte| {
+			let _ = write!(hex, "{byte:02x}");
+			hex
+		});
+		(self.dir.join(format!("{key}.json")), self.dir.join(format!("{key}.body")))
+	}
+
+	/// Reads the cached response of a URL. Unreadable entries are treated as missing.
+	pub(crate) fn get(&self, url: &Url) -> Option<CachedResponse> {
+		let (metadata_path, body_path) = self.paths(url);
+		let metadata: Metadata = serde_json::from_slice(&std::fs::read(metadata_path).ok()?).ok()?;
+		if &metadata.url != url {
+			return None;
+		}
+
+		let mut headers = HeaderMap::new();
+		for (name, value) in metadata.headers {
+			headers.append(HeaderName::from_bytes(name.as_bytes()).ok()?, HeaderValue::from_str(&value).ok()?);
+		}
+		Some(CachedResponse {
+			url: metadata.url,
+			status: StatusCode::from_u16(metadata.status).ok()?,
+			headers,
+			body: std::fs::read(body_path).ok()?
+		})
+	}
+
+	/// Stores a response.
+	pub(crate) fn put(&self, response: &CachedResponse) -> crate::api::Result<()> {
+		let (metadata_path, body_path) = self.paths(&response.url);
+		let metadata = Metadata {
+			url: response.url.clone(),
+			status: response.status.as_u16(),
+			headers: response
+				.headers
+				.iter()
+				.filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+				.collect()
+		};
+
+		std::fs::create_dir_all(&self.dir)?;
+		// the metadata is written last, so an interrupted write is never read
+		let _ = std::fs::remove_file(&metadata_path);
+		std::fs::write(body_path, &response.body)?;
+		std::fs::write(metadata_path, serde_json::to_vec(&metadata)?)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		super::header::{self, HeaderMap},
+		Cache, CachedResponse
+	};
+
+	#[test]
+	fn stores_responses() {
+		let dir = tempfile::tempdir().unwrap();
+		let cache = Cache::new(dir.path().to_path_buf());
+		let url = "https://example.com/data.json".parse().unwrap();
+		assert!(cache.get(&url).is_none());
+
+		let mut headers = HeaderMap::new();
+		headers.insert(header::ETAG, "\"v1\"".parse().unwrap());
+		assert!(CachedResponse::is_cacheable(http::StatusCode::OK, &headers));
+		cache
+			.put(&CachedResponse {
+				url: url.clone(),
+				status: http::StatusCode::OK,
+				headers,
+				body: b"{}".to_vec()
+			})
+			.unwrap();
+
+		let cached = cache.get(&url).unwrap();
+		assert_eq!(cached.body, b"{}");
+		let mut request = HeaderMap::new();
+		cached.add_validators(&mut request);
+		assert_eq!(request[header::IF_NONE_MATCH], "\"v1\"");
+		assert!(!request.contains_key(header::IF_MODIFIED_SINCE));
+	}
+
+	#[test]
+	fn skips_uncacheable_responses() {
+		let mut headers = HeaderMap::new();
+		assert!(!CachedResponse::is_cacheable(http::StatusCode::OK, &headers));
+		headers.insert(header::LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+		assert!(!CachedResponse::is_cacheable(http::StatusCode::NOT_FOUND, &headers));
+		headers.insert(header::CACHE_CONTROL, "private, no-store".parse().unwrap());
+		assert!(!CachedResponse::is_cacheable(http::StatusCode::OK, &headers));
+
+		let mut headers = HeaderMap::new();
+		headers.insert(header::ETAG, "\"v1\"".parse().unwrap());
+		headers.insert(header::VARY, "Accept-Language".parse().unwrap());
+		assert!(!CachedResponse::is_cacheable(http::StatusCode::OK, &headers));
+	}
+}
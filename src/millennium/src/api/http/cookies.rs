@@ -0,0 +1,198 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+	collections::HashMap,
+	fs::File,
+	io::BufReader,
+	path::PathBuf,
+	sync::{Arc, Mutex, Weak}
+};
+
+use cookie_store::CookieStore;
+use once_cell::sync::Lazy;
+use url::Url;
+
+use super::header::{self, HeaderMap, HeaderValue};
+
+/// The jars in use, by path, so that clients persisting cookies in the same file share them.
+static JARS: Lazy<Mutex<HashMap<PathBuf, Weak<CookieJar>>>> = Lazy::new(Default::default);
+
+/// A cookie jar persisted to a JSON file.
+///
+/// Session cookies are only kept in memory.
+#[derive(Debug)]
+pub(crate) struct CookieJar {
+	path: PathBuf,
+	store: Mutex<CookieStore>
+}
+
+impl CookieJar {
+	/// Returns the jar persisted in the given file, loading it if no other client uses it.
+	pub(crate) fn shared(path: PathBuf) -> crate::api::Result<Arc<Self>> {
+		let mut jars = JARS.lock().unwrap();
+		if let Some(jar) = jars.get(&path).and_then(Weak::upgrade) {
+			return Ok(jar);
+		}
+
+		let jar = Arc::new(Self::load(path.clone())?);
+		jars.retain(|_, jar| jar.strong_count() > 0);
+		jars.insert(path, Arc::downgrade(&jar));
+		Ok(jar)
+	}
+
+	/// Loads the jar from its file, if it exists.
+	fn load(path: PathBuf) -> crate::api::Result<Self> {
+		let store = if path.exists() {
+			CookieStore::load_json(BufReader::new(File::open(&path)?)).map_err(|e| crate::api::Error::CookieJar(e.to_string()))?
+		} else {
+			CookieStore::default()
+		};
+		Ok(Self { path, store: Mutex::new(store) })
+	}
+
+	/// The value of the `Cookie` header of a request to the given URL, if any cookie matches it.
+	fn request_cookies(&self, url: &Url) -> Option<HeaderValue> {
+		let cookies = self
+			.store
+			.lock()
+			.unwrap()
+			.get_request_values(url)
+			.map(|(name, value)| format!("{name}={value}"))
+			.collect::<Vec<_>>()
+			.join("; ");
+		HeaderValue::from_str(&cookies).ok().filter(|cookies| !cookies.is_empty())
+	}
+
+	/// Adds the `Cookie` header to a request, unless it is already set.
+	#[cfg_attr(feature = "reqwest-client", allow(dead_code))]
+	pub(crate) fn add_request_cookies(&self, url: &Url, headers: &mut HeaderMap) {
+		if headers.contains_key(header::COOKIE) {
+			return;
+		}
+		if let Some(cookies) = self.request_cookies(url) {
+			headers.insert(header::COOKIE, cookies);
+		}
+	}
+
+	/// Stores the cookies set by a response, and persists them.
+	#[cfg_attr(feature = "reqwest-client", allow(dead_code))]
+	pub(crate) fn store_response_cookies(&self, url: &Url, headers: &HeaderMap) -> crate::api::Result<()> {
+		self.store_cookies(url, &mut headers.get_all(header::SET_COOKIE).iter())
+	}
+
+	fn store_cookies(&self, url: &Url, cookies: &mut dyn Iterator<Item = &HeaderValue>) -> crate::api::Result<()> {
+		let mut store = self.store.lock().unwrap();
+		let mut changed = false;
+		for cookie in cookies.filter_map(|value| value.to_str().ok()) {
+			changed |= store.parse(cookie, url).is_ok();
+		}
+		if changed {
+			// the lock is held while saving, so concurrent saves can't interleave
+			self.save(&store)?;
+		}
+		Ok(())
+	}
+
+	/// Persists the cookies, replacing the file atomically so it is never left half-written.
+	fn save(&self, store: &CookieStore) -> crate::api::Result<()> {
+		let dir = match self.path.parent() {
+			Some(parent) if parent.components().count() > 0 => parent,
+			_ => std::path::Path::new(".")
+		};
+		std::fs::create_dir_all(dir)?;
+		let mut file = tempfile::NamedTempFile::new_in(dir)?;
+		store.save_json(&mut file).map_err(|e| crate::api::Error::CookieJar(e.to_string()))?;
+		file.as_file().sync_all()?;
+		file.persist(&self.path).map_err(|e| e.error)?;
+		Ok(())
+	}
+}
+
+#[cfg(feature = "reqwest-client")]
+impl reqwest::cookie::CookieStore for CookieJar {
+	fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+		// reqwest can't report errors here: the cookies are kept in memory even if saving them fails
+		let _ = self.store_cookies(url, cookie_headers);
+	}
+
+	fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+		self.request_cookies(url)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use super::{
+		super::header::{self, HeaderMap},
+		CookieJar
+	};
+
+	#[test]
+	fn persists_cookies() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("cookies.json");
+		let url = "https://example.com/account".parse().unwrap();
+
+		let jar = CookieJar::shared(path.clone()).unwrap();
+		let mut headers = HeaderMap::new();
+		headers.append(header::SET_COOKIE, "session=1; Path=/".parse().unwrap());
+		headers.append(header::SET_COOKIE, "token=2; Path=/; Max-Age=3600".parse().unwrap());
+		jar.store_response_cookies(&url, &headers).unwrap();
+
+		let mut request = HeaderMap::new();
+		jar.add_request_cookies(&url, &mut request);
+		let cookies = request[header::COOKIE].to_str().unwrap();
+		assert!(cookies.contains("session=1") && cookies.contains("token=2"));
+
+		// session cookies are not persisted
+		let jar = CookieJar::load(path).unwrap();
+		let mut request = HeaderMap::new();
+		jar.add_request_cookies(&url, &mut request);
+		assert_eq!(request[header::COOKIE], "token=2");
+
+		let mut request = HeaderMap::new();
+		jar.add_request_cookies(&"https://example.org".parse().unwrap(), &mut request);
+		assert!(request.is_empty());
+	}
+
+	#[test]
+	fn shares_jars_by_path() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("cookies.json");
+		let url = "https://example.com".parse().unwrap();
+
+		let first = CookieJar::shared(path.clone()).unwrap();
+		let second = CookieJar::shared(path.clone()).unwrap();
+		assert!(Arc::ptr_eq(&first, &second));
+
+		let mut headers = HeaderMap::new();
+		headers.append(header::SET_COOKIE, "a=1; Max-Age=3600".parse().unwrap());
+		first.store_response_cookies(&url, &headers).unwrap();
+		let mut headers = HeaderMap::new();
+		headers.append(header::SET_COOKIE, "b=2; Max-Age=3600".parse().unwrap());
+		second.store_response_cookies(&url, &headers).unwrap();
+
+		// both cookies were saved, and no temporary file was left behind
+		drop((first, second));
+		let jar = CookieJar::shared(path).unwrap();
+		let mut request = HeaderMap::new();
+		jar.add_request_cookies(&url, &mut request);
+		let cookies = request[header::COOKIE].to_str().unwrap();
+		assert!(cookies.contains("a=1") && cookies.contains("b=2"));
+		assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+	}
+}
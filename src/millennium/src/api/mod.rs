@@ -16,6 +16,9 @@
 
 //! The Millennium API interface.
 
+#[cfg(all(desktop, feature = "autostart"))]
+#[cfg_attr(doc_cfg, doc(cfg(all(desktop, feature = "autostart"))))]
+pub mod autostart;
 #[cfg(all(desktop, feature = "dialog"))]
 #[cfg_attr(doc_cfg, doc(cfg(all(desktop, feature = "dialog"))))]
 pub mod dialog;
@@ -27,10 +30,16 @@ pub mod http;
 pub mod ipc;
 pub mod path;
 pub mod process;
+#[cfg(feature = "secrets")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "secrets")))]
+pub mod secrets;
 #[cfg(feature = "shell-open-api")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "shell-open-api")))]
 pub mod shell;
 pub mod version;
+#[cfg(feature = "websocket-api")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "websocket-api")))]
+pub mod websocket;
 
 #[cfg(feature = "cli")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "cli")))]
@@ -28,6 +28,8 @@ mod command;
 #[cfg(feature = "process-command-api")]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "process-command-api")))]
 pub use command::*;
+#[cfg(all(feature = "process-command-api", target_os = "linux"))]
+mod sandbox;
 
 /// Finds the current running binary's path.
 ///
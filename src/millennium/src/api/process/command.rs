@@ -22,25 +22,179 @@ use std::os::unix::process::ExitStatusExt;
 use std::os::windows::process::CommandExt;
 use std::{
 	collections::HashMap,
+	fmt,
 	io::{BufReader, Write},
 	path::PathBuf,
 	process::{Command as StdCommand, Stdio},
-	sync::{Arc, Mutex, RwLock},
-	thread::spawn
+	sync::{Arc, Condvar, Mutex, RwLock},
+	thread::spawn,
+	time::{Duration, Instant}
 };
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x0800_0000;
 
 pub use encoding_rs::Encoding;
-use millennium_utils::platform;
-use os_pipe::{pipe, PipeReader, PipeWriter};
-use serde::Serialize;
+use millennium_utils::{config::ShellSandboxProfile, platform};
+use os_pipe::{pipe, PipeReader};
+use serde::{Deserialize, Serialize};
 use shared_child::SharedChild;
 
 use crate::async_runtime::{block_on as block_on_task, channel, Receiver, Sender};
 
-type ChildStore = Arc<Mutex<HashMap<u32, Arc<SharedChild>>>>;
+/// How long [`terminate_children`] lets child processes exit before killing
+/// them when the app exits, restarts or is updated.
+pub const TERMINATE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Kills a spawned process.
+#[derive(Debug)]
+enum Killer {
+	/// A process with piped stdio.
+	Piped(Arc<SharedChild>),
+	/// A process attached to a pseudo-terminal.
+	#[cfg(feature = "process-pty")]
+	Pty(Mutex<Box<dyn portable_pty::ChildKiller + Send + Sync>>)
+}
+
+/// A spawned process, which can be signaled and waited on from any thread.
+///
+/// On Unix, the process leads its own process group, so signals reach the
+/// processes it spawned too. On Windows, its process tree is walked instead.
+#[derive(Debug)]
+struct Process {
+	pid: u32,
+	killer: Killer,
+	status: Mutex<Option<ExitStatus>>,
+	exited: Condvar
+}
+
+impl Process {
+	fn new(pid: u32, killer: Killer) -> Self {
+		Self {
+			pid,
+			killer,
+			status: Default::default(),
+			exited: Condvar::new()
+		}
+	}
+
+	/// Waits for the process to exit, then reaps it with `reap` and records
+	/// its exit status, waking up the threads waiting on it.
+	///
+	/// Its pid, and so its process group id, can be reused once it is reaped.
+	/// On Unix, it is reaped with the status lock held, so the process group
+	/// is only signaled while the status is unset. On Windows, the handle of
+	/// the child keeps its pid from being reused.
+	fn reap(&self, reap: impl FnOnce() -> std::io::Result<ExitStatus>) -> std::io::Result<ExitStatus> {
+		#[cfg(unix)]
+		let exited = wait_exited(self.pid);
+		#[cfg(windows)]
+		let exited = false;
+		let (mut status, result) = if exited {
+			let status = self.status.lock().unwrap();
+			(status, reap())
+		} else {
+			let result = reap();
+			(self.status.lock().unwrap(), result)
+		};
+		status.replace(result.as_ref().map_or_else(|_| ExitStatus::default(), |status| *status));
+		drop(status);
+		self.exited.notify_all();
+		result
+	}
+
+	/// Waits for the process to exit, for at most `timeout` if set.
+	fn wait(&self, timeout: Option<Duration>) -> Option<ExitStatus> {
+		let status = self.status.lock().unwrap();
+		let status = match timeout {
+			Some(timeout) => self.exited.wait_timeout_while(status, timeout, |status| status.is_none()).unwrap().0,
+			None => self.exited.wait_while(status, |status| status.is_none()).unwrap()
+		};
+		*status
+	}
+
+	/// Asks the process and its descendants to exit, unless it has exited.
+	fn request_exit(&self) {
+		// the pid may have been reused once the process has exited
+		let status = self.status.lock().unwrap();
+		if status.is_some() {
+			return;
+		}
+		#[cfg(unix)]
+		signal_group(self.pid, libc::SIGTERM);
+		#[cfg(windows)]
+		taskkill(self.pid, false);
+	}
+
+	/// Kills the process and its descendants, unless it has exited.
+	fn kill(&self) -> std::io::Result<()> {
+		// the pid may have been reused once the process has exited
+		let status = self.status.lock().unwrap();
+		if status.is_some() {
+			return Ok(());
+		}
+		#[cfg(unix)]
+		signal_group(self.pid, libc::SIGKILL);
+		#[cfg(windows)]
+		taskkill(self.pid, true);
+
+		match &self.killer {
+			Killer::Piped(child) => child.kill(),
+			#[cfg(feature = "process-pty")]
+			Killer::Pty(killer) => killer.lock().unwrap().kill()
+		}
+	}
+
+	/// Asks the process and its descendants to exit, killing them if the
+	/// process is still running after `timeout`.
+	fn terminate(&self, timeout: Duration) -> std::io::Result<ExitStatus> {
+		self.request_exit();
+		if self.wait(Some(timeout)).is_none() {
+			self.kill()?;
+		}
+		Ok(self.wait(None).unwrap_or_default())
+	}
+}
+
+/// Blocks until the given child process exits, without reaping it. Returns
+/// whether it exited, rather than failing to wait on it.
+#[cfg(unix)]
+fn wait_exited(pid: u32) -> bool {
+	loop {
+		let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+		if unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, libc::WEXITED | libc::WNOWAIT) } == 0 {
+			return true;
+		}
+		if std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+			return false;
+		}
+	}
+}
+
+/// Sends a signal to the process group led by the given process.
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) {
+	unsafe {
+		libc::kill(-(pid as libc::pid_t), signal);
+	}
+}
+
+/// Ends the process tree of the given process, forcefully if `force` is set.
+#[cfg(windows)]
+fn taskkill(pid: u32, force: bool) {
+	let mut command = StdCommand::new("taskkill");
+	if force {
+		command.arg("/F");
+	}
+	let _ = command
+		.args(["/T", "/PID", &pid.to_string()])
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.creation_flags(CREATE_NO_WINDOW)
+		.status();
+}
+
+type ChildStore = Arc<Mutex<HashMap<u32, Arc<Process>>>>;
 
 fn commands() -> &'static ChildStore {
 	use once_cell::sync::Lazy;
@@ -48,8 +202,8 @@ fn commands() -> &'static ChildStore {
 	&STORE
 }
 
-/// Kills all child processes created with [`Command`].
-/// By default it's called before the [`crate::App`] exits.
+/// Kills all child processes created with [`Command`], along with the
+/// processes they spawned.
 pub fn kill_children() {
 	let commands = commands().lock().unwrap();
 	let children = commands.values();
@@ -58,6 +212,23 @@ pub fn kill_children() {
 	}
 }
 
+/// Asks all child processes created with [`Command`] and the processes they
+/// spawned to exit, killing those still running after `timeout`.
+///
+/// By default it's called with [`TERMINATE_TIMEOUT`] before the
+/// [`crate::App`] exits or restarts, and before the updater replaces it.
+pub fn terminate_children(timeout: Duration) {
+	let children: Vec<Arc<Process>> = commands().lock().unwrap().values().cloned().collect();
+	for child in &children {
+		child.request_exit();
+	}
+
+	let deadline = Instant::now() + timeout;
+	for child in &children {
+		let _ = child.terminate(deadline.saturating_duration_since(Instant::now()));
+	}
+}
+
 /// Payload for the [`CommandEvent::Terminated`] command event.
 #[derive(Debug, Clone, Serialize)]
 pub struct TerminatedPayload {
@@ -76,6 +247,9 @@ pub enum CommandEvent {
 	Stderr(String),
 	/// Stdout bytes until a newline (\n) or carriage return (\r) is found.
 	Stdout(String),
+	/// Raw bytes read from the pseudo-terminal of a command spawned with
+	/// [`Command::pty`].
+	Data(Vec<u8>),
 	/// An error happened waiting for the command to finish or converting the
 	/// stdout/stderr bytes to an UTF-8 string.
 	Error(String),
@@ -91,39 +265,168 @@ pub struct Command {
 	env_clear: bool,
 	env: HashMap<String, String>,
 	current_dir: Option<PathBuf>,
-	encoding: Option<&'static Encoding>
+	encoding: Option<&'static Encoding>,
+	#[cfg_attr(not(feature = "process-pty"), allow(dead_code))]
+	pty: Option<PtySize>,
+	timeout: Option<Duration>,
+	limits: Limits,
+	env_allowlist: Option<Vec<String>>,
+	cwd_jail: Option<PathBuf>
+}
+
+/// Resource limits and sandbox applied to a process before it executes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Limits {
+	max_memory: Option<u64>,
+	max_cpu_time: Option<u64>,
+	sandbox: ShellSandboxProfile
+}
+
+impl Limits {
+	fn is_empty(&self) -> bool {
+		self.max_memory.is_none() && self.max_cpu_time.is_none() && self.sandbox == ShellSandboxProfile::None
+	}
+
+	/// Spawns the process in its own process group, and applies the limits.
+	#[cfg(unix)]
+	fn apply(self, command: &mut StdCommand) -> crate::api::Result<()> {
+		use std::os::unix::process::CommandExt;
+
+		#[cfg(target_os = "linux")]
+		let sandbox = super::sandbox::Sandbox::new(self.sandbox)?;
+		#[cfg(not(target_os = "linux"))]
+		if self.sandbox != ShellSandboxProfile::None {
+			return Err(crate::api::Error::Command("sandbox profiles are only supported on Linux".into()));
+		}
+
+		let rlimits = [(libc::RLIMIT_AS, self.max_memory), (libc::RLIMIT_CPU, self.max_cpu_time)];
+		// SAFETY: the hook runs between `fork` and `exec`, and only makes system calls
+		unsafe {
+			command.pre_exec(move || {
+				if libc::setpgid(0, 0) != 0 {
+					return Err(std::io::Error::last_os_error());
+				}
+				for (resource, limit) in rlimits {
+					if let Some(limit) = limit {
+						let limit = libc::rlimit {
+							rlim_cur: limit as libc::rlim_t,
+							rlim_max: limit as libc::rlim_t
+						};
+						if libc::setrlimit(resource, &limit) != 0 {
+							return Err(std::io::Error::last_os_error());
+						}
+					}
+				}
+				#[cfg(target_os = "linux")]
+				sandbox.enter()?;
+				Ok(())
+			});
+		}
+		Ok(())
+	}
+
+	#[cfg(not(unix))]
+	fn apply(self, _command: &mut StdCommand) -> crate::api::Result<()> {
+		if self.is_empty() {
+			Ok(())
+		} else {
+			Err(crate::api::Error::Command("resource limits and sandbox profiles are only supported on Unix".into()))
+		}
+	}
+}
+
+/// The size of a pseudo-terminal, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct PtySize {
+	/// Number of rows.
+	pub rows: u16,
+	/// Number of columns.
+	pub cols: u16
+}
+
+impl Default for PtySize {
+	fn default() -> Self {
+		Self { rows: 24, cols: 80 }
+	}
+}
+
+#[cfg(feature = "process-pty")]
+impl From<PtySize> for portable_pty::PtySize {
+	fn from(size: PtySize) -> Self {
+		Self {
+			rows: size.rows,
+			cols: size.cols,
+			..Default::default()
+		}
+	}
 }
 
 /// Spawned child process.
-#[derive(Debug)]
 pub struct CommandChild {
-	inner: Arc<SharedChild>,
-	stdin_writer: PipeWriter
+	inner: Arc<Process>,
+	pid: u32,
+	stdin_writer: Box<dyn Write + Send>,
+	#[cfg(feature = "process-pty")]
+	pty: Option<Box<dyn portable_pty::MasterPty + Send>>
+}
+
+impl fmt::Debug for CommandChild {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CommandChild")
+			.field("inner", &self.inner)
+			.field("pid", &self.pid)
+			.finish_non_exhaustive()
+	}
 }
 
 impl CommandChild {
-	/// Writes to process stdin.
+	/// Writes to process stdin, or to the pseudo-terminal if the command was
+	/// spawned with [`Command::pty`].
 	pub fn write(&mut self, buf: &[u8]) -> crate::api::Result<()> {
 		self.stdin_writer.write_all(buf)?;
+		self.stdin_writer.flush()?;
 		Ok(())
 	}
 
-	/// Sends a kill signal to the child.
+	/// Resizes the pseudo-terminal of a command spawned with [`Command::pty`].
+	#[cfg(feature = "process-pty")]
+	#[cfg_attr(doc_cfg, doc(cfg(feature = "process-pty")))]
+	pub fn resize(&self, size: PtySize) -> crate::api::Result<()> {
+		match &self.pty {
+			Some(pty) => pty.resize(size.into()).map_err(|e| crate::api::Error::Command(e.to_string())),
+			None => Err(crate::api::Error::Command("the command was not spawned in a pseudo-terminal".into()))
+		}
+	}
+
+	/// Kills the child and the processes it spawned.
 	pub fn kill(self) -> crate::api::Result<()> {
 		self.inner.kill()?;
 		Ok(())
 	}
 
+	/// Asks the child and the processes it spawned to exit, with `SIGTERM` on
+	/// Unix and `taskkill` on Windows, killing them if the child is still
+	/// running after `timeout`. Returns the exit status of the child.
+	pub fn terminate(self, timeout: Duration) -> crate::api::Result<ExitStatus> {
+		Ok(self.inner.terminate(timeout)?)
+	}
+
+	/// Waits for the child to exit, returning its exit status.
+	pub fn wait(&self) -> crate::api::Result<ExitStatus> {
+		Ok(self.inner.wait(None).unwrap_or_default())
+	}
+
 	/// Returns the process pid.
 	pub fn pid(&self) -> u32 {
-		self.inner.id()
+		self.pid
 	}
 }
 
 /// Describes the result of a process after it has terminated.
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct ExitStatus {
-	code: Option<i32>
+	code: Option<i32>,
+	signal: Option<i32>
 }
 
 impl ExitStatus {
@@ -132,6 +435,12 @@ impl ExitStatus {
 		self.code
 	}
 
+	/// Returns the signal that terminated the process, if any. Always `None`
+	/// on Windows.
+	pub fn signal(&self) -> Option<i32> {
+		self.signal
+	}
+
 	/// Returns true if exit status is zero. Signal termination is not
 	/// considered a success, and success is defined as a zero exit status.
 	pub fn success(&self) -> bool {
@@ -189,7 +498,12 @@ impl Command {
 			env_clear: false,
 			env: Default::default(),
 			current_dir: None,
-			encoding: None
+			encoding: None,
+			pty: None,
+			timeout: None,
+			limits: Default::default(),
+			env_allowlist: None,
+			cwd_jail: None
 		}
 	}
 
@@ -243,6 +557,78 @@ impl Command {
 		self
 	}
 
+	/// Kills the process if it is still running after the given duration.
+	#[must_use]
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout.replace(timeout);
+		self
+	}
+
+	/// Limits the size of the virtual memory of the process, in bytes.
+	///
+	/// Only supported on Unix; spawning fails on other platforms.
+	#[must_use]
+	pub fn max_memory(mut self, bytes: u64) -> Self {
+		self.limits.max_memory.replace(bytes);
+		self
+	}
+
+	/// Limits the CPU time the process can use. The process is killed once it
+	/// is exceeded.
+	///
+	/// Only supported on Unix; spawning fails on other platforms.
+	#[must_use]
+	pub fn max_cpu_time(mut self, cpu_time: Duration) -> Self {
+		self.limits.max_cpu_time.replace(cpu_time.as_secs().max(1));
+		self
+	}
+
+	/// Clears the environment of the child process, except for the given
+	/// variables. Variables set with [`Self::envs`] are also dropped unless
+	/// they are in the list.
+	#[must_use]
+	pub fn env_allowlist<I, S>(mut self, vars: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>
+	{
+		self.env_allowlist = Some(vars.into_iter().map(|var| var.as_ref().to_string()).collect());
+		self
+	}
+
+	/// Requires the working directory of the child process to be inside of
+	/// the given directory, which is also its default working directory.
+	///
+	/// A relative [`Self::current_dir`] is resolved against it.
+	#[must_use]
+	pub fn cwd_jail(mut self, dir: PathBuf) -> Self {
+		self.cwd_jail.replace(dir);
+		self
+	}
+
+	/// Spawns the child process in the given sandbox.
+	///
+	/// Sandboxes other than [`ShellSandboxProfile::None`] are only supported
+	/// on Linux; spawning fails on other platforms.
+	#[must_use]
+	pub fn sandbox(mut self, profile: ShellSandboxProfile) -> Self {
+		self.limits.sandbox = profile;
+		self
+	}
+
+	/// Spawns the command in a pseudo-terminal of the given size, so it
+	/// behaves as if run from an interactive terminal.
+	///
+	/// The output of the command is emitted as raw [`CommandEvent::Data`]
+	/// bytes instead of lines, and stderr is merged into it.
+	#[cfg(feature = "process-pty")]
+	#[cfg_attr(doc_cfg, doc(cfg(feature = "process-pty")))]
+	#[must_use]
+	pub fn pty(mut self, size: PtySize) -> Self {
+		self.pty.replace(size);
+		self
+	}
+
 	/// Spawns the command.
 	///
 	/// # Examples
@@ -267,8 +653,46 @@ impl Command {
 	/// });
 	/// ```
 	pub fn spawn(self) -> crate::api::Result<(Receiver<CommandEvent>, CommandChild)> {
+		let command = self.confine()?;
+
+		#[cfg(feature = "process-pty")]
+		if let Some(size) = command.pty {
+			return command.spawn_pty(size);
+		}
+
+		command.spawn_piped()
+	}
+
+	/// Applies the environment allowlist and the working directory jail.
+	fn confine(mut self) -> crate::api::Result<Self> {
+		if let Some(allowlist) = &self.env_allowlist {
+			let mut env: HashMap<String, String> = std::env::vars().filter(|(key, _)| allowlist.contains(key)).collect();
+			env.extend(self.env.drain().filter(|(key, _)| allowlist.contains(key)));
+			self.env = env;
+			self.env_clear = true;
+		}
+
+		if let Some(jail) = &self.cwd_jail {
+			let jail = jail.canonicalize()?;
+			let current_dir = match &self.current_dir {
+				Some(current_dir) => jail.join(current_dir).canonicalize()?,
+				None => jail.clone()
+			};
+			if !current_dir.starts_with(&jail) {
+				return Err(crate::api::Error::Command(format!("the working directory {} is outside of {}", current_dir.display(), jail.display())));
+			}
+			self.current_dir.replace(current_dir);
+		}
+
+		Ok(self)
+	}
+
+	fn spawn_piped(self) -> crate::api::Result<(Receiver<CommandEvent>, CommandChild)> {
 		let encoding = self.encoding;
+		let timeout = self.timeout;
+		let limits = self.limits;
 		let mut command: StdCommand = self.into();
+		limits.apply(&mut command)?;
 		let (stdout_reader, stdout_writer) = pipe()?;
 		let (stderr_reader, stderr_writer) = pipe()?;
 		let (stdin_reader, stdin_writer) = pipe()?;
@@ -281,7 +705,12 @@ impl Command {
 		let child_ = child.clone();
 		let guard = Arc::new(RwLock::new(()));
 
-		commands().lock().unwrap().insert(child.id(), child.clone());
+		let process = Arc::new(Process::new(child.id(), Killer::Piped(child.clone())));
+		commands().lock().unwrap().insert(child.id(), process.clone());
+		if let Some(timeout) = timeout {
+			kill_on_timeout(process.clone(), timeout);
+		}
+		let process_ = process.clone();
 
 		let (tx, rx) = channel(1);
 
@@ -289,17 +718,23 @@ impl Command {
 		spawn_pipe_reader(tx.clone(), guard.clone(), stderr_reader, CommandEvent::Stderr, encoding);
 
 		spawn(move || {
-			let _ = match child_.wait() {
+			let status = process_.reap(|| {
+				child_.wait().map(|status| ExitStatus {
+					code: status.code(),
+					#[cfg(windows)]
+					signal: None,
+					#[cfg(unix)]
+					signal: status.signal()
+				})
+			});
+			let _ = match status {
 				Ok(status) => {
 					let _l = guard.write().unwrap();
 					commands().lock().unwrap().remove(&child_.id());
 					block_on_task(async move {
 						tx.send(CommandEvent::Terminated(TerminatedPayload {
-							code: status.code(),
-							#[cfg(windows)]
-							signal: None,
-							#[cfg(unix)]
-							signal: status.signal()
+							code: status.code,
+							signal: status.signal
 						}))
 						.await
 					})
@@ -311,7 +746,88 @@ impl Command {
 			};
 		});
 
-		Ok((rx, CommandChild { inner: child, stdin_writer }))
+		Ok((
+			rx,
+			CommandChild {
+				inner: process,
+				pid: child.id(),
+				stdin_writer: Box::new(stdin_writer),
+				#[cfg(feature = "process-pty")]
+				pty: None
+			}
+		))
+	}
+
+	#[cfg(feature = "process-pty")]
+	fn spawn_pty(self, size: PtySize) -> crate::api::Result<(Receiver<CommandEvent>, CommandChild)> {
+		use portable_pty::ChildKiller;
+
+		if !self.limits.is_empty() {
+			return Err(crate::api::Error::Command("resource limits and sandbox profiles are not supported in PTY mode".into()));
+		}
+
+		let pty_error = |e: anyhow::Error| crate::api::Error::Command(e.to_string());
+
+		let pair = portable_pty::native_pty_system().openpty(size.into()).map_err(pty_error)?;
+		let mut command = portable_pty::CommandBuilder::new(&self.program);
+		command.args(&self.args);
+		if self.env_clear {
+			command.env_clear();
+		}
+		for (key, value) in &self.env {
+			command.env(key, value);
+		}
+		if let Some(current_dir) = &self.current_dir {
+			command.cwd(current_dir);
+		}
+
+		let mut child = pair.slave.spawn_command(command).map_err(pty_error)?;
+		// the reader only reaches EOF once every handle to the slave is closed
+		drop(pair.slave);
+		let pid = child.process_id().unwrap_or_default();
+		let reader = pair.master.try_clone_reader().map_err(pty_error)?;
+		let stdin_writer = pair.master.take_writer().map_err(pty_error)?;
+		let guard = Arc::new(RwLock::new(()));
+
+		let process = Arc::new(Process::new(pid, Killer::Pty(Mutex::new(child.clone_killer()))));
+		commands().lock().unwrap().insert(pid, process.clone());
+		if let Some(timeout) = self.timeout {
+			kill_on_timeout(process.clone(), timeout);
+		}
+		let process_ = process.clone();
+
+		let (tx, rx) = channel(1);
+
+		spawn_pty_reader(tx.clone(), guard.clone(), reader);
+
+		spawn(move || {
+			let status = process_.reap(|| {
+				child.wait().map(|status| ExitStatus {
+					code: Some(status.exit_code() as i32),
+					signal: None
+				})
+			});
+			let event = match status {
+				Ok(status) => CommandEvent::Terminated(TerminatedPayload {
+					code: status.code,
+					signal: status.signal
+				}),
+				Err(e) => CommandEvent::Error(e.to_string())
+			};
+			let _l = guard.write().unwrap();
+			commands().lock().unwrap().remove(&pid);
+			let _ = block_on_task(async move { tx.send(event).await });
+		});
+
+		Ok((
+			rx,
+			CommandChild {
+				inner: process,
+				pid,
+				stdin_writer,
+				pty: Some(pair.master)
+			}
+		))
 	}
 
 	/// Executes a command as a child process, waiting for it to finish and
@@ -325,17 +841,20 @@ impl Command {
 	/// ```
 	pub fn status(self) -> crate::api::Result<ExitStatus> {
 		let (mut rx, _child) = self.spawn()?;
-		let code = crate::async_runtime::safe_block_on(async move {
-			let mut code = None;
+		let status = crate::async_runtime::safe_block_on(async move {
+			let mut status = ExitStatus::default();
 			#[allow(clippy::collapsible_match)]
 			while let Some(event) = rx.recv().await {
 				if let CommandEvent::Terminated(payload) = event {
-					code = payload.code;
+					status = ExitStatus {
+						code: payload.code,
+						signal: payload.signal
+					};
 				}
 			}
-			code
+			status
 		});
-		Ok(ExitStatus { code })
+		Ok(status)
 	}
 
 	/// Executes the command as a child process, waiting for it to finish and
@@ -353,13 +872,16 @@ impl Command {
 		let (mut rx, _child) = self.spawn()?;
 
 		let output = crate::async_runtime::safe_block_on(async move {
-			let mut code = None;
+			let mut status = ExitStatus::default();
 			let mut stdout = String::new();
 			let mut stderr = String::new();
 			while let Some(event) = rx.recv().await {
 				match event {
 					CommandEvent::Terminated(payload) => {
-						code = payload.code;
+						status = ExitStatus {
+							code: payload.code,
+							signal: payload.signal
+						};
 					}
 					CommandEvent::Stdout(line) => {
 						stdout.push_str(line.as_str());
@@ -369,20 +891,28 @@ impl Command {
 						stderr.push_str(line.as_str());
 						stderr.push('\n');
 					}
+					CommandEvent::Data(data) => {
+						stdout.push_str(&String::from_utf8_lossy(&data));
+					}
 					CommandEvent::Error(_) => {}
 				}
 			}
-			Output {
-				status: ExitStatus { code },
-				stdout,
-				stderr
-			}
+			Output { status, stdout, stderr }
 		});
 
 		Ok(output)
 	}
 }
 
+/// Kills the process if it is still running once the timeout elapses.
+fn kill_on_timeout(process: Arc<Process>, timeout: Duration) {
+	spawn(move || {
+		if process.wait(Some(timeout)).is_none() {
+			let _ = process.kill();
+		}
+	});
+}
+
 fn spawn_pipe_reader<F: Fn(String) -> CommandEvent + Send + Copy + 'static>(
 	tx: Sender<CommandEvent>,
 	guard: Arc<RwLock<()>>,
@@ -423,6 +953,27 @@ fn spawn_pipe_reader<F: Fn(String) -> CommandEvent + Send + Copy + 'static>(
 	});
 }
 
+#[cfg(feature = "process-pty")]
+fn spawn_pty_reader(tx: Sender<CommandEvent>, guard: Arc<RwLock<()>>, mut reader: Box<dyn std::io::Read + Send>) {
+	spawn(move || {
+		let _lock = guard.read().unwrap();
+		let mut buf = vec![0; 4096];
+		loop {
+			match reader.read(&mut buf) {
+				Ok(0) => break,
+				Ok(n) => {
+					let tx_ = tx.clone();
+					let data = buf[..n].to_vec();
+					let _ = block_on_task(async move { tx_.send(CommandEvent::Data(data)).await });
+				}
+				Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+				// reading a pseudo-terminal fails with EIO once the child has exited on Linux
+				Err(_) => break
+			}
+		}
+	});
+}
+
 // tests for the commands functions.
 #[cfg(test)]
 mod test {
@@ -472,4 +1023,155 @@ mod test {
 			}
 		});
 	}
+
+	#[cfg(all(unix, feature = "process-pty"))]
+	#[test]
+	fn test_cmd_pty() {
+		let cmd = Command::new("sh").args(["-c", "test -t 1 && echo interactive"]).pty(PtySize::default());
+		let (mut rx, _) = cmd.spawn().unwrap();
+
+		crate::async_runtime::block_on(async move {
+			let mut output = Vec::new();
+			while let Some(event) = rx.recv().await {
+				match event {
+					CommandEvent::Terminated(payload) => {
+						assert_eq!(payload.code, Some(0));
+					}
+					CommandEvent::Data(data) => output.extend(data),
+					_ => {}
+				}
+			}
+			assert_eq!(String::from_utf8_lossy(&output).trim(), "interactive");
+		});
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn test_cmd_timeout() {
+		let cmd = Command::new("sleep").args(["10"]).timeout(Duration::from_millis(100));
+		let (mut rx, _) = cmd.spawn().unwrap();
+
+		crate::async_runtime::block_on(async move {
+			let mut terminated = false;
+			while let Some(event) = rx.recv().await {
+				if let CommandEvent::Terminated(payload) = event {
+					assert_eq!(payload.signal, Some(libc::SIGKILL));
+					terminated = true;
+				}
+			}
+			assert!(terminated);
+		});
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn test_cmd_terminate_tree() {
+		let (_rx, child) = Command::new("sh").args(["-c", "sleep 10 & sleep 10"]).spawn().unwrap();
+		let pid = child.pid();
+		// give the shell time to spawn its children
+		std::thread::sleep(Duration::from_millis(100));
+
+		let status = child.terminate(Duration::from_secs(1)).unwrap();
+		assert_eq!(status.signal(), Some(libc::SIGTERM));
+		// the orphaned children are reaped by init, so give it some time
+		let deadline = std::time::Instant::now() + Duration::from_secs(1);
+		while unsafe { libc::kill(-(pid as libc::pid_t), 0) } == 0 {
+			assert!(std::time::Instant::now() < deadline, "the process group is still running");
+			std::thread::sleep(Duration::from_millis(10));
+		}
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	fn test_cmd_max_memory() {
+		let output = Command::new("sh")
+			.args(["-c", "ulimit -v"])
+			.max_memory(64 * 1024 * 1024)
+			.output()
+			.unwrap();
+		assert_eq!(output.stdout.trim(), "65536");
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn test_cmd_max_cpu_time() {
+		let output = Command::new("sh")
+			.args(["-c", "ulimit -t"])
+			.max_cpu_time(Duration::from_secs(2))
+			.output()
+			.unwrap();
+		assert_eq!(output.stdout.trim(), "2");
+
+		// the process is signaled once it used up its CPU time
+		let output = Command::new("sh")
+			.args(["-c", "while :; do :; done"])
+			.max_cpu_time(Duration::from_secs(1))
+			.output()
+			.unwrap();
+		assert_eq!(output.status.signal(), Some(libc::SIGXCPU));
+	}
+
+	/// Runs `script` in the given sandbox. The sandbox tests are ignored by
+	/// default, as unprivileged user namespaces are disabled in some containers.
+	#[cfg(target_os = "linux")]
+	fn sandboxed_output(profile: ShellSandboxProfile, script: &str) -> Output {
+		Command::new("sh")
+			.args(["-c", script])
+			.sandbox(profile)
+			.output()
+			.expect("failed to spawn a sandboxed process, are unprivileged user namespaces enabled?")
+	}
+
+	/// Lists the network interfaces from the output of `cat /proc/net/dev`.
+	#[cfg(target_os = "linux")]
+	fn network_interfaces(output: &str) -> Vec<&str> {
+		// the first two lines are headers
+		output.lines().skip(2).filter_map(|line| line.split(':').next()).map(str::trim).collect()
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	#[ignore = "requires unprivileged user namespaces"]
+	fn test_cmd_sandbox_no_network() {
+		let output = sandboxed_output(ShellSandboxProfile::NoNetwork, "cat /proc/net/dev");
+		assert_eq!(network_interfaces(&output.stdout), ["lo"]);
+	}
+
+	#[cfg(target_os = "linux")]
+	#[test]
+	#[ignore = "requires unprivileged user namespaces"]
+	fn test_cmd_sandbox_strict() {
+		let output = sandboxed_output(ShellSandboxProfile::Strict, "cat /proc/net/dev");
+		assert_eq!(network_interfaces(&output.stdout), ["lo"]);
+
+		let output = sandboxed_output(ShellSandboxProfile::Strict, "grep Seccomp: /proc/self/status");
+		assert_eq!(output.stdout.trim(), "Seccomp:\t2");
+
+		// new namespaces can't be created
+		let output = sandboxed_output(ShellSandboxProfile::Strict, "command -v unshare >/dev/null || exit 0; unshare -U true && echo allowed");
+		assert_eq!(output.stdout, "");
+
+		// processes can still be spawned
+		let output = sandboxed_output(ShellSandboxProfile::Strict, "echo $(echo spawned)");
+		assert_eq!(output.stdout.trim(), "spawned");
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn test_cmd_env_allowlist() {
+		let env = HashMap::from([("ALLOWED".to_string(), "1".to_string()), ("DENIED".to_string(), "2".to_string())]);
+		let output = Command::new("env").envs(env).env_allowlist(["ALLOWED"]).output().unwrap();
+		assert_eq!(output.stdout.trim(), "ALLOWED=1");
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn test_cmd_cwd_jail() {
+		let jail = std::env::current_dir().unwrap().join("test");
+		let output = Command::new("pwd").cwd_jail(jail.clone()).current_dir("api".into()).output().unwrap();
+		assert_eq!(PathBuf::from(output.stdout.trim()), jail.join("api").canonicalize().unwrap());
+
+		let result = Command::new("pwd").cwd_jail(jail).current_dir("..".into()).spawn();
+		assert!(matches!(result, Err(crate::api::Error::Command(_))));
+	}
 }
@@ -0,0 +1,138 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sandboxing of spawned processes with user namespaces and seccomp.
+
+use std::{collections::BTreeMap, io};
+
+use millennium_utils::config::ShellSandboxProfile;
+use seccompiler::{BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition, SeccompFilter, SeccompRule, TargetArch};
+
+/// The system calls denied to processes spawned with the
+/// [`ShellSandboxProfile::Strict`] profile.
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+	libc::SYS_ptrace,
+	libc::SYS_process_vm_readv,
+	libc::SYS_process_vm_writev,
+	libc::SYS_mount,
+	libc::SYS_umount2,
+	libc::SYS_pivot_root,
+	libc::SYS_chroot,
+	libc::SYS_init_module,
+	libc::SYS_finit_module,
+	libc::SYS_delete_module,
+	libc::SYS_kexec_load,
+	libc::SYS_reboot,
+	libc::SYS_bpf,
+	libc::SYS_perf_event_open,
+	libc::SYS_unshare,
+	libc::SYS_setns,
+	libc::SYS_keyctl,
+	libc::SYS_add_key,
+	libc::SYS_request_key
+];
+
+/// The `clone` flags creating namespaces, denied to processes spawned with the
+/// [`ShellSandboxProfile::Strict`] profile.
+const DENIED_CLONE_FLAGS: &[libc::c_int] = &[
+	libc::CLONE_NEWNS,
+	libc::CLONE_NEWCGROUP,
+	libc::CLONE_NEWUTS,
+	libc::CLONE_NEWIPC,
+	libc::CLONE_NEWUSER,
+	libc::CLONE_NEWPID,
+	libc::CLONE_NEWNET
+];
+
+/// A sandbox, prepared in the parent process so that entering it in the child
+/// does not allocate.
+pub(crate) struct Sandbox {
+	isolate: bool,
+	filters: Vec<BpfProgram>
+}
+
+impl Sandbox {
+	pub(crate) fn new(profile: ShellSandboxProfile) -> crate::api::Result<Self> {
+		let filters = match profile {
+			ShellSandboxProfile::Strict => strict_filters().map_err(|e| crate::api::Error::Command(e.to_string()))?,
+			_ => Vec::new()
+		};
+		Ok(Self {
+			isolate: profile != ShellSandboxProfile::None,
+			filters
+		})
+	}
+
+	/// Moves the current process into the sandbox. Called in the child process
+	/// between `fork` and `exec`.
+	pub(crate) fn enter(&self) -> io::Result<()> {
+		// a new user namespace allows unprivileged processes to create the network namespace
+		if self.isolate && unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+		for filter in &self.filters {
+			seccompiler::apply_filter(filter).map_err(|_| io::Error::from(io::ErrorKind::PermissionDenied))?;
+		}
+		Ok(())
+	}
+}
+
+fn strict_filters() -> Result<Vec<BpfProgram>, seccompiler::Error> {
+	let arch: TargetArch = std::env::consts::ARCH.try_into()?;
+	let mut rules = DENIED_SYSCALLS
+		.iter()
+		.map(|syscall| (*syscall as i64, vec![]))
+		.collect::<BTreeMap<_, _>>();
+	// `clone` also spawns processes and threads, so only its namespace flags are denied
+	let clone_rules = DENIED_CLONE_FLAGS
+		.iter()
+		.map(|flag| {
+			let flag = *flag as u64;
+			Ok(SeccompRule::new(vec![SeccompCondition::new(0, SeccompCmpArgLen::Qword, SeccompCmpOp::MaskedEq(flag), flag)?])?)
+		})
+		.collect::<Result<Vec<_>, seccompiler::Error>>()?;
+	rules.insert(libc::SYS_clone as i64, clone_rules);
+	let denied = SeccompFilter::new(rules, SeccompAction::Allow, SeccompAction::Errno(libc::EPERM as u32), arch)?;
+
+	// the flags of `clone3` are behind a pointer, which filters can't read. `ENOSYS`
+	// makes the C library fall back to `clone`
+	let rules = BTreeMap::from([(libc::SYS_clone3 as i64, vec![])]);
+	let clone3 = SeccompFilter::new(rules, SeccompAction::Allow, SeccompAction::Errno(libc::ENOSYS as u32), arch)?;
+
+	Ok(vec![denied.try_into()?, clone3.try_into()?])
+}
+
+#[cfg(test)]
+mod tests {
+	use millennium_utils::config::ShellSandboxProfile;
+
+	use super::Sandbox;
+
+	#[test]
+	fn builds_profiles() {
+		let sandbox = Sandbox::new(ShellSandboxProfile::None).unwrap();
+		assert!(!sandbox.isolate);
+		assert!(sandbox.filters.is_empty());
+
+		let sandbox = Sandbox::new(ShellSandboxProfile::NoNetwork).unwrap();
+		assert!(sandbox.isolate);
+		assert!(sandbox.filters.is_empty());
+
+		// the seccomp filters compile for the current architecture
+		let sandbox = Sandbox::new(ShellSandboxProfile::Strict).unwrap();
+		assert!(sandbox.isolate);
+		assert_eq!(sandbox.filters.len(), 2);
+		assert!(sandbox.filters.iter().all(|filter| !filter.is_empty()));
+	}
+}
@@ -0,0 +1,150 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(clippy::tabs_in_doc_comments)]
+
+//! Secure storage for credentials such as OAuth refresh tokens.
+//!
+//! Secrets are keyed by a service and an account name, and namespaced by the application's `bundle > identifier` so
+//! that applications can't read each other's secrets through this API.
+//!
+//! On Linux, secrets are stored with the [freedesktop Secret Service] (GNOME Keyring, KWallet, KeePassXC...). When no
+//! Secret Service is available, and on other platforms, secrets are stored in a file in the app data directory,
+//! encrypted with XChaCha20-Poly1305 using a key stored next to it. Note that the encrypted file only protects secrets
+//! from accidental disclosure (e.g. backups of the data file); it does not protect them from other programs running
+//! as the same user.
+//!
+//! [freedesktop Secret Service]: https://specifications.freedesktop.org/secret-service/latest/
+
+mod encrypted_file;
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+mod secret_service;
+
+use std::path::PathBuf;
+
+pub use encrypted_file::EncryptedFile;
+#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+pub use secret_service::SecretService;
+
+use crate::Config;
+
+enum Backend {
+	#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+	SecretService(SecretService),
+	EncryptedFile(EncryptedFile)
+}
+
+/// The secret store of an application.
+///
+/// # Examples
+/// ```rust,no_run
+/// use millennium::{api::secrets::Secrets, Manager};
+/// millennium::Builder::default().setup(|app| {
+/// 	let secrets = Secrets::new(&app.config())?;
+/// 	secrets.set("api.example.com", "user@example.com", "refresh-token")?;
+/// 	assert_eq!(secrets.get("api.example.com", "user@example.com")?.as_deref(), Some("refresh-token"));
+/// 	Ok(())
+/// });
+/// ```
+pub struct Secrets {
+	namespace: String,
+	backend: Backend
+}
+
+impl Secrets {
+	/// Opens the secret store of the application described by the given
+	/// config.
+	///
+	/// Uses the Secret Service on Linux if it is available, and an encrypted
+	/// file in the app data directory otherwise.
+	pub fn new(config: &Config) -> crate::api::Result<Self> {
+		let namespace = config.millennium.bundle.identifier.clone();
+		if namespace.is_empty() {
+			return Err(crate::api::Error::Secrets("the `bundle > identifier` config is not set".into()));
+		}
+
+		#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+		match SecretService::connect() {
+			Ok(secret_service) => return Ok(Self::with_secret_service(namespace, secret_service)),
+			Err(e) => tracing::debug!("Secret Service unavailable, falling back to an encrypted file: {e}")
+		}
+
+		let dir = crate::api::path::app_data_dir(config).ok_or_else(|| crate::api::Error::Secrets("could not resolve the app data directory".into()))?;
+		Ok(Self::with_encrypted_file(namespace, EncryptedFile::open_in(dir)?))
+	}
+
+	/// Creates a secret store backed by the given Secret Service connection.
+	#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+	#[cfg_attr(
+		doc_cfg,
+		doc(cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd")))
+	)]
+	pub fn with_secret_service(namespace: impl Into<String>, secret_service: SecretService) -> Self {
+		Self {
+			namespace: namespace.into(),
+			backend: Backend::SecretService(secret_service)
+		}
+	}
+
+	/// Creates a secret store backed by the given encrypted file.
+	pub fn with_encrypted_file(namespace: impl Into<String>, file: EncryptedFile) -> Self {
+		Self {
+			namespace: namespace.into(),
+			backend: Backend::EncryptedFile(file)
+		}
+	}
+
+	/// The path of the encrypted file backing this store, if the Secret
+	/// Service is not used.
+	pub fn file_path(&self) -> Option<PathBuf> {
+		match &self.backend {
+			Backend::EncryptedFile(file) => Some(file.path().to_path_buf()),
+			#[allow(unreachable_patterns)]
+			_ => None
+		}
+	}
+
+	/// Reads the secret stored for the given service and account.
+	pub fn get(&self, service: &str, account: &str) -> crate::api::Result<Option<String>> {
+		match &self.backend {
+			#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+			Backend::SecretService(secret_service) => secret_service
+				.get(&self.namespace, service, account)?
+				.map(|secret| String::from_utf8(secret).map_err(Into::into))
+				.transpose(),
+			Backend::EncryptedFile(file) => file.get(&self.namespace, service, account)
+		}
+	}
+
+	/// Stores a secret for the given service and account, replacing the
+	/// existing one.
+	pub fn set(&self, service: &str, account: &str, secret: &str) -> crate::api::Result<()> {
+		match &self.backend {
+			#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+			Backend::SecretService(secret_service) => secret_service.set(&self.namespace, service, account, secret.as_bytes()),
+			Backend::EncryptedFile(file) => file.set(&self.namespace, service, account, secret)
+		}
+	}
+
+	/// Deletes the secret stored for the given service and account.
+	///
+	/// Returns `false` if there was no such secret.
+	pub fn delete(&self, service: &str, account: &str) -> crate::api::Result<bool> {
+		match &self.backend {
+			#[cfg(any(target_os = "linux", target_os = "dragonfly", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+			Backend::SecretService(secret_service) => secret_service.delete(&self.namespace, service, account),
+			Backend::EncryptedFile(file) => file.delete(&self.namespace, service, account)
+		}
+	}
+}
@@ -0,0 +1,240 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+	fs::{self, OpenOptions},
+	io::{ErrorKind, Write},
+	path::{Path, PathBuf},
+	sync::Mutex
+};
+
+use chacha20poly1305::{
+	aead::{Aead, KeyInit},
+	XChaCha20Poly1305, XNonce
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const FILE_NAME: &str = "secrets.bin";
+const KEY_FILE_NAME: &str = "secrets.key";
+const NONCE_LEN: usize = 24;
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+	namespace: String,
+	service: String,
+	account: String,
+	secret: String
+}
+
+/// A secret store saved in a file encrypted with XChaCha20-Poly1305.
+pub struct EncryptedFile {
+	path: PathBuf,
+	cipher: XChaCha20Poly1305,
+	/// Serialises the read-modify-write cycles of [`Self::set`] and
+	/// [`Self::delete`].
+	lock: Mutex<()>
+}
+
+impl EncryptedFile {
+	/// Opens the encrypted file at `path` with the given key.
+	pub fn new(path: impl Into<PathBuf>, key: &[u8; 32]) -> Self {
+		Self {
+			path: path.into(),
+			cipher: XChaCha20Poly1305::new(key.into()),
+			lock: Mutex::new(())
+		}
+	}
+
+	/// Opens the `secrets.bin` file in the given directory, using the key
+	/// stored in `secrets.key`. The key is randomly generated if it does not
+	/// exist yet.
+	pub fn open_in(dir: impl AsRef<Path>) -> crate::api::Result<Self> {
+		let dir = dir.as_ref();
+		fs::create_dir_all(dir)?;
+
+		let key_path = dir.join(KEY_FILE_NAME);
+		let key = match fs::read(&key_path) {
+			Ok(key) => key
+				.try_into()
+				.map_err(|_| crate::api::Error::Secrets(format!("the key stored in {} is invalid", key_path.display())))?,
+			Err(e) if e.kind() == ErrorKind::NotFound => {
+				let mut key = [0; 32];
+				rand::thread_rng().fill_bytes(&mut key);
+				create_private(&key_path)?.write_all(&key)?;
+				key
+			}
+			Err(e) => return Err(e.into())
+		};
+
+		Ok(Self::new(dir.join(FILE_NAME), &key))
+	}
+
+	/// The path of the encrypted file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	fn load(&self) -> crate::api::Result<Vec<Entry>> {
+		let data = match fs::read(&self.path) {
+			Ok(data) => data,
+			Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+			Err(e) => return Err(e.into())
+		};
+		if data.len() < NONCE_LEN {
+			return Err(crate::api::Error::Secrets(format!("{} is corrupted", self.path.display())));
+		}
+
+		let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+		let plaintext = self
+			.cipher
+			.decrypt(XNonce::from_slice(nonce), ciphertext)
+			.map_err(|_| crate::api::Error::Secrets(format!("failed to decrypt {}; the file is corrupted or the key is wrong", self.path.display())))?;
+		serde_json::from_slice(&plaintext).map_err(Into::into)
+	}
+
+	fn store(&self, entries: &[Entry]) -> crate::api::Result<()> {
+		let plaintext = serde_json::to_vec(entries)?;
+		let mut nonce = [0; NONCE_LEN];
+		rand::thread_rng().fill_bytes(&mut nonce);
+		let ciphertext = self
+			.cipher
+			.encrypt(XNonce::from_slice(&nonce), plaintext.as_slice())
+			.map_err(|_| crate::api::Error::Secrets("failed to encrypt secrets".into()))?;
+
+		// write to a temporary file first so the store is never left half-written; it is only readable by the current user
+		let dir = match self.path.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => dir,
+			_ => Path::new(".")
+		};
+		let mut file = tempfile::NamedTempFile::new_in(dir)?;
+		file.write_all(&nonce)?;
+		file.write_all(&ciphertext)?;
+		file.as_file().sync_all()?;
+		file.persist(&self.path).map_err(|e| e.error)?;
+		Ok(())
+	}
+
+	/// Reads the secret stored for the given service and account.
+	pub fn get(&self, namespace: &str, service: &str, account: &str) -> crate::api::Result<Option<String>> {
+		Ok(self
+			.load()?
+			.into_iter()
+			.find(|e| e.namespace == namespace && e.service == service && e.account == account)
+			.map(|e| e.secret))
+	}
+
+	/// Stores a secret for the given service and account, replacing the
+	/// existing one.
+	pub fn set(&self, namespace: &str, service: &str, account: &str, secret: &str) -> crate::api::Result<()> {
+		let _guard = self.lock.lock().unwrap();
+		let mut entries = self.load()?;
+		entries.retain(|e| !(e.namespace == namespace && e.service == service && e.account == account));
+		entries.push(Entry {
+			namespace: namespace.into(),
+			service: service.into(),
+			account: account.into(),
+			secret: secret.into()
+		});
+		self.store(&entries)
+	}
+
+	/// Deletes the secret stored for the given service and account.
+	///
+	/// Returns `false` if there was no such secret.
+	pub fn delete(&self, namespace: &str, service: &str, account: &str) -> crate::api::Result<bool> {
+		let _guard = self.lock.lock().unwrap();
+		let mut entries = self.load()?;
+		let len = entries.len();
+		entries.retain(|e| !(e.namespace == namespace && e.service == service && e.account == account));
+		if entries.len() == len {
+			return Ok(false);
+		}
+		self.store(&entries)?;
+		Ok(true)
+	}
+}
+
+/// Creates a file only readable by the current user.
+fn create_private(path: &Path) -> std::io::Result<fs::File> {
+	let mut options = OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::OpenOptionsExt;
+		options.mode(0o600);
+	}
+	options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::EncryptedFile;
+
+	#[test]
+	fn round_trip() {
+		let dir = tempfile::tempdir().unwrap();
+		let file = EncryptedFile::open_in(dir.path()).unwrap();
+		assert_eq!(file.get("com.millennium.test", "api", "user").unwrap(), None);
+
+		file.set("com.millennium.test", "api", "user", "refresh-token").unwrap();
+		file.set("com.millennium.other", "api", "user", "other-token").unwrap();
+		assert_eq!(file.get("com.millennium.test", "api", "user").unwrap().as_deref(), Some("refresh-token"));
+		assert_eq!(file.get("com.millennium.other", "api", "user").unwrap().as_deref(), Some("other-token"));
+
+		file.set("com.millennium.test", "api", "user", "new-token").unwrap();
+		assert_eq!(file.get("com.millennium.test", "api", "user").unwrap().as_deref(), Some("new-token"));
+
+		// secrets are never written in plaintext
+		let data = std::fs::read(file.path()).unwrap();
+		assert!(!data.windows(b"new-token".len()).any(|w| w == b"new-token"));
+
+		// the key is reused when the store is reopened
+		let file = EncryptedFile::open_in(dir.path()).unwrap();
+		assert!(file.delete("com.millennium.test", "api", "user").unwrap());
+		assert!(!file.delete("com.millennium.test", "api", "user").unwrap());
+		assert_eq!(file.get("com.millennium.test", "api", "user").unwrap(), None);
+		assert_eq!(file.get("com.millennium.other", "api", "user").unwrap().as_deref(), Some("other-token"));
+	}
+
+	#[test]
+	fn concurrent_writes() {
+		let dir = tempfile::tempdir().unwrap();
+		let file = std::sync::Arc::new(EncryptedFile::open_in(dir.path()).unwrap());
+		let threads = (0..8)
+			.map(|i| {
+				let file = file.clone();
+				std::thread::spawn(move || file.set("com.millennium.test", "api", &format!("user{i}"), "token").unwrap())
+			})
+			.collect::<Vec<_>>();
+		for thread in threads {
+			thread.join().unwrap();
+		}
+		for i in 0..8 {
+			assert_eq!(file.get("com.millennium.test", "api", &format!("user{i}")).unwrap().as_deref(), Some("token"));
+		}
+		// no temporary file is left behind
+		assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+	}
+
+	#[test]
+	fn wrong_key() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("secrets.bin");
+		EncryptedFile::new(&path, &[1; 32])
+			.set("com.millennium.test", "api", "user", "token")
+			.unwrap();
+		assert!(EncryptedFile::new(&path, &[2; 32]).get("com.millennium.test", "api", "user").is_err());
+	}
+}
@@ -0,0 +1,391 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use zbus::{
+	blocking::{Connection, Proxy},
+	zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value}
+};
+
+const DESTINATION: &str = "org.freedesktop.secrets";
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const SERVICE_INTERFACE: &str = "org.freedesktop.Secret.Service";
+const COLLECTION_INTERFACE: &str = "org.freedesktop.Secret.Collection";
+const ITEM_INTERFACE: &str = "org.freedesktop.Secret.Item";
+const PROMPT_INTERFACE: &str = "org.freedesktop.Secret.Prompt";
+const SCHEMA: &str = "io.pyke.millennium.Secret";
+
+/// A secret as transferred over D-Bus: the session, the parameters, the
+/// value, and its content type.
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+fn map_err(e: zbus::Error) -> crate::api::Error {
+	crate::api::Error::Secrets(e.to_string())
+}
+
+/// A connection to the [freedesktop Secret Service](https://specifications.freedesktop.org/secret-service/latest/).
+pub struct SecretService {
+	connection: Connection,
+	session: OwnedObjectPath
+}
+
+impl SecretService {
+	/// Connects to the Secret Service on the session bus.
+	pub fn connect() -> crate::api::Result<Self> {
+		Self::with_connection(Connection::session().map_err(map_err)?)
+	}
+
+	/// Opens a Secret Service session on the given connection.
+	pub fn with_connection(connection: Connection) -> crate::api::Result<Self> {
+		let service = Proxy::new(&connection, DESTINATION, SERVICE_PATH, SERVICE_INTERFACE).map_err(map_err)?;
+		// the session bus is only reachable by the current user, so secrets are transferred without encryption
+		let (_, session): (OwnedValue, OwnedObjectPath) = service.call("OpenSession", &("plain", Value::from(""))).map_err(map_err)?;
+		Ok(Self { connection, session })
+	}
+
+	fn proxy<'a>(&'a self, path: &'a str, interface: &'a str) -> crate::api::Result<Proxy<'a>> {
+		Proxy::new(&self.connection, DESTINATION, path, interface).map_err(map_err)
+	}
+
+	/// Waits for the user to answer the given prompt, returning `false` if it
+	/// was dismissed.
+	fn prompt(&self, prompt: &ObjectPath<'_>) -> crate::api::Result<bool> {
+		Ok(prompt.as_str() == "/" || self.complete_prompt(prompt)?.is_some())
+	}
+
+	/// Shows the given prompt and returns its result, or `None` if the user
+	/// dismissed it.
+	fn complete_prompt(&self, prompt: &ObjectPath<'_>) -> crate::api::Result<Option<OwnedValue>> {
+		let proxy = self.proxy(prompt.as_str(), PROMPT_INTERFACE)?;
+		let mut completed = proxy.receive_signal("Completed").map_err(map_err)?;
+		proxy.call::<_, _, ()>("Prompt", &("",)).map_err(map_err)?;
+		let message = completed
+			.next()
+			.ok_or_else(|| crate::api::Error::Secrets("the Secret Service prompt was closed".into()))?;
+		let (dismissed, result): (bool, OwnedValue) = message.body().map_err(map_err)?;
+		Ok(if dismissed { None } else { Some(result) })
+	}
+
+	fn unlock(&self, objects: Vec<OwnedObjectPath>) -> crate::api::Result<()> {
+		if objects.is_empty() {
+			return Ok(());
+		}
+		let (_, prompt): (Vec<OwnedObjectPath>, OwnedObjectPath) = self
+			.proxy(SERVICE_PATH, SERVICE_INTERFACE)?
+			.call("Unlock", &(objects,))
+			.map_err(map_err)?;
+		if self.prompt(&prompt)? {
+			Ok(())
+		} else {
+			Err(crate::api::Error::Secrets("the user refused to unlock the keyring".into()))
+		}
+	}
+
+	fn attributes<'a>(namespace: &'a str, service: &'a str, account: &'a str) -> HashMap<&'a str, &'a str> {
+		HashMap::from([("xdg:schema", SCHEMA), ("application", namespace), ("service", service), ("account", account)])
+	}
+
+	/// Finds the items matching the given attributes, unlocking them if
+	/// needed.
+	fn find_items(&self, namespace: &str, service: &str, account: &str) -> crate::api::Result<Vec<OwnedObjectPath>> {
+		let (mut unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) = self
+			.proxy(SERVICE_PATH, SERVICE_INTERFACE)?
+			.call("SearchItems", &(Self::attributes(namespace, service, account),))
+			.map_err(map_err)?;
+		self.unlock(locked.clone())?;
+		unlocked.extend(locked);
+		Ok(unlocked)
+	}
+
+	/// Reads the secret stored for the given service and account.
+	pub fn get(&self, namespace: &str, service: &str, account: &str) -> crate::api::Result<Option<Vec<u8>>> {
+		match self.find_items(namespace, service, account)?.first() {
+			Some(item) => {
+				let (_, _, value, _): Secret = self
+					.proxy(item.as_str(), ITEM_INTERFACE)?
+					.call("GetSecret", &(&self.session,))
+					.map_err(map_err)?;
+				Ok(Some(value))
+			}
+			None => Ok(None)
+		}
+	}
+
+	/// Finds the collection secrets are stored in: the default collection, or
+	/// the first collection if none is the default. The default collection is
+	/// created if the service has no collection at all.
+	fn collection(&self) -> crate::api::Result<OwnedObjectPath> {
+		let service = self.proxy(SERVICE_PATH, SERVICE_INTERFACE)?;
+		let collection: OwnedObjectPath = service.call("ReadAlias", &("default",)).map_err(map_err)?;
+		if collection.as_str() != "/" {
+			return Ok(collection);
+		}
+		let collections: Vec<OwnedObjectPath> = service.get_property("Collections").map_err(map_err)?;
+		if let Some(collection) = collections.into_iter().next() {
+			return Ok(collection);
+		}
+
+		let properties = HashMap::from([("org.freedesktop.Secret.Collection.Label", Value::from("Default"))]);
+		let (collection, prompt): (OwnedObjectPath, OwnedObjectPath) = service.call("CreateCollection", &(properties, "default")).map_err(map_err)?;
+		if prompt.as_str() == "/" {
+			return Ok(collection);
+		}
+		match self.complete_prompt(&prompt)?.as_deref() {
+			Some(Value::ObjectPath(collection)) => Ok(collection.clone().into()),
+			Some(_) => Err(crate::api::Error::Secrets("the Secret Service did not return the created collection".into())),
+			None => Err(crate::api::Error::Secrets("the user refused to create a keyring".into()))
+		}
+	}
+
+	/// Stores a secret for the given service and account in the default
+	/// collection, replacing the existing one.
+	pub fn set(&self, namespace: &str, service: &str, account: &str, secret: &[u8]) -> crate::api::Result<()> {
+		let collection = self.collection()?;
+		let collection_proxy = self.proxy(collection.as_str(), COLLECTION_INTERFACE)?;
+		if collection_proxy.get_property::<bool>("Locked").map_err(map_err)? {
+			self.unlock(vec![collection.clone()])?;
+		}
+
+		let label = format!("{service} ({account})");
+		let properties = HashMap::from([
+			("org.freedesktop.Secret.Item.Label", Value::from(label.as_str())),
+			("org.freedesktop.Secret.Item.Attributes", Value::from(Self::attributes(namespace, service, account)))
+		]);
+		let secret: Secret = (self.session.clone(), Vec::new(), secret.to_vec(), "text/plain".into());
+		let (_, prompt): (OwnedObjectPath, OwnedObjectPath) = collection_proxy.call("CreateItem", &(properties, secret, true)).map_err(map_err)?;
+		if self.prompt(&prompt)? {
+			Ok(())
+		} else {
+			Err(crate::api::Error::Secrets("the user refused to store the secret".into()))
+		}
+	}
+
+	/// Deletes the secret stored for the given service and account.
+	///
+	/// Returns `false` if there was no such secret.
+	pub fn delete(&self, namespace: &str, service: &str, account: &str) -> crate::api::Result<bool> {
+		let items = self.find_items(namespace, service, account)?;
+		for item in &items {
+			let prompt: OwnedObjectPath = self.proxy(item.as_str(), ITEM_INTERFACE)?.call("Delete", &()).map_err(map_err)?;
+			if !self.prompt(&prompt)? {
+				return Err(crate::api::Error::Secrets("the user refused to delete the secret".into()));
+			}
+		}
+		Ok(!items.is_empty())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::HashMap,
+		sync::{Arc, Mutex}
+	};
+
+	use zbus::{
+		blocking::Connection,
+		dbus_interface, fdo,
+		zvariant::{OwnedObjectPath, OwnedValue, Value}
+	};
+
+	use super::{Secret, SecretService, DESTINATION, SERVICE_PATH};
+	use crate::test_utils::PrivateBus;
+
+	const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/default";
+	/// The number of items the mock service can store.
+	const ITEMS: usize = 8;
+
+	#[derive(Default)]
+	struct Store {
+		has_collection: bool,
+		has_default_alias: bool,
+		items: Vec<Option<(HashMap<String, String>, Vec<u8>)>>
+	}
+
+	impl Store {
+		fn find(&self, attributes: &HashMap<String, String>) -> Option<usize> {
+			self.items
+				.iter()
+				.position(|item| matches!(item, Some((a, _)) if attributes.iter().all(|(k, v)| a.get(k) == Some(v))))
+		}
+	}
+
+	type SharedStore = Arc<Mutex<Store>>;
+
+	fn path(path: &str) -> OwnedObjectPath {
+		OwnedObjectPath::try_from(path.to_string()).unwrap()
+	}
+
+	fn item_path(id: usize) -> String {
+		format!("{COLLECTION_PATH}/{id}")
+	}
+
+	/// A Secret Service storing secrets in memory, without ever prompting the
+	/// user.
+	struct MockService(SharedStore);
+
+	#[dbus_interface(name = "org.freedesktop.Secret.Service")]
+	impl MockService {
+		fn open_session(&self, algorithm: &str, _input: OwnedValue) -> fdo::Result<(OwnedValue, OwnedObjectPath)> {
+			match algorithm {
+				"plain" => Ok((Value::from("").into(), path("/org/freedesktop/secrets/session/0"))),
+				_ => Err(fdo::Error::NotSupported(algorithm.into()))
+			}
+		}
+
+		fn search_items(&self, attributes: HashMap<String, String>) -> (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) {
+			let found = self.0.lock().unwrap().find(&attributes).map(|id| path(&item_path(id)));
+			(found.into_iter().collect(), Vec::new())
+		}
+
+		fn read_alias(&self, name: &str) -> OwnedObjectPath {
+			let has_alias = name == "default" && self.0.lock().unwrap().has_default_alias;
+			path(if has_alias { COLLECTION_PATH } else { "/" })
+		}
+
+		fn create_collection(&self, _properties: HashMap<String, OwnedValue>, alias: &str) -> (OwnedObjectPath, OwnedObjectPath) {
+			let mut store = self.0.lock().unwrap();
+			store.has_collection = true;
+			store.has_default_alias = alias == "default";
+			(path(COLLECTION_PATH), path("/"))
+		}
+
+		#[dbus_interface(property)]
+		fn collections(&self) -> Vec<OwnedObjectPath> {
+			if self.0.lock().unwrap().has_collection { vec![path(COLLECTION_PATH)] } else { Vec::new() }
+		}
+	}
+
+	struct MockCollection(SharedStore);
+
+	#[dbus_interface(name = "org.freedesktop.Secret.Collection")]
+	impl MockCollection {
+		fn create_item(&self, properties: HashMap<String, OwnedValue>, secret: Secret, replace: bool) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+			let attributes = properties
+				.get("org.freedesktop.Secret.Item.Attributes")
+				.and_then(|attributes| HashMap::<String, String>::try_from(Value::from(attributes.clone())).ok())
+				.ok_or_else(|| fdo::Error::InvalidArgs("missing item attributes".into()))?;
+
+			let mut store = self.0.lock().unwrap();
+			let id = match store.find(&attributes).filter(|_| replace) {
+				Some(id) => id,
+				None if store.items.len() < ITEMS => {
+					store.items.push(None);
+					store.items.len() - 1
+				}
+				None => return Err(fdo::Error::LimitsExceeded("the mock Secret Service is full".into()))
+			};
+			store.items[id] = Some((attributes, secret.2));
+			Ok((path(&item_path(id)), path("/")))
+		}
+
+		#[dbus_interface(property)]
+		fn locked(&self) -> bool {
+			false
+		}
+	}
+
+	struct MockItem {
+		id: usize,
+		store: SharedStore
+	}
+
+	#[dbus_interface(name = "org.freedesktop.Secret.Item")]
+	impl MockItem {
+		fn get_secret(&self, session: OwnedObjectPath) -> fdo::Result<Secret> {
+			match self.store.lock().unwrap().items.get(self.id) {
+				Some(Some((_, value))) => Ok((session, Vec::new(), value.clone(), "text/plain".into())),
+				_ => Err(fdo::Error::UnknownObject(item_path(self.id)))
+			}
+		}
+
+		fn delete(&self) -> OwnedObjectPath {
+			if let Some(item) = self.store.lock().unwrap().items.get_mut(self.id) {
+				*item = None;
+			}
+			path("/")
+		}
+	}
+
+	/// Serves a mock Secret Service on the given bus.
+	fn serve(bus: &PrivateBus, store: &SharedStore) -> Connection {
+		let mut builder = bus
+			.connection()
+			.name(DESTINATION)
+			.unwrap()
+			.serve_at(SERVICE_PATH, MockService(store.clone()))
+			.unwrap()
+			.serve_at(COLLECTION_PATH, MockCollection(store.clone()))
+			.unwrap();
+		for id in 0..ITEMS {
+			builder = builder.serve_at(item_path(id), MockItem { id, store: store.clone() }).unwrap();
+		}
+		builder.build().unwrap()
+	}
+
+	#[test]
+	#[ignore = "requires dbus-daemon"]
+	fn stores_secrets() {
+		let bus = PrivateBus::start();
+		let store = SharedStore::default();
+		let _server = serve(&bus, &store);
+		let secret_service = SecretService::with_connection(bus.connection().build().unwrap()).unwrap();
+
+		assert_eq!(secret_service.get("com.millennium.test", "api", "user").unwrap(), None);
+		secret_service.set("com.millennium.test", "api", "user", b"refresh-token").unwrap();
+		secret_service.set("com.millennium.other", "api", "user", b"other-token").unwrap();
+		assert_eq!(secret_service.get("com.millennium.test", "api", "user").unwrap().as_deref(), Some(&b"refresh-token"[..]));
+		assert_eq!(secret_service.get("com.millennium.other", "api", "user").unwrap().as_deref(), Some(&b"other-token"[..]));
+
+		// storing a secret again replaces the existing item
+		secret_service.set("com.millennium.test", "api", "user", b"new-token").unwrap();
+		assert_eq!(secret_service.get("com.millennium.test", "api", "user").unwrap().as_deref(), Some(&b"new-token"[..]));
+		assert_eq!(store.lock().unwrap().items.len(), 2);
+
+		assert!(secret_service.delete("com.millennium.test", "api", "user").unwrap());
+		assert!(!secret_service.delete("com.millennium.test", "api", "user").unwrap());
+		assert_eq!(secret_service.get("com.millennium.test", "api", "user").unwrap(), None);
+		assert_eq!(secret_service.get("com.millennium.other", "api", "user").unwrap().as_deref(), Some(&b"other-token"[..]));
+	}
+
+	#[test]
+	#[ignore = "requires dbus-daemon"]
+	fn creates_the_default_collection() {
+		let bus = PrivateBus::start();
+		let store = SharedStore::default();
+		let _server = serve(&bus, &store);
+		let secret_service = SecretService::with_connection(bus.connection().build().unwrap()).unwrap();
+
+		secret_service.set("com.millennium.test", "api", "user", b"refresh-token").unwrap();
+		assert!(store.lock().unwrap().has_default_alias);
+		assert_eq!(secret_service.get("com.millennium.test", "api", "user").unwrap().as_deref(), Some(&b"refresh-token"[..]));
+	}
+
+	#[test]
+	#[ignore = "requires dbus-daemon"]
+	fn falls_back_to_another_collection() {
+		let bus = PrivateBus::start();
+		let store = SharedStore::new(Mutex::new(Store {
+			has_collection: true,
+			..Default::default()
+		}));
+		let _server = serve(&bus, &store);
+		let secret_service = SecretService::with_connection(bus.connection().build().unwrap()).unwrap();
+
+		secret_service.set("com.millennium.test", "api", "user", b"refresh-token").unwrap();
+		assert!(!store.lock().unwrap().has_default_alias);
+		assert_eq!(secret_service.get("com.millennium.test", "api", "user").unwrap().as_deref(), Some(&b"refresh-token"[..]));
+	}
+}
@@ -0,0 +1,288 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Types and functions related to WebSocket connections.
+
+use std::{borrow::Cow, collections::HashMap};
+
+use futures_util::{
+	stream::{SplitSink, SplitStream},
+	SinkExt, StreamExt
+};
+use http::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+	tungstenite::{
+		client::IntoClientRequest,
+		protocol::{frame::coding::CloseCode, CloseFrame as TungsteniteCloseFrame},
+		Message as TungsteniteMessage
+	},
+	MaybeTlsStream, WebSocketStream
+};
+use url::Url;
+
+use super::Result;
+
+type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The options of a WebSocket connection.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectOptions {
+	/// Headers of the handshake request.
+	#[serde(default)]
+	pub headers: HashMap<String, String>
+}
+
+impl ConnectOptions {
+	/// Creates the default options.
+	pub fn new() -> Self {
+		Default::default()
+	}
+
+	/// Adds a header to the handshake request.
+	#[must_use]
+	pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+		self.headers.insert(name.into(), value.into());
+		self
+	}
+}
+
+/// The frame of a close message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CloseFrame {
+	/// The [status code](https://www.rfc-editor.org/rfc/rfc6455#section-7.4) of the close.
+	pub code: u16,
+	/// The reason of the close.
+	pub reason: String
+}
+
+/// A WebSocket message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Message {
+	/// A text message.
+	Text(String),
+	/// A binary message.
+	Binary(Vec<u8>),
+	/// A ping message.
+	Ping(Vec<u8>),
+	/// A pong message.
+	Pong(Vec<u8>),
+	/// A close message.
+	Close(Option<CloseFrame>)
+}
+
+impl From<Message> for TungsteniteMessage {
+	fn from(message: Message) -> Self {
+		match message {
+			Message::Text(text) => Self::Text(text),
+			Message::Binary(data) => Self::Binary(data),
+			Message::Ping(data) => Self::Ping(data),
+			Message::Pong(data) => Self::Pong(data),
+			Message::Close(frame) => Self::Close(frame.map(|frame| TungsteniteCloseFrame {
+				code: CloseCode::from(frame.code),
+				reason: Cow::Owned(frame.reason)
+			}))
+		}
+	}
+}
+
+impl Message {
+	fn from_tungstenite(message: TungsteniteMessage) -> Option<Self> {
+		Some(match message {
+			TungsteniteMessage::Text(text) => Self::Text(text),
+			TungsteniteMessage::Binary(data) => Self::Binary(data),
+			TungsteniteMessage::Ping(data) => Self::Ping(data),
+			TungsteniteMessage::Pong(data) => Self::Pong(data),
+			TungsteniteMessage::Close(frame) => Self::Close(frame.map(|frame| CloseFrame {
+				code: frame.code.into(),
+				reason: frame.reason.into_owned()
+			})),
+			// raw frames are never returned when reading
+			TungsteniteMessage::Frame(_) => return None
+		})
+	}
+}
+
+/// A WebSocket connection.
+///
+/// # Examples
+/// ```no_run
+/// use millennium::api::websocket::{ConnectOptions, Message, WebSocket};
+/// # async fn run() -> millennium::api::Result<()> {
+/// let mut socket = WebSocket::connect(
+/// 	&"wss://echo.example.com".parse().unwrap(),
+/// 	ConnectOptions::new().header("Authorization", "Bearer token")
+/// )
+/// .await?;
+/// socket.send(Message::Text("hello".into())).await?;
+/// while let Some(message) = socket.next().await {
+/// 	println!("{:?}", message?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WebSocket {
+	sender: WebSocketSender,
+	receiver: WebSocketReceiver
+}
+
+impl WebSocket {
+	/// Connects to a WebSocket server.
+	pub async fn connect(url: &Url, options: ConnectOptions) -> Result<Self> {
+		let mut request = url.as_str().into_client_request()?;
+		for (name, value) in options.headers {
+			request
+				.headers_mut()
+				.insert(HeaderName::from_bytes(name.as_bytes())?, HeaderValue::from_str(&value)?);
+		}
+		let (stream, _) = tokio_tungstenite::connect_async(request).await?;
+		let (sink, stream) = stream.split();
+		Ok(Self {
+			sender: WebSocketSender(sink),
+			receiver: WebSocketReceiver(stream)
+		})
+	}
+
+	/// Sends a message.
+	pub async fn send(&mut self, message: Message) -> Result<()> {
+		self.sender.send(message).await
+	}
+
+	/// Starts the close handshake.
+	pub async fn close(&mut self, frame: Option<CloseFrame>) -> Result<()> {
+		self.sender.close(frame).await
+	}
+
+	/// Receives the next message, or `None` if the connection is closed.
+	pub async fn next(&mut self) -> Option<Result<Message>> {
+		self.receiver.next().await
+	}
+
+	/// Splits the connection into its sending and receiving halves, to use them concurrently.
+	pub fn split(self) -> (WebSocketSender, WebSocketReceiver) {
+		(self.sender, self.receiver)
+	}
+}
+
+/// The sending half of a [`WebSocket`].
+#[derive(Debug)]
+pub struct WebSocketSender(SplitSink<Stream, TungsteniteMessage>);
+
+impl WebSocketSender {
+	/// Sends a message.
+	pub async fn send(&mut self, message: Message) -> Result<()> {
+		self.0.send(message.into()).await.map_err(Into::into)
+	}
+
+	/// Starts the close handshake.
+	pub async fn close(&mut self, frame: Option<CloseFrame>) -> Result<()> {
+		self.send(Message::Close(frame)).await
+	}
+}
+
+/// The receiving half of a [`WebSocket`].
+#[derive(Debug)]
+pub struct WebSocketReceiver(SplitStream<Stream>);
+
+impl WebSocketReceiver {
+	/// Receives the next message, or `None` if the connection is closed.
+	pub async fn next(&mut self) -> Option<Result<Message>> {
+		loop {
+			match self.0.next().await? {
+				Ok(message) => {
+					if let Some(message) = Message::from_tungstenite(message) {
+						return Some(Ok(message));
+					}
+				}
+				Err(e) => return Some(Err(e.into()))
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use futures_util::{SinkExt, StreamExt};
+	use tokio::net::TcpListener;
+	use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+
+	use super::{CloseFrame, ConnectOptions, Message, WebSocket};
+
+	/// Starts a server echoing the messages and the `x-echo` header of the handshake.
+	async fn echo_server() -> url::Url {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let address = listener.local_addr().unwrap();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				tokio::spawn(async move {
+					let mut header = None;
+					let mut socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+						header = request.headers().get("x-echo").map(|value| value.to_str().unwrap().to_string());
+						Ok(response)
+					})
+					.await
+					.unwrap();
+					if let Some(header) = header {
+						socket.send(header.into()).await.unwrap();
+					}
+					while let Some(Ok(message)) = socket.next().await {
+						if message.is_text() || message.is_binary() {
+							socket.send(message).await.unwrap();
+						}
+					}
+				});
+			}
+		});
+		format!("ws://{address}").parse().unwrap()
+	}
+
+	#[tokio::test]
+	async fn echoes_messages() {
+		let url = echo_server().await;
+		let mut socket = WebSocket::connect(&url, ConnectOptions::new().header("X-Echo", "hello")).await.unwrap();
+		assert_eq!(socket.next().await.unwrap().unwrap(), Message::Text("hello".into()));
+
+		socket.send(Message::Text("text".into())).await.unwrap();
+		assert_eq!(socket.next().await.unwrap().unwrap(), Message::Text("text".into()));
+		socket.send(Message::Binary(vec![0, 1, 2])).await.unwrap();
+		assert_eq!(socket.next().await.unwrap().unwrap(), Message::Binary(vec![0, 1, 2]));
+
+		socket.close(Some(CloseFrame { code: 1000, reason: "done".into() })).await.unwrap();
+		assert_eq!(socket.next().await.unwrap().unwrap(), Message::Close(Some(CloseFrame { code: 1000, reason: "done".into() })));
+		assert!(socket.next().await.is_none());
+	}
+
+	#[tokio::test]
+	async fn rejects_invalid_headers() {
+		let url = echo_server().await;
+		assert!(
+			WebSocket::connect(&url, ConnectOptions::new().header("invalid header", "value"))
+				.await
+				.is_err()
+		);
+	}
+
+	#[test]
+	fn serializes_messages() {
+		assert_eq!(serde_json::to_value(Message::Text("hi".into())).unwrap(), serde_json::json!({ "type": "Text", "data": "hi" }));
+		assert_eq!(
+			serde_json::from_value::<Message>(serde_json::json!({ "type": "Close", "data": { "code": 1001, "reason": "bye" } })).unwrap(),
+			Message::Close(Some(CloseFrame { code: 1001, reason: "bye".into() }))
+		);
+	}
+}
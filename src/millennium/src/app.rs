@@ -66,6 +66,10 @@ pub(crate) type GlobalWindowEventListener<R> = Box<dyn Fn(GlobalWindowEvent<R>)
 #[cfg(all(desktop, feature = "system-tray"))]
 type SystemTrayEventListener<R> = Box<dyn Fn(&AppHandle<R>, tray::SystemTrayEvent) + Send + Sync>;
 
+/// The script that defines `window.__MILLENNIUM_POST_MESSAGE__` with the default invoke system.
+pub(crate) const DEFAULT_INVOKE_INITIALIZATION_SCRIPT: &str =
+	"Object.defineProperty(window, '__MILLENNIUM_POST_MESSAGE__', { value: message => window.ipc.postMessage(JSON.stringify(message)) })";
+
 /// Api exposed on the `ExitRequested` event.
 #[derive(Debug)]
 pub struct ExitRequestApi(Sender<ExitRequestedEventAction>);
@@ -489,7 +493,7 @@ impl<R: Runtime> AppHandle<R> {
 	fn cleanup_before_exit(&self) {
 		#[cfg(any(shell_execute, shell_sidecar))]
 		{
-			crate::api::process::kill_children();
+			crate::api::process::terminate_children(crate::api::process::TERMINATE_TIMEOUT);
 		}
 		#[cfg(all(windows, feature = "system-tray"))]
 		{
@@ -808,7 +812,7 @@ impl<R: Runtime> App<R> {
 	/// Runs a iteration of the runtime event loop and immediately return.
 	///
 	/// Note that when using this API, app cleanup is not automatically done.
-	/// The cleanup calls [`crate::api::process::kill_children`] so you may want
+	/// The cleanup calls [`crate::api::process::terminate_children`] so you may want
 	/// to call that function before exiting the application. Additionally, the
 	/// cleanup calls
 	/// [AppHandle#remove_system_tray](`AppHandle#method.remove_system_tray`)
@@ -954,8 +958,7 @@ impl<R: Runtime> Builder<R> {
 			setup: Box::new(|_| Ok(())),
 			invoke_handler: Box::new(|_| ()),
 			invoke_responder: Arc::new(window_invoke_responder),
-			invoke_initialization_script:
-				"Object.defineProperty(window, '__MILLENNIUM_POST_MESSAGE__', { value: message => window.ipc.postMessage(JSON.stringify(message)) })".into(),
+			invoke_initialization_script: DEFAULT_INVOKE_INITIALIZATION_SCRIPT.into(),
 			on_page_load: Box::new(|_, _| ()),
 			pending_windows: Default::default(),
 			plugins: PluginStore::default(),
@@ -1011,6 +1014,35 @@ impl<R: Runtime> Builder<R> {
 		self
 	}
 
+	/// Sets the TypeScript bindings of the application.
+	///
+	/// While running `millennium dev`, the bindings are written to the
+	/// `build > bindings` path of the config, if set. They are ignored in
+	/// production builds.
+	///
+	/// # Examples
+	/// ```no_run
+	/// #[millennium::command]
+	/// fn greet(name: String) -> String {
+	/// 	format!("Hello, {}!", name)
+	/// }
+	/// millennium::Builder::default()
+	/// 	.invoke_handler(millennium::generate_handler![greet])
+	/// 	.typescript_bindings(millennium::generate_bindings![greet]);
+	/// ```
+	#[must_use]
+	pub fn typescript_bindings(self, bindings: crate::typescript::Bindings) -> Self {
+		#[cfg(dev)]
+		if let Some(path) = std::env::var_os(crate::typescript::BINDINGS_PATH_ENV) {
+			if let Err(e) = bindings.write(&path) {
+				tracing::warn!("failed to write the TypeScript bindings to {}: {}", std::path::Path::new(&path).display(), e);
+			}
+		}
+		#[cfg(not(dev))]
+		let _ = bindings;
+		self
+	}
+
 	/// Defines a custom JS message system.
 	///
 	/// The `responder` is a function that will be called when a command has
@@ -1497,8 +1529,8 @@ impl<R: Runtime> Builder<R> {
 			fs: FsScope::for_fs_api(&app.manager.config(), app.package_info(), &env, &app.config().millennium.allowlist.fs.scope)?,
 			#[cfg(protocol_asset)]
 			asset_protocol: FsScope::for_fs_api(&app.manager.config(), app.package_info(), &env, &app.config().millennium.allowlist.protocol.asset_scope)?,
-			#[cfg(http_request)]
-			http: crate::scope::HttpScope::for_http_api(&app.config().millennium.allowlist.http.scope),
+			#[cfg(any(http_request, http_websocket))]
+			http: crate::scope::HttpScope::for_http_api(&app.config().millennium.allowlist.http.scope)?,
 			#[cfg(shell_scope)]
 			shell: ShellScope::new(&app.manager.config(), app.package_info(), &env, shell_scope)
 		});
@@ -26,6 +26,8 @@ use crate::{
 };
 
 mod app;
+#[cfg(autostart_any)]
+mod autostart;
 #[cfg(cli)]
 mod cli;
 #[cfg(clipboard_any)]
@@ -46,6 +48,8 @@ mod operating_system;
 mod path;
 #[cfg(process_any)]
 mod process;
+#[cfg(secrets_any)]
+mod secrets;
 #[cfg(shell_any)]
 mod shell;
 mod window;
@@ -85,8 +89,12 @@ impl<T: Serialize> From<T> for InvokeResponse {
 #[serde(tag = "module", content = "message")]
 enum Module {
 	App(app::Cmd),
+	#[cfg(autostart_any)]
+	Autostart(autostart::Cmd),
 	#[cfg(process_any)]
 	Process(process::Cmd),
+	#[cfg(secrets_any)]
+	Secrets(secrets::Cmd),
 	#[cfg(fs_any)]
 	Fs(file_system::Cmd),
 	#[cfg(os_any)]
@@ -115,8 +123,12 @@ impl Module {
 		let context = InvokeContext { window, config, package_info };
 		match self {
 			Self::App(cmd) => resolver.respond_async(async move { cmd.run(context).and_then(|r| r.json).map_err(InvokeError::from_anyhow) }),
+			#[cfg(autostart_any)]
+			Self::Autostart(cmd) => resolver.respond_async(async move { cmd.run(context).and_then(|r| r.json).map_err(InvokeError::from_anyhow) }),
 			#[cfg(process_any)]
 			Self::Process(cmd) => resolver.respond_async(async move { cmd.run(context).and_then(|r| r.json).map_err(InvokeError::from_anyhow) }),
+			#[cfg(secrets_any)]
+			Self::Secrets(cmd) => resolver.respond_async(async move { cmd.run(context).and_then(|r| r.json).map_err(InvokeError::from_anyhow) }),
 			#[cfg(fs_any)]
 			Self::Fs(cmd) => resolver.respond_async(async move { cmd.run(context).and_then(|r| r.json).map_err(InvokeError::from_anyhow) }),
 			#[cfg(path_any)]
@@ -0,0 +1,76 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(unused_imports)]
+
+use millennium_macros::{command_enum, module_command_handler, CommandModule};
+use serde::Deserialize;
+
+use super::InvokeContext;
+use crate::{api::autostart::AutoLaunch, Env, Manager, Runtime};
+
+/// The API descriptor.
+#[command_enum]
+#[derive(Deserialize, CommandModule)]
+#[serde(tag = "cmd", rename_all = "camelCase")]
+pub enum Cmd {
+	/// Registers the application to be launched at login.
+	#[cmd(autostart_enable, "autostart > enable")]
+	Enable,
+	/// Unregisters the application from being launched at login.
+	#[cmd(autostart_disable, "autostart > disable")]
+	Disable,
+	/// Checks whether the application is registered to be launched at login.
+	#[cmd(autostart_is_enabled, "autostart > isEnabled")]
+	IsEnabled
+}
+
+impl Cmd {
+	#[module_command_handler(autostart_enable)]
+	fn enable<R: Runtime>(context: InvokeContext<R>) -> super::Result<()> {
+		auto_launch(&context)?.enable()?;
+		Ok(())
+	}
+
+	#[module_command_handler(autostart_disable)]
+	fn disable<R: Runtime>(context: InvokeContext<R>) -> super::Result<()> {
+		auto_launch(&context)?.disable()?;
+		Ok(())
+	}
+
+	#[module_command_handler(autostart_is_enabled)]
+	fn is_enabled<R: Runtime>(context: InvokeContext<R>) -> super::Result<bool> {
+		auto_launch(&context)?.is_enabled().map_err(Into::into)
+	}
+}
+
+fn auto_launch<R: Runtime>(context: &InvokeContext<R>) -> crate::api::Result<AutoLaunch> {
+	let args = context.config.millennium.allowlist.autostart.args.clone();
+	AutoLaunch::for_app(&context.config, &context.package_info, context.window.state::<Env>().inner()).map(|auto_launch| auto_launch.args(args))
+}
+
+#[cfg(test)]
+mod tests {
+	#[millennium_macros::module_command_test(autostart_enable, "autostart > enable")]
+	#[quickcheck_macros::quickcheck]
+	fn enable() {}
+
+	#[millennium_macros::module_command_test(autostart_disable, "autostart > disable")]
+	#[quickcheck_macros::quickcheck]
+	fn disable() {}
+
+	#[millennium_macros::module_command_test(autostart_is_enabled, "autostart > isEnabled")]
+	#[quickcheck_macros::quickcheck]
+	fn is_enabled() {}
+}
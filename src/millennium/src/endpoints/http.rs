@@ -16,10 +16,12 @@
 
 #![allow(unused_imports)]
 
-#[cfg(http_request)]
+#[cfg(any(http_request, http_websocket))]
 use std::{
 	collections::HashMap,
-	sync::{Arc, Mutex}
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant}
 };
 
 use millennium_macros::{command_enum, module_command_handler, CommandModule};
@@ -27,19 +29,55 @@ use serde::Deserialize;
 
 use super::InvokeContext;
 #[cfg(http_request)]
-use crate::api::http::{ClientBuilder, HttpRequestBuilder, ResponseData};
+use crate::api::http::{ClientBuilder, DownloadOptions, HttpRequestBuilder, MultipartUpload, Progress, ResponseData};
 use crate::Runtime;
 #[cfg(not(http_request))]
-type ClientBuilder = ();
+type ClientOptions = ();
 #[cfg(not(http_request))]
 type HttpRequestBuilder = ();
 #[cfg(not(http_request))]
+type DownloadOptions = ();
+#[cfg(not(http_request))]
+type MultipartUpload = ();
+#[cfg(not(http_request))]
 #[allow(dead_code)]
 type ResponseData = ();
+#[cfg(http_websocket)]
+use crate::api::websocket::{CloseFrame, ConnectOptions, Message, WebSocket, WebSocketSender};
+#[cfg(not(http_websocket))]
+type ConnectOptions = ();
+#[cfg(not(http_websocket))]
+type Message = ();
+#[cfg(not(http_websocket))]
+type CloseFrame = ();
+
+/// The options of a client created by the frontend.
+#[cfg(http_request)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientOptions {
+	#[serde(flatten)]
+	builder: ClientBuilder,
+	/// Persist cookies in the app data directory.
+	#[serde(default)]
+	cookies: bool,
+	/// Cache `GET` responses in the app cache directory.
+	#[serde(default)]
+	cache: bool
+}
+
+/// A client created by the frontend.
+#[cfg(http_request)]
+#[derive(Clone)]
+struct WebviewClient {
+	client: crate::api::http::Client,
+	/// Merged into the headers of each request before the HTTP scope restricts them, so they can't bypass it.
+	default_headers: http::HeaderMap
+}
 
 type ClientId = u32;
 #[cfg(http_request)]
-type ClientStore = Arc<Mutex<HashMap<ClientId, crate::api::http::Client>>>;
+type ClientStore = Arc<Mutex<HashMap<ClientId, WebviewClient>>>;
 
 #[cfg(http_request)]
 fn clients() -> &'static ClientStore {
@@ -48,6 +86,146 @@ fn clients() -> &'static ClientStore {
 	&STORE
 }
 
+type TransferId = u32;
+
+/// The minimum interval between two progress events of a transfer.
+#[cfg(http_request)]
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Emits an event to the given window only; [`crate::Window::emit`] delivers it to every window listening to it.
+#[cfg(any(http_request, http_websocket))]
+fn emit_to_window<R: Runtime, S: serde::Serialize + Clone>(window: &crate::Window<R>, event: &str, payload: S) {
+	use crate::Manager;
+	let _ = window.emit_to(window.label(), event, payload);
+}
+
+/// Emits the progress of a transfer to the window as the `millennium://http/progress/<id>` event.
+#[cfg(http_request)]
+fn progress_emitter<R: Runtime>(window: crate::Window<R>, id: TransferId) -> impl FnMut(Progress) + Send + 'static {
+	let event = format!("millennium://http/progress/{id}");
+	let mut last_emit: Option<Instant> = None;
+	move |progress| {
+		let done = progress.total == Some(progress.transferred);
+		if done || last_emit.map_or(true, |instant| instant.elapsed() >= PROGRESS_INTERVAL) {
+			last_emit.replace(Instant::now());
+			emit_to_window(&window, &event, progress);
+		}
+	}
+}
+
+#[cfg(http_request)]
+fn is_path_allowed(scopes: &crate::Scopes, path: &Path) -> bool {
+	crate::api::file::SafePathBuf::new(path.to_path_buf()).is_ok() && scopes.fs.is_allowed(path)
+}
+
+/// Checks a request against the HTTP scope, merges the default headers of the client into it, and applies the
+/// header restrictions of the rule allowing it to the result.
+#[cfg(http_request)]
+fn authorize_request(scope: &crate::scope::HttpScope, client_id: ClientId, request: &mut HttpRequestBuilder) -> crate::Result<crate::api::http::Client> {
+	let rule = http::Method::from_bytes(request.method.to_uppercase().as_bytes())
+		.ok()
+		.and_then(|method| scope.rule_for(&method, &request.url))
+		.ok_or_else(|| crate::Error::UrlNotAllowed(request.url.clone()))?;
+	let client = clients()
+		.lock()
+		.unwrap()
+		.get(&client_id)
+		.cloned()
+		.ok_or(crate::Error::HttpClientNotInitialized)?;
+
+	let headers = &mut request.headers.get_or_insert_with(Default::default).0;
+	for (name, value) in &client.default_headers {
+		if !headers.contains_key(name) {
+			headers.insert(name.clone(), value.clone());
+		}
+	}
+	rule.apply(headers);
+	Ok(client.client)
+}
+
+type ConnectionId = u32;
+
+/// A WebSocket connection opened by the frontend.
+#[cfg(http_websocket)]
+struct WebviewConnection {
+	/// The label of the window that opened the connection; other windows can't use it.
+	window: String,
+	/// `None` while connecting.
+	sender: Option<Arc<tokio::sync::Mutex<WebSocketSender>>>,
+	/// The task emitting the received messages to the window.
+	reader: Option<crate::async_runtime::JoinHandle<()>>
+}
+
+#[cfg(http_websocket)]
+type ConnectionStore = Arc<Mutex<HashMap<ConnectionId, WebviewConnection>>>;
+
+#[cfg(http_websocket)]
+fn connections() -> &'static ConnectionStore {
+	use once_cell::sync::Lazy;
+	static STORE: Lazy<ConnectionStore> = Lazy::new(Default::default);
+	&STORE
+}
+
+/// Gets the sender of a connection opened by the given window.
+#[cfg(http_websocket)]
+fn connection_sender(window: &str, id: ConnectionId) -> crate::Result<Arc<tokio::sync::Mutex<WebSocketSender>>> {
+	connections()
+		.lock()
+		.unwrap()
+		.get(&id)
+		.filter(|connection| connection.window == window)
+		.and_then(|connection| connection.sender.clone())
+		.ok_or(crate::Error::WebSocketNotConnected(id))
+}
+
+/// Closes the connections opened by the given window.
+#[cfg(http_websocket)]
+fn close_connections(window: &str) {
+	let closed = {
+		let mut connections = connections().lock().unwrap();
+		let ids = connections
+			.iter()
+			.filter(|(_, connection)| connection.window == window)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		ids.into_iter().filter_map(|id| connections.remove(&id)).collect::<Vec<_>>()
+	};
+	for connection in closed {
+		if let Some(reader) = connection.reader {
+			reader.abort();
+		}
+		if let Some(sender) = connection.sender {
+			crate::async_runtime::spawn(async move {
+				let _ = sender.lock().await.close(None).await;
+			});
+		}
+	}
+}
+
+/// Closes the connections of the window when it is destroyed.
+#[cfg(http_websocket)]
+fn close_connections_on_destroy<R: Runtime>(window: &crate::Window<R>) {
+	use once_cell::sync::Lazy;
+	static WATCHED_WINDOWS: Lazy<Mutex<std::collections::HashSet<String>>> = Lazy::new(Default::default);
+
+	let label = window.label().to_string();
+	if WATCHED_WINDOWS.lock().unwrap().insert(label.clone()) {
+		window.on_window_event(move |event| {
+			if let crate::WindowEvent::Destroyed = event {
+				WATCHED_WINDOWS.lock().unwrap().remove(&label);
+				close_connections(&label);
+			}
+		});
+	}
+}
+
+/// The close message emitted when a connection is lost without a close handshake.
+#[cfg(http_websocket)]
+fn abnormal_closure(reason: String) -> Message {
+	// https://www.rfc-editor.org/rfc/rfc6455#section-7.4.1
+	Message::Close(Some(CloseFrame { code: 1006, reason }))
+}
+
 /// The API descriptor.
 #[command_enum]
 #[derive(Deserialize, CommandModule)]
@@ -56,19 +234,74 @@ fn clients() -> &'static ClientStore {
 pub enum Cmd {
 	/// Create a new HTTP client.
 	#[cmd(http_request, "http > request")]
-	CreateClient { options: Option<ClientBuilder> },
+	CreateClient { options: Option<ClientOptions> },
 	/// Drop a HTTP client.
 	#[cmd(http_request, "http > request")]
 	DropClient { client: ClientId },
 	/// The HTTP request API.
 	#[cmd(http_request, "http > request")]
-	HttpRequest { client: ClientId, options: Box<HttpRequestBuilder> }
+	HttpRequest { client: ClientId, options: Box<HttpRequestBuilder> },
+	/// Download the response of a request to a file. Its progress is emitted to the
+	/// calling window as the `millennium://http/progress/<id>` event.
+	#[cmd(http_request, "http > request")]
+	Download {
+		client: ClientId,
+		id: TransferId,
+		options: Box<HttpRequestBuilder>,
+		path: std::path::PathBuf,
+		download: Option<DownloadOptions>
+	},
+	/// Upload a file as a multipart request. Its progress is emitted to the
+	/// calling window as the `millennium://http/progress/<id>` event.
+	#[cmd(http_request, "http > request")]
+	Upload {
+		client: ClientId,
+		id: TransferId,
+		options: Box<HttpRequestBuilder>,
+		upload: MultipartUpload
+	},
+	/// Connect to a WebSocket server. Its messages are emitted to the calling
+	/// window as the `millennium://websocket/<id>` event.
+	#[cmd(http_websocket, "http > websocket")]
+	WebsocketConnect {
+		id: ConnectionId,
+		url: url::Url,
+		options: Option<ConnectOptions>
+	},
+	/// Send a message on a WebSocket connection.
+	#[cmd(http_websocket, "http > websocket")]
+	WebsocketSend { id: ConnectionId, message: Message },
+	/// Close a WebSocket connection.
+	#[cmd(http_websocket, "http > websocket")]
+	WebsocketClose { id: ConnectionId, frame: Option<CloseFrame> }
 }
 
 impl Cmd {
 	#[module_command_handler(http_request)]
-	async fn create_client<R: Runtime>(_context: InvokeContext<R>, options: Option<ClientBuilder>) -> super::Result<ClientId> {
-		let client = options.unwrap_or_default().build()?;
+	async fn create_client<R: Runtime>(context: InvokeContext<R>, options: Option<ClientOptions>) -> super::Result<ClientId> {
+		use crate::Manager;
+		let scopes = context.window.state::<crate::Scopes>();
+		let ClientOptions { mut builder, cookies, cache } = options.unwrap_or_default();
+		// the default headers are applied by `authorize_request`, where the HTTP scope can restrict them
+		let default_headers = builder.default_headers.take().map(|headers| headers.0).unwrap_or_default();
+		let identity = builder.identity.as_ref().map(|identity| &identity.path);
+		for path in builder.root_certificates.iter().chain(identity) {
+			if !is_path_allowed(&scopes, path) {
+				return Err(crate::Error::PathNotAllowed(path.clone()).into_anyhow());
+			}
+		}
+		if cookies {
+			let dir = crate::api::path::app_data_dir(&context.config).ok_or_else(|| anyhow::anyhow!("could not resolve the app data directory"))?;
+			builder = builder.cookie_jar(dir.join("http").join("cookies.json"));
+		}
+		if cache {
+			let dir = crate::api::path::app_cache_dir(&context.config).ok_or_else(|| anyhow::anyhow!("could not resolve the app cache directory"))?;
+			builder = builder.cache_dir(dir.join("http"));
+		}
+		let client = WebviewClient {
+			client: builder.build()?,
+			default_headers
+		};
 		let mut store = clients().lock().unwrap();
 		let id = rand::random::<ClientId>();
 		store.insert(id, client);
@@ -86,45 +319,220 @@ impl Cmd {
 	async fn http_request<R: Runtime>(context: InvokeContext<R>, client_id: ClientId, options: Box<HttpRequestBuilder>) -> super::Result<ResponseData> {
 		use crate::Manager;
 		let scopes = context.window.state::<crate::Scopes>();
-		if scopes.http.is_allowed(&options.url) {
-			let client = clients()
-				.lock()
-				.unwrap()
-				.get(&client_id)
-				.ok_or_else(|| crate::Error::HttpClientNotInitialized.into_anyhow())?
-				.clone();
-			let options = *options;
-			if let Some(crate::api::http::Body::Form(form)) = &options.body {
-				for value in form.0.values() {
-					if let crate::api::http::FormPart::File {
-						file: crate::api::http::FilePart::Path(path),
-						..
-					} = value
-					{
-						if crate::api::file::SafePathBuf::new(path.clone()).is_err() || !scopes.fs.is_allowed(path) {
-							return Err(crate::Error::PathNotAllowed(path.clone()).into_anyhow());
+		let mut options = *options;
+		let client = authorize_request(&scopes.http, client_id, &mut options).map_err(crate::Error::into_anyhow)?;
+		if let Some(crate::api::http::Body::Form(form)) = &options.body {
+			for value in form.0.values() {
+				if let crate::api::http::FormPart::File {
+					file: crate::api::http::FilePart::Path(path),
+					..
+				} = value
+				{
+					if !is_path_allowed(&scopes, path) {
+						return Err(crate::Error::PathNotAllowed(path.clone()).into_anyhow());
+					}
+				}
+			}
+		}
+		let response = client.send(options).await?;
+		Ok(response.read().await?)
+	}
+
+	#[module_command_handler(http_request)]
+	async fn download<R: Runtime>(
+		context: InvokeContext<R>,
+		client_id: ClientId,
+		id: TransferId,
+		options: Box<HttpRequestBuilder>,
+		path: PathBuf,
+		download: Option<DownloadOptions>
+	) -> super::Result<()> {
+		use crate::Manager;
+		let scopes = context.window.state::<crate::Scopes>();
+		let mut options = *options;
+		let client = authorize_request(&scopes.http, client_id, &mut options).map_err(crate::Error::into_anyhow)?;
+		if !is_path_allowed(&scopes, &path) {
+			return Err(crate::Error::PathNotAllowed(path).into_anyhow());
+		}
+
+		client
+			.download(options, &path, download.unwrap_or_default(), progress_emitter(context.window.clone(), id))
+			.await?;
+		Ok(())
+	}
+
+	#[module_command_handler(http_request)]
+	async fn upload<R: Runtime>(
+		context: InvokeContext<R>,
+		client_id: ClientId,
+		id: TransferId,
+		options: Box<HttpRequestBuilder>,
+		upload: MultipartUpload
+	) -> super::Result<ResponseData> {
+		use crate::Manager;
+		let scopes = context.window.state::<crate::Scopes>();
+		let mut options = *options;
+		let client = authorize_request(&scopes.http, client_id, &mut options).map_err(crate::Error::into_anyhow)?;
+		if !is_path_allowed(&scopes, &upload.path) {
+			return Err(crate::Error::PathNotAllowed(upload.path).into_anyhow());
+		}
+
+		let response = client.upload(options, upload, progress_emitter(context.window.clone(), id)).await?;
+		Ok(response.read().await?)
+	}
+
+	#[module_command_handler(http_websocket)]
+	async fn websocket_connect<R: Runtime>(context: InvokeContext<R>, id: ConnectionId, url: url::Url, options: Option<ConnectOptions>) -> super::Result<()> {
+		use std::collections::hash_map::Entry;
+
+		use crate::Manager;
+		let scopes = context.window.state::<crate::Scopes>();
+		let rule = match scopes.http.rule_for(&http::Method::GET, &url) {
+			Some(rule) => rule,
+			None => return Err(crate::Error::UrlNotAllowed(url).into_anyhow())
+		};
+		let mut options = options.unwrap_or_default();
+		options
+			.headers
+			.retain(|name, _| http::header::HeaderName::from_bytes(name.as_bytes()).map_or(true, |name| rule.allows_header(&name)));
+		for (name, value) in rule.forced_headers() {
+			if let Ok(value) = value.to_str() {
+				options.headers.insert(name.to_string(), value.into());
+			}
+		}
+
+		// reserve the id first, so that it can't be connected twice
+		match connections().lock().unwrap().entry(id) {
+			Entry::Occupied(_) => return Err(crate::Error::WebSocketAlreadyConnected(id).into_anyhow()),
+			Entry::Vacant(entry) => {
+				entry.insert(WebviewConnection {
+					window: context.window.label().into(),
+					sender: None,
+					reader: None
+				});
+			}
+		}
+		close_connections_on_destroy(&context.window);
+
+		let (sender, mut receiver) = match WebSocket::connect(&url, options).await {
+			Ok(socket) => socket.split(),
+			Err(e) => {
+				connections().lock().unwrap().remove(&id);
+				return Err(e.into());
+			}
+		};
+		let sender = Arc::new(tokio::sync::Mutex::new(sender));
+		match connections().lock().unwrap().get_mut(&id) {
+			Some(connection) => connection.sender = Some(sender.clone()),
+			// the window was destroyed while connecting
+			None => {
+				crate::async_runtime::spawn(async move {
+					let _ = sender.lock().await.close(None).await;
+				});
+				return Ok(());
+			}
+		}
+
+		let window = context.window;
+		let event = format!("millennium://websocket/{id}");
+		let reader = crate::async_runtime::spawn(async move {
+			let mut closed = false;
+			while let Some(message) = receiver.next().await {
+				match message {
+					Ok(message) => {
+						closed |= matches!(message, Message::Close(_));
+						emit_to_window(&window, &event, message);
+					}
+					Err(e) => {
+						if !closed {
+							closed = true;
+							emit_to_window(&window, &event, abnormal_closure(e.to_string()));
 						}
+						break;
 					}
 				}
 			}
-			let response = client.send(options).await?;
-			Ok(response.read().await?)
-		} else {
-			Err(crate::Error::UrlNotAllowed(options.url).into_anyhow())
+			if !closed {
+				emit_to_window(&window, &event, abnormal_closure("the connection was closed without a close frame".into()));
+			}
+			connections().lock().unwrap().remove(&id);
+		});
+		match connections().lock().unwrap().get_mut(&id) {
+			Some(connection) => connection.reader = Some(reader),
+			None => reader.abort()
 		}
+		Ok(())
+	}
+
+	#[module_command_handler(http_websocket)]
+	async fn websocket_send<R: Runtime>(context: InvokeContext<R>, id: ConnectionId, message: Message) -> super::Result<()> {
+		let sender = connection_sender(context.window.label(), id).map_err(crate::Error::into_anyhow)?;
+		let mut sender = sender.lock().await;
+		sender.send(message).await?;
+		Ok(())
+	}
+
+	#[module_command_handler(http_websocket)]
+	async fn websocket_close<R: Runtime>(context: InvokeContext<R>, id: ConnectionId, frame: Option<CloseFrame>) -> super::Result<()> {
+		let sender = connection_sender(context.window.label(), id).map_err(crate::Error::into_anyhow)?;
+		let mut sender = sender.lock().await;
+		sender.close(frame).await?;
+		Ok(())
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{ClientBuilder, ClientId};
+	use super::{ClientId, ClientOptions, ConnectionId, TransferId};
+
+	#[cfg(http_request)]
+	impl quickcheck::Arbitrary for ClientOptions {
+		fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+			Self {
+				builder: quickcheck::Arbitrary::arbitrary(g),
+				..Default::default()
+			}
+		}
+	}
 
 	#[millennium_macros::module_command_test(http_request, "http > request")]
 	#[quickcheck_macros::quickcheck]
-	fn create_client(options: Option<ClientBuilder>) {
+	fn create_client(options: Option<ClientOptions>) {
 		crate::async_runtime::block_on(super::Cmd::create_client(crate::test::mock_invoke_context(), options)).unwrap();
 	}
 
+	#[cfg(http_request)]
+	#[test]
+	fn scope_restricts_default_headers() {
+		use millennium_utils::config::{HttpAllowlistScope, HttpScopeEntry};
+
+		let scope = crate::scope::HttpScope::for_http_api(&HttpAllowlistScope::AllowedUrls(vec![HttpScopeEntry::Rule {
+			url: "https://api.example.com/**".parse().unwrap(),
+			methods: None,
+			headers: Some(vec!["Accept".into()]),
+			forced_headers: Default::default()
+		}]))
+		.unwrap();
+		let mut default_headers = http::HeaderMap::new();
+		default_headers.insert("accept", "application/json".parse().unwrap());
+		default_headers.insert("authorization", "Bearer page".parse().unwrap());
+		let id = rand::random::<ClientId>();
+		super::clients().lock().unwrap().insert(
+			id,
+			super::WebviewClient {
+				client: crate::api::http::ClientBuilder::new().build().unwrap(),
+				default_headers
+			}
+		);
+
+		let mut request = crate::api::http::HttpRequestBuilder::new("GET", "https://api.example.com/users").unwrap();
+		super::authorize_request(&scope, id, &mut request).unwrap();
+		let headers = request.headers.unwrap().0;
+		assert_eq!(headers.len(), 1);
+		assert_eq!(headers["accept"], "application/json");
+		super::clients().lock().unwrap().remove(&id);
+	}
+
 	#[millennium_macros::module_command_test(http_request, "http > request")]
 	#[quickcheck_macros::quickcheck]
 	fn drop_client(client_id: ClientId) {
@@ -134,4 +542,52 @@ mod tests {
 			super::Cmd::drop_client(crate::test::mock_invoke_context(), id).await.unwrap();
 		});
 	}
+
+	#[millennium_macros::module_command_test(http_request, "http > request")]
+	#[quickcheck_macros::quickcheck]
+	fn download(client_id: ClientId, id: TransferId) {
+		// the mock context has an empty HTTP scope
+		let options = Box::new(crate::api::http::HttpRequestBuilder::new("GET", "https://example.com/file").unwrap());
+		let result = crate::async_runtime::block_on(super::Cmd::download(crate::test::mock_invoke_context(), client_id, id, options, "file".into(), None));
+		assert!(result.unwrap_err().to_string().contains("url not allowed"));
+	}
+
+	#[millennium_macros::module_command_test(http_websocket, "http > websocket")]
+	#[quickcheck_macros::quickcheck]
+	fn websocket_send(id: ConnectionId) {
+		let result = crate::async_runtime::block_on(super::Cmd::websocket_send(crate::test::mock_invoke_context(), id, super::Message::Text("hello".into())));
+		assert!(result.unwrap_err().to_string().contains("not connected"));
+	}
+
+	#[millennium_macros::module_command_test(http_websocket, "http > websocket")]
+	#[quickcheck_macros::quickcheck]
+	fn websocket_connect(id: ConnectionId) {
+		// the mock context has an empty HTTP scope
+		let url: url::Url = "ws://localhost:8080".parse().unwrap();
+		let result = crate::async_runtime::block_on(super::Cmd::websocket_connect(crate::test::mock_invoke_context(), id, url, None));
+		assert!(result.unwrap_err().to_string().contains("url not allowed"));
+	}
+
+	#[cfg(http_websocket)]
+	#[test]
+	fn closes_window_connections() {
+		let reserve = |id: ConnectionId, window: &str| {
+			super::connections().lock().unwrap().insert(
+				id,
+				super::WebviewConnection {
+					window: window.into(),
+					sender: None,
+					reader: None
+				}
+			);
+		};
+		let (closed, kept) = (ConnectionId::MAX, ConnectionId::MAX - 1);
+		reserve(closed, "destroyed");
+		reserve(kept, "main");
+
+		super::close_connections("destroyed");
+		let connections = super::connections().lock().unwrap();
+		assert!(!connections.contains_key(&closed));
+		assert!(connections.contains_key(&kept));
+	}
 }
@@ -0,0 +1,92 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(unused_imports)]
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex}
+};
+
+use millennium_macros::{command_enum, module_command_handler, CommandModule};
+use serde::Deserialize;
+
+use super::InvokeContext;
+use crate::{api::secrets::Secrets, Config, Runtime};
+
+/// Opens the secret store of the application on first use, so that every command reuses the same Secret Service
+/// connection.
+#[cfg(secrets_any)]
+fn secrets(config: &Config) -> crate::api::Result<Arc<Secrets>> {
+	use once_cell::sync::Lazy;
+	static STORE: Lazy<Mutex<HashMap<String, Arc<Secrets>>>> = Lazy::new(Default::default);
+
+	let mut store = STORE.lock().unwrap();
+	let namespace = &config.millennium.bundle.identifier;
+	if let Some(secrets) = store.get(namespace) {
+		return Ok(secrets.clone());
+	}
+	let secrets = Arc::new(Secrets::new(config)?);
+	store.insert(namespace.clone(), secrets.clone());
+	Ok(secrets)
+}
+
+/// The API descriptor.
+#[command_enum]
+#[derive(Deserialize, CommandModule)]
+#[serde(tag = "cmd", rename_all = "camelCase")]
+pub enum Cmd {
+	/// Reads a secret.
+	#[cmd(secrets_get, "secrets > get")]
+	Get { service: String, account: String },
+	/// Stores a secret.
+	#[cmd(secrets_set, "secrets > set")]
+	Set { service: String, account: String, secret: String },
+	/// Deletes a secret.
+	#[cmd(secrets_delete, "secrets > delete")]
+	Delete { service: String, account: String }
+}
+
+impl Cmd {
+	#[module_command_handler(secrets_get)]
+	fn get<R: Runtime>(context: InvokeContext<R>, service: String, account: String) -> super::Result<Option<String>> {
+		secrets(&context.config)?.get(&service, &account).map_err(Into::into)
+	}
+
+	#[module_command_handler(secrets_set)]
+	fn set<R: Runtime>(context: InvokeContext<R>, service: String, account: String, secret: String) -> super::Result<()> {
+		secrets(&context.config)?.set(&service, &account, &secret)?;
+		Ok(())
+	}
+
+	#[module_command_handler(secrets_delete)]
+	fn delete<R: Runtime>(context: InvokeContext<R>, service: String, account: String) -> super::Result<bool> {
+		secrets(&context.config)?.delete(&service, &account).map_err(Into::into)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	#[millennium_macros::module_command_test(secrets_get, "secrets > get")]
+	#[quickcheck_macros::quickcheck]
+	fn get(_service: String, _account: String) {}
+
+	#[millennium_macros::module_command_test(secrets_set, "secrets > set")]
+	#[quickcheck_macros::quickcheck]
+	fn set(_service: String, _account: String, _secret: String) {}
+
+	#[millennium_macros::module_command_test(secrets_delete, "secrets > delete")]
+	#[quickcheck_macros::quickcheck]
+	fn delete(_service: String, _account: String) {}
+}
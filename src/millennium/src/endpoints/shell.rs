@@ -27,6 +27,10 @@ use crate::{api::ipc::CallbackFn, Runtime};
 use crate::{Manager, Scopes};
 #[cfg(not(shell_scope))]
 type ExecuteArgs = ();
+#[cfg(any(shell_execute, shell_sidecar))]
+use crate::api::process::PtySize;
+#[cfg(not(any(shell_execute, shell_sidecar)))]
+type PtySize = ();
 
 #[cfg(any(shell_execute, shell_sidecar))]
 use std::sync::{Arc, Mutex};
@@ -67,7 +71,9 @@ pub struct CommandOptions {
 	#[serde(default = "default_env")]
 	env: Option<HashMap<String, String>>,
 	// Character encoding for stdout/stderr
-	encoding: Option<String>
+	encoding: Option<String>,
+	// Spawn the command in a pseudo-terminal of this size
+	pty: Option<PtySize>
 }
 
 /// The API descriptor.
@@ -89,6 +95,8 @@ pub enum Cmd {
 	StdinWrite { pid: ChildId, buffer: Buffer },
 	#[cmd(shell_script, "shell > execute or shell > sidecar")]
 	KillChild { pid: ChildId },
+	#[cmd(shell_script, "shell > execute or shell > sidecar")]
+	ResizePty { pid: ChildId, size: PtySize },
 	#[cmd(shell_open, "shell > open")]
 	Open { path: String, with: Option<String> }
 }
@@ -160,6 +168,14 @@ impl Cmd {
 					return Err(anyhow::anyhow!(format!("unknown encoding {encoding}")));
 				}
 			}
+			if let Some(size) = options.pty {
+				#[cfg(feature = "process-pty")]
+				{
+					command = command.pty(size);
+				}
+				#[cfg(not(feature = "process-pty"))]
+				return Err(anyhow::anyhow!("spawning commands in a pseudo-terminal requires the `process-pty` feature"));
+			}
 			let (mut rx, child) = command.spawn()?;
 
 			let pid = child.pid();
@@ -199,6 +215,16 @@ impl Cmd {
 		Ok(())
 	}
 
+	#[module_command_handler(shell_script)]
+	#[allow(unused_variables)]
+	fn resize_pty<R: Runtime>(_context: InvokeContext<R>, pid: ChildId, size: PtySize) -> super::Result<()> {
+		#[cfg(feature = "process-pty")]
+		if let Some(child) = command_childs().lock().unwrap().get(&pid) {
+			child.resize(size)?;
+		}
+		Ok(())
+	}
+
 	/// Open a (url) path with a default or specific browser opening program.
 	///
 	/// See [`crate::api::shell::open`] for how it handles security-related
@@ -221,7 +247,7 @@ impl Cmd {
 mod tests {
 	use quickcheck::{Arbitrary, Gen};
 
-	use super::{Buffer, ChildId, CommandOptions, ExecuteArgs};
+	use super::{Buffer, ChildId, CommandOptions, ExecuteArgs, PtySize};
 	use crate::api::ipc::CallbackFn;
 
 	impl Arbitrary for CommandOptions {
@@ -230,7 +256,8 @@ mod tests {
 				sidecar: false,
 				cwd: Option::arbitrary(g),
 				env: Option::arbitrary(g),
-				encoding: Option::arbitrary(g)
+				encoding: Option::arbitrary(g),
+				pty: None
 			}
 		}
 	}
@@ -241,6 +268,16 @@ mod tests {
 		}
 	}
 
+	#[cfg(any(shell_execute, shell_sidecar))]
+	impl Arbitrary for PtySize {
+		fn arbitrary(g: &mut Gen) -> Self {
+			Self {
+				rows: u16::arbitrary(g),
+				cols: u16::arbitrary(g)
+			}
+		}
+	}
+
 	#[cfg(shell_scope)]
 	impl Arbitrary for ExecuteArgs {
 		fn arbitrary(_: &mut Gen) -> Self {
@@ -260,6 +297,10 @@ mod tests {
 	#[quickcheck_macros::quickcheck]
 	fn kill_child(_pid: ChildId) {}
 
+	#[millennium_macros::module_command_test(shell_execute, "shell > execute or shell > sidecar")]
+	#[quickcheck_macros::quickcheck]
+	fn resize_pty(_pid: ChildId, _size: PtySize) {}
+
 	#[millennium_macros::module_command_test(shell_open, "shell > open")]
 	#[quickcheck_macros::quickcheck]
 	fn open(_path: String, _with: Option<String>) {}
@@ -83,6 +83,12 @@ pub enum Error {
 	/// Client with specified ID not found.
 	#[error("http client dropped or not initialized")]
 	HttpClientNotInitialized,
+	/// WebSocket connection with specified ID not found.
+	#[error("WebSocket connection {0} closed or not connected")]
+	WebSocketNotConnected(u32),
+	/// WebSocket connection ID already in use.
+	#[error("WebSocket connection {0} already exists")]
+	WebSocketAlreadyConnected(u32),
 	/// API not whitelisted in config
 	#[error("The API '{0}' is not enabled in the allowlist; check your Cargo.toml & Millennium config file")]
 	ApiNotAllowlisted(String),
@@ -136,6 +142,9 @@ pub enum Error {
 	/// Invalid glob pattern.
 	#[error("invalid glob pattern: {0}")]
 	GlobPattern(#[from] glob::PatternError),
+	/// Invalid HTTP scope configuration.
+	#[error("invalid HTTP scope: {0}")]
+	InvalidHttpScope(String),
 	/// Error decoding PNG image.
 	#[cfg(feature = "icon-png")]
 	#[error("failed to decode PNG: {0}")]
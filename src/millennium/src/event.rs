@@ -15,6 +15,7 @@
 // limitations under the License.
 
 use std::{
+	any::Any,
 	boxed::Box,
 	cell::Cell,
 	collections::HashMap,
@@ -23,8 +24,11 @@ use std::{
 	sync::{Arc, Mutex}
 };
 
+use serde::{de::DeserializeOwned, Serialize};
 use uuid::Uuid;
 
+use crate::typescript::TsType;
+
 /// Checks if an event name is valid.
 pub fn is_event_name_valid(event: &str) -> bool {
 	event.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '/' || c == ':' || c == '_')
@@ -44,11 +48,75 @@ impl fmt::Display for EventHandler {
 	}
 }
 
+/// An event with a payload of a known type.
+///
+/// The trait ties an event name to its payload type, so the payload can be
+/// passed to the typed event APIs such as
+/// [`Manager::listen_global_typed`](crate::Manager::listen_global_typed) and
+/// [`Window::emit_typed`](crate::Window::emit_typed) without spelling the
+/// event name or (de)serializing it by hand. It is usually implemented with
+/// [`derive@TypedEvent`].
+///
+/// # Examples
+/// ```rust,no_run
+/// use millennium::{Manager, TsType, TypedEvent};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Clone, Serialize, Deserialize, TsType, TypedEvent)]
+/// #[event(name = "download-progress")]
+/// struct DownloadProgress {
+/// 	downloaded: u64
+/// }
+///
+/// millennium::Builder::default().setup(|app| {
+/// 	app.listen_global_typed(|progress: DownloadProgress| {
+/// 		println!("downloaded {} bytes", progress.downloaded);
+/// 	});
+/// 	app.trigger_global_typed(DownloadProgress { downloaded: 42 })?;
+/// 	Ok(())
+/// });
+/// ```
+pub trait TypedEvent: Serialize + DeserializeOwned + TsType + Clone + Send + Sync + 'static {
+	/// The event name.
+	const NAME: &'static str;
+}
+
+/// The payload of a triggered event.
+///
+/// Payloads triggered from Rust with the typed APIs keep their value so typed
+/// listeners don't have to deserialize it.
+#[derive(Clone, Default)]
+pub(crate) struct Payload {
+	json: Option<String>,
+	value: Option<Arc<dyn Any + Send + Sync>>
+}
+
+impl Payload {
+	pub(crate) fn typed<E: TypedEvent>(payload: E) -> crate::Result<Self> {
+		Ok(Self {
+			json: Some(serde_json::to_string(&payload)?),
+			value: Some(Arc::new(payload))
+		})
+	}
+}
+
+impl From<Option<String>> for Payload {
+	fn from(json: Option<String>) -> Self {
+		Self { json, value: None }
+	}
+}
+
+impl fmt::Debug for Payload {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.json.fmt(f)
+	}
+}
+
 /// An event that was triggered.
 #[derive(Debug, Clone)]
 pub struct Event {
 	id: EventHandler,
-	data: Option<String>
+	data: Payload
 }
 
 impl Event {
@@ -59,7 +127,36 @@ impl Event {
 
 	/// The event payload.
 	pub fn payload(&self) -> Option<&str> {
-		self.data.as_deref()
+		self.data.json.as_deref()
+	}
+
+	/// The event payload as a value of the given event type.
+	///
+	/// Payloads triggered with the typed APIs are cloned, and payloads
+	/// triggered as JSON (e.g. from the webview) are deserialized. A missing
+	/// payload is deserialized from `null`.
+	pub fn typed_payload<E: TypedEvent>(&self) -> crate::Result<E> {
+		if let Some(value) = self.data.value.as_ref().and_then(|v| v.downcast_ref::<E>()) {
+			return Ok(value.clone());
+		}
+		serde_json::from_str(self.data.json.as_deref().unwrap_or("null")).map_err(Into::into)
+	}
+}
+
+/// Wraps a typed event handler so it can be registered as a regular handler.
+pub(crate) fn typed_handler<E: TypedEvent, F: Fn(E) + Send + 'static>(handler: F) -> impl Fn(Event) + Send + 'static {
+	move |event| match event.typed_payload::<E>() {
+		Ok(payload) => handler(payload),
+		Err(e) => tracing::warn!("failed to deserialize the payload of the `{}` event: {e}", E::NAME)
+	}
+}
+
+/// Wraps a typed one-off event handler so it can be registered as a regular
+/// handler.
+pub(crate) fn typed_once_handler<E: TypedEvent, F: FnOnce(E) + Send + 'static>(handler: F) -> impl FnOnce(Event) + Send + 'static {
+	move |event| match event.typed_payload::<E>() {
+		Ok(payload) => handler(payload),
+		Err(e) => tracing::warn!("failed to deserialize the payload of the `{}` event: {e}", E::NAME)
 	}
 }
 
@@ -67,7 +164,7 @@ impl Event {
 enum Pending {
 	Unlisten(EventHandler),
 	Listen(EventHandler, String, Handler),
-	Trigger(String, Option<String>, Option<String>)
+	Trigger(String, Option<String>, Payload)
 }
 
 /// Stored in [`Listeners`] to be called upon when the event that stored it is
@@ -186,7 +283,7 @@ impl Listeners {
 	}
 
 	/// Triggers the given global event with its payload.
-	pub(crate) fn trigger(&self, event: &str, window: Option<String>, payload: Option<String>) {
+	pub(crate) fn trigger(&self, event: &str, window: Option<String>, payload: Payload) {
 		let mut maybe_pending = false;
 		match self.inner.handlers.try_lock() {
 			Err(_) => self.insert_pending(Pending::Trigger(event.to_owned(), window, payload)),
@@ -273,7 +370,7 @@ mod test {
 			// call listen with e and the event_fn dummy func
 			listeners.listen(e.clone(), None, event_fn);
 			// call on event with e and d.
-			listeners.trigger(&e, None, Some(d));
+			listeners.trigger(&e, None, Some(d).into());
 
 			// lock the mutex
 			let l = listeners.inner.handlers.lock().unwrap();
@@ -282,6 +379,45 @@ mod test {
 			assert!(l.contains_key(&key));
 		}
 	}
+
+	#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+	struct Progress {
+		downloaded: u64
+	}
+
+	impl TsType for Progress {
+		fn ts_type() -> String {
+			"Progress".into()
+		}
+	}
+
+	impl TypedEvent for Progress {
+		const NAME: &'static str = "progress";
+	}
+
+	#[test]
+	fn typed_payload() {
+		let listeners: Listeners = Default::default();
+		let received = Arc::new(Mutex::new(Vec::new()));
+		let received_ = received.clone();
+		listeners.listen(Progress::NAME.into(), None, typed_handler(move |progress: Progress| received_.lock().unwrap().push(progress)));
+		let raw = Arc::new(Mutex::new(Vec::new()));
+		let raw_ = raw.clone();
+		listeners.listen(Progress::NAME.into(), None, move |event| raw_.lock().unwrap().push(event.payload().map(ToString::to_string)));
+
+		// typed payloads are handed to typed listeners as is, and serialized once for the others
+		listeners.trigger(Progress::NAME, None, Payload::typed(Progress { downloaded: 1 }).unwrap());
+		// JSON payloads, e.g. from the webview, are deserialized
+		listeners.trigger(Progress::NAME, None, Some(r#"{"downloaded":2}"#.to_string()).into());
+		// invalid payloads are not passed to typed listeners
+		listeners.trigger(Progress::NAME, None, Some(r#"{"uploaded":3}"#.to_string()).into());
+
+		assert_eq!(*received.lock().unwrap(), vec![Progress { downloaded: 1 }, Progress { downloaded: 2 }]);
+		assert_eq!(
+			*raw.lock().unwrap(),
+			vec![Some(r#"{"downloaded":1}"#.to_string()), Some(r#"{"downloaded":2}"#.to_string()), Some(r#"{"uploaded":3}"#.to_string())]
+		);
+	}
 }
 
 pub fn unlisten_js(listeners_object_name: String, event_name: String, event_id: u64) -> String {
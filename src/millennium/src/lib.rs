@@ -43,6 +43,7 @@
 //! - **shell-open-api**: Enables the [`api::shell`] module.
 //! - **http-api**: Enables the [`api::http`] module.
 //! - **http-multipart**: Adds support for `multipart/form-data` HTTP requests.
+//! - **websocket-api**: Enables the [`api::websocket`] module.
 //! - **reqwest-client**: Uses `reqwest` as HTTP client on the `http` APIs. Improves performance, but increases the
 //!   bundle size.
 //! - **native-tls-vendored**: Compile and statically link to a vendored copy of OpenSSL (applies to the default HTTP
@@ -50,12 +51,16 @@
 //! - **reqwest-native-tls-vendored**: Compile and statically link to a vendored copy of OpenSSL (applies to the
 //!   `reqwest` HTTP client).
 //! - **process-command-api**: Enables the [`api::process::Command`] APIs.
+//! - **process-pty**: Allows [`api::process::Command`]s to be spawned in a pseudo-terminal.
 //! - **global-shortcut**: Enables the global shortcut APIs.
 //! - **clipboard**: Enables the clipboard APIs.
 //! - **process-relaunch-dangerous-allow-symlink-macos**: Allows the [`api::process::current_binary`] function to allow
 //!   symlinks on macOS. **This is dangerous**, see the Security section in the function's documentation.
 //! - **dialog**: Enables the [`api::dialog`] module.
 //! - **notification**: Enables the [`api::notification`] module.
+//! - **autostart**: Enables the [`api::autostart`] module.
+//! - **secrets**: Enables the [`api::secrets`] module.
+//! - **log**: Enables the [`log`] module, a plugin that collects application and webview logs.
 //! - **fs-extract-api**: Enables the [`api::file::Extract`] API.
 //! - **cli**: Enables usage of `clap` for CLI argument parsing. Enabled by default if the `cli` config is defined on
 //!   the Millennium config file.
@@ -70,6 +75,8 @@
 //!   release builds, though it produces larger binaries.
 //! - **icon-ico**: Adds support to set `.ico` window icons. Enables [`Icon::File`] and [`Icon::Raw`] variants.
 //! - **icon-png**: Adds support to set `.png` window icons. Enables [`Icon::File`] and [`Icon::Raw`] variants.
+//! - **test**: Enables the [`test`] module, a mock runtime and harness to test the IPC of an application without a
+//!   webview.
 //!
 //! ## Cargo allowlist features
 //!
@@ -79,6 +86,13 @@
 //!
 //! - **api-all**: Enables all API endpoints.
 //!
+//! ### Autostart allowlist
+//!
+//! - **autostart-all**: Enables all Autostart APIs.
+//! - **autostart-enable**: Enables the `enable` API.
+//! - **autostart-disable**: Enables the `disable` API.
+//! - **autostart-is-enabled**: Enables the `isEnabled` API.
+//!
 //! ### Clipboard allowlist
 //!
 //! - **clipboard-all**: Enables all [Clipboard APIs](https://tauri.studio/en/docs/api/js/modules/clipboard/).
@@ -116,6 +130,7 @@
 //!
 //! - **http-all**: Enables all [HTTP APIs](https://tauri.studio/en/docs/api/js/modules/http).
 //! - **http-request**: Enables the [`request` APIs](https://tauri.studio/en/docs/api/js/classes/http.client/).
+//! - **http-websocket**: Enables the `WebSocket` API.
 //!
 //! ### Notification allowlist
 //!
@@ -140,6 +155,13 @@
 //! - **protocol-all**: Enables all Protocol APIs.
 //! - **protocol-asset**: Enables the `asset` custom protocol.
 //!
+//! ### Secrets allowlist
+//!
+//! - **secrets-all**: Enables all Secrets APIs.
+//! - **secrets-get**: Enables the `get` API.
+//! - **secrets-set**: Enables the `set` API.
+//! - **secrets-delete**: Enables the `delete` API.
+//!
 //! ### Shell allowlist
 //!
 //! - **shell-all**: Enables all [Clipboard APIs](https://tauri.studio/en/docs/api/js/modules/shell).
@@ -187,7 +209,7 @@
 pub use embed_plist;
 /// The Millennium error enum.
 pub use error::Error;
-pub use millennium_macros::{command, generate_handler};
+pub use millennium_macros::{command, generate_bindings, generate_handler, TypedEvent};
 #[cfg(shell_scope)]
 #[doc(hidden)]
 pub use regex;
@@ -201,6 +223,9 @@ mod endpoints;
 mod error;
 mod event;
 mod hooks;
+#[cfg(feature = "log")]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "log")))]
+pub mod log;
 mod manager;
 mod pattern;
 pub mod plugin;
@@ -209,6 +234,7 @@ use millennium_runtime as runtime;
 /// The allowlist scopes.
 pub mod scope;
 mod state;
+pub mod typescript;
 #[cfg(updater)]
 #[cfg_attr(doc_cfg, doc(cfg(feature = "updater")))]
 pub mod updater;
@@ -261,7 +287,8 @@ pub use {
 };
 pub use {
 	self::app::WindowMenuEvent,
-	self::event::{Event, EventHandler},
+	self::event::{Event, EventHandler, TypedEvent},
+	self::typescript::TsType,
 	self::runtime::menu::{AboutMetadata, CustomMenuItem, Menu, MenuEntry, MenuItem, Submenu},
 	self::utils::TitleBarStyle,
 	self::window::menu::MenuEvent
@@ -634,6 +661,43 @@ pub trait Manager<R: Runtime>: sealed::ManagerBase<R> {
 		self.manager().trigger(event, None, data)
 	}
 
+	/// Emits a typed event to all windows.
+	fn emit_all_typed<E: TypedEvent>(&self, payload: E) -> Result<()> {
+		self.emit_all(E::NAME, payload)
+	}
+
+	/// Emits a typed event to a window with the specified label.
+	fn emit_to_typed<E: TypedEvent>(&self, label: &str, payload: E) -> Result<()> {
+		self.emit_to(label, E::NAME, payload)
+	}
+
+	/// Listen to a global typed event.
+	///
+	/// Payloads that can't be deserialized as `E` are logged and skipped.
+	fn listen_global_typed<E, F>(&self, handler: F) -> EventHandler
+	where
+		E: TypedEvent,
+		F: Fn(E) + Send + 'static
+	{
+		self.manager().listen(E::NAME.into(), None, event::typed_handler(handler))
+	}
+
+	/// Listen to a global typed event only once.
+	fn once_global_typed<E, F>(&self, handler: F) -> EventHandler
+	where
+		E: TypedEvent,
+		F: FnOnce(E) + Send + 'static
+	{
+		self.manager().once(E::NAME.into(), None, event::typed_once_handler(handler))
+	}
+
+	/// Trigger a global typed event.
+	///
+	/// Typed listeners receive the payload without it being serialized.
+	fn trigger_global_typed<E: TypedEvent>(&self, payload: E) -> Result<()> {
+		self.manager().trigger_typed(None, payload)
+	}
+
 	/// Remove an event listener.
 	fn unlisten(&self, handler_id: EventHandler) {
 		self.manager().unlisten(handler_id)
@@ -780,6 +844,12 @@ pub trait Manager<R: Runtime>: sealed::ManagerBase<R> {
 		self.state::<Scopes>().inner().asset_protocol.clone()
 	}
 
+	/// Gets the scope for the HTTP APIs.
+	#[cfg(any(http_request, http_websocket))]
+	fn http_scope(&self) -> HttpScope {
+		self.state::<Scopes>().inner().http.clone()
+	}
+
 	/// Gets the scope for the shell execute APIs.
 	#[cfg(shell_scope)]
 	fn shell_scope(&self) -> ShellScope {
@@ -917,7 +987,8 @@ pub fn running_under_arm64_translation() -> bool {
 }
 
 /// Utilities for unit testing on Millennium applications.
-#[cfg(test)]
+#[cfg(any(test, feature = "test"))]
+#[cfg_attr(doc_cfg, doc(cfg(feature = "test")))]
 pub mod test;
 
 #[cfg(test)]
@@ -968,6 +1039,7 @@ mod tests {
 			"http-api",
 			"http-multipart",
 			"process-command-api",
+			"process-pty",
 			"process-relaunch-dangerous-allow-symlink-macos",
 			"window-data-url"
 		];
@@ -1001,6 +1073,57 @@ mod test_utils {
 		}
 	}
 
+	/// A private `dbus-daemon` for the tests of D-Bus clients, stopped when dropped.
+	///
+	/// Tests using it are `#[ignore]`d, as they need `dbus-daemon` to be installed; run them with `cargo test -- --ignored`.
+	#[cfg(all(unix, feature = "zbus"))]
+	#[allow(dead_code)]
+	pub struct PrivateBus {
+		daemon: std::process::Child,
+		address: String
+	}
+
+	#[cfg(all(unix, feature = "zbus"))]
+	#[allow(dead_code)]
+	impl PrivateBus {
+		/// Starts a bus. Panics if `dbus-daemon` is not installed.
+		pub fn start() -> Self {
+			use std::{
+				io::{BufRead, BufReader},
+				process::{Command, Stdio}
+			};
+
+			let mut daemon = Command::new("dbus-daemon")
+				.args(["--session", "--nofork", "--print-address"])
+				.stdout(Stdio::piped())
+				.stderr(Stdio::null())
+				.spawn()
+				.expect("failed to start `dbus-daemon`, which is required by the D-Bus tests");
+			let mut address = String::new();
+			BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+			Self {
+				daemon,
+				address: address.trim().into()
+			}
+		}
+
+		pub fn address(&self) -> &str {
+			&self.address
+		}
+
+		pub fn connection(&self) -> zbus::blocking::ConnectionBuilder<'static> {
+			zbus::blocking::ConnectionBuilder::address(self.address.as_str()).unwrap()
+		}
+	}
+
+	#[cfg(all(unix, feature = "zbus"))]
+	impl Drop for PrivateBus {
+		fn drop(&mut self) {
+			let _ = self.daemon.kill();
+			let _ = self.daemon.wait();
+		}
+	}
+
 	proptest! {
 		#![proptest_config(ProptestConfig::with_cases(10000))]
 		#[test]
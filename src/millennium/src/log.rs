@@ -0,0 +1,680 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(clippy::tabs_in_doc_comments)]
+
+//! Structured logging for Millennium applications.
+//!
+//! The [`Builder`] creates a plugin that installs a global [`tracing`] subscriber writing to a configurable set of
+//! [`LogTarget`]s, such as the standard output, a rotating log file in the app log directory, or the webview devtools
+//! console.
+//!
+//! Messages logged by the frontend through the `log` module of the JS API (or every `console.*` call, when
+//! [`Builder::forward_console`] is enabled) are forwarded to the same subscriber under the `webview` target, tagged
+//! with the label of the window that logged them.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use millennium::log::{Builder, LevelFilter, LogFormat, LogTarget};
+//!
+//! millennium::Builder::default().plugin(
+//! 	Builder::new()
+//! 		.targets([LogTarget::Stdout, LogTarget::LogDir { file_name: None }, LogTarget::Webview])
+//! 		.level(LevelFilter::INFO)
+//! 		.level_for("hyper", LevelFilter::WARN)
+//! 		.format(LogFormat::Json)
+//! 		.forward_console(true)
+//! 		.build()
+//! );
+//! ```
+
+use std::{
+	cell::Cell,
+	fmt::{self, Write as _},
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::PathBuf,
+	sync::Mutex
+};
+
+use serde::Deserialize;
+use serde_repr::{Deserialize_repr, Serialize_repr};
+pub use tracing::Level;
+use tracing::{
+	field::{Field, Visit},
+	Event, Subscriber
+};
+pub use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::{
+	filter::Targets,
+	fmt::MakeWriter,
+	layer::{Context, SubscriberExt},
+	util::SubscriberInitExt,
+	Layer, Registry
+};
+
+use crate::{
+	plugin::{Builder as PluginBuilder, MillenniumPlugin},
+	AppHandle, Invoke, Manager, Runtime, Window
+};
+
+/// The target used for messages logged by the webview.
+pub const WEBVIEW_TARGET: &str = "webview";
+
+/// The event emitted to every window when a message is logged with the [`LogTarget::Webview`] target.
+pub const LOG_EVENT: &str = "log://log";
+
+const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// The log level of a message sent to or received from the webview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(u8)]
+pub enum LogLevel {
+	/// The "trace" level.
+	Trace = 1,
+	/// The "debug" level.
+	Debug,
+	/// The "info" level.
+	Info,
+	/// The "warn" level.
+	Warn,
+	/// The "error" level.
+	Error
+}
+
+impl From<Level> for LogLevel {
+	fn from(level: Level) -> Self {
+		match level {
+			Level::TRACE => Self::Trace,
+			Level::DEBUG => Self::Debug,
+			Level::INFO => Self::Info,
+			Level::WARN => Self::Warn,
+			Level::ERROR => Self::Error
+		}
+	}
+}
+
+/// A destination for log messages.
+#[derive(Debug, Clone)]
+pub enum LogTarget {
+	/// Print logs to the standard output.
+	Stdout,
+	/// Print logs to the standard error.
+	Stderr,
+	/// Write logs to a rotating file in the app log directory (see
+	/// [`crate::PathResolver::app_log_dir`]).
+	LogDir {
+		/// The name of the log file, without extension. Defaults to the
+		/// application name.
+		file_name: Option<String>
+	},
+	/// Write logs to a rotating file in the given directory.
+	Folder {
+		/// The directory the log files are written to.
+		path: PathBuf,
+		/// The name of the log file, without extension. Defaults to the
+		/// application name.
+		file_name: Option<String>
+	},
+	/// Forward logs to the devtools console of every window.
+	///
+	/// The JS API `log` module's `attachConsole` function must be called on
+	/// the frontend to print the forwarded messages. Messages logged by the
+	/// webview itself are not forwarded back to it.
+	Webview
+}
+
+/// The format log messages are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+	/// Human-readable text, one message per line.
+	Text,
+	/// Newline-delimited JSON objects.
+	Json
+}
+
+impl Default for LogFormat {
+	fn default() -> Self {
+		Self::Text
+	}
+}
+
+/// What to do with a log file once it reaches the maximum file size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationStrategy {
+	/// Keep every rotated log file.
+	KeepAll,
+	/// Keep at most the given number of rotated log files, deleting the
+	/// oldest ones.
+	KeepN(usize)
+}
+
+impl Default for RotationStrategy {
+	fn default() -> Self {
+		Self::KeepN(4)
+	}
+}
+
+/// Builds the logging plugin.
+#[derive(Debug)]
+pub struct Builder {
+	targets: Vec<LogTarget>,
+	level: LevelFilter,
+	module_levels: Vec<(String, LevelFilter)>,
+	format: LogFormat,
+	max_file_size: u64,
+	rotation_strategy: RotationStrategy,
+	forward_console: bool
+}
+
+impl Default for Builder {
+	fn default() -> Self {
+		Self {
+			targets: vec![LogTarget::Stdout, LogTarget::LogDir { file_name: None }],
+			level: if cfg!(debug_assertions) { LevelFilter::DEBUG } else { LevelFilter::INFO },
+			module_levels: Vec::new(),
+			format: LogFormat::default(),
+			max_file_size: DEFAULT_MAX_FILE_SIZE,
+			rotation_strategy: RotationStrategy::default(),
+			forward_console: false
+		}
+	}
+}
+
+impl Builder {
+	/// Creates a new logging plugin builder.
+	///
+	/// By default, logs are written to the standard output and to a log file
+	/// in the app log directory.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a log target.
+	#[must_use]
+	pub fn target(mut self, target: LogTarget) -> Self {
+		self.targets.push(target);
+		self
+	}
+
+	/// Replaces the log targets.
+	#[must_use]
+	pub fn targets(mut self, targets: impl IntoIterator<Item = LogTarget>) -> Self {
+		self.targets = targets.into_iter().collect();
+		self
+	}
+
+	/// Sets the maximum level of messages that are logged.
+	#[must_use]
+	pub fn level(mut self, level: LevelFilter) -> Self {
+		self.level = level;
+		self
+	}
+
+	/// Sets the maximum level of messages logged by the given module and its
+	/// children, overriding [`Self::level`].
+	///
+	/// Use [`WEBVIEW_TARGET`] to filter messages logged by the webview.
+	#[must_use]
+	pub fn level_for(mut self, module: impl Into<String>, level: LevelFilter) -> Self {
+		self.module_levels.push((module.into(), level));
+		self
+	}
+
+	/// Sets the format of messages written to the standard output and log
+	/// files.
+	#[must_use]
+	pub fn format(mut self, format: LogFormat) -> Self {
+		self.format = format;
+		self
+	}
+
+	/// Sets the size in bytes at which log files are rotated. Defaults to 1 MiB.
+	#[must_use]
+	pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+		self.max_file_size = max_file_size;
+		self
+	}
+
+	/// Sets what to do with log files once they are rotated.
+	#[must_use]
+	pub fn rotation_strategy(mut self, rotation_strategy: RotationStrategy) -> Self {
+		self.rotation_strategy = rotation_strategy;
+		self
+	}
+
+	/// Forwards every `console.*` call made by the webview to the logger, in
+	/// addition to messages logged with the JS API `log` module.
+	#[must_use]
+	pub fn forward_console(mut self, forward_console: bool) -> Self {
+		self.forward_console = forward_console;
+		self
+	}
+
+	/// The filter applying [`Self::level`] and [`Self::level_for`].
+	fn filter(&self) -> Targets {
+		Targets::new().with_default(self.level).with_targets(self.module_levels.iter().cloned())
+	}
+
+	/// Builds the plugin.
+	///
+	/// The subscriber is installed when the plugin is registered; registering
+	/// the plugin fails if another global subscriber was already set.
+	pub fn build<R: Runtime>(self) -> MillenniumPlugin<R> {
+		let mut plugin = PluginBuilder::new("log").invoke_handler(handle_invoke);
+		if self.forward_console {
+			plugin = plugin.js_init_script(include_str!("../scripts/log.js").into());
+		}
+		plugin
+			.setup(move |app| {
+				let filter = self.filter();
+				let mut layers: Vec<Box<dyn Layer<Registry> + Send + Sync>> = Vec::new();
+				for target in self.targets {
+					match target {
+						LogTarget::Stdout => layers.push(fmt_layer(self.format, true, io::stdout)),
+						LogTarget::Stderr => layers.push(fmt_layer(self.format, true, io::stderr)),
+						LogTarget::LogDir { file_name } => {
+							let path = app.path_resolver().app_log_dir().ok_or("could not resolve the app log directory")?;
+							let file_name = file_name.unwrap_or_else(|| app.package_info().name.clone());
+							let file = RotatingFile::new(path, file_name, self.format, self.max_file_size, self.rotation_strategy)?;
+							layers.push(fmt_layer(self.format, false, Mutex::new(file)));
+						}
+						LogTarget::Folder { path, file_name } => {
+							let file_name = file_name.unwrap_or_else(|| app.package_info().name.clone());
+							let file = RotatingFile::new(path, file_name, self.format, self.max_file_size, self.rotation_strategy)?;
+							layers.push(fmt_layer(self.format, false, Mutex::new(file)));
+						}
+						LogTarget::Webview => layers.push(Box::new(WebviewLayer { app: app.clone() }))
+					}
+				}
+
+				tracing_subscriber::registry().with(layers.with_filter(filter)).try_init()?;
+				Ok(())
+			})
+			.build()
+	}
+}
+
+fn fmt_layer<W>(format: LogFormat, ansi: bool, writer: W) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+	W: for<'w> MakeWriter<'w> + Send + Sync + 'static
+{
+	let layer = tracing_subscriber::fmt::layer().with_ansi(ansi).with_writer(writer);
+	match format {
+		LogFormat::Text => Box::new(layer),
+		LogFormat::Json => Box::new(layer.json())
+	}
+}
+
+/// A log file that is renamed to `<name>.1.<ext>` once it reaches the maximum size, shifting previously rotated files.
+struct RotatingFile {
+	dir: PathBuf,
+	file_name: String,
+	extension: &'static str,
+	max_size: u64,
+	strategy: RotationStrategy,
+	file: File,
+	size: u64
+}
+
+impl RotatingFile {
+	fn new(dir: PathBuf, file_name: String, format: LogFormat, max_size: u64, strategy: RotationStrategy) -> io::Result<Self> {
+		fs::create_dir_all(&dir)?;
+		let extension = match format {
+			LogFormat::Text => "log",
+			LogFormat::Json => "jsonl"
+		};
+		let path = dir.join(format!("{file_name}.{extension}"));
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		let size = file.metadata()?.len();
+		Ok(Self {
+			dir,
+			file_name,
+			extension,
+			max_size,
+			strategy,
+			file,
+			size
+		})
+	}
+
+	fn path(&self, index: usize) -> PathBuf {
+		if index == 0 {
+			self.dir.join(format!("{}.{}", self.file_name, self.extension))
+		} else {
+			self.dir.join(format!("{}.{index}.{}", self.file_name, self.extension))
+		}
+	}
+
+	fn rotate(&mut self) -> io::Result<()> {
+		self.file.flush()?;
+
+		let mut last = 0;
+		while self.path(last + 1).exists() {
+			last += 1;
+		}
+		if let RotationStrategy::KeepN(keep) = self.strategy {
+			while last >= keep && last > 0 {
+				fs::remove_file(self.path(last))?;
+				last -= 1;
+			}
+		}
+
+		let current = self.path(0);
+		if let RotationStrategy::KeepN(0) = self.strategy {
+			self.file = File::create(current)?;
+		} else {
+			for index in (0..=last).rev() {
+				fs::rename(self.path(index), self.path(index + 1))?;
+			}
+			self.file = OpenOptions::new().create(true).append(true).open(current)?;
+		}
+		self.size = 0;
+		Ok(())
+	}
+}
+
+impl Write for RotatingFile {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		// rotate before writing so a record is never split between two files
+		if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+			self.rotate()?;
+		}
+		let written = self.file.write(buf)?;
+		self.size += written as u64;
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.file.flush()
+	}
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct RecordPayload {
+	level: LogLevel,
+	target: String,
+	message: String
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+	message: String,
+	fields: String
+}
+
+impl Visit for MessageVisitor {
+	fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+		if field.name() == "message" {
+			let _ = write!(self.message, "{value:?}");
+		} else {
+			let _ = write!(self.fields, " {}={value:?}", field.name());
+		}
+	}
+
+	fn record_str(&mut self, field: &Field, value: &str) {
+		if field.name() == "message" {
+			self.message.push_str(value);
+		} else {
+			let _ = write!(self.fields, " {}={value}", field.name());
+		}
+	}
+}
+
+thread_local! {
+	static EMITTING: Cell<bool> = Cell::new(false);
+}
+
+/// Emits log messages to the webview as [`LOG_EVENT`] events.
+struct WebviewLayer<R: Runtime> {
+	app: AppHandle<R>
+}
+
+impl<S: Subscriber, R: Runtime> Layer<S> for WebviewLayer<R> {
+	fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+		let metadata = event.metadata();
+		// messages logged by the webview are already in its console
+		if metadata.target() == WEBVIEW_TARGET || EMITTING.with(Cell::get) {
+			return;
+		}
+
+		let mut visitor = MessageVisitor::default();
+		event.record(&mut visitor);
+		let payload = RecordPayload {
+			level: (*metadata.level()).into(),
+			target: metadata.target().into(),
+			message: visitor.message + &visitor.fields
+		};
+
+		// guard against events logged while emitting causing infinite recursion
+		EMITTING.with(|emitting| emitting.set(true));
+		let _ = self.app.emit_all(LOG_EVENT, payload);
+		EMITTING.with(|emitting| emitting.set(false));
+	}
+}
+
+#[derive(Deserialize)]
+struct WebviewRecord {
+	level: LogLevel,
+	message: String,
+	location: Option<String>
+}
+
+fn log_webview_record<R: Runtime>(window: &Window<R>, record: WebviewRecord) {
+	let label = window.label();
+	let location = record.location.as_deref().unwrap_or_default();
+	let message = record.message;
+	macro_rules! log {
+		($level:expr) => {
+			tracing::event!(target: WEBVIEW_TARGET, $level, window = label, location, "{}", message)
+		};
+	}
+	match record.level {
+		LogLevel::Trace => log!(Level::TRACE),
+		LogLevel::Debug => log!(Level::DEBUG),
+		LogLevel::Info => log!(Level::INFO),
+		LogLevel::Warn => log!(Level::WARN),
+		LogLevel::Error => log!(Level::ERROR)
+	}
+}
+
+fn handle_invoke<R: Runtime>(invoke: Invoke<R>) {
+	let Invoke { message, resolver } = invoke;
+	match message.command() {
+		"log" => match serde_json::from_value::<WebviewRecord>(message.payload().clone()) {
+			Ok(record) => {
+				log_webview_record(message.window_ref(), record);
+				resolver.resolve(());
+			}
+			Err(e) => resolver.reject(e.to_string())
+		},
+		command => resolver.reject(format!("command {command} not found"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		io::Write,
+		sync::{Arc, Mutex}
+	};
+
+	use serde_json::{json, Value as JsonValue};
+	use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, Layer};
+
+	use super::{fmt_layer, handle_invoke, Builder, LevelFilter, LogFormat, RotatingFile, RotationStrategy, WebviewLayer, LOG_EVENT, WEBVIEW_TARGET};
+	use crate::{
+		async_runtime::block_on,
+		plugin::Builder as PluginBuilder,
+		test::{mock_builder, EmittedEvent, MockApp}
+	};
+
+	fn read(dir: &std::path::Path, name: &str) -> String {
+		std::fs::read_to_string(dir.join(name)).unwrap()
+	}
+
+	/// An in-memory writer for the formatting layers.
+	#[derive(Clone, Default)]
+	struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+	impl Buffer {
+		fn lines(&self) -> Vec<String> {
+			let buffer = self.0.lock().unwrap();
+			String::from_utf8_lossy(&buffer).lines().map(Into::into).collect()
+		}
+	}
+
+	impl Write for Buffer {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().write(buf)
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	impl<'a> MakeWriter<'a> for Buffer {
+		type Writer = Self;
+
+		fn make_writer(&'a self) -> Self::Writer {
+			self.clone()
+		}
+	}
+
+	/// Runs `f` with a subscriber formatting records in `format` for `builder`'s filter, and returns the written lines.
+	fn capture(builder: &Builder, format: LogFormat, f: impl FnOnce()) -> Vec<String> {
+		let buffer = Buffer::default();
+		let subscriber = tracing_subscriber::registry().with(fmt_layer(format, false, buffer.clone()).with_filter(builder.filter()));
+		tracing::subscriber::with_default(subscriber, f);
+		buffer.lines()
+	}
+
+	fn parse(line: &str) -> JsonValue {
+		serde_json::from_str(line).unwrap()
+	}
+
+	#[test]
+	fn rotates_and_keeps_n_files() {
+		let dir = tempfile::tempdir().unwrap();
+		let mut file = RotatingFile::new(dir.path().to_path_buf(), "app".into(), LogFormat::Text, 8, RotationStrategy::KeepN(2)).unwrap();
+		for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+			file.write_all(line.as_bytes()).unwrap();
+		}
+		assert_eq!(read(dir.path(), "app.log"), "fourth\n");
+		assert_eq!(read(dir.path(), "app.1.log"), "third\n");
+		assert_eq!(read(dir.path(), "app.2.log"), "second\n");
+		assert!(!dir.path().join("app.3.log").exists());
+	}
+
+	#[test]
+	fn keeps_all_files() {
+		let dir = tempfile::tempdir().unwrap();
+		let mut file = RotatingFile::new(dir.path().to_path_buf(), "app".into(), LogFormat::Json, 8, RotationStrategy::KeepAll).unwrap();
+		for line in ["{\"a\":1}\n", "{\"b\":2}\n", "{\"c\":3}\n"] {
+			file.write_all(line.as_bytes()).unwrap();
+		}
+		assert_eq!(read(dir.path(), "app.jsonl"), "{\"c\":3}\n");
+		assert_eq!(read(dir.path(), "app.1.jsonl"), "{\"b\":2}\n");
+		assert_eq!(read(dir.path(), "app.2.jsonl"), "{\"a\":1}\n");
+	}
+
+	#[test]
+	fn truncates_when_keeping_no_files() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("app.log"), "existing\n").unwrap();
+		let mut file = RotatingFile::new(dir.path().to_path_buf(), "app".into(), LogFormat::Text, 12, RotationStrategy::KeepN(0)).unwrap();
+		file.write_all(b"appended\n").unwrap();
+		assert_eq!(read(dir.path(), "app.log"), "appended\n");
+		assert!(!dir.path().join("app.1.log").exists());
+	}
+
+	#[test]
+	fn filters_by_module_level() {
+		let builder = Builder::new()
+			.level(LevelFilter::INFO)
+			.level_for("noisy", LevelFilter::WARN)
+			.level_for("chatty", LevelFilter::TRACE)
+			.level_for(WEBVIEW_TARGET, LevelFilter::OFF);
+		let lines = capture(&builder, LogFormat::Json, || {
+			tracing::info!(target: "app", "app info");
+			tracing::debug!(target: "app", "app debug");
+			tracing::info!(target: "noisy::child", "noisy info");
+			tracing::warn!(target: "noisy::child", "noisy warn");
+			tracing::trace!(target: "chatty", "chatty trace");
+			tracing::error!(target: WEBVIEW_TARGET, "webview error");
+		});
+		let messages = lines.iter().map(|line| parse(line)["fields"]["message"].clone()).collect::<Vec<_>>();
+		assert_eq!(messages, vec![json!("app info"), json!("noisy warn"), json!("chatty trace")]);
+	}
+
+	#[test]
+	fn formats_records() {
+		let builder = Builder::new().level(LevelFilter::INFO);
+		let log = || tracing::warn!(target: "app::module", answer = 42, "hello");
+
+		let lines = capture(&builder, LogFormat::Json, log);
+		assert_eq!(lines.len(), 1);
+		let record = parse(&lines[0]);
+		assert_eq!(record["level"], "WARN");
+		assert_eq!(record["target"], "app::module");
+		assert_eq!(record["fields"], json!({ "message": "hello", "answer": 42 }));
+		assert!(record["timestamp"].is_string());
+
+		let lines = capture(&builder, LogFormat::Text, log);
+		assert_eq!(lines.len(), 1);
+		assert!(lines[0].ends_with(" WARN app::module: hello answer=42"), "unexpected text record `{}`", lines[0]);
+	}
+
+	#[test]
+	fn logs_webview_records() {
+		let app = MockApp::new(mock_builder().plugin(PluginBuilder::new("log").invoke_handler(handle_invoke).build())).unwrap();
+		let window = app.window("main").unwrap();
+
+		let builder = Builder::new().level(LevelFilter::TRACE);
+		let lines = capture(&builder, LogFormat::Json, || {
+			let record = json!({ "level": 4, "message": "something happened", "location": "http://localhost/main.js:12:3" });
+			assert_eq!(block_on(window.invoke("plugin:log|log", record)), Ok(JsonValue::Null));
+			assert!(block_on(window.invoke("plugin:log|log", json!({ "level": 9, "message": "invalid level" }))).is_err());
+		});
+		assert_eq!(lines.len(), 1);
+		let record = parse(&lines[0]);
+		assert_eq!(record["level"], "WARN");
+		assert_eq!(record["target"], WEBVIEW_TARGET);
+		assert_eq!(record["fields"], json!({ "message": "something happened", "window": "main", "location": "http://localhost/main.js:12:3" }));
+	}
+
+	#[test]
+	fn emits_records_to_webviews() {
+		let app = MockApp::new(mock_builder()).unwrap();
+		let window = app.window("main").unwrap();
+		window.listen_js(LOG_EVENT, None);
+
+		let subscriber = tracing_subscriber::registry().with(WebviewLayer { app: app.app().handle() });
+		tracing::subscriber::with_default(subscriber, || {
+			tracing::warn!(target: "app::module", answer = 42, "hello");
+			// records logged by the webview are not sent back to it
+			tracing::info!(target: WEBVIEW_TARGET, "from the webview");
+		});
+		assert_eq!(
+			window.emitted_events(),
+			vec![EmittedEvent {
+				event: LOG_EVENT.into(),
+				window_label: None,
+				payload: json!({ "level": 4, "target": "app::module", "message": "hello answer=42" })
+			}]
+		);
+	}
+}
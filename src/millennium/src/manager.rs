@@ -41,7 +41,7 @@ use crate::api::path::{resolve_path, BaseDirectory};
 use crate::hooks::IsolationJavascript;
 use crate::{
 	app::{AppHandle, GlobalMenuEventListener, GlobalWindowEvent, GlobalWindowEventListener, WindowMenuEvent},
-	event::{assert_event_name_is_valid, Event, EventHandler, Listeners},
+	event::{assert_event_name_is_valid, Event, EventHandler, Listeners, Payload, TypedEvent},
 	hooks::{InvokeHandler, InvokePayload, InvokeResponder, IpcJavascript, OnPageLoad, PageLoadPayload},
 	pattern::{format_real_schema, PatternJavascript},
 	plugin::PluginStore,
@@ -1148,7 +1148,13 @@ impl<R: Runtime> WindowManager<R> {
 
 	pub fn trigger(&self, event: &str, window: Option<String>, data: Option<String>) {
 		assert_event_name_is_valid(event);
-		self.inner.listeners.trigger(event, window, data)
+		self.inner.listeners.trigger(event, window, data.into())
+	}
+
+	pub fn trigger_typed<E: TypedEvent>(&self, window: Option<String>, payload: E) -> crate::Result<()> {
+		assert_event_name_is_valid(E::NAME);
+		self.inner.listeners.trigger(E::NAME, window, Payload::typed(payload)?);
+		Ok(())
 	}
 
 	pub fn listen<F: Fn(Event) + Send + 'static>(&self, event: String, window: Option<String>, handler: F) -> EventHandler {
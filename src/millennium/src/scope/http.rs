@@ -14,42 +14,193 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::{Arc, Mutex};
+
 use glob::Pattern;
-use millennium_utils::config::HttpAllowlistScope;
+use http::{
+	header::{HeaderMap, HeaderName, HeaderValue},
+	Method
+};
+use millennium_utils::config::{HttpAllowlistScope, HttpScopeEntry};
+use url::Url;
+
+/// A URL allowed by the [`Scope`], with restrictions on the requests made to it.
+#[derive(Debug, Clone)]
+pub struct Rule {
+	pattern: Pattern,
+	methods: Option<Vec<Method>>,
+	headers: Option<Vec<HeaderName>>,
+	forced_headers: Vec<(HeaderName, HeaderValue)>
+}
+
+impl Rule {
+	/// Creates a rule allowing any request to the URLs matching the given glob pattern.
+	pub fn new(pattern: &str) -> crate::Result<Self> {
+		Ok(Self {
+			pattern: Pattern::new(pattern)?,
+			methods: None,
+			headers: None,
+			forced_headers: Vec::new()
+		})
+	}
+
+	/// Only allows requests with the given methods.
+	#[must_use]
+	pub fn methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+		self.methods.replace(methods.into_iter().collect());
+		self
+	}
+
+	/// Only allows the webview to set the given headers. Other headers are removed from the requests.
+	#[must_use]
+	pub fn headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+		self.headers.replace(headers.into_iter().collect());
+		self
+	}
+
+	/// Sets a header on every request, replacing the header set by the webview.
+	#[must_use]
+	pub fn force_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+		self.forced_headers.retain(|(forced, _)| forced != &name);
+		self.forced_headers.push((name, value));
+		self
+	}
+
+	fn for_entry(entry: &HttpScopeEntry) -> crate::Result<Self> {
+		let url = entry.url();
+		let rule = Self::new(url.as_str())?;
+		match entry {
+			HttpScopeEntry::Url(_) => Ok(rule),
+			HttpScopeEntry::Rule { methods, headers, forced_headers, .. } => {
+				let invalid = |what: String| crate::Error::InvalidHttpScope(format!("{what} is not valid in the rule of `{url}`"));
+				let mut rule = rule;
+				if let Some(methods) = methods {
+					let methods = methods
+						.iter()
+						.map(|method| Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| invalid(format!("HTTP method `{method}`"))))
+						.collect::<crate::Result<Vec<_>>>()?;
+					rule = rule.methods(methods);
+				}
+				if let Some(headers) = headers {
+					let headers = headers
+						.iter()
+						.map(|name| HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid(format!("header name `{name}`"))))
+						.collect::<crate::Result<Vec<_>>>()?;
+					rule = rule.headers(headers);
+				}
+				for (name, value) in forced_headers {
+					let (name, value) = HeaderName::from_bytes(name.as_bytes())
+						.ok()
+						.zip(HeaderValue::from_str(value).ok())
+						.ok_or_else(|| invalid(format!("forced header `{name}: {value}`")))?;
+					rule = rule.force_header(name, value);
+				}
+				Ok(rule)
+			}
+		}
+	}
+
+	fn matches(&self, method: &Method, url: &Url) -> bool {
+		self.pattern.matches(url.as_str()) && self.methods.as_ref().map_or(true, |methods| methods.contains(method))
+	}
+
+	/// Determines if the webview is allowed to set the given header.
+	pub fn allows_header(&self, name: &HeaderName) -> bool {
+		self.headers.as_ref().map_or(true, |headers| headers.contains(name)) && !self.forced_headers.iter().any(|(forced, _)| forced == name)
+	}
+
+	/// The headers set on every request.
+	pub fn forced_headers(&self) -> &[(HeaderName, HeaderValue)] {
+		&self.forced_headers
+	}
+
+	/// Removes the headers the webview is not allowed to set, and sets the forced headers.
+	pub fn apply(&self, headers: &mut HeaderMap) {
+		let denied: Vec<HeaderName> = headers.keys().filter(|name| !self.allows_header(name)).cloned().collect();
+		for name in denied {
+			headers.remove(name);
+		}
+		for (name, value) in &self.forced_headers {
+			headers.insert(name.clone(), value.clone());
+		}
+	}
+}
 
-/// Scope for filesystem access.
+/// Scope for HTTP access.
 #[derive(Debug, Clone)]
 pub struct Scope {
-	allowed_urls: Vec<Pattern>
+	allowed: Arc<Mutex<Vec<Rule>>>,
+	forbidden: Arc<Mutex<Vec<Pattern>>>
 }
 
 impl Scope {
 	/// Creates a new scope from the allowlist's `http` scope configuration.
 	#[allow(dead_code)]
-	pub(crate) fn for_http_api(scope: &HttpAllowlistScope) -> Self {
-		Self {
-			allowed_urls: scope
-				.0
-				.iter()
-				.map(|url| glob::Pattern::new(url.as_str()).unwrap_or_else(|_| panic!("scoped URL is not a valid glob pattern: `{url}`")))
-				.collect()
+	pub(crate) fn for_http_api(scope: &HttpAllowlistScope) -> crate::Result<Self> {
+		let forbidden = scope
+			.forbidden_urls()
+			.into_iter()
+			.flatten()
+			.map(|url| Pattern::new(url.as_str()))
+			.collect::<Result<_, _>>()?;
+		let allowed = scope.allowed_urls().iter().map(Rule::for_entry).collect::<crate::Result<_>>()?;
+		Ok(Self {
+			allowed: Arc::new(Mutex::new(allowed)),
+			forbidden: Arc::new(Mutex::new(forbidden))
+		})
+	}
+
+	/// Extends the scope with the given rule.
+	///
+	/// Rules are matched in order, so this rule only applies to the requests not matched by the existing rules.
+	pub fn allow(&self, rule: Rule) {
+		self.allowed.lock().unwrap().push(rule);
+	}
+
+	/// Extends the scope with the URLs matching the given glob pattern, allowing any request to them.
+	pub fn allow_url(&self, pattern: &str) -> crate::Result<()> {
+		self.allow(Rule::new(pattern)?);
+		Ok(())
+	}
+
+	/// Forbids the URLs matching the given glob pattern.
+	///
+	/// **Note**: this takes precedence over allowed URLs, so their access gets
+	/// denied **always**.
+	pub fn forbid_url(&self, pattern: &str) -> crate::Result<()> {
+		self.forbidden.lock().unwrap().push(Pattern::new(pattern)?);
+		Ok(())
+	}
+
+	/// Returns the rule that allows a request with the given method to the given URL, or `None` if the request is not
+	/// allowed on this scope.
+	pub fn rule_for(&self, method: &Method, url: &Url) -> Option<Rule> {
+		if self.forbidden.lock().unwrap().iter().any(|forbidden| forbidden.matches(url.as_str())) {
+			return None;
 		}
+		self.allowed.lock().unwrap().iter().find(|rule| rule.matches(method, url)).cloned()
 	}
 
-	/// Determines if the given URL is allowed on this scope.
-	pub fn is_allowed(&self, url: &url::Url) -> bool {
-		self.allowed_urls.iter().any(|allowed| allowed.matches(url.as_str()))
+	/// Determines if the given URL is allowed on this scope, with any method.
+	pub fn is_allowed(&self, url: &Url) -> bool {
+		!self.forbidden.lock().unwrap().iter().any(|forbidden| forbidden.matches(url.as_str()))
+			&& self.allowed.lock().unwrap().iter().any(|rule| rule.pattern.matches(url.as_str()))
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use millennium_utils::config::HttpAllowlistScope;
+	use http::{header::HeaderMap, Method};
+	use millennium_utils::config::{HttpAllowlistScope, HttpScopeEntry};
+
+	fn url_scope(urls: &[&str]) -> super::Scope {
+		super::Scope::for_http_api(&HttpAllowlistScope::AllowedUrls(urls.iter().map(|url| HttpScopeEntry::Url(url.parse().unwrap())).collect())).unwrap()
+	}
 
 	#[test]
 	fn is_allowed() {
 		// plain URL
-		let scope = super::Scope::for_http_api(&HttpAllowlistScope(vec!["http://localhost:8080".parse().unwrap()]));
+		let scope = url_scope(&["http://localhost:8080"]);
 		assert!(scope.is_allowed(&"http://localhost:8080".parse().unwrap()));
 		assert!(scope.is_allowed(&"http://localhost:8080/".parse().unwrap()));
 
@@ -60,7 +211,7 @@ mod tests {
 		assert!(!scope.is_allowed(&"http://local:8080".parse().unwrap()));
 
 		// URL with fixed path
-		let scope = super::Scope::for_http_api(&HttpAllowlistScope(vec!["http://localhost:8080/file.png".parse().unwrap()]));
+		let scope = url_scope(&["http://localhost:8080/file.png"]);
 
 		assert!(scope.is_allowed(&"http://localhost:8080/file.png".parse().unwrap()));
 
@@ -69,22 +220,80 @@ mod tests {
 		assert!(!scope.is_allowed(&"http://localhost:8080/file.png/other.jpg".parse().unwrap()));
 
 		// URL with glob pattern
-		let scope = super::Scope::for_http_api(&HttpAllowlistScope(vec!["http://localhost:8080/*.png".parse().unwrap()]));
+		let scope = url_scope(&["http://localhost:8080/*.png"]);
 
 		assert!(scope.is_allowed(&"http://localhost:8080/file.png".parse().unwrap()));
 		assert!(scope.is_allowed(&"http://localhost:8080/assets/file.png".parse().unwrap()));
 
 		assert!(!scope.is_allowed(&"http://localhost:8080/file.jpeg".parse().unwrap()));
 
-		let scope = super::Scope::for_http_api(&HttpAllowlistScope(vec!["http://*".parse().unwrap()]));
+		let scope = url_scope(&["http://*"]);
 
 		assert!(scope.is_allowed(&"http://something.else".parse().unwrap()));
 		assert!(!scope.is_allowed(&"http://something.else/path/to/file".parse().unwrap()));
 		assert!(!scope.is_allowed(&"https://something.else".parse().unwrap()));
 
-		let scope = super::Scope::for_http_api(&HttpAllowlistScope(vec!["http://**".parse().unwrap()]));
+		let scope = url_scope(&["http://**"]);
 
 		assert!(scope.is_allowed(&"http://something.else".parse().unwrap()));
 		assert!(scope.is_allowed(&"http://something.else/path/to/file".parse().unwrap()));
 	}
+
+	#[test]
+	fn forbids_urls() {
+		let scope = super::Scope::for_http_api(&HttpAllowlistScope::Scope {
+			allow: vec![HttpScopeEntry::Url("https://api.example.com/**".parse().unwrap())],
+			deny: vec!["https://api.example.com/admin/**".parse().unwrap()]
+		})
+		.unwrap();
+		assert!(scope.is_allowed(&"https://api.example.com/users".parse().unwrap()));
+		assert!(!scope.is_allowed(&"https://api.example.com/admin/users".parse().unwrap()));
+
+		scope.forbid_url("https://api.example.com/internal/**").unwrap();
+		assert!(!scope.is_allowed(&"https://api.example.com/internal/metrics".parse().unwrap()));
+
+		scope.allow_url("https://cdn.example.com/**").unwrap();
+		assert!(scope.is_allowed(&"https://cdn.example.com/image.png".parse().unwrap()));
+	}
+
+	#[test]
+	fn restricts_methods_and_headers() {
+		let scope = super::Scope::for_http_api(&HttpAllowlistScope::AllowedUrls(vec![HttpScopeEntry::Rule {
+			url: "https://api.example.com/**".parse().unwrap(),
+			methods: Some(vec!["get".into()]),
+			headers: Some(vec!["Accept".into(), "Authorization".into()]),
+			forced_headers: [("Authorization".to_string(), "Bearer app".to_string())].into()
+		}]))
+		.unwrap();
+		let url = "https://api.example.com/users".parse().unwrap();
+		assert!(scope.rule_for(&Method::POST, &url).is_none());
+
+		let rule = scope.rule_for(&Method::GET, &url).unwrap();
+		let mut headers = HeaderMap::new();
+		headers.insert("accept", "application/json".parse().unwrap());
+		headers.insert("authorization", "Bearer user".parse().unwrap());
+		headers.insert("cookie", "session=1".parse().unwrap());
+		rule.apply(&mut headers);
+		assert_eq!(headers.len(), 2);
+		assert_eq!(headers["accept"], "application/json");
+		assert_eq!(headers["authorization"], "Bearer app");
+	}
+
+	#[test]
+	fn rejects_invalid_rules() {
+		let rule = |methods: Vec<&str>, headers: Vec<&str>, forced_headers: Vec<(&str, &str)>| {
+			super::Scope::for_http_api(&HttpAllowlistScope::AllowedUrls(vec![HttpScopeEntry::Rule {
+				url: "https://api.example.com/**".parse().unwrap(),
+				methods: Some(methods.into_iter().map(Into::into).collect()),
+				headers: Some(headers.into_iter().map(Into::into).collect()),
+				forced_headers: forced_headers.into_iter().map(|(name, value)| (name.into(), value.into())).collect()
+			}]))
+		};
+		assert!(rule(vec!["GET"], vec!["Accept"], vec![("X-Client", "app")]).is_ok());
+		let error = rule(vec!["GET /"], vec![], vec![]).unwrap_err().to_string();
+		assert!(error.contains("HTTP method `GET /`"));
+		let error = rule(vec![], vec!["Bad Header"], vec![]).unwrap_err().to_string();
+		assert!(error.contains("header name `Bad Header`"));
+		assert!(rule(vec![], vec![], vec![("X-Client", "line\nbreak")]).is_err());
+	}
 }
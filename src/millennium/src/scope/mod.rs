@@ -28,13 +28,13 @@ pub use shell::{
 	ScopeConfig as ShellScopeConfig, ScopeError as ShellScopeError
 };
 
-pub use self::http::Scope as HttpScope;
+pub use self::http::{Rule as HttpScopeRule, Scope as HttpScope};
 
 pub(crate) struct Scopes {
 	pub fs: FsScope,
 	#[cfg(protocol_asset)]
 	pub asset_protocol: FsScope,
-	#[cfg(http_request)]
+	#[cfg(any(http_request, http_websocket))]
 	pub http: HttpScope,
 	#[cfg(shell_scope)]
 	pub shell: ShellScope
@@ -16,7 +16,10 @@
 
 use std::collections::HashMap;
 
-use millennium_utils::{config::Config, Env, PackageInfo};
+use millennium_utils::{
+	config::{Config, ShellCommandRestrictions},
+	Env, PackageInfo
+};
 use regex::Regex;
 
 #[cfg(any(shell_execute, shell_sidecar))]
@@ -89,7 +92,10 @@ pub struct ScopeAllowedCommand {
 	pub args: Option<Vec<ScopeAllowedArg>>,
 
 	/// If this command is a sidecar command.
-	pub sidecar: bool
+	pub sidecar: bool,
+
+	/// Restrictions applied to the spawned process.
+	pub restrictions: Option<ShellCommandRestrictions>
 }
 
 /// A configured argument to a scoped shell command.
@@ -188,6 +194,11 @@ impl Scope {
 			if let Ok(path) = crate::api::path::parse(config, package_info, env, &cmd.command) {
 				cmd.command = path;
 			}
+			if let Some(cwd) = cmd.restrictions.as_mut().and_then(|restrictions| restrictions.cwd.as_mut()) {
+				if let Ok(path) = crate::api::path::parse(config, package_info, env, &*cwd) {
+					*cwd = path;
+				}
+			}
 		}
 		Self(scope)
 	}
@@ -260,13 +271,17 @@ impl Scope {
 					.into_owned()
 			})
 			.unwrap_or_else(|| command.command.to_string_lossy().into_owned());
-		let command = if command.sidecar {
+		let mut prepared = if command.sidecar {
 			Command::new_sidecar(command_s).map_err(ScopeError::Sidecar)?
 		} else {
 			Command::new(command_s)
 		};
 
-		Ok(command.args(args))
+		if let Some(restrictions) = &command.restrictions {
+			prepared = restrict(prepared, restrictions);
+		}
+
+		Ok(prepared.args(args))
 	}
 
 	/// Open a path in the default (or specified) browser.
@@ -295,3 +310,23 @@ impl Scope {
 		.map_err(Into::into)
 	}
 }
+
+/// Applies the restrictions of a scoped command to the [`Command`] spawning it.
+#[cfg(any(shell_execute, shell_sidecar))]
+fn restrict(mut command: Command, restrictions: &ShellCommandRestrictions) -> Command {
+	use std::time::Duration;
+
+	if let Some(timeout) = restrictions.timeout {
+		command = command.timeout(Duration::from_secs(timeout));
+	}
+	if let Some(max_memory) = restrictions.max_memory {
+		command = command.max_memory(max_memory);
+	}
+	if let Some(max_cpu_time) = restrictions.max_cpu_time {
+		command = command.max_cpu_time(Duration::from_secs(max_cpu_time));
+	}
+	if let Some(cwd) = &restrictions.cwd {
+		command = command.cwd_jail(cwd.clone());
+	}
+	command.env_allowlist(&restrictions.env).sandbox(restrictions.sandbox)
+}
@@ -0,0 +1,292 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, AtomicUsize, Ordering},
+		mpsc::channel,
+		Arc, Mutex
+	}
+};
+
+use millennium_runtime::window::WindowEvent;
+use serde_json::Value as JsonValue;
+use tokio::sync::oneshot;
+
+use super::{mock_context, noop_assets, EmittedEvent, MockRuntime};
+use crate::{
+	api::ipc::CallbackFn, app::DEFAULT_INVOKE_INITIALIZATION_SCRIPT, hooks::window_invoke_responder, sealed::ManagerBase, utils::assets::Assets, App,
+	Builder, Context, InvokePayload, Manager, Window
+};
+
+type InvokeResult = Result<JsonValue, JsonValue>;
+type PendingInvokes = Arc<Mutex<HashMap<usize, oneshot::Sender<InvokeResult>>>>;
+
+static NEXT_CALLBACK: AtomicUsize = AtomicUsize::new(1);
+static NEXT_LISTENER: AtomicU64 = AtomicU64::new(1);
+
+/// An application running on the [`MockRuntime`], to drive its IPC from tests.
+///
+/// The application is built with [`mock_context`] by default, which has a single `main` window.
+///
+/// # Examples
+/// ```
+/// use millennium::test::{mock_builder, MockApp};
+/// use serde_json::json;
+///
+/// #[millennium::command]
+/// fn add(a: i32, b: i32) -> i32 {
+/// 	a + b
+/// }
+///
+/// let app = MockApp::new(mock_builder().invoke_handler(millennium::generate_handler![add])).unwrap();
+/// let window = app.window("main").unwrap();
+/// let sum = millennium::async_runtime::block_on(window.invoke("add", json!({ "a": 1, "b": 2 })));
+/// assert_eq!(sum, Ok(json!(3)));
+/// ```
+pub struct MockApp {
+	app: App<MockRuntime>,
+	pending: PendingInvokes
+}
+
+/// Creates a [`Builder`] for the [`MockRuntime`].
+pub fn mock_builder() -> Builder<MockRuntime> {
+	Builder::<MockRuntime>::new()
+}
+
+impl MockApp {
+	/// Builds the application with [`mock_context`].
+	///
+	/// As with [`MockApp::with_context`], a custom invoke system set on the builder is silently replaced.
+	pub fn new(builder: Builder<MockRuntime>) -> crate::Result<Self> {
+		Self::with_context(builder, mock_context(noop_assets()))
+	}
+
+	/// Builds the application with the given context.
+	///
+	/// The invoke system of the builder is replaced, so the harness can capture the responses of its invokes.
+	pub fn with_context<A: Assets>(builder: Builder<MockRuntime>, context: Context<A>) -> crate::Result<Self> {
+		let pending = PendingInvokes::default();
+		let pending_ = pending.clone();
+		let app = builder
+			.invoke_system(DEFAULT_INVOKE_INITIALIZATION_SCRIPT.into(), move |window, response, success_callback, error_callback| {
+				let tx = pending_.lock().unwrap().remove(&success_callback.0);
+				match tx {
+					Some(tx) => {
+						let _ = tx.send(response.into_result());
+					}
+					None => window_invoke_responder(window, response, success_callback, error_callback)
+				}
+			})
+			.build(context)?;
+		Ok(Self { app, pending })
+	}
+
+	/// The application.
+	pub fn app(&self) -> &App<MockRuntime> {
+		&self.app
+	}
+
+	/// Gets the window with the given label.
+	pub fn window(&self, label: &str) -> Option<MockWindow> {
+		self.app.get_window(label).map(|window| MockWindow {
+			window,
+			pending: self.pending.clone()
+		})
+	}
+}
+
+/// A window of a [`MockApp`].
+#[derive(Clone)]
+pub struct MockWindow {
+	window: Window<MockRuntime>,
+	pending: PendingInvokes
+}
+
+impl MockWindow {
+	/// The window.
+	pub fn window(&self) -> &Window<MockRuntime> {
+		&self.window
+	}
+
+	/// Invokes a command as the webview would, and returns the value it resolved or rejected with.
+	///
+	/// Plugin commands are invoked with the `plugin:<name>|<command>` name.
+	///
+	/// # Panics
+	///
+	/// Panics if the command is dropped without responding, e.g. if it isn't handled by the invoke handler.
+	pub async fn invoke(&self, command: &str, args: JsonValue) -> Result<JsonValue, JsonValue> {
+		let callback = NEXT_CALLBACK.fetch_add(2, Ordering::Relaxed);
+		let (tx, rx) = oneshot::channel();
+		self.pending.lock().unwrap().insert(callback, tx);
+
+		let payload = InvokePayload {
+			cmd: command.into(),
+			millennium_module: None,
+			callback: CallbackFn(callback),
+			error: CallbackFn(callback + 1),
+			inner: args
+		};
+		if let Err(e) = self.window.clone().on_message(payload) {
+			self.pending.lock().unwrap().remove(&callback);
+			return Err(JsonValue::String(e.to_string()));
+		}
+
+		rx.await
+			.unwrap_or_else(|_| panic!("the `{command}` command was dropped without a response"))
+	}
+
+	/// The scripts evaluated on the webview, in order.
+	pub fn scripts(&self) -> Vec<String> {
+		self.window.dispatcher().state().scripts()
+	}
+
+	/// Forgets the scripts evaluated on the webview so far, and the events emitted to it.
+	pub fn clear_scripts(&self) {
+		self.window.dispatcher().state().clear_scripts()
+	}
+
+	/// The events emitted to the webview, in order.
+	///
+	/// Like in a real webview, [`Window::emit`] only reaches windows listening to the event, see
+	/// [`listen_js`](Self::listen_js).
+	pub fn emitted_events(&self) -> Vec<EmittedEvent> {
+		self.window.dispatcher().state().emitted_events()
+	}
+
+	/// Registers a webview listener for `event`, as `listen` from the `event` module of `@pyke/millennium-api` would.
+	///
+	/// If `window_label` is set, only listens to events emitted by that window.
+	pub fn listen_js(&self, event: &str, window_label: Option<&str>) {
+		let id = NEXT_LISTENER.fetch_add(1, Ordering::Relaxed);
+		self.window.register_js_listener(window_label.map(Into::into), event.into(), id);
+	}
+
+	/// Sends a window event to the listeners of the window, as the event loop would.
+	pub fn send_event(&self, event: WindowEvent) {
+		self.window.dispatcher().state().send_event(&event)
+	}
+
+	/// Requests the window to close, as clicking its close button would. Returns whether the window was closed.
+	///
+	/// The window is destroyed unless a listener prevents the close with
+	/// [`CloseRequestApi::prevent_close`](crate::CloseRequestApi::prevent_close).
+	pub fn request_close(&self) -> bool {
+		let (signal_tx, signal_rx) = channel();
+		self.send_event(WindowEvent::CloseRequested { signal_tx });
+		if signal_rx.try_iter().any(|prevent| prevent) {
+			return false;
+		}
+
+		self.send_event(WindowEvent::Destroyed);
+		self.window.manager().on_window_close(self.window.label());
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc
+	};
+
+	use serde_json::json;
+
+	use super::{mock_builder, EmittedEvent, MockApp};
+	use crate::{async_runtime::block_on, Invoke, Manager, WindowEvent};
+
+	fn app() -> MockApp {
+		MockApp::new(mock_builder().invoke_handler(|invoke: Invoke<_>| match invoke.message.command() {
+			"double" => match invoke.message.payload()["value"].as_i64() {
+				Some(value) => invoke.resolver.resolve(value * 2),
+				None => invoke.resolver.reject("`value` must be an integer")
+			},
+			"greet" => {
+				let window = invoke.message.window();
+				invoke.resolver.respond_async(async move {
+					window.emit("greeted", "hello")?;
+					Ok(())
+				})
+			}
+			_ => invoke.resolver.reject("unknown command")
+		}))
+		.unwrap()
+	}
+
+	#[test]
+	fn invokes_commands() {
+		let app = app();
+		let window = app.window("main").unwrap();
+
+		assert_eq!(block_on(window.invoke("double", json!({ "value": 21 }))), Ok(json!(42)));
+		assert_eq!(block_on(window.invoke("double", json!({ "value": "21" }))), Err(json!("`value` must be an integer")));
+		assert_eq!(block_on(window.invoke("triple", json!({}))), Err(json!("unknown command")));
+	}
+
+	#[test]
+	fn captures_emitted_events() {
+		let app = app();
+		let window = app.window("main").unwrap();
+
+		assert_eq!(block_on(window.invoke("greet", json!({}))), Ok(json!(null)));
+		assert!(window.emitted_events().is_empty());
+
+		window.listen_js("greeted", None);
+		block_on(window.invoke("greet", json!({}))).unwrap();
+		app.app().emit_all("greeted", "everyone").unwrap();
+		assert_eq!(
+			window.emitted_events(),
+			vec![
+				EmittedEvent {
+					event: "greeted".into(),
+					window_label: Some("main".into()),
+					payload: json!("hello")
+				},
+				EmittedEvent {
+					event: "greeted".into(),
+					window_label: None,
+					payload: json!("everyone")
+				}
+			]
+		);
+
+		window.clear_scripts();
+		assert!(window.emitted_events().is_empty());
+	}
+
+	#[test]
+	fn simulates_close_requests() {
+		let prevent = Arc::new(AtomicBool::new(true));
+		let prevent_ = prevent.clone();
+		let app = MockApp::new(mock_builder().on_window_event(move |event| {
+			if let WindowEvent::CloseRequested { api, .. } = event.event() {
+				if prevent_.load(Ordering::SeqCst) {
+					api.prevent_close();
+				}
+			}
+		}))
+		.unwrap();
+		let window = app.window("main").unwrap();
+
+		assert!(!window.request_close());
+		assert!(app.window("main").is_some());
+
+		prevent.store(false, Ordering::SeqCst);
+		assert!(window.request_close());
+		assert!(app.window("main").is_none());
+	}
+}
@@ -40,12 +40,15 @@ use millennium_runtime::{
 #[cfg(any(target_os = "macos", target_os = "windows"))]
 use millennium_utils::TitleBarStyle;
 use millennium_utils::{config::WindowConfig, Theme};
+use serde_json::Value as JsonValue;
 use uuid::Uuid;
 #[cfg(windows)]
 use windows::Win32::Foundation::HWND;
 
 type ShortcutMap = HashMap<String, Box<dyn Fn() + Send + 'static>>;
+type WindowEventListeners = Arc<Mutex<HashMap<Uuid, Box<dyn Fn(&WindowEvent) + Send + 'static>>>>;
 
+/// The state shared by the [`MockRuntime`] and its handles.
 #[derive(Clone)]
 pub struct RuntimeContext {
 	shortcuts: Arc<Mutex<ShortcutMap>>,
@@ -58,6 +61,73 @@ impl fmt::Debug for RuntimeContext {
 	}
 }
 
+/// An event emitted to the webview of a window created on the [`MockRuntime`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmittedEvent {
+	/// The event name.
+	pub event: String,
+	/// The label of the window that emitted the event, `None` for events emitted by the application.
+	pub window_label: Option<String>,
+	/// The event payload.
+	pub payload: JsonValue
+}
+
+/// What a window created on the [`MockRuntime`] received: the scripts evaluated on its webview, the events emitted to
+/// it and its window event listeners.
+#[derive(Clone, Default)]
+pub struct MockWindowState {
+	scripts: Arc<Mutex<Vec<String>>>,
+	events: Arc<Mutex<Vec<EmittedEvent>>>,
+	window_event_listeners: WindowEventListeners
+}
+
+impl fmt::Debug for MockWindowState {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MockWindowState")
+			.field("scripts", &self.scripts)
+			.field("events", &self.events)
+			.finish()
+	}
+}
+
+impl MockWindowState {
+	/// The scripts evaluated on the window, in order.
+	pub fn scripts(&self) -> Vec<String> {
+		self.scripts.lock().unwrap().clone()
+	}
+
+	/// Forgets the scripts evaluated so far, and the events emitted to the window.
+	pub fn clear_scripts(&self) {
+		self.scripts.lock().unwrap().clear();
+		self.events.lock().unwrap().clear();
+	}
+
+	/// The events emitted to the window, in order.
+	pub fn emitted_events(&self) -> Vec<EmittedEvent> {
+		self.events.lock().unwrap().clone()
+	}
+
+	/// Sends an event to the window event listeners, as the event loop would.
+	pub fn send_event(&self, event: &WindowEvent) {
+		for listener in self.window_event_listeners.lock().unwrap().values() {
+			listener(event);
+		}
+	}
+}
+
+fn detached_window<T: UserEvent>(context: &RuntimeContext, pending: PendingWindow<T, MockRuntime>) -> DetachedWindow<T, MockRuntime> {
+	DetachedWindow {
+		label: pending.label,
+		dispatcher: MockDispatcher {
+			context: context.clone(),
+			window: Default::default()
+		},
+		menu_ids: Default::default(),
+		js_event_listeners: Default::default()
+	}
+}
+
+/// A handle to the [`MockRuntime`].
 #[derive(Debug, Clone)]
 pub struct MockRuntimeHandle {
 	context: RuntimeContext
@@ -72,17 +142,13 @@ impl<T: UserEvent> RuntimeHandle<T> for MockRuntimeHandle {
 
 	/// Create a new webview window.
 	fn create_window(&self, pending: PendingWindow<T, Self::Runtime>) -> Result<DetachedWindow<T, Self::Runtime>> {
-		Ok(DetachedWindow {
-			label: pending.label,
-			dispatcher: MockDispatcher { context: self.context.clone() },
-			menu_ids: Default::default(),
-			js_event_listeners: Default::default()
-		})
+		Ok(detached_window(&self.context, pending))
 	}
 
 	/// Run a task on the main thread.
 	fn run_on_main_thread<F: FnOnce() + Send + 'static>(&self, f: F) -> Result<()> {
-		unimplemented!()
+		f();
+		Ok(())
 	}
 
 	#[cfg(all(desktop, feature = "system-tray"))]
@@ -108,11 +174,40 @@ impl<T: UserEvent> RuntimeHandle<T> for MockRuntimeHandle {
 	}
 }
 
+/// The dispatcher of a window created on the [`MockRuntime`].
 #[derive(Debug, Clone)]
 pub struct MockDispatcher {
-	context: RuntimeContext
+	context: RuntimeContext,
+	window: MockWindowState
 }
 
+impl MockDispatcher {
+	/// What the window received so far.
+	pub fn state(&self) -> &MockWindowState {
+		&self.window
+	}
+}
+
+/// Parses the event out of the script evaluated by [`crate::Window::emit`], `None` for any other script.
+fn parse_emitted_event(script: &str) -> Option<EmittedEvent> {
+	fn next_value(script: &str) -> Option<(JsonValue, &str)> {
+		let mut values = serde_json::Deserializer::from_str(script).into_iter::<JsonValue>();
+		let value = values.next()?.ok()?;
+		Some((value, &script[values.byte_offset()..]))
+	}
+
+	let (_, script) = script.split_once("fn && fn({ event: ")?;
+	let (event, script) = next_value(script)?;
+	let (window_label, script) = next_value(script.strip_prefix(", windowLabel: ")?)?;
+	let (payload, _) = next_value(script.strip_prefix(", payload: ")?)?;
+	Some(EmittedEvent {
+		event: serde_json::from_value(event).ok()?,
+		window_label: serde_json::from_value(window_label).ok()?,
+		payload
+	})
+}
+
+/// A global shortcut manager keeping the shortcuts in memory.
 #[cfg(all(desktop, feature = "global-shortcut"))]
 #[derive(Debug, Clone)]
 pub struct MockGlobalShortcutManager {
@@ -141,6 +236,7 @@ impl millennium_runtime::GlobalShortcutManager for MockGlobalShortcutManager {
 	}
 }
 
+/// A clipboard manager keeping the clipboard in memory.
 #[cfg(feature = "clipboard")]
 #[derive(Debug, Clone)]
 pub struct MockClipboardManager {
@@ -159,6 +255,7 @@ impl millennium_runtime::ClipboardManager for MockClipboardManager {
 	}
 }
 
+/// A window builder ignoring the window attributes.
 #[derive(Debug, Clone)]
 pub struct MockWindowBuilder {}
 
@@ -296,11 +393,14 @@ impl<T: UserEvent> Dispatch<T> for MockDispatcher {
 	type WindowBuilder = MockWindowBuilder;
 
 	fn run_on_main_thread<F: FnOnce() + Send + 'static>(&self, f: F) -> Result<()> {
+		f();
 		Ok(())
 	}
 
 	fn on_window_event<F: Fn(&WindowEvent) + Send + 'static>(&self, f: F) -> Uuid {
-		Uuid::new_v4()
+		let id = Uuid::new_v4();
+		self.window.window_event_listeners.lock().unwrap().insert(id, Box::new(f));
+		id
 	}
 
 	fn on_menu_event<F: Fn(&MenuEvent) + Send + 'static>(&self, f: F) -> Uuid {
@@ -412,7 +512,7 @@ impl<T: UserEvent> Dispatch<T> for MockDispatcher {
 	}
 
 	fn create_window(&mut self, pending: PendingWindow<T, Self::Runtime>) -> Result<DetachedWindow<T, Self::Runtime>> {
-		unimplemented!()
+		Ok(detached_window(&self.context, pending))
 	}
 
 	fn set_resizable(&self, resizable: bool) -> Result<()> {
@@ -528,6 +628,11 @@ impl<T: UserEvent> Dispatch<T> for MockDispatcher {
 	}
 
 	fn eval_script<S: Into<String>>(&self, script: S) -> Result<()> {
+		let script = script.into();
+		if let Some(event) = parse_emitted_event(&script) {
+			self.window.events.lock().unwrap().push(event);
+		}
+		self.window.scripts.lock().unwrap().push(script);
 		Ok(())
 	}
 
@@ -536,6 +641,7 @@ impl<T: UserEvent> Dispatch<T> for MockDispatcher {
 	}
 }
 
+/// A system tray handle which does nothing.
 #[cfg(all(desktop, feature = "system-tray"))]
 #[derive(Debug, Clone)]
 pub struct MockTrayHandler {
@@ -572,6 +678,7 @@ impl TrayHandle for MockTrayHandler {
 	}
 }
 
+/// An event loop proxy dropping the events.
 #[derive(Debug, Clone)]
 pub struct EventProxy {}
 
@@ -581,8 +688,10 @@ impl<T: UserEvent> EventLoopProxy<T> for EventProxy {
 	}
 }
 
+/// A [`Runtime`] which creates no windows or event loop, to test Millennium applications.
 #[derive(Debug)]
 pub struct MockRuntime {
+	/// The state shared with the runtime handles.
 	pub context: RuntimeContext,
 	#[cfg(all(desktop, feature = "global-shortcut"))]
 	global_shortcut_manager: MockGlobalShortcutManager,
@@ -649,12 +758,7 @@ impl<T: UserEvent> Runtime<T> for MockRuntime {
 	}
 
 	fn create_window(&self, pending: PendingWindow<T, Self>) -> Result<DetachedWindow<T, Self>> {
-		Ok(DetachedWindow {
-			label: pending.label,
-			dispatcher: MockDispatcher { context: self.context.clone() },
-			menu_ids: Default::default(),
-			js_event_listeners: Default::default()
-		})
+		Ok(detached_window(&self.context, pending))
 	}
 
 	#[cfg(all(desktop, feature = "system-tray"))]
@@ -16,6 +16,7 @@
 
 #![allow(unused_variables)]
 
+mod harness;
 mod mock_runtime;
 #[cfg(shell_scope)]
 use std::collections::HashMap;
@@ -25,12 +26,14 @@ use millennium_utils::{
 	assets::{AssetKey, Assets, CspHash},
 	config::{CliConfig, Config, MillenniumConfig, PatternKind}
 };
+pub use harness::*;
 pub use mock_runtime::*;
 
 #[cfg(shell_scope)]
 use crate::ShellScopeConfig;
-use crate::{Manager, Pattern};
+use crate::Pattern;
 
+/// Assets which contain no files.
 pub struct NoopAsset {
 	csp_hashes: Vec<CspHash<'static>>
 }
@@ -45,10 +48,12 @@ impl Assets for NoopAsset {
 	}
 }
 
+/// Creates assets which contain no files.
 pub fn noop_assets() -> NoopAsset {
 	NoopAsset { csp_hashes: Default::default() }
 }
 
+/// Creates a context with a single `main` window, serving the given assets.
 pub fn mock_context<A: Assets>(assets: A) -> crate::Context<A> {
 	crate::Context {
 		config: Config {
@@ -92,11 +97,17 @@ pub fn mock_context<A: Assets>(assets: A) -> crate::Context<A> {
 	}
 }
 
+/// Builds an application on the [`MockRuntime`] with [`mock_context`].
+///
+/// Use a [`MockApp`] to invoke commands on it.
 pub fn mock_app() -> crate::App<MockRuntime> {
 	crate::Builder::<MockRuntime>::new().build(mock_context(noop_assets())).unwrap()
 }
 
+#[cfg(test)]
 pub(crate) fn mock_invoke_context() -> crate::endpoints::InvokeContext<MockRuntime> {
+	use crate::Manager;
+
 	let app = mock_app();
 	crate::endpoints::InvokeContext {
 		window: app.get_window("main").unwrap(),
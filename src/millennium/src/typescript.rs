@@ -0,0 +1,654 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![allow(clippy::tabs_in_doc_comments)]
+
+//! TypeScript definitions for the values exchanged with the frontend.
+//!
+//! Types implementing [`TsType`] describe the TypeScript type of their serialized value. The trait is implemented for
+//! the standard library types serde knows about, and can be derived for structs and enums with
+//! [`derive@TsType`]. [`Bindings`] collects those types into a TypeScript module, along with typed functions for the
+//! commands listed in [`generate_bindings!`](crate::generate_bindings).
+//!
+//! # Examples
+//! ```rust,no_run
+//! use millennium::{typescript::Bindings, TsType, TypedEvent};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Clone, Serialize, Deserialize, TsType, TypedEvent)]
+//! #[serde(rename_all = "camelCase")]
+//! #[event(name = "download-progress")]
+//! struct DownloadProgress {
+//! 	downloaded: u64,
+//! 	total_size: Option<u64>
+//! }
+//!
+//! #[millennium::command]
+//! fn download(url: String, retries: Option<u32>) -> Result<Vec<u8>, String> {
+//! 	Err(format!("can't download {url} in {retries:?} tries"))
+//! }
+//!
+//! millennium::generate_bindings![download]
+//! 	.event::<DownloadProgress>()
+//! 	.write("src/bindings.ts")
+//! 	.unwrap();
+//! ```
+
+use std::{
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+	path::{Path, PathBuf},
+	rc::Rc,
+	sync::Arc
+};
+
+pub use millennium_macros::TsType;
+
+use crate::TypedEvent;
+
+/// The module whose `EventMap` interface is augmented with the events
+/// registered with [`Bindings::event`].
+const EVENT_MODULE: &str = "@pyke/millennium-api/event";
+/// The module the command functions import `invoke` from.
+const INVOKE_MODULE: &str = "@pyke/millennium-api/millennium";
+/// The environment variable `millennium dev` uses to tell the application
+/// where to write its bindings.
+#[cfg(dev)]
+pub(crate) const BINDINGS_PATH_ENV: &str = "MILLENNIUM_BINDINGS_PATH";
+
+/// Words that can't be used as function names.
+const RESERVED_WORDS: &[&str] = &[
+	"break",
+	"case",
+	"catch",
+	"class",
+	"const",
+	"continue",
+	"debugger",
+	"default",
+	"delete",
+	"do",
+	"else",
+	"enum",
+	"export",
+	"extends",
+	"false",
+	"finally",
+	"for",
+	"function",
+	"if",
+	"import",
+	"in",
+	"instanceof",
+	"new",
+	"null",
+	"return",
+	"super",
+	"switch",
+	"this",
+	"throw",
+	"true",
+	"try",
+	"typeof",
+	"var",
+	"void",
+	"while",
+	"with"
+];
+
+/// A type whose serialized value can be described with a TypeScript type.
+pub trait TsType {
+	/// The TypeScript type expression of this type, e.g. `string`, `number[]`
+	/// or `DownloadProgress`.
+	fn ts_type() -> String;
+
+	/// Adds the declarations of this type and of the named types it refers to.
+	///
+	/// Types that are described inline by [`TsType::ts_type`] don't need to
+	/// declare anything.
+	fn ts_declarations(_declarations: &mut Declarations) {}
+}
+
+/// Named TypeScript type declarations, keyed by type name.
+#[derive(Debug, Default, Clone)]
+pub struct Declarations(BTreeMap<String, String>);
+
+impl Declarations {
+	/// Whether a type with the given name was declared.
+	pub fn contains(&self, name: &str) -> bool {
+		self.0.contains_key(name)
+	}
+
+	/// Declares a type, replacing any previous declaration with the same name.
+	pub fn insert(&mut self, name: impl Into<String>, declaration: impl Into<String>) {
+		self.0.insert(name.into(), declaration.into());
+	}
+
+	/// Adds the declarations needed by `T`.
+	pub fn add<T: TsType + ?Sized>(&mut self) {
+		T::ts_declarations(self);
+	}
+
+	/// Iterates over the declarations, sorted by type name.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.0.iter().map(|(name, declaration)| (name.as_str(), declaration.as_str()))
+	}
+}
+
+/// The signature of a command, as seen from the frontend.
+///
+/// Usually created by [`generate_bindings!`](crate::generate_bindings) from
+/// the signature of a [`command`](crate::command) function.
+#[derive(Debug, Clone)]
+pub struct Command {
+	name: String,
+	args: Vec<String>,
+	returns: String,
+	declarations: Declarations
+}
+
+impl Command {
+	/// Creates a command that takes no arguments and returns nothing.
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			args: Vec::new(),
+			returns: "void".into(),
+			declarations: Declarations::default()
+		}
+	}
+
+	/// Adds an argument to the command.
+	#[must_use]
+	pub fn arg<T: TsType + ?Sized>(mut self, key: &str) -> Self {
+		self.declarations.add::<T>();
+		self.args.push(format!("{}: {}", ts_key(key), T::ts_type()));
+		self
+	}
+
+	/// Adds an argument that can be omitted, e.g. an `Option`.
+	#[must_use]
+	pub fn optional_arg<T: TsType + ?Sized>(mut self, key: &str) -> Self {
+		self.declarations.add::<T>();
+		self.args.push(format!("{}?: {}", ts_key(key), T::ts_type()));
+		self
+	}
+
+	/// Sets the type of the value the command resolves to.
+	#[must_use]
+	pub fn returns<T: TsType + ?Sized>(mut self) -> Self {
+		self.declarations.add::<T>();
+		self.returns = T::ts_type();
+		self
+	}
+
+	fn args_type(&self) -> String {
+		if self.args.is_empty() {
+			"Record<string, never>".into()
+		} else {
+			format!("{{ {} }}", self.args.join("; "))
+		}
+	}
+
+	/// The name of the function invoking the command.
+	fn function_name(&self) -> String {
+		let mut name = String::with_capacity(self.name.len());
+		let mut upper = false;
+		for c in self.name.chars() {
+			if c == '_' || c == '-' {
+				upper = !name.is_empty();
+			} else if upper {
+				name.extend(c.to_uppercase());
+				upper = false;
+			} else {
+				name.push(c);
+			}
+		}
+		if RESERVED_WORDS.contains(&name.as_str()) {
+			name.push('_');
+		}
+		name
+	}
+
+	fn render_function(&self) -> String {
+		let name = self.function_name();
+		if self.args.is_empty() {
+			return format!("export async function {name}(): Promise<{}> {{\n\treturn invoke('{}');\n}}", self.returns, self.name);
+		}
+		let default = if self.args.iter().all(|arg| arg.contains("?: ")) { " = {}" } else { "" };
+		format!(
+			"export async function {name}(args: {}{default}): Promise<{}> {{\n\treturn invoke('{}', args);\n}}",
+			self.args_type(),
+			self.returns,
+			self.name
+		)
+	}
+}
+
+/// Items used by the code generated by [`command`](crate::command) to
+/// describe the signature of a command.
+#[doc(hidden)]
+pub mod private {
+	use super::{Command, TsType};
+
+	/// Describes the types of a command.
+	///
+	/// The generated functions are generic over the implementor, so the
+	/// argument and return types are only required to implement [`TsType`]
+	/// when the bindings of the command are generated.
+	pub trait Describe<T: ?Sized> {
+		fn arg(command: Command, key: &str) -> Command;
+		fn optional_arg(command: Command, key: &str) -> Command;
+		fn returns(command: Command) -> Command;
+	}
+
+	pub struct Describer;
+
+	impl<T: TsType + ?Sized> Describe<T> for Describer {
+		fn arg(command: Command, key: &str) -> Command {
+			command.arg::<T>(key)
+		}
+
+		fn optional_arg(command: Command, key: &str) -> Command {
+			command.optional_arg::<T>(key)
+		}
+
+		fn returns(command: Command) -> Command {
+			command.returns::<T>()
+		}
+	}
+
+	/// A type that can not be described, e.g. because it depends on the
+	/// generic parameters of the command.
+	pub struct Unknown;
+
+	impl TsType for Unknown {
+		fn ts_type() -> String {
+			"unknown".into()
+		}
+	}
+}
+
+/// Formats an object key, quoting it if it is not a valid identifier.
+fn ts_key(key: &str) -> String {
+	let mut chars = key.chars();
+	let starts_like_ident = chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_' || c == '$');
+	if starts_like_ident && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+		key.to_string()
+	} else {
+		format!("{key:?}")
+	}
+}
+
+/// A TypeScript module with the types used by an application's frontend.
+///
+/// While running `millennium dev`, the bindings passed to
+/// [`Builder::typescript_bindings`](crate::Builder::typescript_bindings) are
+/// written to the `build > bindings` path of the config.
+#[derive(Debug, Default, Clone)]
+pub struct Bindings {
+	declarations: Declarations,
+	events: BTreeMap<String, String>,
+	commands: BTreeMap<String, Command>
+}
+
+impl Bindings {
+	/// Creates an empty set of bindings.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Declares the type `T`.
+	#[must_use]
+	pub fn ty<T: TsType + ?Sized>(mut self) -> Self {
+		self.declarations.add::<T>();
+		self
+	}
+
+	/// Declares the payload type of the event `E` and registers the event
+	/// in the `EventMap` of `@pyke/millennium-api/event`, which types the
+	/// `listenTyped`, `onceTyped` and `emitTyped` functions.
+	#[must_use]
+	pub fn event<E: TypedEvent>(mut self) -> Self {
+		self.declarations.add::<E>();
+		self.events.insert(E::NAME.to_string(), E::ts_type());
+		self
+	}
+
+	/// Declares a command, its argument and return types, and a function
+	/// invoking it.
+	#[must_use]
+	pub fn command(mut self, command: Command) -> Self {
+		for (name, declaration) in command.declarations.iter() {
+			self.declarations.insert(name, declaration);
+		}
+		self.commands.insert(command.name.clone(), command);
+		self
+	}
+
+	/// Renders the TypeScript module.
+	///
+	/// # Examples
+	/// ```rust
+	/// use std::collections::HashMap;
+	///
+	/// use millennium::{typescript::Bindings, TsType, TypedEvent};
+	/// use serde::{Deserialize, Serialize};
+	///
+	/// #[derive(Clone, Serialize, Deserialize, TsType, TypedEvent)]
+	/// #[serde(rename_all = "camelCase")]
+	/// #[event(name = "download-progress")]
+	/// struct DownloadProgress {
+	/// 	downloaded: u64,
+	/// 	#[serde(default, skip_serializing_if = "Option::is_none")]
+	/// 	total_size: Option<u64>,
+	/// 	state: State,
+	/// 	#[serde(skip)]
+	/// 	started: bool
+	/// }
+	///
+	/// #[derive(Clone, Serialize, Deserialize, TsType)]
+	/// #[serde(rename_all = "lowercase")]
+	/// enum State {
+	/// 	Running,
+	/// 	Finished
+	/// }
+	///
+	/// #[derive(Clone, Serialize, Deserialize, TsType)]
+	/// #[serde(tag = "type")]
+	/// enum Message {
+	/// 	Text { body: String },
+	/// 	Ping,
+	/// 	Tree(Box<Tree>)
+	/// }
+	///
+	/// #[derive(Clone, Serialize, Deserialize, TsType)]
+	/// struct Tree {
+	/// 	children: Vec<Tree>,
+	/// 	labels: HashMap<String, Option<String>>
+	/// }
+	///
+	/// assert_eq!(
+	/// 	Bindings::new().event::<DownloadProgress>().ty::<Message>().render(),
+	/// 	r#"// This file was generated by Millennium. Do not edit it manually.
+	///
+	/// export interface DownloadProgress {
+	/// 	downloaded: number;
+	/// 	totalSize?: number | null;
+	/// 	state: State;
+	/// }
+	///
+	/// export type Message = { type: "Text"; body: string } | { type: "Ping" } | { type: "Tree" } & Tree;
+	///
+	/// export type State = "running" | "finished";
+	///
+	/// export interface Tree {
+	/// 	children: Tree[];
+	/// 	labels: Record<string, string | null>;
+	/// }
+	///
+	/// declare module '@pyke/millennium-api/event' {
+	/// 	interface EventMap {
+	/// 		"download-progress": DownloadProgress;
+	/// 	}
+	/// }
+	/// "#
+	/// );
+	/// ```
+	pub fn render(&self) -> String {
+		let mut module = String::from("// This file was generated by Millennium. Do not edit it manually.\n");
+		if !self.commands.is_empty() {
+			module.push_str(&format!("\nimport {{ invoke }} from '{INVOKE_MODULE}';\n"));
+		}
+		for (_, declaration) in self.declarations.iter() {
+			module.push('\n');
+			module.push_str(declaration);
+			module.push('\n');
+		}
+		if !self.commands.is_empty() {
+			module.push_str("\nexport interface Commands {\n");
+			for (name, command) in &self.commands {
+				module.push_str(&format!("\t{}: {{ args: {}; returns: {} }};\n", ts_key(name), command.args_type(), command.returns));
+			}
+			module.push_str("}\n");
+			for command in self.commands.values() {
+				module.push('\n');
+				module.push_str(&command.render_function());
+				module.push('\n');
+			}
+		}
+		if !self.events.is_empty() {
+			module.push_str(&format!("\ndeclare module '{EVENT_MODULE}' {{\n\tinterface EventMap {{\n"));
+			for (name, ty) in &self.events {
+				module.push_str(&format!("\t\t{name:?}: {ty};\n"));
+			}
+			module.push_str("\t}\n}\n");
+		}
+		if self.declarations.0.is_empty() && self.commands.is_empty() {
+			// make sure the file is a module so the `declare module` block is an augmentation
+			module.push_str("\nexport {};\n");
+		}
+		module
+	}
+
+	/// Writes the TypeScript module to the given path, leaving the file
+	/// untouched if it is already up to date.
+	pub fn write(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+		let path = path.as_ref();
+		let module = self.render();
+		if std::fs::read_to_string(path).ok().as_deref() != Some(module.as_str()) {
+			if let Some(parent) = path.parent() {
+				std::fs::create_dir_all(parent)?;
+			}
+			std::fs::write(path, module)?;
+		}
+		Ok(())
+	}
+}
+
+/// Wraps a type in parentheses if it is a union or an intersection, so it can
+/// be used as an array element.
+fn parenthesize(ty: String) -> String {
+	if ty.contains(" | ") || ty.contains(" & ") { format!("({ty})") } else { ty }
+}
+
+macro_rules! impl_ts_type {
+	($ts:literal => $($ty:ty),+) => {
+		$(
+			impl TsType for $ty {
+				fn ts_type() -> String {
+					$ts.into()
+				}
+			}
+		)+
+	};
+}
+
+impl_ts_type!("string" => String, str, char, PathBuf, Path);
+impl_ts_type!("number" => u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+impl_ts_type!("boolean" => bool);
+impl_ts_type!("null" => ());
+impl_ts_type!("unknown" => serde_json::Value);
+
+macro_rules! impl_ts_type_transparent {
+	($($ty:ty),+) => {
+		$(
+			impl<T: TsType + ?Sized> TsType for $ty {
+				fn ts_type() -> String {
+					T::ts_type()
+				}
+
+				fn ts_declarations(declarations: &mut Declarations) {
+					T::ts_declarations(declarations)
+				}
+			}
+		)+
+	};
+}
+
+impl_ts_type_transparent!(&T, &mut T, Box<T>, Rc<T>, Arc<T>);
+
+impl<T: TsType + ToOwned + ?Sized> TsType for Cow<'_, T> {
+	fn ts_type() -> String {
+		T::ts_type()
+	}
+
+	fn ts_declarations(declarations: &mut Declarations) {
+		T::ts_declarations(declarations)
+	}
+}
+
+impl<T: TsType> TsType for Option<T> {
+	fn ts_type() -> String {
+		format!("{} | null", T::ts_type())
+	}
+
+	fn ts_declarations(declarations: &mut Declarations) {
+		T::ts_declarations(declarations)
+	}
+}
+
+macro_rules! impl_ts_type_array {
+	($($ty:ty),+) => {
+		$(
+			impl<T: TsType> TsType for $ty {
+				fn ts_type() -> String {
+					format!("{}[]", parenthesize(T::ts_type()))
+				}
+
+				fn ts_declarations(declarations: &mut Declarations) {
+					T::ts_declarations(declarations)
+				}
+			}
+		)+
+	};
+}
+
+impl_ts_type_array!([T], Vec<T>, VecDeque<T>, BTreeSet<T>);
+
+impl<T: TsType, S> TsType for HashSet<T, S> {
+	fn ts_type() -> String {
+		format!("{}[]", parenthesize(T::ts_type()))
+	}
+
+	fn ts_declarations(declarations: &mut Declarations) {
+		T::ts_declarations(declarations)
+	}
+}
+
+impl<T: TsType, const N: usize> TsType for [T; N] {
+	fn ts_type() -> String {
+		format!("{}[]", parenthesize(T::ts_type()))
+	}
+
+	fn ts_declarations(declarations: &mut Declarations) {
+		T::ts_declarations(declarations)
+	}
+}
+
+impl<K: TsType, V: TsType> TsType for BTreeMap<K, V> {
+	fn ts_type() -> String {
+		format!("Record<{}, {}>", K::ts_type(), V::ts_type())
+	}
+
+	fn ts_declarations(declarations: &mut Declarations) {
+		K::ts_declarations(declarations);
+		V::ts_declarations(declarations);
+	}
+}
+
+impl<K: TsType, V: TsType, S> TsType for HashMap<K, V, S> {
+	fn ts_type() -> String {
+		format!("Record<{}, {}>", K::ts_type(), V::ts_type())
+	}
+
+	fn ts_declarations(declarations: &mut Declarations) {
+		K::ts_declarations(declarations);
+		V::ts_declarations(declarations);
+	}
+}
+
+macro_rules! impl_ts_type_tuple {
+	($($name:ident),+) => {
+		impl<$($name: TsType),+> TsType for ($($name,)+) {
+			fn ts_type() -> String {
+				format!("[{}]", [$($name::ts_type()),+].join(", "))
+			}
+
+			fn ts_declarations(declarations: &mut Declarations) {
+				$($name::ts_declarations(declarations);)+
+			}
+		}
+	};
+}
+
+impl_ts_type_tuple!(A);
+impl_ts_type_tuple!(A, B);
+impl_ts_type_tuple!(A, B, C);
+impl_ts_type_tuple!(A, B, C, D);
+impl_ts_type_tuple!(A, B, C, D, E);
+impl_ts_type_tuple!(A, B, C, D, E, F);
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::{Bindings, Command, TsType};
+
+	#[test]
+	fn std_types() {
+		assert_eq!(<Vec<Option<u8>>>::ts_type(), "(number | null)[]");
+		assert_eq!(<(String, bool)>::ts_type(), "[string, boolean]");
+		assert_eq!(<HashMap<String, Vec<f64>>>::ts_type(), "Record<string, number[]>");
+		assert_eq!(<Option<Box<str>>>::ts_type(), "string | null");
+	}
+
+	#[test]
+	fn commands() {
+		let bindings = Bindings::new()
+			.command(
+				Command::new("download_file")
+					.arg::<String>("url")
+					.optional_arg::<Option<u32>>("retries")
+					.returns::<Vec<u8>>()
+			)
+			.command(Command::new("delete"))
+			.command(Command::new("clear_cache").optional_arg::<Option<bool>>("all"));
+		assert_eq!(
+			bindings.render(),
+			r#"// This file was generated by Millennium. Do not edit it manually.
+
+import { invoke } from '@pyke/millennium-api/millennium';
+
+export interface Commands {
+	clear_cache: { args: { all?: boolean | null }; returns: void };
+	delete: { args: Record<string, never>; returns: void };
+	download_file: { args: { url: string; retries?: number | null }; returns: number[] };
+}
+
+export async function clearCache(args: { all?: boolean | null } = {}): Promise<void> {
+	return invoke('clear_cache', args);
+}
+
+export async function delete_(): Promise<void> {
+	return invoke('delete');
+}
+
+export async function downloadFile(args: { url: string; retries?: number | null }): Promise<number[]> {
+	return invoke('download_file', args);
+}
+"#
+		);
+	}
+}
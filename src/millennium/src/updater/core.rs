@@ -37,14 +37,16 @@ use http::{
 	header::{HeaderName, HeaderValue},
 	HeaderMap, StatusCode
 };
-use millennium_utils::{platform::current_exe, Env};
-use minisign_verify::{PublicKey, Signature};
+use millennium_utils::{config::UpdaterConfig, platform::current_exe, Env};
 use semver::Version;
 use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize};
 use time::OffsetDateTime;
 use url::Url;
 
-use super::error::{Error, Result};
+use super::{
+	error::{Error, Result},
+	keys::{ReleaseKey, TrustedKeys}
+};
 #[cfg(all(desktop, not(target_os = "windows")))]
 use crate::api::file::Compression;
 #[cfg(desktop)]
@@ -74,6 +76,8 @@ pub struct RemoteRelease {
 	notes: Option<String>,
 	/// Release date.
 	pub_date: Option<OffsetDateTime>,
+	/// Keys introduced by the release.
+	keys: Vec<ReleaseKey>,
 	/// Release data.
 	data: RemoteReleaseInner
 }
@@ -90,9 +94,13 @@ impl<'de> Deserialize<'de> for RemoteRelease {
 			notes: Option<String>,
 			pub_date: Option<String>,
 			platforms: Option<HashMap<String, ReleaseManifestPlatform>>,
+			#[serde(default)]
+			keys: Vec<ReleaseKey>,
 			// dynamic platform response
 			url: Option<Url>,
 			signature: Option<String>,
+			#[serde(default)]
+			signatures: Vec<String>,
 			#[cfg(target_os = "windows")]
 			#[serde(default)]
 			with_elevated_task: bool
@@ -113,16 +121,19 @@ impl<'de> Deserialize<'de> for RemoteRelease {
 			version: release.version,
 			notes: release.notes,
 			pub_date,
+			keys: release.keys,
 			data: if let Some(platforms) = release.platforms {
 				RemoteReleaseInner::Static { platforms }
 			} else {
+				if release.signature.is_none() && release.signatures.is_empty() {
+					return Err(DeError::custom("the `signature` field was not set on the updater response"));
+				}
 				RemoteReleaseInner::Dynamic(ReleaseManifestPlatform {
 					url: release
 						.url
 						.ok_or_else(|| DeError::custom("the `url` field was not set on the updater response"))?,
-					signature: release
-						.signature
-						.ok_or_else(|| DeError::custom("the `signature` field was not set on the updater response"))?,
+					signature: release.signature.unwrap_or_default(),
+					signatures: release.signatures,
 					#[cfg(target_os = "windows")]
 					with_elevated_task: release.with_elevated_task
 				})
@@ -136,7 +147,11 @@ pub struct ReleaseManifestPlatform {
 	/// Download URL for the platform.
 	pub url: Url,
 	/// Signature for the platform.
+	#[serde(default)]
 	pub signature: String,
+	/// Signatures for the platform from other keys, e.g. while rotating keys.
+	#[serde(default)]
+	pub signatures: Vec<String>,
 	#[cfg(target_os = "windows")]
 	#[serde(default)]
 	/// On Windows, only attempt to run the updater with elevated privileges.
@@ -187,6 +202,24 @@ impl RemoteRelease {
 		}
 	}
 
+	/// All the signatures for the release for the given target.
+	pub fn signatures(&self, target: &str) -> Result<Vec<String>> {
+		let platform = match self.data {
+			RemoteReleaseInner::Dynamic(ref platform) => platform,
+			RemoteReleaseInner::Static { ref platforms } => platforms.get(target).ok_or_else(|| Error::TargetNotFound(target.to_string()))?
+		};
+		Ok(std::iter::once(&platform.signature)
+			.chain(&platform.signatures)
+			.filter(|signature| !signature.is_empty())
+			.cloned()
+			.collect())
+	}
+
+	/// The keys introduced by the release, endorsed by a previous key.
+	pub fn keys(&self) -> &[ReleaseKey] {
+		&self.keys
+	}
+
 	#[cfg(target_os = "windows")]
 	/// Optional: Windows only try to use elevated task
 	pub fn with_elevated_task(&self, target: &str) -> Result<bool> {
@@ -426,7 +459,8 @@ impl<R: Runtime> UpdateBuilder<R> {
 			current_version: self.current_version,
 			download_url: final_release.download_url(&json_target)?.to_owned(),
 			body: final_release.notes().cloned(),
-			signature: final_release.signature(&json_target)?.to_owned(),
+			signatures: final_release.signatures(&json_target)?,
+			keys: final_release.keys().to_vec(),
 			#[cfg(target_os = "windows")]
 			with_elevated_task: final_release.with_elevated_task(&json_target)?,
 			timeout: self.timeout,
@@ -460,8 +494,10 @@ pub struct Update<R: Runtime> {
 	extract_path: PathBuf,
 	/// Download URL announced
 	download_url: Url,
-	/// Signature announced
-	signature: String,
+	/// Signatures announced
+	signatures: Vec<String>,
+	/// Keys introduced by the release
+	keys: Vec<ReleaseKey>,
 	#[cfg(target_os = "windows")]
 	/// Optional: Windows only try to use elevated task
 	/// Default to false
@@ -484,7 +520,8 @@ impl<R: Runtime> Clone for Update<R> {
 			target: self.target.clone(),
 			extract_path: self.extract_path.clone(),
 			download_url: self.download_url.clone(),
-			signature: self.signature.clone(),
+			signatures: self.signatures.clone(),
+			keys: self.keys.clone(),
 			#[cfg(target_os = "windows")]
 			with_elevated_task: self.with_elevated_task,
 			timeout: self.timeout,
@@ -497,7 +534,12 @@ impl<R: Runtime> Update<R> {
 	// Download and install our update
 	// @todo(lemarier): Split into download and install (two step) but need to be
 	// thread safe
-	pub async fn download_and_install<C: Fn(usize, Option<u64>), D: FnOnce()>(&self, pub_key: String, on_chunk: C, on_download_finish: D) -> Result {
+	pub async fn download_and_install<C: Fn(usize, Option<u64>), D: FnOnce()>(
+		&self,
+		updater_config: &UpdaterConfig,
+		on_chunk: C,
+		on_download_finish: D
+	) -> Result {
 		// make sure we can install the update on linux
 		// We fail here because later we can add more linux support
 		// actually if we use APPIMAGE, our extract path should already
@@ -571,11 +613,18 @@ impl<R: Runtime> Update<R> {
 		let mut archive_buffer = Cursor::new(buffer);
 
 		// we make sure the archive is valid and signed with the private key linked with
-		// the publickey
-		verify_signature(&mut archive_buffer, &self.signature, &pub_key)?;
+		// a trusted public key, which the release may have rotated
+		let now = OffsetDateTime::now_utc();
+		let mut trusted_keys = TrustedKeys::from_config(updater_config)?;
+		trusted_keys.endorse(&self.keys, now);
+		trusted_keys.verify(&mut archive_buffer, &self.signatures, now)?;
 
 		#[cfg(desktop)]
 		{
+			// let the child processes exit before their binaries are replaced
+			#[cfg(any(shell_execute, shell_sidecar))]
+			crate::api::process::terminate_children(crate::api::process::TERMINATE_TIMEOUT);
+
 			// we copy the files depending of the operating system
 			// we run the setup, appimage re-install or overwrite the
 			// macos .app
@@ -904,35 +953,30 @@ pub fn extract_path_from_executable(env: &Env, executable_path: &Path) -> PathBu
 	extract_path
 }
 
+/// Validates the archive against a release signature from `pub_key`.
+///
+/// NOTE: The buffer position is not reset.
+#[deprecated(
+	since = "1.0.0-beta.4",
+	note = "Updates are verified against all the keys of `pubkey` and `trustedKeys`, and the keys introduced by releases"
+)]
+pub fn verify_signature<R>(archive_reader: &mut R, release_signature: &str, pub_key: &str) -> Result<bool>
+where
+	R: Read
+{
+	let config = UpdaterConfig {
+		pubkey: pub_key.into(),
+		..Default::default()
+	};
+	TrustedKeys::from_config(&config)?.verify(archive_reader, &[release_signature.into()], OffsetDateTime::now_utc())?;
+	Ok(true)
+}
+
 // Convert base64 to string and prevent failing
-fn base64_to_string(base64_string: &str) -> Result<String> {
+pub(crate) fn base64_to_string(base64_string: &str) -> Result<String> {
 	let decoded_string = &BASE64_STANDARD.decode(base64_string)?;
 	let result = from_utf8(decoded_string)
 		.map_err(|_| Error::SignatureUtf8(base64_string.into()))?
 		.to_string();
 	Ok(result)
 }
-
-// Validate signature
-// need to be public because its been used
-// by our tests in the bundler
-//
-// NOTE: The buffer position is not reset.
-pub fn verify_signature<R>(archive_reader: &mut R, release_signature: &str, pub_key: &str) -> Result<bool>
-where
-	R: Read
-{
-	// we need to convert the pub key
-	let pub_key_decoded = base64_to_string(pub_key)?;
-	let public_key = PublicKey::decode(&pub_key_decoded)?;
-	let signature_base64_decoded = base64_to_string(release_signature)?;
-	let signature = Signature::decode(&signature_base64_decoded)?;
-
-	// read all bytes until EOF in the buffer
-	let mut data = Vec::new();
-	archive_reader.read_to_end(&mut data)?;
-
-	// Validate signature or bail out
-	public_key.verify(&data, &signature, true)?;
-	Ok(true)
-}
@@ -40,6 +40,12 @@ pub enum Error {
 		"The signature {0} could not be decoded; check if it's a valid Base64 string. The signature must be the contents of the `.sig` file generated by the Millennium bundler, as a string."
 	)]
 	SignatureUtf8(String),
+	/// None of the release signatures come from a trusted, unexpired and unrevoked key.
+	#[error("The update is not signed by any trusted key. Check the updater `pubkey` and `trustedKeys` configuration.")]
+	UntrustedSignature,
+	/// The expiry date of a trusted key could not be parsed.
+	#[error("The updater key expiry `{0}` is not a valid RFC 3339 date: {1}")]
+	InvalidKeyExpiry(String, String),
 	/// Millennium utils, mainly extract and file move.
 	#[error("Millennium API error: {0}")]
 	MillenniumApi(#[from] crate::api::Error),
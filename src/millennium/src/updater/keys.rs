@@ -0,0 +1,339 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Read;
+
+use millennium_utils::config::UpdaterConfig;
+use minisign_verify::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::{
+	core::base64_to_string,
+	error::{Error, Result}
+};
+
+/// A public key introduced by a release, to rotate the updater keys.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReleaseKey {
+	/// The new public key.
+	pub pubkey: String,
+	/// The signature of `pubkey` from a key the application already trusts.
+	pub signature: String
+}
+
+struct TrustedKey {
+	public_key: PublicKey,
+	expires: Option<OffsetDateTime>
+}
+
+/// The keys the updater accepts release signatures from.
+pub(crate) struct TrustedKeys {
+	keys: Vec<TrustedKey>,
+	/// The base64 key line of the revoked keys.
+	revoked: Vec<String>
+}
+
+/// Identifies a key by the base64 line of its minisign box, so keys match
+/// regardless of their untrusted comment.
+fn key_id(decoded_key: &str) -> String {
+	decoded_key
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty())
+		.last()
+		.unwrap_or_default()
+		.to_string()
+}
+
+impl TrustedKeys {
+	/// Loads the trusted and revoked keys of the updater configuration.
+	pub fn from_config(config: &UpdaterConfig) -> Result<Self> {
+		let revoked = config
+			.revoked_keys
+			.iter()
+			.map(|key| base64_to_string(key).map(|key| key_id(&key)))
+			.collect::<Result<_>>()?;
+		let mut keys = Self { keys: Vec::new(), revoked };
+		if !config.pubkey.is_empty() {
+			keys.insert(&config.pubkey, None)?;
+		}
+		for key in &config.trusted_keys {
+			let expires = key
+				.expires
+				.as_deref()
+				.map(|expires| OffsetDateTime::parse(expires, &Rfc3339).map_err(|e| Error::InvalidKeyExpiry(expires.into(), e.to_string())))
+				.transpose()?;
+			keys.insert(&key.pubkey, expires)?;
+		}
+		Ok(keys)
+	}
+
+	fn insert(&mut self, pubkey: &str, expires: Option<OffsetDateTime>) -> Result<()> {
+		let decoded = base64_to_string(pubkey)?;
+		if !self.revoked.contains(&key_id(&decoded)) {
+			self.keys.push(TrustedKey {
+				public_key: PublicKey::decode(&decoded)?,
+				expires
+			});
+		}
+		Ok(())
+	}
+
+	/// Whether `signature` is a valid signature of `data` from a key which hasn't expired at `now`.
+	fn is_trusted(&self, data: &[u8], signature: &str, now: OffsetDateTime) -> Result<bool> {
+		let signature = Signature::decode(&base64_to_string(signature)?)?;
+		Ok(self
+			.keys
+			.iter()
+			.filter(|key| key.expires.map_or(true, |expires| now < expires))
+			.any(|key| key.public_key.verify(data, &signature, true).is_ok()))
+	}
+
+	/// Trusts the keys introduced by a release which are signed by a trusted
+	/// key. Keys can be endorsed by a key introduced before them.
+	///
+	/// Other keys are ignored, so a stale endorsement doesn't block updates
+	/// signed by another key.
+	pub fn endorse(&mut self, keys: &[ReleaseKey], now: OffsetDateTime) {
+		for key in keys {
+			if let Ok(true) = self.is_trusted(key.pubkey.as_bytes(), &key.signature, now) {
+				let _ = self.insert(&key.pubkey, None);
+			}
+		}
+	}
+
+	/// Validates the archive against the release signatures, one of which must
+	/// come from a trusted key.
+	///
+	/// Signatures which can't be decoded are skipped, so a malformed signature
+	/// doesn't block updates signed by another key.
+	///
+	/// NOTE: The buffer position is not reset.
+	pub fn verify<R: Read>(&self, archive_reader: &mut R, signatures: &[String], now: OffsetDateTime) -> Result<()> {
+		let mut data = Vec::new();
+		archive_reader.read_to_end(&mut data)?;
+
+		for signature in signatures {
+			if let Ok(true) = self.is_trusted(&data, signature, now) {
+				return Ok(());
+			}
+		}
+		Err(Error::UntrustedSignature)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+
+	use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+	use millennium_utils::config::{UpdaterConfig, UpdaterKey};
+	use minisign::{KeyPair, PublicKey, SecretKey};
+	use time::OffsetDateTime;
+
+	use super::{ReleaseKey, TrustedKeys};
+	use crate::updater::Error;
+
+	const ARCHIVE: &[u8] = b"update archive";
+
+	/// 2023-06-01T00:00:00Z
+	fn now() -> OffsetDateTime {
+		OffsetDateTime::from_unix_timestamp(1_685_577_600).unwrap()
+	}
+
+	struct Key {
+		pk: PublicKey,
+		sk: SecretKey,
+		pubkey: String
+	}
+
+	fn key() -> Key {
+		let KeyPair { pk, sk } = KeyPair::generate_unencrypted_keypair().unwrap();
+		let pubkey = BASE64_STANDARD.encode(pk.to_box().unwrap().to_string());
+		Key { pk, sk, pubkey }
+	}
+
+	fn sign(key: &Key, data: &[u8]) -> String {
+		let signature = minisign::sign(Some(&key.pk), &key.sk, Cursor::new(data), None, None).unwrap();
+		BASE64_STANDARD.encode(signature.to_string())
+	}
+
+	fn trusted_key(key: &Key, expires: Option<&str>) -> UpdaterKey {
+		UpdaterKey {
+			pubkey: key.pubkey.clone(),
+			expires: expires.map(Into::into)
+		}
+	}
+
+	fn verify(keys: &TrustedKeys, signatures: &[String]) -> bool {
+		match keys.verify(&mut Cursor::new(ARCHIVE), signatures, now()) {
+			Ok(()) => true,
+			Err(Error::UntrustedSignature) => false,
+			Err(e) => panic!("unexpected error: {e}")
+		}
+	}
+
+	#[test]
+	fn accepts_any_trusted_key() {
+		let (primary, secondary, unknown) = (key(), key(), key());
+		let config = UpdaterConfig {
+			pubkey: primary.pubkey.clone(),
+			trusted_keys: vec![trusted_key(&secondary, Some("2024-01-01T00:00:00Z"))],
+			..Default::default()
+		};
+		let keys = TrustedKeys::from_config(&config).unwrap();
+
+		assert!(verify(&keys, &[sign(&primary, ARCHIVE)]));
+		assert!(verify(&keys, &[sign(&secondary, ARCHIVE)]));
+		assert!(verify(&keys, &[sign(&unknown, ARCHIVE), sign(&secondary, ARCHIVE)]));
+		assert!(!verify(&keys, &[sign(&unknown, ARCHIVE)]));
+		assert!(!verify(&keys, &[sign(&primary, b"another archive")]));
+	}
+
+	#[test]
+	fn skips_malformed_signatures() {
+		let key = key();
+		let config = UpdaterConfig {
+			pubkey: key.pubkey.clone(),
+			..Default::default()
+		};
+		let keys = TrustedKeys::from_config(&config).unwrap();
+
+		let malformed = BASE64_STANDARD.encode("not a signature");
+		assert!(verify(&keys, &["not base64!".into(), malformed.clone(), sign(&key, ARCHIVE)]));
+		assert!(!verify(&keys, &["not base64!".into(), malformed]));
+	}
+
+	#[test]
+	fn rejects_expired_keys() {
+		let (expired, current) = (key(), key());
+		let config = UpdaterConfig {
+			trusted_keys: vec![trusted_key(&expired, Some("2023-01-01T00:00:00Z")), trusted_key(&current, None)],
+			..Default::default()
+		};
+		let keys = TrustedKeys::from_config(&config).unwrap();
+
+		assert!(!verify(&keys, &[sign(&expired, ARCHIVE)]));
+		assert!(verify(&keys, &[sign(&expired, ARCHIVE), sign(&current, ARCHIVE)]));
+	}
+
+	#[test]
+	fn rejects_revoked_keys() {
+		let (revoked, current) = (key(), key());
+		let config = UpdaterConfig {
+			pubkey: revoked.pubkey.clone(),
+			trusted_keys: vec![trusted_key(&current, None)],
+			revoked_keys: vec![revoked.pubkey.clone()],
+			..Default::default()
+		};
+		let keys = TrustedKeys::from_config(&config).unwrap();
+
+		assert!(!verify(&keys, &[sign(&revoked, ARCHIVE)]));
+		assert!(verify(&keys, &[sign(&current, ARCHIVE)]));
+	}
+
+	#[test]
+	fn rejects_invalid_expiry() {
+		let config = UpdaterConfig {
+			trusted_keys: vec![trusted_key(&key(), Some("next year"))],
+			..Default::default()
+		};
+		assert!(matches!(TrustedKeys::from_config(&config), Err(Error::InvalidKeyExpiry(..))));
+	}
+
+	#[test]
+	fn rotates_keys_endorsed_by_a_trusted_key() {
+		let (old, new, newer) = (key(), key(), key());
+		let config = UpdaterConfig {
+			pubkey: old.pubkey.clone(),
+			..Default::default()
+		};
+		let mut keys = TrustedKeys::from_config(&config).unwrap();
+		assert!(!verify(&keys, &[sign(&new, ARCHIVE)]));
+
+		keys.endorse(
+			&[
+				ReleaseKey {
+					pubkey: new.pubkey.clone(),
+					signature: sign(&old, new.pubkey.as_bytes())
+				},
+				ReleaseKey {
+					pubkey: newer.pubkey.clone(),
+					signature: sign(&new, newer.pubkey.as_bytes())
+				}
+			],
+			now()
+		);
+		assert!(verify(&keys, &[sign(&new, ARCHIVE)]));
+		assert!(verify(&keys, &[sign(&newer, ARCHIVE)]));
+	}
+
+	#[test]
+	fn ignores_untrusted_endorsements() {
+		let (expired, unknown, new) = (key(), key(), key());
+		let config = UpdaterConfig {
+			trusted_keys: vec![trusted_key(&expired, Some("2023-01-01T00:00:00Z"))],
+			..Default::default()
+		};
+		let mut keys = TrustedKeys::from_config(&config).unwrap();
+
+		keys.endorse(
+			&[
+				ReleaseKey {
+					pubkey: new.pubkey.clone(),
+					signature: sign(&expired, new.pubkey.as_bytes())
+				},
+				ReleaseKey {
+					pubkey: new.pubkey.clone(),
+					signature: sign(&unknown, new.pubkey.as_bytes())
+				},
+				ReleaseKey {
+					pubkey: new.pubkey.clone(),
+					signature: "not a signature".into()
+				}
+			],
+			now()
+		);
+		assert!(!verify(&keys, &[sign(&new, ARCHIVE)]));
+	}
+
+	#[test]
+	fn does_not_rotate_to_revoked_keys() {
+		let (old, revoked) = (key(), key());
+		let config = UpdaterConfig {
+			pubkey: old.pubkey.clone(),
+			revoked_keys: vec![revoked.pubkey.clone()],
+			..Default::default()
+		};
+		let mut keys = TrustedKeys::from_config(&config).unwrap();
+
+		keys.endorse(
+			&[ReleaseKey {
+				pubkey: revoked.pubkey.clone(),
+				signature: sign(&old, revoked.pubkey.as_bytes())
+			}],
+			now()
+		);
+		assert!(!verify(&keys, &[sign(&revoked, ARCHIVE)]));
+	}
+
+	#[test]
+	#[allow(deprecated)]
+	fn verifies_signatures_of_a_single_key() {
+		let (key, other) = (key(), key());
+		assert!(crate::updater::verify_signature(&mut Cursor::new(ARCHIVE), &sign(&key, ARCHIVE), &key.pubkey).unwrap());
+		assert!(crate::updater::verify_signature(&mut Cursor::new(ARCHIVE), &sign(&other, ARCHIVE), &key.pubkey).is_err());
+	}
+}
@@ -388,6 +388,38 @@
 //! }
 //! ```
 //!
+//! ## Rotating keys
+//!
+//! Besides "pubkey", the updater trusts the keys listed in "trustedKeys", each
+//! with an optional RFC 3339 "expires" date, and never trusts the keys listed
+//! in "revokedKeys":
+//!
+//! ```toml
+//! [updater]
+//! pubkey = "OLD_PUBLIC_KEY"
+//! trustedKeys = [ { pubkey = "NEW_PUBLIC_KEY", expires = "2025-01-01T00:00:00Z" } ]
+//! revokedKeys = [ "COMPROMISED_PUBLIC_KEY" ]
+//! ```
+//!
+//! A release can be signed by several keys with the "signatures" field, next
+//! to "signature". To roll out a new key to installed clients before they
+//! update, a release can introduce it in its "keys" field, together with the
+//! signature of the new public key string from a key the clients already
+//! trust (e.g. made with `millennium signer sign` on a file containing the new
+//! public key):
+//!
+//! ```json
+//! {
+//! 	"version": "1.1.0",
+//! 	"keys": [{ "pubkey": "NEW_PUBLIC_KEY", "signature": "SIGNATURE_OF_NEW_PUBLIC_KEY_BY_OLD_KEY" }],
+//! 	"url": "https://mycompany.example.com/myapp/releases/myrelease.tar.gz",
+//! 	"signature": "SIGNATURE_BY_NEW_KEY"
+//! }
+//! ```
+//!
+//! Keep introducing the new key until every client runs a version which
+//! trusts it in its configuration.
+//!
 //!
 //! # Bundler (Artifacts)
 //!
@@ -397,6 +429,11 @@
 //! If the bundler can locate your private and pubkey, your update artifacts
 //! will be automatically signed.
 //!
+//! To package binaries built elsewhere, e.g. in another CI job, run
+//! `millennium bundle path/to/binary --target <target triple>` instead of
+//! `millennium build`; it bundles and signs the same artifacts without
+//! invoking cargo.
+//!
 //! The signature can be found in the `sig` file. The signature can be uploaded
 //! to GitHub safely or made public as long as your private key is secure.
 //!
@@ -488,9 +525,25 @@
 //! `MILLENNIUM_PRIVATE_KEY`: Path or String of your private key
 //!
 //! `MILLENNIUM_KEY_PASSWORD`: Your private key password (optional)
+//!
+//! `MILLENNIUM_SIGNER`: `command:<command>` or a signing service URL to sign
+//! with instead of a private key (optional)
+//!
+//! `MILLENNIUM_SIGNER_TOKEN`: The bearer token of the `MILLENNIUM_SIGNER`
+//! signing service (optional)
+//!
+//! `MILLENNIUM_SIGNER_PUBLIC_KEY`: The public key of the `MILLENNIUM_SIGNER`
+//! key, when it isn't "pubkey" (optional)
+//!
+//! The artifacts can be signed by the key of "pubkey" or of any
+//! "trustedKeys" entry, otherwise the build fails. To list the signatures of
+//! several keys in "signatures", sign the artifact once per key with
+//! `millennium signer sign`, which overwrites the `.sig` file, so save each
+//! signature before the next one.
 
 mod core;
 mod error;
+mod keys;
 
 use std::time::Duration;
 
@@ -498,12 +551,14 @@ use http::header::{HeaderName, HeaderValue};
 use semver::Version;
 use time::OffsetDateTime;
 
-pub use self::{core::RemoteRelease, error::Error};
+#[allow(deprecated)]
+pub use self::core::verify_signature;
+pub use self::{core::RemoteRelease, error::Error, keys::ReleaseKey};
 /// Alias for [`std::result::Result`] using our own [`Error`].
 pub type Result<T> = std::result::Result<T, Error>;
 #[cfg(desktop)]
 use crate::api::dialog::blocking::ask;
-use crate::{runtime::EventLoopProxy, AppHandle, EventLoopMessage, Manager, Runtime, UpdaterEvent};
+use crate::{runtime::EventLoopProxy, utils::config::UpdaterConfig, AppHandle, EventLoopMessage, Manager, Runtime, UpdaterEvent};
 
 #[cfg(mobile)]
 fn ask<R: Runtime>(_parent_window: Option<&crate::Window<R>>, _title: impl AsRef<str>, _message: impl AsRef<str>) -> bool {
@@ -827,12 +882,10 @@ pub(crate) async fn check_update_with_dialog<R: Runtime>(handle: AppHandle<R>) {
 		// check updates
 		match builder.build().await {
 			Ok(updater) => {
-				let pubkey = updater_config.pubkey.clone();
-
 				// if dialog enabled only
 				if updater.should_update && updater_config.dialog {
 					let body = updater.body.clone().unwrap_or_else(|| String::from(""));
-					let dialog = prompt_for_install(&updater.clone(), &package_info.name, &body.clone(), pubkey).await;
+					let dialog = prompt_for_install(&updater.clone(), &package_info.name, &body.clone(), &updater_config).await;
 
 					if let Err(e) = dialog {
 						send_status_update(&handle, UpdaterEvent::Error(e.to_string()));
@@ -873,7 +926,7 @@ pub(crate) async fn download_and_install<R: Runtime>(update: core::Update<R>) ->
 	// AppImage by launching a new install, which starts a new AppImage instance, and closes the old one.
 	let update_result = update
 		.download_and_install(
-			update.app.config().millennium.updater.pubkey.clone(),
+			&update.app.config().millennium.updater,
 			move |chunk_length, content_length| {
 				send_download_progress_event(&handle, chunk_length, content_length);
 			},
@@ -945,7 +998,7 @@ fn send_status_update<R: Runtime>(handle: &AppHandle<R>, message: UpdaterEvent)
 
 // Prompt a dialog asking if the user want to install the new version
 // Maybe we should add an option to customize it in future versions.
-async fn prompt_for_install<R: Runtime>(update: &self::core::Update<R>, app_name: &str, body: &str, pubkey: String) -> Result<()> {
+async fn prompt_for_install<R: Runtime>(update: &self::core::Update<R>, app_name: &str, body: &str, updater_config: &UpdaterConfig) -> Result<()> {
 	let windows = update.app.windows();
 	let parent_window = windows.values().next();
 
@@ -972,7 +1025,7 @@ Release Notes:
 		// (the process stop here) Linux we replace the AppImage by launching a new
 		// install, it start a new AppImage instance, so we're closing the previous.
 		// (the process stop here)
-		update.download_and_install(pubkey.clone(), |_, _| (), || ()).await?;
+		update.download_and_install(updater_config, |_, _| (), || ()).await?;
 
 		// Ask user if we need to restart the application
 		let should_exit = ask(parent_window, "Ready to Restart", "The installation was successful, do you want to restart the application now?");
@@ -39,7 +39,7 @@ use crate::TitleBarStyle;
 use crate::{
 	app::AppHandle,
 	command::{CommandArg, CommandItem},
-	event::{Event, EventHandler},
+	event::{typed_handler, typed_once_handler, Event, EventHandler, TypedEvent},
 	hooks::{InvokePayload, InvokeResponder},
 	manager::WindowManager,
 	runtime::{
@@ -1358,6 +1358,45 @@ impl<R: Runtime> Window<R> {
 		let label = self.window.label.clone();
 		self.manager.trigger(event, Some(label), data)
 	}
+
+	/// Emits a typed event to both the JavaScript and the Rust listeners.
+	///
+	/// Typed Rust listeners receive the payload without it being serialized.
+	pub fn emit_and_trigger_typed<E: TypedEvent>(&self, payload: E) -> crate::Result<()> {
+		self.trigger_typed(payload.clone())?;
+		self.emit_typed(payload)
+	}
+
+	/// Emits a typed event to the JavaScript listeners on the current window.
+	pub fn emit_typed<E: TypedEvent>(&self, payload: E) -> crate::Result<()> {
+		self.emit(E::NAME, payload)
+	}
+
+	/// Listen to a typed event on this window.
+	///
+	/// Payloads that can't be deserialized as `E` are logged and skipped.
+	pub fn listen_typed<E, F>(&self, handler: F) -> EventHandler
+	where
+		E: TypedEvent,
+		F: Fn(E) + Send + 'static
+	{
+		self.listen(E::NAME, typed_handler(handler))
+	}
+
+	/// Listen to a typed event on this window a single time.
+	pub fn once_typed<E, F>(&self, handler: F) -> EventHandler
+	where
+		E: TypedEvent,
+		F: FnOnce(E) + Send + 'static
+	{
+		self.once(E::NAME, typed_once_handler(handler))
+	}
+
+	/// Triggers a typed event to the Rust listeners on this window.
+	pub fn trigger_typed<E: TypedEvent>(&self, payload: E) -> crate::Result<()> {
+		let label = self.window.label.clone();
+		self.manager.trigger_typed(Some(label), payload)
+	}
 }
 
 #[cfg(test)]
@@ -34,8 +34,9 @@ use log::{info, warn};
 pub use self::{
 	category::AppCategory,
 	settings::{
-		BundleBinary, BundleSettings, DebianSettings, MacOsSettings, NsisSettings, PackageSettings, PackageType, Settings, SettingsBuilder, UpdaterSettings,
-		WindowsSettings, WixLanguage, WixLanguageConfig, WixSettings
+		AppImageSettings, AppStreamContentRating, AppStreamScreenshot, AppStreamSettings, BundleBinary, BundleSettings, DebianSettings, FlatpakSettings,
+		MacOsSettings, NsisSettings, PackageSettings, PackageType, PacmanSettings, Settings, SettingsBuilder, UpdaterSettings, WindowsSettings, WixLanguage,
+		WixLanguageConfig, WixSettings
 	}
 };
 
@@ -70,6 +71,10 @@ pub fn bundle_project(settings: Settings) -> crate::Result<Vec<Bundle>> {
 			PackageType::Rpm => linux::rpm::bundle_project(&settings)?,
 			#[cfg(target_os = "linux")]
 			PackageType::AppImage => linux::appimage::bundle_project(&settings)?,
+			#[cfg(target_os = "linux")]
+			PackageType::Flatpak => linux::flatpak::bundle_project(&settings)?,
+			#[cfg(target_os = "linux")]
+			PackageType::Pacman => linux::pacman::bundle_project(&settings)?,
 			// dmg is dependant of MacOsBundle, we send our bundles to prevent rebuilding
 			#[cfg(target_os = "macos")]
 			PackageType::Dmg => macos::dmg::bundle_project(&settings, &bundles)?,
@@ -20,7 +20,8 @@ use std::{
 	io::{self, BufReader, BufWriter},
 	path::Path,
 	process::{Command, Output, Stdio},
-	sync::{Arc, Mutex}
+	sync::{Arc, Mutex},
+	time::{SystemTime, UNIX_EPOCH}
 };
 
 use log::debug;
@@ -130,6 +131,16 @@ pub fn copy_dir(from: &Path, to: &Path) -> crate::Result<()> {
 	Ok(())
 }
 
+/// Returns the `SOURCE_DATE_EPOCH` timestamp for reproducible builds, or the
+/// current time.
+#[allow(dead_code)]
+pub fn build_date() -> u64 {
+	std::env::var("SOURCE_DATE_EPOCH")
+		.ok()
+		.and_then(|epoch| epoch.parse().ok())
+		.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default())
+}
+
 pub trait CommandExt {
 	fn output_ok(&mut self) -> crate::Result<Output>;
 }
@@ -15,21 +15,79 @@
 // limitations under the License.
 
 use std::{
-	collections::BTreeMap,
-	fs::{remove_dir_all, write},
-	path::PathBuf,
-	process::{Command, Stdio}
+	collections::{BTreeMap, BTreeSet, VecDeque},
+	fs::{self, remove_dir_all, File},
+	io::{self, Cursor, Seek, SeekFrom, Write},
+	os::unix::fs::{symlink, MetadataExt, PermissionsExt},
+	path::{Path, PathBuf}
 };
 
 use anyhow::Context;
+use backhand::{compression::Compressor, FilesystemCompressor, FilesystemWriter, NodeHeader};
+use goblin::elf::Elf;
 use handlebars::Handlebars;
-use log::info;
+use log::{info, warn};
 
 use super::{
-	super::{common::CommandExt, path_utils},
+	super::{common, path_utils},
 	debian
 };
 use crate::Settings;
+
+mod zsync;
+
+/// Libraries that must come from the host system, because they are tied to its
+/// kernel, graphics drivers or C library. Based on the AppImage excludelist.
+const EXCLUDED_LIBRARIES: &[&str] = &[
+	"ld-linux",
+	"libc.so",
+	"libdl.so",
+	"libm.so",
+	"libmvec.so",
+	"libpthread.so",
+	"librt.so",
+	"libresolv.so",
+	"libutil.so",
+	"libnsl.so",
+	"libanl.so",
+	"libBrokenLocale.so",
+	"libgcc_s.so",
+	"libGL.so",
+	"libGLX.so",
+	"libGLdispatch.so",
+	"libOpenGL.so",
+	"libEGL.so",
+	"libGLESv2.so",
+	"libdrm.so",
+	"libgbm.so",
+	"libglapi.so",
+	"libxcb.so",
+	"libX11.so",
+	"libX11-xcb.so",
+	"libasound.so",
+	"libjack.so",
+	"libfontconfig.so",
+	"libfreetype.so",
+	"libharfbuzz.so",
+	"libcom_err.so",
+	"libexpat.so",
+	"libgpg-error.so",
+	"libusb-1.0.so",
+	"libuuid.so"
+];
+
+/// The WebKit helpers that are loaded from their install path instead of being
+/// linked.
+const WEBKIT_FILES: &[&str] = &["WebKitNetworkProcess", "WebKitWebProcess", "libwebkit2gtkinjectedbundle.so"];
+
+/// The directories GTK loads its modules from, relative to a library directory.
+const GDK_PIXBUF_DIR: &str = "gdk-pixbuf-2.0/2.10.0";
+const GIO_MODULES_DIR: &str = "gio/modules";
+const GTK_DIR: &str = "gtk-3.0";
+
+/// The directory of the compiled GSettings schemas of the host.
+const GSETTINGS_SCHEMAS_DIR: &str = "/usr/share/glib-2.0/schemas";
+
 /// Bundles the project.
 /// Returns a vector of PathBuf that shows where the AppImage was created.
 pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
@@ -42,7 +100,7 @@ pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
 	let package_dir = settings.project_out_directory().join("bundle/appimage_deb");
 
 	// generate deb_folder structure
-	let (_, icons) = debian::generate_data(settings, &package_dir)?;
+	let (data_dir, icons) = debian::generate_data(settings, &package_dir)?;
 	let icons: Vec<debian::DebIcon> = icons.into_iter().collect();
 
 	let output_path = settings.project_out_directory().join("bundle/appimage");
@@ -53,69 +111,587 @@ pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
 	let app_dir_path = output_path.join(format!("{}.AppDir", settings.main_binary_name()));
 	let appimage_filename = format!("{}_{}_{}.AppImage", settings.main_binary_name(), settings.version_string(), arch);
 	let appimage_path = output_path.join(&appimage_filename);
-	path_utils::create(app_dir_path, true)?;
-
-	let upcase_app_name = settings.main_binary_name().to_uppercase();
-
-	// setup data to insert into shell script
-	let mut sh_map = BTreeMap::new();
-	sh_map.insert("arch", settings.target().split('-').next().unwrap());
-	sh_map.insert("app_name", settings.main_binary_name());
-	sh_map.insert("app_name_uppercase", &upcase_app_name);
-	sh_map.insert("appimage_filename", &appimage_filename);
-	let millennium_tools_path = dirs_next::cache_dir().map_or_else(
-		|| output_path.to_path_buf(),
-		|mut p| {
-			p.push("millennium");
-			p
+
+	info!(action = "Bundling"; "{} ({})", appimage_filename, appimage_path.display());
+
+	let runtime = find_runtime(settings)?;
+	generate_app_dir(settings, &data_dir, &icons, &app_dir_path).with_context(|| "Failed to create AppDir")?;
+	remove_dir_all(&package_dir)?;
+
+	let runtime = fs::read(&runtime).with_context(|| format!("Failed to read AppImage runtime {}", runtime.display()))?;
+	let update_information = settings.appimage().update_information.as_deref();
+	write_appimage(runtime, update_information, &app_dir_path, &appimage_path)?;
+
+	let mut paths = vec![appimage_path];
+	if update_information.map_or(false, |u| u.split('|').next().map_or(false, |transport| transport.ends_with("zsync"))) {
+		let zsync_path = output_path.join(format!("{appimage_filename}.zsync"));
+		zsync::write(&paths[0], &appimage_filename, &zsync_path).with_context(|| "Failed to create zsync file")?;
+		paths.push(zsync_path);
+	}
+	Ok(paths)
+}
+
+/// Finds the AppImage runtime in the configured path, the
+/// `MILLENNIUM_APPIMAGE_RUNTIME` environment variable or the cache directory.
+fn find_runtime(settings: &Settings) -> crate::Result<PathBuf> {
+	if let Some(runtime) = settings
+		.appimage()
+		.runtime
+		.clone()
+		.or_else(|| std::env::var_os("MILLENNIUM_APPIMAGE_RUNTIME").map(Into::into))
+	{
+		return if runtime.is_file() {
+			Ok(runtime)
+		} else {
+			Err(crate::Error::GenericError(format!("AppImage runtime {} does not exist", runtime.display())))
+		};
+	}
+
+	let arch = match settings.binary_arch() {
+		"x86" => "i686",
+		"arm" => "armhf",
+		other => other
+	};
+	let cached = dirs_next::cache_dir().map(|dir| dir.join("millennium").join(format!("runtime-{arch}")));
+	match cached {
+		Some(runtime) if runtime.is_file() => Ok(runtime),
+		cached => Err(crate::Error::GenericError(format!(
+			"could not find an AppImage runtime; download `runtime-{arch}` from https://github.com/AppImage/type2-runtime/releases and set `bundle > appimage > runtime` to its path{}",
+			cached.map(|p| format!(" or save it as {}", p.display())).unwrap_or_default()
+		)))
+	}
+}
+
+/// Creates the AppDir from the Debian data directory.
+fn generate_app_dir(settings: &Settings, data_dir: &Path, icons: &[debian::DebIcon], app_dir: &Path) -> crate::Result<()> {
+	path_utils::create(app_dir, true)?;
+	common::copy_dir(&data_dir.join("usr"), &app_dir.join("usr"))?;
+	let lib_dir = app_dir.join("usr/lib");
+	fs::create_dir_all(&lib_dir)?;
+
+	if std::env::var_os("APPIMAGE_BUNDLE_XDG_OPEN").map_or(false, |v| v != "0") && Path::new("/usr/bin/xdg-open").exists() {
+		common::copy_file("/usr/bin/xdg-open", app_dir.join("usr/bin/xdg-open"))?;
+	}
+
+	if let Some(tray) = std::env::var_os("TRAY_LIBRARY_PATH").filter(|v| v != "0").map(PathBuf::from) {
+		let file_name = tray
+			.file_name()
+			.ok_or_else(|| crate::Error::GenericError(format!("invalid tray library path {}", tray.display())))?;
+		common::copy_file(&tray, lib_dir.join(file_name))?;
+		// some distros only ship the `.so.1` but the library is loaded as `.so`
+		if let Some(old_name) = file_name.to_str().and_then(|name| name.strip_suffix(".1")) {
+			symlink(file_name, lib_dir.join(old_name))?;
+		}
+	}
+
+	let lib_dirs = library_dirs(settings);
+	let mut elves = vec![];
+	for bin in fs::read_dir(app_dir.join("usr/bin"))? {
+		elves.push(bin?.path());
+	}
+
+	let webkit_dirs = mirror_webkit_helpers(&lib_dirs, app_dir, &mut elves)?;
+
+	for library in &settings.appimage().libraries {
+		let file_name = library
+			.file_name()
+			.ok_or_else(|| crate::Error::GenericError(format!("invalid library path {}", library.display())))?;
+		let dest = lib_dir.join(file_name);
+		common::copy_file(library, &dest).with_context(|| format!("Failed to copy library {}", library.display()))?;
+		elves.push(dest);
+	}
+
+	if settings.appimage().bundle_media_framework {
+		match lib_dirs.iter().map(|dir| dir.join("gstreamer-1.0")).find(|dir| dir.is_dir()) {
+			Some(plugins) => copy_modules(&plugins, &lib_dir.join("gstreamer-1.0"), &mut elves)?,
+			None => warn!("could not find the GStreamer plugins; they won't be bundled")
 		}
-	);
-	std::fs::create_dir_all(&millennium_tools_path)?;
-	let millennium_tools_path_str = millennium_tools_path.to_string_lossy();
-	sh_map.insert("millennium_tools_path", &millennium_tools_path_str);
+	}
+
+	bundle_gtk(&lib_dirs, Path::new(GSETTINGS_SCHEMAS_DIR), app_dir, &mut elves)?;
+	bundle_dependencies(&elves, &lib_dirs, &lib_dir)?;
+	patch_webkit(&lib_dir, &webkit_dirs)?;
+	write_entry_points(app_dir, data_dir, settings.main_binary_name(), icons)?;
+
+	Ok(())
+}
+
+/// Writes the entry point of the AppImage, its icon and its desktop entry at
+/// the root of the AppDir.
+fn write_entry_points(app_dir: &Path, data_dir: &Path, app_name: &str, icons: &[debian::DebIcon]) -> crate::Result<()> {
+	let mut data = BTreeMap::new();
+	data.insert("app_name", app_name);
+	let mut handlebars = Handlebars::new();
+	handlebars
+		.register_template_string("AppRun", include_str!("templates/AppRun"))
+		.expect("Failed to register template for handlebars");
+	let app_run = app_dir.join("AppRun");
+	fs::write(&app_run, handlebars.render("AppRun", &data)?)?;
+	fs::set_permissions(&app_run, fs::Permissions::from_mode(0o755))?;
+
 	let larger_icon = icons
 		.iter()
 		.filter(|i| i.width == i.height)
 		.max_by_key(|i| i.width)
-		.expect("couldn't find a square icon to use as AppImage icon");
-	let larger_icon_path = larger_icon
-		.path
-		.strip_prefix(package_dir.join("data"))
-		.unwrap()
-		.to_string_lossy()
-		.to_string();
-	sh_map.insert("icon_path", &larger_icon_path);
-
-	// initialize shell script template.
-	let mut handlebars = Handlebars::new();
-	handlebars
-		.register_template_string("appimage", include_str!("templates/appimage"))
-		.expect("Failed to register template for handlebars");
-	let temp = handlebars.render("appimage", &sh_map)?;
+		.ok_or_else(|| crate::Error::GenericError("couldn't find a square icon to use as AppImage icon".into()))?;
+	let icon_path = larger_icon.path.strip_prefix(data_dir)?;
+	fs::copy(&larger_icon.path, app_dir.join(".DirIcon"))?;
+	symlink(icon_path, app_dir.join(format!("{app_name}.png")))?;
+	symlink(format!("usr/share/applications/{app_name}.desktop"), app_dir.join(format!("{app_name}.desktop")))?;
+	Ok(())
+}
 
-	// create the shell script file in the target/ folder.
-	let sh_file = output_path.join("build_appimage.sh");
+/// Mirrors the WebKit helpers at their install path in the AppDir, returning
+/// the directories they were found in.
+fn mirror_webkit_helpers(lib_dirs: &[PathBuf], app_dir: &Path, elves: &mut Vec<PathBuf>) -> crate::Result<BTreeSet<PathBuf>> {
+	let mut webkit_dirs = BTreeSet::new();
+	for dir in lib_dirs {
+		for entry in walkdir::WalkDir::new(dir).max_depth(3).into_iter().filter_map(Result::ok) {
+			if entry.file_type().is_file() && WEBKIT_FILES.iter().any(|file| entry.file_name() == *file) {
+				let dest = app_dir.join(entry.path().strip_prefix("/")?);
+				if !dest.exists() {
+					common::copy_file(entry.path(), &dest)?;
+					elves.push(dest);
+				}
+				if let Some(parent) = entry.path().parent() {
+					webkit_dirs.insert(parent.to_path_buf());
+				}
+			}
+		}
+	}
+	Ok(webkit_dirs)
+}
 
-	info!(action = "Bundling"; "{} ({})", appimage_filename, appimage_path.display());
+/// Rewrites the paths of the WebKit helper directories compiled into the
+/// bundled WebKitGTK library, e.g. `/usr/lib/webkit2gtk-4.0`, to paths of the
+/// same length relative to `usr`, e.g. `././/lib/webkit2gtk-4.0`. `AppRun`
+/// runs the app in `$APPDIR/usr`, so WebKit starts the mirrored helpers
+/// instead of the ones of the host, which may not match the bundled library.
+fn patch_webkit(lib_dir: &Path, webkit_dirs: &BTreeSet<PathBuf>) -> crate::Result<()> {
+	for entry in fs::read_dir(lib_dir)? {
+		let path = entry?.path();
+		let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+		if !file_name.starts_with("libwebkit2gtk-") {
+			continue;
+		}
+
+		let mut data = fs::read(&path)?;
+		let mut patched = false;
+		for dir in webkit_dirs {
+			if let Some(relative) = dir.to_str().and_then(|dir| dir.strip_prefix("/usr")) {
+				patched |= replace_bytes(&mut data, dir.to_string_lossy().as_bytes(), format!("././{relative}").as_bytes());
+			}
+		}
+		if patched {
+			fs::write(&path, data)?;
+		} else {
+			warn!("could not patch {}; the AppImage will use the WebKit helpers of the host", path.display());
+		}
+	}
+	Ok(())
+}
+
+/// Replaces every occurrence of `from` in `data` with `to`, which must have the
+/// same length. Returns whether `from` was found.
+fn replace_bytes(data: &mut [u8], from: &[u8], to: &[u8]) -> bool {
+	assert_eq!(from.len(), to.len());
+	let mut found = false;
+	let mut start = 0;
+	while let Some(position) = data[start..].windows(from.len()).position(|window| window == from) {
+		start += position;
+		data[start..start + from.len()].copy_from_slice(to);
+		start += from.len();
+		found = true;
+	}
+	found
+}
 
-	write(&sh_file, temp)?;
+/// Bundles the GDK-Pixbuf image loaders, the GIO modules, the GTK modules and
+/// the compiled GSettings schemas, which GTK loads at runtime from the paths
+/// set by `AppRun`.
+fn bundle_gtk(lib_dirs: &[PathBuf], schemas_dir: &Path, app_dir: &Path, elves: &mut Vec<PathBuf>) -> crate::Result<()> {
+	let lib_dir = app_dir.join("usr/lib");
+	let find = |dir: &str| lib_dirs.iter().map(|lib_dir| lib_dir.join(dir)).find(|dir| dir.is_dir());
 
-	// chmod script for execution
-	Command::new("chmod")
-		.arg("777")
-		.arg(&sh_file)
-		.current_dir(output_path.clone())
-		.stdout(Stdio::piped())
-		.stderr(Stdio::piped())
-		.output()
-		.expect("Failed to chmod script");
+	match find(GDK_PIXBUF_DIR) {
+		Some(pixbuf_dir) => {
+			copy_modules(&pixbuf_dir.join("loaders"), &lib_dir.join(GDK_PIXBUF_DIR).join("loaders"), elves)?;
+			rewrite_module_cache(&pixbuf_dir.join("loaders.cache"), &pixbuf_dir.join("loaders"), &lib_dir.join(GDK_PIXBUF_DIR).join("loaders.cache"))?;
+		}
+		None => warn!("could not find the GDK-Pixbuf loaders; the app may fail to load images")
+	}
 
-	// execute the shell script to build the appimage.
-	Command::new(&sh_file)
-		.current_dir(output_path)
-		.output_ok()
-		.context("error running appimage.sh")?;
+	match find(GIO_MODULES_DIR) {
+		Some(gio_dir) => copy_modules(&gio_dir, &lib_dir.join(GIO_MODULES_DIR), elves)?,
+		None => warn!("could not find the GIO modules; they won't be bundled")
+	}
 
-	remove_dir_all(&package_dir)?;
-	Ok(vec![appimage_path])
+	match find(GTK_DIR) {
+		Some(gtk_dir) => {
+			copy_modules(&gtk_dir, &lib_dir.join(GTK_DIR), elves)?;
+			let immodules_cache = gtk_dir.join("3.0.0/immodules.cache");
+			if immodules_cache.is_file() {
+				rewrite_module_cache(&immodules_cache, &gtk_dir.join("3.0.0/immodules"), &lib_dir.join(GTK_DIR).join("3.0.0/immodules.cache"))?;
+			}
+		}
+		None => warn!("could not find the GTK modules; they won't be bundled")
+	}
+
+	let schemas = schemas_dir.join("gschemas.compiled");
+	if schemas.is_file() {
+		common::copy_file(&schemas, app_dir.join("usr/share/glib-2.0/schemas/gschemas.compiled"))?;
+	} else {
+		warn!("could not find the compiled GSettings schemas; they won't be bundled");
+	}
+	Ok(())
+}
+
+/// Copies the shared libraries in `from` and its subdirectories to `to`.
+fn copy_modules(from: &Path, to: &Path, elves: &mut Vec<PathBuf>) -> crate::Result<()> {
+	for entry in walkdir::WalkDir::new(from).into_iter().filter_map(Result::ok) {
+		if entry.file_type().is_file() && entry.path().extension().map_or(false, |ext| ext == "so") {
+			let dest = to.join(entry.path().strip_prefix(from)?);
+			common::copy_file(entry.path(), &dest)?;
+			elves.push(dest);
+		}
+	}
+	Ok(())
+}
+
+/// Copies a GDK-Pixbuf loaders or GTK input methods cache, making the paths of
+/// the modules relative to their directory, where they are looked up at runtime.
+fn rewrite_module_cache(cache: &Path, modules_dir: &Path, dest: &Path) -> crate::Result<()> {
+	let contents = fs::read_to_string(cache).with_context(|| format!("Failed to read module cache {}", cache.display()))?;
+	let contents = contents.replace(&format!("\"{}/", modules_dir.display()), "\"");
+	if let Some(parent) = dest.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	fs::write(dest, contents)?;
+	Ok(())
+}
+
+/// The directories shared libraries are looked up in.
+fn library_dirs(settings: &Settings) -> Vec<PathBuf> {
+	let multiarch = match settings.binary_arch() {
+		"x86" => "i386-linux-gnu",
+		"arm" => "arm-linux-gnueabihf",
+		"aarch64" => "aarch64-linux-gnu",
+		_ => "x86_64-linux-gnu"
+	};
+	let mut dirs: Vec<PathBuf> = std::env::var_os("LD_LIBRARY_PATH")
+		.map(|paths| std::env::split_paths(&paths).collect())
+		.unwrap_or_default();
+	for prefix in ["/usr/lib", "/lib"] {
+		dirs.push(Path::new(prefix).join(multiarch));
+	}
+	dirs.extend(["/usr/lib64", "/lib64", "/usr/lib", "/lib"].iter().map(PathBuf::from));
+	dirs.retain(|dir| dir.is_dir());
+	dirs
+}
+
+/// Copies the shared libraries the given ELF files depend on to `lib_dir`,
+/// recursively.
+fn bundle_dependencies(elves: &[PathBuf], lib_dirs: &[PathBuf], lib_dir: &Path) -> crate::Result<()> {
+	let mut seen = BTreeSet::new();
+	let mut queue: VecDeque<PathBuf> = elves.iter().cloned().collect();
+	while let Some(path) = queue.pop_front() {
+		let data = fs::read(&path)?;
+		let elf = match Elf::parse(&data) {
+			Ok(elf) => elf,
+			// scripts and other files that are not ELF binaries have no dependencies
+			Err(_) => continue
+		};
+		for library in elf.libraries {
+			if !seen.insert(library.to_string()) || EXCLUDED_LIBRARIES.iter().any(|excluded| library.starts_with(excluded)) {
+				continue;
+			}
+			let dest = lib_dir.join(library);
+			if dest.exists() {
+				continue;
+			}
+			match lib_dirs.iter().map(|dir| dir.join(library)).find(|path| path.is_file()) {
+				Some(source) => {
+					// copy the contents, in case the library is a symlink
+					fs::copy(&source, &dest).with_context(|| format!("Failed to copy library {}", source.display()))?;
+					queue.push_back(dest);
+				}
+				None => warn!("could not find library {} needed by {}", library, path.display())
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Writes the AppImage: the runtime, with the update information embedded,
+/// followed by the AppDir as a SquashFS image, which the runtime finds right
+/// after its ELF section headers.
+fn write_appimage(mut runtime: Vec<u8>, update_information: Option<&str>, app_dir: &Path, appimage_path: &Path) -> crate::Result<()> {
+	if let Some(update_information) = update_information {
+		embed_update_information(&mut runtime, update_information).with_context(|| "Failed to embed the update information")?;
+	}
+
+	let mut appimage = File::create(appimage_path)?;
+	appimage.write_all(&runtime)?;
+	let mut squashfs = tempfile::tempfile()?;
+	create_squashfs(app_dir, &mut squashfs).with_context(|| "Failed to create the AppImage filesystem")?;
+	squashfs.seek(SeekFrom::Start(0))?;
+	io::copy(&mut squashfs, &mut appimage)?;
+	appimage.sync_all()?;
+	drop(appimage);
+	fs::set_permissions(appimage_path, fs::Permissions::from_mode(0o755))?;
+	Ok(())
+}
+
+/// Writes the update information to the `.upd_info` section of the runtime.
+fn embed_update_information(runtime: &mut [u8], update_information: &str) -> crate::Result<()> {
+	let (offset, size) = {
+		let elf = Elf::parse(runtime)?;
+		let section = elf
+			.section_headers
+			.iter()
+			.find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(".upd_info"))
+			.ok_or_else(|| crate::Error::GenericError("the AppImage runtime has no `.upd_info` section".into()))?;
+		(section.sh_offset as usize, section.sh_size as usize)
+	};
+	if update_information.len() >= size {
+		return Err(crate::Error::GenericError(format!("the update information can't be longer than {} bytes", size - 1)));
+	}
+	let section = &mut runtime[offset..offset + size];
+	section.fill(0);
+	section[..update_information.len()].copy_from_slice(update_information.as_bytes());
+	Ok(())
+}
+
+/// Writes the AppDir as a gzip-compressed SquashFS image, the filesystem format
+/// mounted by the AppImage runtime.
+fn create_squashfs(app_dir: &Path, output: &mut File) -> crate::Result<()> {
+	let mut writer = FilesystemWriter::default();
+	writer.set_compressor(FilesystemCompressor::new(Compressor::Gzip, None)?);
+	writer.set_root_mode(0o755);
+
+	for entry in walkdir::WalkDir::new(app_dir).min_depth(1).sort_by_file_name() {
+		let entry = entry?;
+		let path = entry.path().strip_prefix(app_dir)?;
+		let metadata = entry.path().symlink_metadata()?;
+		let header = NodeHeader::new((metadata.mode() & 0o7777) as u16, 0, 0, metadata.mtime().clamp(0, u32::MAX as i64) as u32);
+		if entry.file_type().is_symlink() {
+			writer.push_symlink(fs::read_link(entry.path())?, path, header)?;
+		} else if entry.file_type().is_dir() {
+			writer.push_dir(path, header)?;
+		} else {
+			// read the file now instead of keeping a descriptor open for every file until the image is written
+			writer.push_file(Cursor::new(fs::read(entry.path())?), path, header)?;
+		}
+	}
+
+	writer.write(output)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::BTreeSet,
+		fs,
+		io::Cursor,
+		os::unix::fs::PermissionsExt,
+		path::{Path, PathBuf}
+	};
+
+	use backhand::{FilesystemReader, InnerNode};
+	use goblin::elf::Elf;
+
+	use super::{bundle_gtk, debian::DebIcon, mirror_webkit_helpers, patch_webkit, write_appimage, write_entry_points};
+
+	fn write(path: &Path, contents: impl AsRef<[u8]>) {
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, contents).unwrap();
+	}
+
+	fn icon(data_dir: &Path, width: u32, height: u32) -> DebIcon {
+		let path = data_dir.join(format!("usr/share/icons/hicolor/{width}x{height}/apps/app.png"));
+		write(&path, format!("{width}x{height}"));
+		DebIcon {
+			width,
+			height,
+			is_high_density: false,
+			path
+		}
+	}
+
+	/// A minimal 64-bit ELF file standing in for the AppImage runtime, with an
+	/// empty 64 byte `.upd_info` section and its section headers at the end.
+	fn runtime() -> Vec<u8> {
+		const UPD_INFO_OFFSET: u64 = 64;
+		const UPD_INFO_SIZE: u64 = 64;
+		const SHSTRTAB: &[u8] = b"\0.upd_info\0.shstrtab\0";
+		const SHSTRTAB_OFFSET: u64 = UPD_INFO_OFFSET + UPD_INFO_SIZE;
+		const SHOFF: u64 = 152;
+
+		let mut elf = Vec::new();
+		elf.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+		elf.extend_from_slice(&2u16.to_le_bytes()); // e_type: executable
+		elf.extend_from_slice(&62u16.to_le_bytes()); // e_machine: x86_64
+		elf.extend_from_slice(&1u32.to_le_bytes()); // e_version
+		elf.extend_from_slice(&0u64.to_le_bytes()); // e_entry
+		elf.extend_from_slice(&0u64.to_le_bytes()); // e_phoff
+		elf.extend_from_slice(&SHOFF.to_le_bytes()); // e_shoff
+		elf.extend_from_slice(&0u32.to_le_bytes()); // e_flags
+		for value in [64u16, 56, 0, 64, 3, 2] {
+			// e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+			elf.extend_from_slice(&value.to_le_bytes());
+		}
+		elf.resize((UPD_INFO_OFFSET + UPD_INFO_SIZE) as usize, 0);
+		elf.extend_from_slice(SHSTRTAB);
+		elf.resize(SHOFF as usize, 0);
+
+		let mut section = |name: u32, kind: u32, offset: u64, size: u64| {
+			elf.extend_from_slice(&name.to_le_bytes());
+			elf.extend_from_slice(&kind.to_le_bytes());
+			elf.extend_from_slice(&[0; 16]); // sh_flags, sh_addr
+			elf.extend_from_slice(&offset.to_le_bytes());
+			elf.extend_from_slice(&size.to_le_bytes());
+			elf.extend_from_slice(&[0; 8]); // sh_link, sh_info
+			elf.extend_from_slice(&1u64.to_le_bytes()); // sh_addralign
+			elf.extend_from_slice(&0u64.to_le_bytes()); // sh_entsize
+		};
+		section(0, 0, 0, 0);
+		section(1, 1, UPD_INFO_OFFSET, UPD_INFO_SIZE); // PROGBITS
+		section(11, 3, SHSTRTAB_OFFSET, SHSTRTAB.len() as u64); // STRTAB
+		elf
+	}
+
+	#[test]
+	fn writes_appimages() {
+		let dir = tempfile::tempdir().unwrap();
+		let app_dir = dir.path().join("app.AppDir");
+		write(&app_dir.join("AppRun"), "#!/bin/sh\n");
+		fs::set_permissions(app_dir.join("AppRun"), fs::Permissions::from_mode(0o755)).unwrap();
+		write(&app_dir.join("usr/bin/app"), "app");
+		std::os::unix::fs::symlink("usr/bin/app", app_dir.join("app")).unwrap();
+		let appimage_path = dir.path().join("app.AppImage");
+		let update_information = "gh-releases-zsync|pykeio|app|latest|app_*_amd64.AppImage.zsync";
+
+		let runtime = runtime();
+		write_appimage(runtime.clone(), Some(update_information), &app_dir, &appimage_path).unwrap();
+		let appimage = fs::read(&appimage_path).unwrap();
+		assert_eq!(fs::metadata(&appimage_path).unwrap().permissions().mode() & 0o777, 0o755);
+
+		// the runtime mounts the filesystem found right after its section headers
+		let elf = Elf::parse(&appimage).unwrap();
+		let offset = elf.header.e_shoff + u64::from(elf.header.e_shentsize) * u64::from(elf.header.e_shnum);
+		assert_eq!(offset, runtime.len() as u64);
+
+		let section = elf
+			.section_headers
+			.iter()
+			.find(|section| elf.shdr_strtab.get_at(section.sh_name) == Some(".upd_info"))
+			.unwrap();
+		let embedded = &appimage[section.sh_offset as usize..(section.sh_offset + section.sh_size) as usize];
+		assert_eq!(embedded.split(|byte| *byte == 0).next().unwrap(), update_information.as_bytes());
+
+		let filesystem = FilesystemReader::from_reader_with_offset(Cursor::new(appimage), offset).unwrap();
+		let nodes = filesystem
+			.files()
+			.map(|node| (node.fullpath.clone(), node.header.permissions, matches!(node.inner, InnerNode::Symlink(_))))
+			.collect::<Vec<_>>();
+		for expected in [("/AppRun", 0o755, false), ("/app", 0o777, true), ("/usr/bin/app", 0o644, false)] {
+			assert!(nodes.contains(&(PathBuf::from(expected.0), expected.1, expected.2)), "{expected:?} not in {nodes:?}");
+		}
+
+		// the update information must fit in the section
+		let too_long = "x".repeat(64);
+		assert!(write_appimage(runtime, Some(&too_long), &app_dir, &appimage_path).is_err());
+	}
+
+	#[test]
+	fn writes_entry_points() {
+		let dir = tempfile::tempdir().unwrap();
+		let data_dir = dir.path().join("data");
+		let app_dir = dir.path().join("app.AppDir");
+		write(&data_dir.join("usr/share/applications/app.desktop"), "[Desktop Entry]");
+		let icons = vec![icon(&data_dir, 32, 32), icon(&data_dir, 128, 128), icon(&data_dir, 256, 128)];
+		fs::create_dir_all(&app_dir).unwrap();
+
+		assert!(write_entry_points(&app_dir, &data_dir, "app", &icons[2..]).is_err());
+		write_entry_points(&app_dir, &data_dir, "app", &icons).unwrap();
+
+		let app_run = fs::read_to_string(app_dir.join("AppRun")).unwrap();
+		assert!(app_run.contains(r#"exec "$APPDIR/usr/bin/app" "$@""#));
+		assert!(app_run.contains(r#"export GDK_PIXBUF_MODULE_FILE="$APPDIR/usr/lib/gdk-pixbuf-2.0/2.10.0/loaders.cache""#));
+		assert_eq!(fs::metadata(app_dir.join("AppRun")).unwrap().permissions().mode() & 0o777, 0o755);
+		assert_eq!(fs::read_to_string(app_dir.join(".DirIcon")).unwrap(), "128x128");
+		assert_eq!(fs::read_link(app_dir.join("app.png")).unwrap(), Path::new("usr/share/icons/hicolor/128x128/apps/app.png"));
+		assert_eq!(fs::read_link(app_dir.join("app.desktop")).unwrap(), Path::new("usr/share/applications/app.desktop"));
+	}
+
+	#[test]
+	fn bundles_gtk_modules() {
+		let dir = tempfile::tempdir().unwrap();
+		let host = dir.path().join("host/lib");
+		let loaders = host.join("gdk-pixbuf-2.0/2.10.0/loaders");
+		write(&loaders.join("libpixbufloader-png.so"), "png");
+		write(
+			&host.join("gdk-pixbuf-2.0/2.10.0/loaders.cache"),
+			format!("\"{}/libpixbufloader-png.so\"\n\"png\" 5 \"gdk-pixbuf\" \"PNG\" \"LGPL\"\n", loaders.display())
+		);
+		write(&host.join("gio/modules/libgvfsdbus.so"), "gvfs");
+		let immodules = host.join("gtk-3.0/3.0.0/immodules");
+		write(&immodules.join("im-ibus.so"), "ibus");
+		write(&host.join("gtk-3.0/3.0.0/immodules.cache"), format!("\"{}/im-ibus.so\"\n\"ibus\" \"IBus\" \"ibus\" \"\" \"\"\n", immodules.display()));
+		write(&host.join("gtk-3.0/modules/libcanberra-gtk-module.so"), "canberra");
+		let schemas = dir.path().join("schemas");
+		write(&schemas.join("gschemas.compiled"), "schemas");
+		let app_dir = dir.path().join("app.AppDir");
+
+		let mut elves = Vec::new();
+		bundle_gtk(&[dir.path().join("missing"), host], &schemas, &app_dir, &mut elves).unwrap();
+
+		let lib_dir = app_dir.join("usr/lib");
+		assert_eq!(
+			elves.iter().collect::<BTreeSet<_>>(),
+			[
+				lib_dir.join("gdk-pixbuf-2.0/2.10.0/loaders/libpixbufloader-png.so"),
+				lib_dir.join("gio/modules/libgvfsdbus.so"),
+				lib_dir.join("gtk-3.0/3.0.0/immodules/im-ibus.so"),
+				lib_dir.join("gtk-3.0/modules/libcanberra-gtk-module.so")
+			]
+			.iter()
+			.collect()
+		);
+		assert!(elves.iter().all(|elf| elf.is_file()));
+		let loaders_cache = fs::read_to_string(lib_dir.join("gdk-pixbuf-2.0/2.10.0/loaders.cache")).unwrap();
+		assert!(loaders_cache.starts_with("\"libpixbufloader-png.so\"\n"));
+		let immodules_cache = fs::read_to_string(lib_dir.join("gtk-3.0/3.0.0/immodules.cache")).unwrap();
+		assert!(immodules_cache.starts_with("\"im-ibus.so\"\n"));
+		assert!(app_dir.join("usr/share/glib-2.0/schemas/gschemas.compiled").is_file());
+	}
+
+	#[test]
+	fn mirrors_and_patches_webkit_helpers() {
+		let dir = tempfile::tempdir().unwrap();
+		let host = dir.path().join("host/lib");
+		write(&host.join("webkit2gtk-4.0/WebKitWebProcess"), "web");
+		write(&host.join("webkit2gtk-4.0/injected-bundle/libwebkit2gtkinjectedbundle.so"), "bundle");
+		let app_dir = dir.path().join("app.AppDir");
+
+		let mut elves = Vec::new();
+		let webkit_dirs = mirror_webkit_helpers(std::slice::from_ref(&host), &app_dir, &mut elves).unwrap();
+		assert_eq!(elves.len(), 2);
+		let mirrored = app_dir.join(host.strip_prefix("/").unwrap());
+		assert!(mirrored.join("webkit2gtk-4.0/WebKitWebProcess").is_file());
+		assert!(webkit_dirs.contains(&host.join("webkit2gtk-4.0")));
+
+		// the helpers of the host are found under `/usr`
+		let lib_dir = app_dir.join("usr/lib");
+		let library = lib_dir.join("libwebkit2gtk-4.0.so.37");
+		write(&library, b"\0/usr/lib/x86_64-linux-gnu/webkit2gtk-4.0\0/usr/share\0/usr/lib/x86_64-linux-gnu/webkit2gtk-4.0/injected-bundle/\0");
+		write(&lib_dir.join("libgtk-3.so.0"), b"\0/usr/lib/x86_64-linux-gnu/webkit2gtk-4.0\0");
+		patch_webkit(&lib_dir, &[PathBuf::from("/usr/lib/x86_64-linux-gnu/webkit2gtk-4.0")].into()).unwrap();
+		assert_eq!(
+			fs::read(&library).unwrap(),
+			b"\0././/lib/x86_64-linux-gnu/webkit2gtk-4.0\0/usr/share\0././/lib/x86_64-linux-gnu/webkit2gtk-4.0/injected-bundle/\0"
+		);
+		assert_eq!(fs::read(lib_dir.join("libgtk-3.so.0")).unwrap(), b"\0/usr/lib/x86_64-linux-gnu/webkit2gtk-4.0\0");
+	}
 }
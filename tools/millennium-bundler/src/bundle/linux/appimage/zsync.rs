@@ -0,0 +1,149 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of the `.zsync` control files AppImageUpdate uses to only
+//! download the blocks of an AppImage that changed, equivalent to `zsyncmake`.
+
+use std::{
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::Path
+};
+
+use md4::{Digest, Md4};
+use sha1::Sha1;
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+/// The parameters of the checksums, chosen like `zsyncmake` does.
+struct HashLengths {
+	block_size: usize,
+	seq_matches: usize,
+	rsum_len: usize,
+	checksum_len: usize
+}
+
+impl HashLengths {
+	fn new(len: u64) -> Self {
+		let block_size: usize = if len < 100_000_000 { 2048 } else { 4096 };
+		let seq_matches = if len > block_size as u64 { 2 } else { 1 };
+		let log_len = (len.max(1) as f64).ln();
+		let log_blocks = (1.0 + (len / block_size as u64) as f64).ln();
+		let ln2 = 2f64.ln();
+
+		let rsum_len = (((log_len + (block_size as f64).ln()) / ln2 - 8.6) / seq_matches as f64 / 8.0).ceil();
+		let rsum_len = (rsum_len as usize).clamp(2, 4);
+
+		let checksum_len = ((20.0 + (log_len + log_blocks) / ln2) / seq_matches as f64 / 8.0).ceil() as usize;
+		let min_checksum_len = ((7.9 + (20.0 + log_blocks / ln2)) / 8.0) as usize;
+		let checksum_len = checksum_len.max(min_checksum_len).min(16);
+
+		Self {
+			block_size,
+			seq_matches,
+			rsum_len,
+			checksum_len
+		}
+	}
+}
+
+/// The rolling checksum of a block, as big endian `a` and `b` halves.
+fn rsum(block: &[u8]) -> [u8; 4] {
+	let (mut a, mut b) = (0u16, 0u16);
+	for &byte in block {
+		a = a.wrapping_add(byte as u16);
+		b = b.wrapping_add(a);
+	}
+	let [a0, a1] = a.to_be_bytes();
+	let [b0, b1] = b.to_be_bytes();
+	[a0, a1, b0, b1]
+}
+
+/// Renders the control file of `data`, which is downloaded from `url`.
+fn render(data: &[u8], file_name: &str, url: &str, mtime: OffsetDateTime) -> crate::Result<Vec<u8>> {
+	let lengths = HashLengths::new(data.len() as u64);
+	let sha1 = Sha1::digest(data).iter().map(|b| format!("{b:02x}")).collect::<String>();
+
+	let mut out = Vec::new();
+	write!(
+		out,
+		"zsync: 0.6.2\nFilename: {}\nMTime: {}\nBlocksize: {}\nLength: {}\nHash-Lengths: {},{},{}\nURL: {}\nSHA-1: {}\n\n",
+		file_name,
+		mtime.format(&Rfc2822)?,
+		lengths.block_size,
+		data.len(),
+		lengths.seq_matches,
+		lengths.rsum_len,
+		lengths.checksum_len,
+		url,
+		sha1
+	)?;
+
+	let mut block = vec![0; lengths.block_size];
+	for chunk in data.chunks(lengths.block_size) {
+		// the last block is padded with zeros
+		block[..chunk.len()].copy_from_slice(chunk);
+		block[chunk.len()..].fill(0);
+		out.extend_from_slice(&rsum(&block)[4 - lengths.rsum_len..]);
+		out.extend_from_slice(&Md4::digest(&block)[..lengths.checksum_len]);
+	}
+	Ok(out)
+}
+
+/// Writes the control file of the file at `path` to `output`.
+pub fn write(path: &Path, url: &str, output: &Path) -> crate::Result<()> {
+	let data = fs::read(path)?;
+	let mtime = OffsetDateTime::from(fs::metadata(path)?.modified()?);
+	let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or(url);
+
+	let mut file = BufWriter::new(File::create(output)?);
+	file.write_all(&render(&data, file_name, url, mtime)?)?;
+	file.flush()?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use time::OffsetDateTime;
+
+	use super::{render, rsum, HashLengths};
+
+	#[test]
+	fn hash_lengths() {
+		let small = HashLengths::new(1000);
+		assert_eq!((small.block_size, small.seq_matches, small.rsum_len, small.checksum_len), (2048, 1, 2, 4));
+
+		let large = HashLengths::new(80_000_000);
+		assert_eq!((large.block_size, large.seq_matches, large.rsum_len, large.checksum_len), (2048, 2, 2, 5));
+	}
+
+	#[test]
+	fn rolling_checksum() {
+		// a = 1 + 2 + 3, b = 1 + (1 + 2) + (1 + 2 + 3)
+		assert_eq!(rsum(&[1, 2, 3]), [0, 6, 0, 10]);
+	}
+
+	#[test]
+	fn control_file() {
+		let data = vec![7u8; 3000];
+		let out = render(&data, "app.AppImage", "app.AppImage", OffsetDateTime::UNIX_EPOCH).unwrap();
+		let header_end = out.windows(2).position(|w| w == b"\n\n").unwrap() + 2;
+		let header = std::str::from_utf8(&out[..header_end]).unwrap();
+		assert!(header.starts_with("zsync: 0.6.2\nFilename: app.AppImage\nMTime: Thu, 01 Jan 1970 00:00:00 +0000\nBlocksize: 2048\nLength: 3000\n"));
+		assert!(header.contains("URL: app.AppImage\n"));
+
+		let lengths = HashLengths::new(3000);
+		// two blocks, the second one padded
+		assert_eq!(out.len() - header_end, 2 * (lengths.rsum_len + lengths.checksum_len));
+	}
+}
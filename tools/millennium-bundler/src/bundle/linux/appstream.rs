@@ -0,0 +1,463 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generation of the AppStream metainfo file software centers use to list the
+//! application. See <https://www.freedesktop.org/software/appstream/docs/>.
+
+use std::{
+	fmt::Write as _,
+	fs,
+	io::{self, Write},
+	path::Path,
+	process::Command
+};
+
+use anyhow::Context;
+use log::warn;
+use time::OffsetDateTime;
+
+use super::super::common;
+use crate::Settings;
+
+/// A release of the application, read from the changelog.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Release {
+	version: String,
+	date: Option<String>,
+	/// The notes of the release, grouped by changelog section (e.g. `Added`).
+	notes: Vec<(Option<String>, Vec<String>)>
+}
+
+/// Escapes the XML special characters of `text`.
+fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c)
+		}
+	}
+	escaped
+}
+
+/// Whether `date` is in the `YYYY-MM-DD` format AppStream requires.
+fn is_date(date: &str) -> bool {
+	date.len() == 10
+		&& date
+			.bytes()
+			.enumerate()
+			.all(|(i, b)| if i == 4 || i == 7 { b == b'-' } else { b.is_ascii_digit() })
+}
+
+/// Parses the releases of a changelog in the Keep a Changelog format, e.g.
+/// `## [1.2.0] - 2022-10-01` followed by `### Added` sections and list items.
+/// The `Unreleased` section is skipped.
+fn parse_changelog(changelog: &str) -> Vec<Release> {
+	let mut releases: Vec<Release> = Vec::new();
+	let mut current: Option<Release> = None;
+	for line in changelog.lines() {
+		if let Some(heading) = line.strip_prefix("## ") {
+			releases.extend(current.take());
+			let mut tokens = heading.split_whitespace();
+			let version = tokens.next().unwrap_or_default().trim_matches(|c| c == '[' || c == ']');
+			let version = version.strip_prefix('v').unwrap_or(version);
+			if version.is_empty() || version.eq_ignore_ascii_case("unreleased") {
+				continue;
+			}
+			current = Some(Release {
+				version: version.to_string(),
+				date: tokens
+					.map(|t| t.trim_matches(|c| c == '(' || c == ')'))
+					.find(|t| is_date(t))
+					.map(ToString::to_string),
+				notes: Vec::new()
+			});
+		} else if let Some(release) = current.as_mut() {
+			let trimmed = line.trim();
+			if let Some(section) = line.strip_prefix("### ") {
+				release.notes.push((Some(section.trim().to_string()), Vec::new()));
+			} else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+				if release.notes.is_empty() {
+					release.notes.push((None, Vec::new()));
+				}
+				release.notes.last_mut().unwrap().1.push(item.trim().to_string());
+			} else if !trimmed.is_empty() {
+				// continuation of a wrapped list item
+				if let Some(item) = release.notes.last_mut().and_then(|(_, items)| items.last_mut()) {
+					item.push(' ');
+					item.push_str(trimmed);
+				}
+			}
+		}
+	}
+	releases.extend(current);
+	releases
+}
+
+fn summary(settings: &Settings) -> &str {
+	settings
+		.appstream()
+		.summary
+		.as_deref()
+		.unwrap_or_else(|| settings.short_description())
+		.trim()
+}
+
+fn description(settings: &Settings) -> &str {
+	settings
+		.appstream()
+		.description
+		.as_deref()
+		.or_else(|| settings.long_description())
+		.unwrap_or_else(|| settings.short_description())
+		.trim()
+}
+
+/// Reads the releases from the changelog, adding the version being bundled
+/// if the changelog doesn't mention it yet. The version being bundled is
+/// dated `build_date` unless the changelog dates it; other releases without a
+/// date are skipped, since AppStream requires one.
+fn releases(settings: &Settings, build_date: &str) -> crate::Result<Vec<Release>> {
+	let changelog = match &settings.appstream().changelog {
+		Some(path) => parse_changelog(&fs::read_to_string(path).with_context(|| format!("Failed to read changelog {}", path.display()))?),
+		None => Vec::new()
+	};
+	let mut releases = Vec::new();
+	for mut release in changelog {
+		if release.date.is_none() {
+			if release.version != settings.version_string() {
+				warn!("skipping the release {} of the changelog in the AppStream metainfo, as it has no `YYYY-MM-DD` date", release.version);
+				continue;
+			}
+			release.date = Some(build_date.to_string());
+		}
+		releases.push(release);
+	}
+	if !releases.iter().any(|r| r.version == settings.version_string()) {
+		releases.insert(
+			0,
+			Release {
+				version: settings.version_string().to_string(),
+				date: Some(build_date.to_string()),
+				notes: Vec::new()
+			}
+		);
+	}
+	Ok(releases)
+}
+
+/// Validates the metainfo at `path` with `appstreamcli`, so mistakes fail the
+/// build instead of hiding the app from software centers.
+fn validate(path: &Path) -> crate::Result<()> {
+	let output = Command::new("appstreamcli")
+		.args(["validate", "--no-net", "--no-color"])
+		.arg(path)
+		.output();
+	let output = match output {
+		Ok(output) => output,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {
+			warn!("`appstreamcli` was not found, so the AppStream metainfo is not validated");
+			return Ok(());
+		}
+		Err(e) => return Err(e.into())
+	};
+	if output.status.success() {
+		Ok(())
+	} else {
+		Err(crate::Error::GenericError(format!(
+			"Invalid AppStream metainfo:\n{}{}",
+			String::from_utf8_lossy(&output.stdout),
+			String::from_utf8_lossy(&output.stderr)
+		)))
+	}
+}
+
+/// Renders `text` as AppStream description markup: paragraphs are separated
+/// by blank lines, and lines starting with `- ` form lists.
+fn render_description(text: &str, indent: &str) -> String {
+	let mut xml = String::new();
+	for block in text.split("\n\n").map(str::trim).filter(|b| !b.is_empty()) {
+		let lines: Vec<&str> = block.lines().map(str::trim).collect();
+		if lines.iter().all(|line| line.starts_with("- ")) {
+			let _ = writeln!(xml, "{indent}<ul>");
+			for line in lines {
+				let _ = writeln!(xml, "{indent}  <li>{}</li>", escape(&line[2..]));
+			}
+			let _ = writeln!(xml, "{indent}</ul>");
+		} else {
+			let _ = writeln!(xml, "{indent}<p>{}</p>", escape(&lines.join(" ")));
+		}
+	}
+	xml
+}
+
+/// Renders the metainfo of the application with the component `id`, launched
+/// by the desktop file `desktop_id`.
+fn render(settings: &Settings, id: &str, desktop_id: &str, releases: &[Release]) -> String {
+	let appstream = settings.appstream();
+	let mut xml = String::new();
+	// writing to a `String` can't fail
+	let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+	let _ = writeln!(xml, r#"<component type="desktop-application">"#);
+	let _ = writeln!(xml, "  <id>{}</id>", escape(id));
+	let _ = writeln!(xml, "  <metadata_license>CC0-1.0</metadata_license>");
+	if let Some(license) = &appstream.project_license {
+		let _ = writeln!(xml, "  <project_license>{}</project_license>", escape(license));
+	}
+	let _ = writeln!(xml, "  <name>{}</name>", escape(settings.product_name()));
+	let _ = writeln!(xml, "  <summary>{}</summary>", escape(summary(settings)));
+	let _ = writeln!(xml, "  <description>");
+	xml.push_str(&render_description(description(settings), "    "));
+	let _ = writeln!(xml, "  </description>");
+	let _ = writeln!(xml, r#"  <launchable type="desktop-id">{}</launchable>"#, escape(desktop_id));
+	if !settings.homepage_url().is_empty() {
+		let _ = writeln!(xml, r#"  <url type="homepage">{}</url>"#, escape(settings.homepage_url()));
+	}
+	if let Some(developer) = settings
+		.publisher()
+		.map(ToString::to_string)
+		.or_else(|| settings.authors_comma_separated())
+	{
+		let _ = writeln!(xml, "  <developer_name>{}</developer_name>", escape(&developer));
+	}
+
+	if !appstream.screenshots.is_empty() {
+		let _ = writeln!(xml, "  <screenshots>");
+		for (i, screenshot) in appstream.screenshots.iter().enumerate() {
+			let _ = writeln!(xml, "    {}", if i == 0 { r#"<screenshot type="default">"# } else { "<screenshot>" });
+			if let Some(caption) = &screenshot.caption {
+				let _ = writeln!(xml, "      <caption>{}</caption>", escape(caption));
+			}
+			let _ = writeln!(xml, "      <image>{}</image>", escape(&screenshot.url));
+			let _ = writeln!(xml, "    </screenshot>");
+		}
+		let _ = writeln!(xml, "  </screenshots>");
+	}
+
+	let _ = writeln!(xml, "  <releases>");
+	for release in releases {
+		let date = release.date.as_deref().unwrap_or_default();
+		if release.notes.is_empty() {
+			let _ = writeln!(xml, r#"    <release version="{}" date="{}"/>"#, escape(&release.version), date);
+			continue;
+		}
+		let _ = writeln!(xml, r#"    <release version="{}" date="{}">"#, escape(&release.version), date);
+		let _ = writeln!(xml, "      <description>");
+		for (section, items) in &release.notes {
+			if let Some(section) = section {
+				let _ = writeln!(xml, "        <p>{}</p>", escape(section));
+			}
+			if !items.is_empty() {
+				let _ = writeln!(xml, "        <ul>");
+				for item in items {
+					let _ = writeln!(xml, "          <li>{}</li>", escape(item));
+				}
+				let _ = writeln!(xml, "        </ul>");
+			}
+		}
+		let _ = writeln!(xml, "      </description>");
+		let _ = writeln!(xml, "    </release>");
+	}
+	let _ = writeln!(xml, "  </releases>");
+
+	let mut content_rating: Vec<_> = appstream.content_rating.iter().collect();
+	content_rating.sort_by_key(|(attribute, _)| *attribute);
+	if content_rating.is_empty() {
+		let _ = writeln!(xml, r#"  <content_rating type="oars-1.1"/>"#);
+	} else {
+		let _ = writeln!(xml, r#"  <content_rating type="oars-1.1">"#);
+		for (attribute, value) in content_rating {
+			let _ = writeln!(xml, r#"    <content_attribute id="{}">{}</content_attribute>"#, escape(attribute), escape(&value.to_string()));
+		}
+		let _ = writeln!(xml, "  </content_rating>");
+	}
+	let _ = writeln!(xml, "</component>");
+	xml
+}
+
+/// Writes the metainfo of the application with the component `id` to `path`
+/// and validates it. Apps without any description are skipped, since their
+/// metainfo would be invalid.
+pub fn generate_metainfo_file(settings: &Settings, id: &str, desktop_id: &str, path: &Path) -> crate::Result<()> {
+	if summary(settings).is_empty() && settings.appstream().summary.is_none() {
+		warn!("skipping the AppStream metainfo, set `shortDescription` or `linux.appstream.summary` to list the app in software centers");
+		return Ok(());
+	}
+
+	let build_date = OffsetDateTime::from_unix_timestamp(common::build_date() as i64).with_context(|| "Invalid `SOURCE_DATE_EPOCH`")?;
+	let releases = releases(settings, &build_date.date().to_string())?;
+
+	let mut file = common::create_file(path)?;
+	file.write_all(render(settings, id, desktop_id, &releases).as_bytes())?;
+	file.flush()?;
+	drop(file);
+	validate(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::HashMap,
+		fs,
+		path::{Path, PathBuf},
+		process::Command
+	};
+
+	use super::{escape, generate_metainfo_file, parse_changelog, releases, render_description, Release};
+	use crate::{AppStreamContentRating, AppStreamScreenshot, AppStreamSettings, BundleBinary, BundleSettings, PackageSettings, Settings, SettingsBuilder};
+
+	fn settings(appstream: AppStreamSettings) -> Settings {
+		SettingsBuilder::new()
+			.project_out_directory(std::env::temp_dir())
+			.package_settings(PackageSettings {
+				product_name: "Test App".into(),
+				version: "1.2.0".into(),
+				description: "Tests AppStream metainfo".into(),
+				homepage: Some("https://example.com".into()),
+				authors: Some(vec!["pyke.io".into()]),
+				default_run: None
+			})
+			.bundle_settings(BundleSettings { appstream, ..Default::default() })
+			.binaries(vec![BundleBinary::new("test-app".into(), true)])
+			.target("x86_64-unknown-linux-gnu".into())
+			.build()
+			.unwrap()
+	}
+
+	fn has_appstreamcli() -> bool {
+		Command::new("appstreamcli").arg("--version").output().is_ok()
+	}
+
+	fn write_changelog(dir: &Path) -> PathBuf {
+		let path = dir.join("CHANGELOG.md");
+		fs::write(&path, "## [1.2.0]\n### Added\n- Dark mode\n\n## [1.1.0] - 2022-10-01\n- Fixes\n\n## [1.0.0]\n- Initial release\n").unwrap();
+		path
+	}
+
+	#[test]
+	fn escapes_markup() {
+		assert_eq!(escape(r#"Tom & Jerry's <"app">"#), "Tom &amp; Jerry&apos;s &lt;&quot;app&quot;&gt;");
+	}
+
+	#[test]
+	fn parses_changelog() {
+		let changelog = "# Changelog\n\n## [Unreleased]\n- Work in progress\n\n## [1.1.0] - 2022-10-01\n### Added\n- Dark mode\n- A very long\n  wrapped item\n### Fixed\n- Crash on startup\n\n## v1.0.0 (2022-09-01)\n* Initial release\n\n## 0.9.0\n";
+		assert_eq!(
+			parse_changelog(changelog),
+			vec![
+				Release {
+					version: "1.1.0".into(),
+					date: Some("2022-10-01".into()),
+					notes: vec![
+						(Some("Added".into()), vec!["Dark mode".into(), "A very long wrapped item".into()]),
+						(Some("Fixed".into()), vec!["Crash on startup".into()])
+					]
+				},
+				Release {
+					version: "1.0.0".into(),
+					date: Some("2022-09-01".into()),
+					notes: vec![(None, vec!["Initial release".into()])]
+				},
+				Release {
+					version: "0.9.0".into(),
+					date: None,
+					notes: vec![]
+				}
+			]
+		);
+	}
+
+	#[test]
+	fn renders_description() {
+		assert_eq!(
+			render_description("An app.\nIt does things.\n\n- One\n- Two & three", ""),
+			"<p>An app. It does things.</p>\n<ul>\n  <li>One</li>\n  <li>Two &amp; three</li>\n</ul>\n"
+		);
+	}
+
+	#[test]
+	fn dates_releases() {
+		let dir = tempfile::tempdir().unwrap();
+		let with_changelog = settings(AppStreamSettings {
+			changelog: Some(write_changelog(dir.path())),
+			..Default::default()
+		});
+
+		// the release being bundled gets the build date, other undated releases are skipped
+		assert_eq!(
+			releases(&with_changelog, "2022-11-01").unwrap(),
+			vec![
+				Release {
+					version: "1.2.0".into(),
+					date: Some("2022-11-01".into()),
+					notes: vec![(Some("Added".into()), vec!["Dark mode".into()])]
+				},
+				Release {
+					version: "1.1.0".into(),
+					date: Some("2022-10-01".into()),
+					notes: vec![(None, vec!["Fixes".into()])]
+				}
+			]
+		);
+
+		let releases = releases(&settings(AppStreamSettings::default()), "2022-11-01").unwrap();
+		assert_eq!(releases.len(), 1);
+		assert_eq!(releases[0].date.as_deref(), Some("2022-11-01"));
+	}
+
+	#[test]
+	fn generates_valid_metainfo() {
+		let dir = tempfile::tempdir().unwrap();
+		let settings = settings(AppStreamSettings {
+			description: Some("An app which tests the AppStream metainfo generated by the bundler.\n\n- One\n- Two".into()),
+			screenshots: vec![AppStreamScreenshot {
+				url: "https://example.com/screenshot.png".into(),
+				caption: Some("The main window".into())
+			}],
+			content_rating: HashMap::from([("social-chat".to_string(), AppStreamContentRating::Moderate)]),
+			project_license: Some("MIT".into()),
+			changelog: Some(write_changelog(dir.path())),
+			..Default::default()
+		});
+		let path = dir.path().join("com.example.testapp.metainfo.xml");
+
+		generate_metainfo_file(&settings, "com.example.testapp", "test-app.desktop", &path).unwrap();
+		let metainfo = fs::read_to_string(&path).unwrap();
+		assert!(metainfo.contains("<summary>Tests AppStream metainfo</summary>"));
+		assert!(metainfo.contains(r#"<release version="1.1.0" date="2022-10-01">"#));
+		assert!(metainfo.contains(r#"<content_attribute id="social-chat">moderate</content_attribute>"#));
+		assert!(!metainfo.contains(r#"version="1.0.0""#));
+	}
+
+	#[test]
+	fn rejects_invalid_metainfo() {
+		if !has_appstreamcli() {
+			return;
+		}
+		let dir = tempfile::tempdir().unwrap();
+		let settings = settings(AppStreamSettings {
+			screenshots: vec![AppStreamScreenshot {
+				url: "screenshot.png".into(),
+				caption: None
+			}],
+			..Default::default()
+		});
+		let path = dir.path().join("com.example.testapp.metainfo.xml");
+		assert!(generate_metainfo_file(&settings, "com.example.testapp", "test-app.desktop", &path).is_err());
+	}
+}
@@ -26,6 +26,7 @@
 //     data.tar.gz             # Contains files to be installed:
 //         usr/bin/foobar                            # Binary executable file
 //         usr/share/applications/foobar.desktop     # Desktop file (for apps)
+//         usr/share/metainfo/<id>.metainfo.xml      # AppStream metainfo (for apps)
 //         usr/share/icons/hicolor/...               # Icon files (for apps)
 //         usr/lib/foobar/...                        # Other resource files
 //
@@ -49,7 +50,7 @@ use libflate::gzip;
 use log::info;
 use walkdir::WalkDir;
 
-use super::super::common;
+use super::{super::common, appstream};
 use crate::Settings;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -120,6 +121,7 @@ pub fn generate_data(settings: &Settings, package_dir: &Path) -> crate::Result<(
 
 	let icons = generate_icon_files(settings, &data_dir).with_context(|| "Failed to create icon files")?;
 	generate_desktop_file(settings, &data_dir).with_context(|| "Failed to create desktop file")?;
+	generate_metainfo_file(settings, &data_dir).with_context(|| "Failed to create AppStream metainfo file")?;
 
 	Ok((data_dir, icons))
 }
@@ -149,6 +151,13 @@ fn generate_desktop_file(settings: &Settings, data_dir: &Path) -> crate::Result<
 	Ok(())
 }
 
+/// Generate the AppStream metainfo file and store it under the `data_dir`.
+fn generate_metainfo_file(settings: &Settings, data_dir: &Path) -> crate::Result<()> {
+	let id = settings.bundle_identifier();
+	let metainfo_path = data_dir.join("usr/share/metainfo").join(format!("{id}.metainfo.xml"));
+	appstream::generate_metainfo_file(settings, id, &format!("{}.desktop", settings.main_binary_name()), &metainfo_path)
+}
+
 /// Generates the debian control file and stores it under the `control_dir`.
 fn generate_control_file(settings: &Settings, arch: &str, control_dir: &Path, data_dir: &Path) -> crate::Result<()> {
 	// For more information about the format of this file, see
@@ -254,6 +263,10 @@ fn generate_icon_files(settings: &Settings, data_dir: &Path) -> crate::Result<BT
 	let mut icons = BTreeSet::new();
 	for icon_path in settings.icon_files() {
 		let icon_path = icon_path?;
+		if icon_path.extension() == Some(OsStr::new("svg")) {
+			common::copy_file(&icon_path, base_dir.join(format!("scalable/apps/{}.svg", settings.main_binary_name())))?;
+			continue;
+		}
 		if icon_path.extension() != Some(OsStr::new("png")) {
 			continue;
 		}
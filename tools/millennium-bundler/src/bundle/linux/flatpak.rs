@@ -0,0 +1,254 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// A Flatpak is built from a manifest describing the runtime the app runs on,
+// its sandbox permissions and how to build it. We prebuild the application, so
+// the manifest has a single module which copies the prepared files into /app:
+//
+// bundle/flatpak/
+//     com.example.App.json                              # The manifest
+//     files/
+//         bin/foobar                                    # Main binary
+//         share/applications/com.example.App.desktop    # Desktop file
+//         share/icons/hicolor/...                       # Icons, named after the app id
+//         share/metainfo/com.example.App.metainfo.xml   # AppStream metainfo
+//
+// If `flatpak-builder` is installed, the manifest is then built into a
+// single-file `.flatpak` bundle.
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	process::Command
+};
+
+use anyhow::Context;
+use log::info;
+use walkdir::WalkDir;
+
+use super::{
+	super::common::{self, CommandExt},
+	debian
+};
+use crate::{FlatpakSettings, Settings};
+
+/// Sandbox permissions every application needs to show a window.
+const DEFAULT_FINISH_ARGS: &[&str] = &["--share=ipc", "--socket=wayland", "--socket=fallback-x11", "--device=dri"];
+
+/// Bundles the project.
+/// Returns a vector of PathBuf that shows where the manifest and the Flatpak
+/// bundle were created.
+pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+	let arch = match settings.binary_arch() {
+		"x86" => "i386",
+		other => other
+	};
+	let id = settings.bundle_identifier();
+	let package_dir = settings.project_out_directory().join("bundle/flatpak_deb");
+
+	let output_path = settings.project_out_directory().join("bundle/flatpak");
+	if output_path.exists() {
+		fs::remove_dir_all(&output_path)?;
+	}
+	let files_dir = output_path.join("files");
+	let manifest_path = output_path.join(format!("{id}.json"));
+
+	info!(action = "Bundling"; "{} ({})", manifest_path.file_name().unwrap().to_string_lossy(), manifest_path.display());
+
+	let (data_dir, _) = debian::generate_data(settings, &package_dir)?;
+	common::copy_dir(&data_dir.join("usr"), &files_dir).with_context(|| "Failed to copy the application files")?;
+	fs::remove_dir_all(&package_dir)?;
+
+	rename_exports(settings, id, &files_dir).with_context(|| "Failed to rename the desktop file and icons")?;
+
+	let manifest = serde_json::json!({
+		"id": id,
+		"runtime": settings.flatpak().runtime,
+		"runtime-version": settings.flatpak().runtime_version,
+		"sdk": settings.flatpak().sdk,
+		"command": settings.main_binary_name(),
+		"finish-args": finish_args(settings.flatpak()),
+		"modules": [{
+			"name": settings.main_binary_name(),
+			"buildsystem": "simple",
+			"build-commands": ["cp -a . /app/"],
+			"sources": [{ "type": "dir", "path": "files" }]
+		}]
+	});
+	fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+
+	let mut paths = vec![manifest_path.clone()];
+	if Command::new("flatpak-builder")
+		.arg("--version")
+		.output()
+		.map_or(false, |o| o.status.success())
+	{
+		let bundle_name = format!("{}_{}_{}.flatpak", settings.main_binary_name(), settings.version_string(), arch);
+		let bundle_path = output_path.join(&bundle_name);
+		info!(action = "Bundling"; "{} ({})", bundle_name, bundle_path.display());
+
+		Command::new("flatpak-builder")
+			.args(["--force-clean", "--repo=repo", "build"])
+			.arg(&manifest_path)
+			.current_dir(&output_path)
+			.output_ok()
+			.context("error running flatpak-builder")?;
+		Command::new("flatpak")
+			.args(["build-bundle", "repo"])
+			.arg(&bundle_path)
+			.arg(id)
+			.current_dir(&output_path)
+			.output_ok()
+			.context("error running flatpak build-bundle")?;
+		paths.push(bundle_path);
+	} else {
+		info!("flatpak-builder was not found, skipping the .flatpak bundle");
+	}
+	Ok(paths)
+}
+
+/// The sandbox permissions of the application: the default ones, followed by
+/// the configured ones that are not already in the defaults.
+fn finish_args(flatpak: &FlatpakSettings) -> Vec<&str> {
+	let mut finish_args: Vec<&str> = DEFAULT_FINISH_ARGS.to_vec();
+	for arg in &flatpak.finish_args {
+		if !finish_args.contains(&arg.as_str()) {
+			finish_args.push(arg);
+		}
+	}
+	finish_args
+}
+
+/// Flatpak only exports the desktop file and icons named after the app id, so
+/// renames the ones generated for the Debian package and points the desktop
+/// file and the metainfo to the renamed files.
+fn rename_exports(settings: &Settings, id: &str, files_dir: &Path) -> crate::Result<()> {
+	let bin_name = settings.main_binary_name();
+	let applications_dir = files_dir.join("share/applications");
+	let desktop_file = fs::read_to_string(applications_dir.join(format!("{bin_name}.desktop")))?;
+	let desktop_file = desktop_file
+		.lines()
+		.map(|line| if line.starts_with("Icon=") { format!("Icon={id}") } else { line.to_string() })
+		.collect::<Vec<_>>()
+		.join("\n");
+	fs::remove_file(applications_dir.join(format!("{bin_name}.desktop")))?;
+	fs::write(applications_dir.join(format!("{id}.desktop")), desktop_file + "\n")?;
+
+	let metainfo_path = files_dir.join(format!("share/metainfo/{id}.metainfo.xml"));
+	if metainfo_path.exists() {
+		let metainfo = fs::read_to_string(&metainfo_path)?.replace(
+			&format!(r#"<launchable type="desktop-id">{bin_name}.desktop</launchable>"#),
+			&format!(r#"<launchable type="desktop-id">{id}.desktop</launchable>"#)
+		);
+		fs::write(&metainfo_path, metainfo)?;
+	}
+
+	let icons_dir = files_dir.join("share/icons");
+	if icons_dir.exists() {
+		// both the PNG icons and the scalable SVG icon are named after the binary
+		let icons = WalkDir::new(&icons_dir)
+			.into_iter()
+			.filter_map(|entry| entry.ok())
+			.map(|entry| entry.into_path())
+			.filter(|path| path.is_file() && path.file_stem().map_or(false, |stem| stem == bin_name))
+			.collect::<Vec<_>>();
+		for path in icons {
+			let icon_name = match path.extension() {
+				Some(extension) => format!("{id}.{}", extension.to_string_lossy()),
+				None => id.to_string()
+			};
+			fs::rename(&path, path.with_file_name(icon_name))?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use super::{finish_args, rename_exports, DEFAULT_FINISH_ARGS};
+	use crate::{BundleBinary, FlatpakSettings, PackageSettings, Settings, SettingsBuilder};
+
+	const ID: &str = "com.example.test";
+
+	fn settings() -> Settings {
+		SettingsBuilder::new()
+			.project_out_directory(std::env::temp_dir())
+			.package_settings(PackageSettings {
+				product_name: "Test App".into(),
+				version: "1.2.0".into(),
+				description: "Tests Flatpak bundles".into(),
+				homepage: None,
+				authors: None,
+				default_run: None
+			})
+			.binaries(vec![BundleBinary::new("test-app".into(), true)])
+			.target("x86_64-unknown-linux-gnu".into())
+			.build()
+			.unwrap()
+	}
+
+	#[test]
+	fn merges_finish_args() {
+		assert_eq!(finish_args(&FlatpakSettings::default()), DEFAULT_FINISH_ARGS);
+
+		let flatpak = FlatpakSettings {
+			finish_args: vec!["--share=network".into(), "--share=ipc".into(), "--filesystem=xdg-download".into()],
+			..Default::default()
+		};
+		let mut expected = DEFAULT_FINISH_ARGS.to_vec();
+		expected.extend(["--share=network", "--filesystem=xdg-download"]);
+		assert_eq!(finish_args(&flatpak), expected);
+	}
+
+	#[test]
+	fn renames_exports() {
+		let dir = tempfile::tempdir().unwrap();
+		let files_dir = dir.path();
+		let share = files_dir.join("share");
+		fs::create_dir_all(share.join("applications")).unwrap();
+		fs::write(share.join("applications/test-app.desktop"), "[Desktop Entry]\nName=Test App\nExec=test-app\nIcon=test-app\nType=Application\n").unwrap();
+		fs::create_dir_all(share.join("metainfo")).unwrap();
+		fs::write(share.join(format!("metainfo/{ID}.metainfo.xml")), r#"<component><launchable type="desktop-id">test-app.desktop</launchable></component>"#)
+			.unwrap();
+		for size in ["32x32", "128x128@2"] {
+			let apps_dir = share.join(format!("icons/hicolor/{size}/apps"));
+			fs::create_dir_all(&apps_dir).unwrap();
+			fs::write(apps_dir.join("test-app.png"), size).unwrap();
+		}
+		let scalable_dir = share.join("icons/hicolor/scalable/apps");
+		fs::create_dir_all(&scalable_dir).unwrap();
+		fs::write(scalable_dir.join("test-app.svg"), "<svg/>").unwrap();
+
+		rename_exports(&settings(), ID, files_dir).unwrap();
+
+		assert!(!share.join("applications/test-app.desktop").exists());
+		assert_eq!(
+			fs::read_to_string(share.join(format!("applications/{ID}.desktop"))).unwrap(),
+			format!("[Desktop Entry]\nName=Test App\nExec=test-app\nIcon={ID}\nType=Application\n")
+		);
+		assert_eq!(
+			fs::read_to_string(share.join(format!("metainfo/{ID}.metainfo.xml"))).unwrap(),
+			format!(r#"<component><launchable type="desktop-id">{ID}.desktop</launchable></component>"#)
+		);
+		for size in ["32x32", "128x128@2"] {
+			let apps_dir = share.join(format!("icons/hicolor/{size}/apps"));
+			assert!(!apps_dir.join("test-app.png").exists());
+			assert_eq!(fs::read_to_string(apps_dir.join(format!("{ID}.png"))).unwrap(), size);
+		}
+		assert!(!scalable_dir.join("test-app.svg").exists());
+		assert_eq!(fs::read_to_string(scalable_dir.join(format!("{ID}.svg"))).unwrap(), "<svg/>");
+	}
+}
@@ -15,5 +15,8 @@
 // limitations under the License.
 
 pub mod appimage;
+mod appstream;
 pub mod debian;
+pub mod flatpak;
+pub mod pacman;
 pub mod rpm;
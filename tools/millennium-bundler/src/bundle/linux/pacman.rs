@@ -0,0 +1,394 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The structure of an Arch Linux package looks something like this:
+//
+// foobar-1.2.3-1-x86_64.pkg.tar.zst   # A zstd-compressed tar archive
+//     .PKGINFO                            # Package metadata
+//     .MTREE                              # Gzipped mtree of the files, with their checksums
+//     usr/bin/foobar                      # Binary executable file
+//     usr/share/applications/...          # Desktop file, same as the Debian package
+//     usr/share/icons/hicolor/...         # Icon files
+//     usr/lib/foobar/...                  # Other resource files
+//
+// All entries are owned by root and stamped with the build date, which is read
+// from `SOURCE_DATE_EPOCH` when set, so we don't need `makepkg` or `fakeroot`.
+
+use std::{
+	fmt::Write as _,
+	fs::{self, File},
+	io::{self, Write},
+	os::unix::fs::PermissionsExt,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use heck::AsKebabCase;
+use libflate::gzip;
+use log::info;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use super::{super::common, debian};
+use crate::Settings;
+
+/// The release number of the package, incremented by distributions when the
+/// packaging changes but the upstream version does not.
+const PKGREL: u32 = 1;
+
+/// A file, directory or symbolic link of the package.
+struct Entry {
+	/// The path relative to the package root.
+	path: PathBuf,
+	/// The path on disk.
+	source: PathBuf,
+	kind: EntryKind,
+	mode: u32
+}
+
+enum EntryKind {
+	File { size: u64, md5: String, sha256: String },
+	Dir,
+	Link(PathBuf)
+}
+
+/// Bundles the project.
+/// Returns a vector of PathBuf that shows where the package was created.
+pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+	let arch = match settings.binary_arch() {
+		"x86" => "i686",
+		"arm" => "armv7h",
+		other => other
+	};
+	let pkgname = AsKebabCase(settings.product_name()).to_string();
+	let pkgver = pkgver(settings.version_string());
+	let package_base_name = format!("{pkgname}-{pkgver}-{PKGREL}-{arch}");
+	let package_name = format!("{package_base_name}.pkg.tar.zst");
+
+	let base_dir = settings.project_out_directory().join("bundle/pacman");
+	let package_dir = base_dir.join(&package_base_name);
+	if package_dir.exists() {
+		fs::remove_dir_all(&package_dir).with_context(|| format!("Failed to remove old {package_base_name}"))?;
+	}
+	let package_path = base_dir.join(&package_name);
+
+	info!(action = "Bundling"; "{} ({})", package_name, package_path.display());
+
+	let (data_dir, _) = debian::generate_data(settings, &package_dir).with_context(|| "Failed to build data folders and files")?;
+	let build_date = common::build_date();
+	let entries = collect_entries(&data_dir).with_context(|| "Failed to read package files")?;
+
+	let pkginfo = render_pkginfo(settings, &pkgname, &pkgver, arch, build_date, &entries);
+	fs::write(data_dir.join(".PKGINFO"), pkginfo)?;
+	let pkginfo = file_entry(&data_dir, &data_dir.join(".PKGINFO"))?;
+
+	let mut mtree = gzip::Encoder::new(common::create_file(&data_dir.join(".MTREE"))?)?;
+	mtree.write_all(render_mtree(std::iter::once(&pkginfo).chain(&entries), build_date).as_bytes())?;
+	mtree.finish().into_result()?.flush()?;
+	let mtree = file_entry(&data_dir, &data_dir.join(".MTREE"))?;
+
+	// pacman reads the metadata from the first entries of the archive
+	let mut encoder = zstd::Encoder::new(common::create_file(&package_path)?, 0)?;
+	{
+		let mut builder = tar::Builder::new(&mut encoder);
+		for entry in [&pkginfo, &mtree].into_iter().chain(&entries) {
+			append_entry(&mut builder, entry, build_date).with_context(|| format!("Failed to archive {}", entry.path.display()))?;
+		}
+		builder.finish()?;
+	}
+	encoder.finish()?.flush()?;
+
+	fs::remove_dir_all(&package_dir)?;
+	Ok(vec![package_path])
+}
+
+/// Converts a Cargo version to a pacman `pkgver`, which can't contain hyphens.
+/// A pre-release like `1.0.0-beta.3` becomes `1.0.0beta.3`: `vercmp` sorts a
+/// letter directly following the release before the release itself, but a
+/// separator like `_` after it.
+fn pkgver(version: &str) -> String {
+	// build metadata isn't part of the version precedence
+	let version = version.split('+').next().unwrap_or(version);
+	match version.split_once('-') {
+		Some((release, pre_release)) => format!("{release}{}", pre_release.replace('-', "_")),
+		None => version.into()
+	}
+}
+
+/// Lists the entries under `data_dir`, parents first.
+fn collect_entries(data_dir: &Path) -> crate::Result<Vec<Entry>> {
+	let mut entries = Vec::new();
+	for entry in WalkDir::new(data_dir).min_depth(1).sort_by_file_name() {
+		let entry = entry?;
+		let metadata = entry.path().symlink_metadata()?;
+		let path = entry.path().strip_prefix(data_dir)?.to_path_buf();
+		let mode = metadata.permissions().mode() & 0o7777;
+		if metadata.file_type().is_symlink() {
+			entries.push(Entry {
+				path,
+				source: entry.path().to_path_buf(),
+				kind: EntryKind::Link(fs::read_link(entry.path())?),
+				mode: 0o777
+			});
+		} else if metadata.is_dir() {
+			entries.push(Entry {
+				path,
+				source: entry.path().to_path_buf(),
+				kind: EntryKind::Dir,
+				mode
+			});
+		} else {
+			entries.push(file_entry(data_dir, entry.path())?);
+		}
+	}
+	Ok(entries)
+}
+
+/// Hashes the file at `path`.
+fn file_entry(data_dir: &Path, path: &Path) -> crate::Result<Entry> {
+	let data = fs::read(path)?;
+	let metadata = fs::metadata(path)?;
+	Ok(Entry {
+		path: path.strip_prefix(data_dir)?.to_path_buf(),
+		source: path.to_path_buf(),
+		kind: EntryKind::File {
+			size: data.len() as u64,
+			md5: format!("{:x}", md5::compute(&data)),
+			sha256: Sha256::digest(&data).iter().map(|b| format!("{b:02x}")).collect()
+		},
+		mode: metadata.permissions().mode() & 0o7777
+	})
+}
+
+/// Renders the `.PKGINFO` file describing the package.
+fn render_pkginfo(settings: &Settings, pkgname: &str, pkgver: &str, arch: &str, build_date: u64, entries: &[Entry]) -> String {
+	let size: u64 = entries
+		.iter()
+		.map(|entry| match entry.kind {
+			EntryKind::File { size, .. } => size,
+			_ => 0
+		})
+		.sum();
+	let packager = std::env::var("PACKAGER")
+		.ok()
+		.or_else(|| settings.authors_comma_separated())
+		.unwrap_or_else(|| "Unknown Packager".to_string());
+
+	let mut pkginfo = String::new();
+	// writing to a `String` can't fail
+	let _ = writeln!(pkginfo, "# Generated by millennium-bundler");
+	let _ = writeln!(pkginfo, "pkgname = {pkgname}");
+	let _ = writeln!(pkginfo, "pkgbase = {pkgname}");
+	let _ = writeln!(pkginfo, "pkgver = {pkgver}-{PKGREL}");
+	let _ = writeln!(pkginfo, "pkgdesc = {}", settings.short_description().lines().next().unwrap_or_default().trim());
+	if !settings.homepage_url().is_empty() {
+		let _ = writeln!(pkginfo, "url = {}", settings.homepage_url());
+	}
+	let _ = writeln!(pkginfo, "builddate = {build_date}");
+	let _ = writeln!(pkginfo, "packager = {packager}");
+	let _ = writeln!(pkginfo, "size = {size}");
+	let _ = writeln!(pkginfo, "arch = {arch}");
+	for depend in settings.pacman().depends.iter().flatten() {
+		let _ = writeln!(pkginfo, "depend = {depend}");
+	}
+	for depend in settings.pacman().optional_depends.iter().flatten() {
+		let _ = writeln!(pkginfo, "optdepend = {depend}");
+	}
+	pkginfo
+}
+
+/// Escapes a path for the mtree format, which encodes whitespace and special
+/// characters as backslash-prefixed octal.
+fn escape_mtree_path(path: &str) -> String {
+	let mut escaped = String::with_capacity(path.len());
+	for &byte in path.as_bytes() {
+		if byte <= b' ' || byte >= 0x7f || matches!(byte, b'\\' | b'#' | b'=') {
+			let _ = write!(escaped, "\\{byte:03o}");
+		} else {
+			escaped.push(byte as char);
+		}
+	}
+	escaped
+}
+
+/// Renders the mtree of `entries`, as `bsdtar --format=mtree` does for
+/// `makepkg`.
+fn render_mtree<'a>(entries: impl Iterator<Item = &'a Entry>, build_date: u64) -> String {
+	let mut mtree = String::from("#mtree\n/set type=file uid=0 gid=0 mode=644\n");
+	for entry in entries {
+		let _ = write!(mtree, "./{} time={build_date}.0", escape_mtree_path(&entry.path.to_string_lossy()));
+		match &entry.kind {
+			EntryKind::File { size, md5, sha256 } => {
+				if entry.mode != 0o644 {
+					let _ = write!(mtree, " mode={:o}", entry.mode);
+				}
+				let _ = writeln!(mtree, " size={size} md5digest={md5} sha256digest={sha256}");
+			}
+			EntryKind::Dir => {
+				let _ = writeln!(mtree, " mode={:o} type=dir", entry.mode);
+			}
+			EntryKind::Link(target) => {
+				let _ = writeln!(mtree, " mode={:o} type=link link={}", entry.mode, escape_mtree_path(&target.to_string_lossy()));
+			}
+		}
+	}
+	mtree
+}
+
+/// Appends `entry` to the archive, owned by root.
+fn append_entry<W: Write>(builder: &mut tar::Builder<W>, entry: &Entry, build_date: u64) -> crate::Result<()> {
+	let mut header = tar::Header::new_gnu();
+	header.set_mode(entry.mode);
+	header.set_uid(0);
+	header.set_gid(0);
+	header.set_username("root")?;
+	header.set_groupname("root")?;
+	header.set_mtime(build_date);
+	match &entry.kind {
+		EntryKind::File { size, .. } => {
+			header.set_entry_type(tar::EntryType::Regular);
+			header.set_size(*size);
+			builder.append_data(&mut header, &entry.path, File::open(&entry.source)?)?;
+		}
+		EntryKind::Dir => {
+			header.set_entry_type(tar::EntryType::Directory);
+			header.set_size(0);
+			builder.append_data(&mut header, &entry.path, io::empty())?;
+		}
+		EntryKind::Link(target) => {
+			header.set_entry_type(tar::EntryType::Symlink);
+			header.set_size(0);
+			builder.append_link(&mut header, &entry.path, target)?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::HashMap,
+		fs,
+		io::Read,
+		os::unix::fs::PermissionsExt,
+		path::{Path, PathBuf}
+	};
+
+	use libflate::gzip;
+	use sha2::Digest;
+
+	use super::{bundle_project, escape_mtree_path, pkgver};
+	use crate::{BundleBinary, BundleSettings, PackageSettings, PacmanSettings, Settings, SettingsBuilder};
+
+	fn settings(out_dir: &Path) -> Settings {
+		SettingsBuilder::new()
+			.project_out_directory(out_dir)
+			.package_settings(PackageSettings {
+				product_name: "Test App".into(),
+				version: "1.2.0-beta.1".into(),
+				description: "Tests pacman packages".into(),
+				homepage: Some("https://example.com".into()),
+				authors: Some(vec!["pyke.io".into()]),
+				default_run: None
+			})
+			.bundle_settings(BundleSettings {
+				identifier: Some("com.example.testapp".into()),
+				pacman: PacmanSettings {
+					depends: Some(vec!["webkit2gtk".into(), "gtk3".into()]),
+					optional_depends: Some(vec!["libnotify: notifications".into()])
+				},
+				..Default::default()
+			})
+			.binaries(vec![BundleBinary::new("test-app".into(), true)])
+			.target("x86_64-unknown-linux-gnu".into())
+			.build()
+			.unwrap()
+	}
+
+	#[test]
+	fn bundles_packages() {
+		let dir = tempfile::tempdir().unwrap();
+		let binary = dir.path().join("test-app");
+		fs::write(&binary, "#!/bin/sh\necho test\n").unwrap();
+		fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+		let paths = bundle_project(&settings(dir.path())).unwrap();
+		assert_eq!(paths, [dir.path().join("bundle/pacman/test-app-1.2.0beta.1-1-x86_64.pkg.tar.zst")]);
+
+		let mut archive = tar::Archive::new(zstd::Decoder::new(fs::File::open(&paths[0]).unwrap()).unwrap());
+		let mut files = Vec::new();
+		let mut contents = HashMap::new();
+		for entry in archive.entries().unwrap() {
+			let mut entry = entry.unwrap();
+			let path = entry.path().unwrap().into_owned();
+			assert_eq!((entry.header().uid().unwrap(), entry.header().gid().unwrap()), (0, 0));
+			let mut data = Vec::new();
+			entry.read_to_end(&mut data).unwrap();
+			files.push(path.clone());
+			contents.insert(path, data);
+		}
+		// pacman reads the metadata from the first entries
+		assert_eq!(files[..2], [PathBuf::from(".PKGINFO"), PathBuf::from(".MTREE")]);
+		assert!(files.contains(&PathBuf::from("usr/bin/test-app")));
+		assert!(files.contains(&PathBuf::from("usr/share/applications/test-app.desktop")));
+
+		let pkginfo = String::from_utf8(contents[Path::new(".PKGINFO")].clone()).unwrap();
+		let fields = pkginfo
+			.lines()
+			.filter_map(|line| line.split_once(" = "))
+			.fold(HashMap::<&str, Vec<&str>>::new(), |mut fields, (key, value)| {
+				fields.entry(key).or_default().push(value);
+				fields
+			});
+		assert_eq!(fields["pkgname"], ["test-app"]);
+		assert_eq!(fields["pkgver"], ["1.2.0beta.1-1"]);
+		assert_eq!(fields["pkgdesc"], ["Tests pacman packages"]);
+		assert_eq!(fields["arch"], ["x86_64"]);
+		assert_eq!(fields["depend"], ["webkit2gtk", "gtk3"]);
+		assert_eq!(fields["optdepend"], ["libnotify: notifications"]);
+		// the size of the installed files
+		let size = contents
+			.iter()
+			.filter(|(path, _)| !path.to_string_lossy().starts_with('.'))
+			.map(|(_, data)| data.len())
+			.sum::<usize>();
+		assert_eq!(fields["size"], [size.to_string().as_str()]);
+
+		let mut mtree = String::new();
+		gzip::Decoder::new(&contents[Path::new(".MTREE")][..])
+			.unwrap()
+			.read_to_string(&mut mtree)
+			.unwrap();
+		assert!(mtree.starts_with("#mtree\n/set type=file uid=0 gid=0 mode=644\n"));
+		let binary_entry = mtree.lines().find(|line| line.starts_with("./usr/bin/test-app ")).unwrap();
+		let sha256 = sha2::Sha256::digest(&contents[Path::new("usr/bin/test-app")]);
+		assert!(binary_entry.contains(" mode=755 size=20 "), "{binary_entry}");
+		assert!(binary_entry.ends_with(&format!("sha256digest={sha256:x}")), "{binary_entry}");
+		assert!(mtree.lines().any(|line| line.starts_with("./.PKGINFO ")));
+		assert!(mtree.lines().any(|line| line.starts_with("./usr/bin ") && line.ends_with(" type=dir")));
+	}
+
+	#[test]
+	fn converts_versions() {
+		assert_eq!(pkgver("1.2.3"), "1.2.3");
+		assert_eq!(pkgver("1.0.0-beta.3"), "1.0.0beta.3");
+		assert_eq!(pkgver("1.0.0-rc-1+build.5"), "1.0.0rc_1");
+	}
+
+	#[test]
+	fn escapes_mtree_paths() {
+		assert_eq!(escape_mtree_path("usr/share/My App/a#b=c"), "usr/share/My\\040App/a\\043b\\075c");
+		assert_eq!(escape_mtree_path("usr/lib/é"), "usr/lib/\\303\\251");
+	}
+}
@@ -19,7 +19,7 @@ use std::{
 	path::{Path, PathBuf}
 };
 
-pub use millennium_utils::config::WebviewInstallMode;
+pub use millennium_utils::config::{AppStreamContentRating, AppStreamScreenshot, WebviewInstallMode};
 use millennium_utils::{
 	config::{BundleType, NSISInstallerMode},
 	resources::{external_binaries, ResourcePaths}
@@ -46,6 +46,10 @@ pub enum PackageType {
 	Rpm,
 	/// The Linux AppImage bundle (.AppImage).
 	AppImage,
+	/// The Linux Flatpak manifest and bundle (.flatpak).
+	Flatpak,
+	/// The Arch Linux package (.pkg.tar.zst).
+	Pacman,
 	/// The macOS DMG bundle (.dmg).
 	Dmg,
 	/// The Updater bundle.
@@ -61,6 +65,8 @@ impl From<BundleType> for PackageType {
 			BundleType::Nsis => Self::Nsis,
 			BundleType::App => Self::MacOsBundle,
 			BundleType::Dmg => Self::Dmg,
+			BundleType::Flatpak => Self::Flatpak,
+			BundleType::Pacman => Self::Pacman,
 			BundleType::Updater => Self::Updater
 		}
 	}
@@ -68,7 +74,7 @@ impl From<BundleType> for PackageType {
 
 impl PackageType {
 	/// Maps a short name to a PackageType.
-	/// Possible values are "deb", "ios", "msi", "app", "rpm", "appimage", "dmg", "updater".
+	/// Possible values are "deb", "ios", "msi", "app", "rpm", "appimage", "flatpak", "pacman", "dmg", "updater".
 	pub fn from_short_name(name: &str) -> Option<PackageType> {
 		// Other types we may eventually want to support: apk.
 		match name {
@@ -79,6 +85,8 @@ impl PackageType {
 			"app" => Some(PackageType::MacOsBundle),
 			"rpm" => Some(PackageType::Rpm),
 			"appimage" => Some(PackageType::AppImage),
+			"flatpak" => Some(PackageType::Flatpak),
+			"pacman" => Some(PackageType::Pacman),
 			"dmg" => Some(PackageType::Dmg),
 			"updater" => Some(PackageType::Updater),
 			_ => None
@@ -96,6 +104,8 @@ impl PackageType {
 			PackageType::MacOsBundle => "app",
 			PackageType::Rpm => "rpm",
 			PackageType::AppImage => "appimage",
+			PackageType::Flatpak => "flatpak",
+			PackageType::Pacman => "pacman",
 			PackageType::Dmg => "dmg",
 			PackageType::Updater => "updater"
 		}
@@ -124,6 +134,10 @@ const ALL_PACKAGE_TYPES: &[PackageType] = &[
 	PackageType::Dmg,
 	#[cfg(target_os = "linux")]
 	PackageType::AppImage,
+	#[cfg(target_os = "linux")]
+	PackageType::Flatpak,
+	#[cfg(target_os = "linux")]
+	PackageType::Pacman,
 	PackageType::Updater
 ];
 
@@ -171,6 +185,58 @@ pub struct DebianSettings {
 	pub files: HashMap<PathBuf, PathBuf>
 }
 
+/// The Linux AppImage bundle settings.
+#[derive(Clone, Debug, Default)]
+pub struct AppImageSettings {
+	/// Whether to bundle the GStreamer plugins needed for audio and video playback.
+	pub bundle_media_framework: bool,
+	/// The path to the AppImage type 2 runtime prepended to the image.
+	pub runtime: Option<PathBuf>,
+	/// Additional shared libraries to bundle, along with their dependencies.
+	pub libraries: Vec<PathBuf>,
+	/// The update information embedded in the AppImage.
+	pub update_information: Option<String>
+}
+
+/// The AppStream metainfo settings, shared by all Linux bundles.
+#[derive(Clone, Debug, Default)]
+pub struct AppStreamSettings {
+	/// A one-line summary of the application. Defaults to the short description.
+	pub summary: Option<String>,
+	/// The description of the application. Defaults to the long description.
+	pub description: Option<String>,
+	/// The screenshots of the application.
+	pub screenshots: Vec<AppStreamScreenshot>,
+	/// The OARS 1.1 content rating.
+	pub content_rating: HashMap<String, AppStreamContentRating>,
+	/// The SPDX license expression of the application.
+	pub project_license: Option<String>,
+	/// The path to the changelog the releases are read from.
+	pub changelog: Option<PathBuf>
+}
+
+/// The Arch Linux bundle settings.
+#[derive(Clone, Debug, Default)]
+pub struct PacmanSettings {
+	/// The list of pacman dependencies your application relies on.
+	pub depends: Option<Vec<String>>,
+	/// The list of optional dependencies, in the `package: reason` format.
+	pub optional_depends: Option<Vec<String>>
+}
+
+/// The Linux Flatpak bundle settings.
+#[derive(Clone, Debug, Default)]
+pub struct FlatpakSettings {
+	/// The runtime the application runs on.
+	pub runtime: String,
+	/// The version of the runtime and of the SDK.
+	pub runtime_version: String,
+	/// The SDK used to build the application.
+	pub sdk: String,
+	/// The sandbox permissions of the application.
+	pub finish_args: Vec<String>
+}
+
 /// The macOS bundle settings.
 #[derive(Clone, Debug, Default)]
 pub struct MacOsSettings {
@@ -381,6 +447,14 @@ pub struct BundleSettings {
 	pub external_bin: Option<Vec<String>>,
 	/// Debian-specific settings.
 	pub deb: DebianSettings,
+	/// AppImage-specific settings.
+	pub appimage: AppImageSettings,
+	/// Flatpak-specific settings.
+	pub flatpak: FlatpakSettings,
+	/// Arch Linux-specific settings.
+	pub pacman: PacmanSettings,
+	/// AppStream metainfo settings.
+	pub appstream: AppStreamSettings,
 	/// MacOS-specific settings.
 	pub macos: MacOsSettings,
 	/// Updater configuration.
@@ -594,15 +668,17 @@ impl Settings {
 	/// command-line, returns the native package type(s) for that target.
 	///
 	/// Otherwise returns the native package type(s) for the host platform.
+	/// Package types for specific distributions or that depend on external
+	/// tooling, like pacman and Flatpak, are only built when requested.
 	///
 	/// Fails if the host/target's native package type is not supported.
 	pub fn package_types(&self) -> crate::Result<Vec<PackageType>> {
 		let target_os = std::env::consts::OS;
-		let mut platform_types = match target_os {
-			"macos" => vec![PackageType::MacOsBundle, PackageType::Dmg],
-			"ios" => vec![PackageType::IosBundle],
-			"linux" => vec![PackageType::Deb, PackageType::AppImage],
-			"windows" => vec![PackageType::WindowsMsi, PackageType::Nsis],
+		let (mut platform_types, opt_in_types) = match target_os {
+			"macos" => (vec![PackageType::MacOsBundle, PackageType::Dmg], vec![]),
+			"ios" => (vec![PackageType::IosBundle], vec![]),
+			"linux" => (vec![PackageType::Deb, PackageType::AppImage], vec![PackageType::Flatpak, PackageType::Pacman]),
+			"windows" => (vec![PackageType::WindowsMsi, PackageType::Nsis], vec![]),
 			os => return Err(crate::Error::GenericError(format!("Native {os} bundles not yet supported.")))
 		};
 
@@ -615,7 +691,7 @@ impl Settings {
 			let mut types = vec![];
 			for package_type in package_types {
 				let package_type = *package_type;
-				if platform_types.clone().into_iter().any(|t| t == package_type) {
+				if platform_types.iter().chain(&opt_in_types).any(|t| *t == package_type) {
 					types.push(package_type);
 				}
 			}
@@ -740,6 +816,26 @@ impl Settings {
 		&self.bundle_settings.deb
 	}
 
+	/// Returns the AppImage settings.
+	pub fn appimage(&self) -> &AppImageSettings {
+		&self.bundle_settings.appimage
+	}
+
+	/// Returns the AppStream metainfo settings.
+	pub fn appstream(&self) -> &AppStreamSettings {
+		&self.bundle_settings.appstream
+	}
+
+	/// Returns the Arch Linux settings.
+	pub fn pacman(&self) -> &PacmanSettings {
+		&self.bundle_settings.pacman
+	}
+
+	/// Returns the Flatpak settings.
+	pub fn flatpak(&self) -> &FlatpakSettings {
+		&self.bundle_settings.flatpak
+	}
+
 	/// Returns the MacOS settings.
 	pub fn macos(&self) -> &MacOsSettings {
 		&self.bundle_settings.macos
@@ -122,7 +122,19 @@ pub enum Error {
 	/// Plist error.
 	#[cfg(target_os = "macos")]
 	#[error(transparent)]
-	Plist(#[from] plist::Error)
+	Plist(#[from] plist::Error),
+	/// SquashFS error.
+	#[cfg(target_os = "linux")]
+	#[error("`{0}`")]
+	SquashfsError(#[from] backhand::BackhandError),
+	/// ELF parsing error.
+	#[cfg(target_os = "linux")]
+	#[error("`{0}`")]
+	ElfError(#[from] goblin::error::Error),
+	/// time error.
+	#[cfg(target_os = "linux")]
+	#[error("`{0}`")]
+	TimeFormatError(#[from] time::error::Format)
 }
 
 /// Convenient type alias of Result type.
@@ -21,7 +21,6 @@ use std::{
 };
 
 use anyhow::{bail, Context};
-use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
 use clap::{ArgAction, Parser};
 use log::{error, info, warn};
 use millennium_bundler::bundle::{bundle_project, Bundle, PackageType};
@@ -30,15 +29,29 @@ use crate::{
 	helpers::{
 		app_paths::{app_dir, millennium_dir},
 		command_env,
-		config::{get as get_config, AppUrl, HookCommand, WindowUrl, MERGE_CONFIG_EXTENSION_NAME},
-		updater_signature::{read_key_from_file, secret_key as updater_secret_key, sign_file}
+		config::{get as get_config, AppUrl, Config, ConfigMetadata, HookCommand, WindowUrl, MERGE_CONFIG_EXTENSION_NAME},
+		updater_signature::{
+			public_key as updater_public_key, read_key_from_file, secret_key as updater_secret_key, sign_file, ExternalSigner, SigningBackend
+		}
 	},
-	interface::{AppInterface, AppSettings, Interface},
+	interface::{AppInterface, AppSettings, Interface, Options as InterfaceOptions},
 	CommandExt, Result
 };
 
+/// Describes how the `updater` bundles are signed, for the help of the commands
+/// producing them.
+pub(crate) const UPDATER_SIGNING_HELP: &str = "\
+The `updater` bundles are signed with the key given by these environment variables:
+  MILLENNIUM_PRIVATE_KEY        Path or string of the updater private key
+  MILLENNIUM_KEY_PASSWORD       Password of the updater private key (optional)
+  MILLENNIUM_SIGNER             `command:<command>` or a signing service URL to sign with instead of a private key
+  MILLENNIUM_SIGNER_TOKEN       Bearer token sent to the `MILLENNIUM_SIGNER` signing service (optional)
+  MILLENNIUM_SIGNER_PUBLIC_KEY  Public key of the `MILLENNIUM_SIGNER` key, when it isn't `updater > pubkey` (optional)
+
+The signing key may be `updater > pubkey` or any of `updater > trustedKeys`.";
+
 #[derive(Debug, Clone, Parser)]
-#[clap(about = "Bundle your Millennium application")]
+#[clap(about = "Bundle your Millennium application", after_help = UPDATER_SIGNING_HELP)]
 pub struct Options {
 	/// Binary to use to build the application, defaults to `cargo`
 	#[clap(short, long)]
@@ -58,7 +71,7 @@ pub struct Options {
 	pub features: Option<Vec<String>>,
 	/// Space or comma-separated list of bundles to package.
 	///
-	/// Bundles must be one of `deb`, `appimage`, `msi`, `app`, `dmg`, or `updater`.
+	/// Bundles must be one of `deb`, `appimage`, `flatpak`, `pacman`, `msi`, `app`, `dmg`, or `updater`.
 	///
 	/// Note that the `updater` bundle is not automatically added, so you must specify it if the updater is enabled.
 	#[clap(short, long, action = ArgAction::Append, num_args(0..))]
@@ -71,15 +84,7 @@ pub struct Options {
 }
 
 pub fn command(mut options: Options) -> Result<()> {
-	let (merge_config, merge_config_path) = if let Some(config) = &options.config {
-		if config.starts_with('{') {
-			(Some(config.to_string()), None)
-		} else {
-			(Some(std::fs::read_to_string(config).with_context(|| "failed to read custom configuration")?), Some(config.clone()))
-		}
-	} else {
-		(None, None)
-	};
+	let (merge_config, merge_config_path) = read_merge_config(options.config.as_deref())?;
 	options.config = merge_config;
 
 	let millennium_path = millennium_dir();
@@ -90,33 +95,8 @@ pub fn command(mut options: Options) -> Result<()> {
 	let config_guard = config.lock().unwrap();
 	let config_ = config_guard.as_ref().unwrap();
 
-	let bundle_identifier_source = match config_.find_bundle_identifier_override() {
-		Some(source) if source == MERGE_CONFIG_EXTENSION_NAME => merge_config_path.unwrap_or(source),
-		Some(source) => source,
-		None => "Millennium.toml".into()
-	};
+	check_bundle_identifier(config_, merge_config_path);
 
-	if config_.millennium.bundle.identifier == "com.millennium.dev" {
-		error!(
-			"You must change the bundle identifier in `{} > millennium > bundle > identifier`. The default value `com.millennium.dev` is not allowed as it must be unique across applications.",
-			bundle_identifier_source
-		);
-		std::process::exit(1);
-	}
-
-	if config_
-		.millennium
-		.bundle
-		.identifier
-		.chars()
-		.any(|ch| !(ch.is_alphanumeric() || ch == '-' || ch == '.'))
-	{
-		error!(
-			"The bundle identifier defined in `{} > millennium > bundle > identifier` is invalid. Check the documentation for more info.",
-			bundle_identifier_source
-		);
-		std::process::exit(1);
-	}
 	if let Some(before_build) = config_.build.before_build_command.clone() {
 		run_hook("beforeBuildCommand", before_build, options.debug)?;
 	}
@@ -171,118 +151,188 @@ pub fn command(mut options: Options) -> Result<()> {
 	let app_settings = interface.app_settings();
 
 	if config_.millennium.bundle.active {
-		let package_types = if let Some(names) = &options.bundles {
-			let mut types = vec![];
-			for name in names.iter().flat_map(|n| n.split(',').map(|s| s.to_string()).collect::<Vec<String>>()) {
-				if name == "none" {
-					break;
-				}
-				match PackageType::from_short_name(&name) {
-					Some(package_type) => {
-						types.push(package_type);
-					}
-					None => {
-						return Err(anyhow::anyhow!(format!("Unsupported bundle format: {name}")));
-					}
-				}
-			}
-			Some(types)
-		} else {
-			let targets = config_.millennium.bundle.targets.to_vec();
-			if targets.is_empty() { None } else { Some(targets.into_iter().map(Into::into).collect()) }
-		};
+		bundle(&options.clone().into(), options.bundles.as_deref(), config_, app_settings, out_dir)?;
+	}
 
-		if let Some(types) = &package_types {
-			if config_.millennium.updater.active && !types.contains(&PackageType::Updater) {
-				warn!("Updater is enabled, but the bundle target list does not contain `updater`; updater artifacts won't be generated.");
-			}
-		}
+	Ok(())
+}
 
-		// if we have a package to bundle, run the `before_bundle_command`
-		if package_types.as_ref().map_or(true, |p| !p.is_empty()) {
-			if let Some(before_bundle) = config_.build.before_bundle_command.clone() {
-				run_hook("beforeBundleCommand", before_bundle, options.debug)?;
-			}
+/// Reads the `--config` argument, which is either a JSON string or the path to
+/// a JSON file. Returns the JSON string and the path it was read from.
+pub(crate) fn read_merge_config(config: Option<&str>) -> Result<(Option<String>, Option<String>)> {
+	Ok(if let Some(config) = config {
+		if config.starts_with('{') {
+			(Some(config.to_string()), None)
+		} else {
+			(Some(std::fs::read_to_string(config).with_context(|| "failed to read custom configuration")?), Some(config.to_string()))
 		}
+	} else {
+		(None, None)
+	})
+}
+
+/// Exits if the bundle identifier is the default one or contains invalid
+/// characters.
+pub(crate) fn check_bundle_identifier(config_: &ConfigMetadata, merge_config_path: Option<String>) {
+	let bundle_identifier_source = match config_.find_bundle_identifier_override() {
+		Some(source) if source == MERGE_CONFIG_EXTENSION_NAME => merge_config_path.unwrap_or(source),
+		Some(source) => source,
+		None => "Millennium.toml".into()
+	};
 
-		let settings = app_settings
-			.get_bundler_settings(&options.into(), config_, out_dir, package_types)
-			.with_context(|| "failed to build bundler settings")?;
+	if config_.millennium.bundle.identifier == "com.millennium.dev" {
+		error!(
+			"You must change the bundle identifier in `{} > millennium > bundle > identifier`. The default value `com.millennium.dev` is not allowed as it must be unique across applications.",
+			bundle_identifier_source
+		);
+		std::process::exit(1);
+	}
+
+	if config_
+		.millennium
+		.bundle
+		.identifier
+		.chars()
+		.any(|ch| !(ch.is_alphanumeric() || ch == '-' || ch == '.'))
+	{
+		error!(
+			"The bundle identifier defined in `{} > millennium > bundle > identifier` is invalid. Check the documentation for more info.",
+			bundle_identifier_source
+		);
+		std::process::exit(1);
+	}
+}
 
-		// set env vars used by the bundler
-		#[cfg(target_os = "linux")]
-		{
-			use crate::helpers::config::ShellAllowlistOpen;
-			if matches!(config_.millennium.allowlist.shell.open, ShellAllowlistOpen::Flag(true) | ShellAllowlistOpen::Validate(_)) {
-				std::env::set_var("APPIMAGE_BUNDLE_XDG_OPEN", "1");
+/// Packages the binaries in `out_dir` into the given bundles, or the ones
+/// configured in `Millennium.toml`, then signs the updater archives.
+pub(crate) fn bundle<A: AppSettings>(options: &InterfaceOptions, bundles: Option<&[String]>, config_: &Config, app_settings: &A, out_dir: &Path) -> Result<()> {
+	let package_types = if let Some(names) = bundles {
+		let mut types = vec![];
+		for name in names.iter().flat_map(|n| n.split(',').map(|s| s.to_string()).collect::<Vec<String>>()) {
+			if name == "none" {
+				break;
 			}
-			if config_.millennium.system_tray.is_some() {
-				if let Ok(tray) = std::env::var("MILLENNIUM_TRAY") {
-					std::env::set_var(
-						"TRAY_LIBRARY_PATH",
-						if tray == "ayatana" {
-							format!(
-								"{}/libayatana-appindicator3.so.1",
-								pkgconfig_utils::get_library_path("ayatana-appindicator3-0.1")
-									.expect("failed to get ayatana-appindicator library path using pkg-config.")
-							)
-						} else {
-							format!(
-								"{}/libappindicator3.so.1",
-								pkgconfig_utils::get_library_path("appindicator3-0.1")
-									.expect("failed to get libappindicator-gtk library path using pkg-config.")
-							)
-						}
-					);
-				} else {
-					std::env::set_var("TRAY_LIBRARY_PATH", pkgconfig_utils::get_appindicator_library_path());
+			match PackageType::from_short_name(&name) {
+				Some(package_type) => {
+					types.push(package_type);
+				}
+				None => {
+					return Err(anyhow::anyhow!(format!("Unsupported bundle format: {name}")));
 				}
 			}
+		}
+		Some(types)
+	} else {
+		let targets = config_.millennium.bundle.targets.to_vec();
+		if targets.is_empty() { None } else { Some(targets.into_iter().map(Into::into).collect()) }
+	};
 
-			if config_.millennium.bundle.appimage.bundle_media_framework {
-				std::env::set_var("APPIMAGE_BUNDLE_GSTREAMER", "1");
-			}
+	if let Some(types) = &package_types {
+		if config_.millennium.updater.active && !types.contains(&PackageType::Updater) {
+			warn!("Updater is enabled, but the bundle target list does not contain `updater`; updater artifacts won't be generated.");
 		}
+	}
 
-		let bundles = bundle_project(settings).with_context(|| "failed to bundle project")?;
+	// if we have a package to bundle, run the `before_bundle_command`
+	if package_types.as_ref().map_or(true, |p| !p.is_empty()) {
+		if let Some(before_bundle) = config_.build.before_bundle_command.clone() {
+			run_hook("beforeBundleCommand", before_bundle, options.debug)?;
+		}
+	}
 
-		let updater_bundles: Vec<&Bundle> = bundles.iter().filter(|bundle| bundle.package_type == PackageType::Updater).collect();
-		// If updater is active and we bundled it
-		if config_.millennium.updater.active && !updater_bundles.is_empty() {
+	let settings = app_settings
+		.get_bundler_settings(options, config_, out_dir, package_types)
+		.with_context(|| "failed to build bundler settings")?;
+
+	// set env vars used by the bundler
+	#[cfg(target_os = "linux")]
+	{
+		use crate::helpers::config::ShellAllowlistOpen;
+		if matches!(config_.millennium.allowlist.shell.open, ShellAllowlistOpen::Flag(true) | ShellAllowlistOpen::Validate(_)) {
+			std::env::set_var("APPIMAGE_BUNDLE_XDG_OPEN", "1");
+		}
+		if config_.millennium.system_tray.is_some() {
+			if let Ok(tray) = std::env::var("MILLENNIUM_TRAY") {
+				std::env::set_var(
+					"TRAY_LIBRARY_PATH",
+					if tray == "ayatana" {
+						format!(
+							"{}/libayatana-appindicator3.so.1",
+							pkgconfig_utils::get_library_path("ayatana-appindicator3-0.1")
+								.expect("failed to get ayatana-appindicator library path using pkg-config.")
+						)
+					} else {
+						format!(
+							"{}/libappindicator3.so.1",
+							pkgconfig_utils::get_library_path("appindicator3-0.1").expect("failed to get libappindicator-gtk library path using pkg-config.")
+						)
+					}
+				);
+			} else {
+				std::env::set_var("TRAY_LIBRARY_PATH", pkgconfig_utils::get_appindicator_library_path());
+			}
+		}
+	}
+
+	let bundles = bundle_project(settings).with_context(|| "failed to bundle project")?;
+
+	let updater_bundles: Vec<&Bundle> = bundles.iter().filter(|bundle| bundle.package_type == PackageType::Updater).collect();
+	// If updater is active and we bundled it
+	if config_.millennium.updater.active && !updater_bundles.is_empty() {
+		let updater_config = &config_.millennium.updater;
+		let public_keys = std::iter::once(&updater_config.pubkey)
+			.chain(updater_config.trusted_keys.iter().map(|key| &key.pubkey))
+			.filter(|key| !key.is_empty())
+			.map(|key| updater_public_key(key))
+			.collect::<Result<Vec<_>>>()?;
+		// `key_source` names where the signing key came from in errors
+		let (backend, key_source) = if let Some(signer) = var_os("MILLENNIUM_SIGNER").map(|v| v.to_str().unwrap().to_string()) {
+			// the external signer's key is the first configured one, unless given
+			let (public_key, key_source) = match var_os("MILLENNIUM_SIGNER_PUBLIC_KEY") {
+				Some(key) => (updater_public_key(key.to_str().unwrap())?, "The `MILLENNIUM_SIGNER` signer's key from `MILLENNIUM_SIGNER_PUBLIC_KEY`"),
+				None => (public_keys.first().cloned().context("no updater public key is configured")?, "The `MILLENNIUM_SIGNER` signer's key")
+			};
+			let backend = SigningBackend::External {
+				signer: signer.parse::<ExternalSigner>()?,
+				public_key
+			};
+			(backend, key_source.to_string())
+		} else {
 			let password = var_os("MILLENNIUM_KEY_PASSWORD").map(|v| v.to_str().unwrap().to_string());
-			let secret_key = if let Some(mut private_key) = var_os("MILLENNIUM_PRIVATE_KEY").map(|v| v.to_str().unwrap().to_string()) {
+			let (private_key, key_source) = if let Some(private_key) = var_os("MILLENNIUM_PRIVATE_KEY").map(|v| v.to_str().unwrap().to_string()) {
 				let pk_dir = Path::new(&private_key);
 				if pk_dir.exists() {
-					private_key = read_key_from_file(pk_dir)?;
+					(read_key_from_file(pk_dir)?, format!("The updater secret key read from `{}` (`MILLENNIUM_PRIVATE_KEY`)", pk_dir.display()))
+				} else {
+					(private_key, "The updater secret key from `MILLENNIUM_PRIVATE_KEY`".to_string())
 				}
-				updater_secret_key(private_key, password)
 			} else {
-				Err(anyhow::anyhow!("A public key was provided, but no private key. Make sure to also set the `MILLENNIUM_PRIVATE_KEY` environment variable."))
-			}?;
-
-			let pubkey = BASE64_STANDARD.decode(&config_.millennium.updater.pubkey)?;
-			let pub_key_decoded = String::from_utf8_lossy(&pubkey);
-			let public_key = minisign::PublicKeyBox::from_string(&pub_key_decoded)?.into_public_key()?;
-
-			// make sure we have our package builts
-			let mut signed_paths = Vec::new();
-			for elem in updater_bundles {
-				// we expect to have only one path in the vec but we iter if we add
-				// another type of updater package who require multiple file signature
-				for path in elem.bundle_paths.iter() {
-					// sign our path from environment variables
-					let (signature_path, signature) = sign_file(&secret_key, path)?;
-					if signature.keynum() != public_key.keynum() {
-						return Err(anyhow::anyhow!(
-							"The updater secret key from `MILLENNIUM_PRIVATE_KEY` does not match the public key defined in `.millenniumrc > millennium > updater > pubkey`."
-						));
-					}
-					signed_paths.append(&mut vec![signature_path]);
+				return Err(anyhow::anyhow!(
+					"A public key was provided, but no private key. Make sure to also set the `MILLENNIUM_PRIVATE_KEY` or `MILLENNIUM_SIGNER` environment variable."
+				));
+			};
+			(SigningBackend::SecretKey(updater_secret_key(private_key, password)?), key_source)
+		};
+
+		// make sure we have our package builts
+		let mut signed_paths = Vec::new();
+		for elem in updater_bundles {
+			// we expect to have only one path in the vec but we iter if we add
+			// another type of updater package who require multiple file signature
+			for path in elem.bundle_paths.iter() {
+				// sign our path from environment variables
+				let (signature_path, signature) = sign_file(&backend, path)?;
+				if !public_keys.iter().any(|public_key| signature.keynum() == public_key.keynum()) {
+					return Err(anyhow::anyhow!(
+						"{} does not match the public keys defined in `.millenniumrc > millennium > updater > pubkey` and `trustedKeys`.",
+						key_source
+					));
 				}
+				signed_paths.append(&mut vec![signature_path]);
 			}
-
-			print_signed_updater_archive(&signed_paths)?;
 		}
+
+		print_signed_updater_archive(&signed_paths)?;
 	}
 
 	Ok(())
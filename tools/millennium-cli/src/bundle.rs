@@ -0,0 +1,178 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+	env::set_current_dir,
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use clap::{ArgAction, Parser};
+use log::info;
+
+use crate::{
+	build::{bundle, check_bundle_identifier, read_merge_config, UPDATER_SIGNING_HELP},
+	helpers::{app_paths::millennium_dir, config::get as get_config},
+	interface::{AppInterface, AppSettings, Interface, Options as InterfaceOptions},
+	Result
+};
+
+#[derive(Debug, Clone, Parser)]
+#[clap(about = "Bundle a prebuilt Millennium application without building it", after_help = UPDATER_SIGNING_HELP)]
+pub struct Options {
+	/// Path to the prebuilt application binary.
+	///
+	/// The other binaries of the application are read from the same directory, and the bundles are written to its
+	/// `bundle` folder.
+	pub binary: PathBuf,
+	/// Target triple the binary was built for. Defaults to the host target.
+	#[clap(short, long)]
+	pub target: Option<String>,
+	/// Whether the binary is a debug build. Passed on to the `beforeBundleCommand`.
+	#[clap(short, long)]
+	pub debug: bool,
+	/// Space or comma-separated list of Cargo features the binary was built with.
+	#[clap(short, long, action = ArgAction::Append, num_args(0..))]
+	pub features: Option<Vec<String>>,
+	/// Space or comma-separated list of bundles to package.
+	///
+	/// Bundles must be one of `deb`, `appimage`, `flatpak`, `pacman`, `msi`, `app`, `dmg`, or `updater`.
+	///
+	/// Note that the `updater` bundle is not automatically added, so you must specify it if the updater is enabled.
+	#[clap(short, long, action = ArgAction::Append, num_args(0..))]
+	pub bundles: Option<Vec<String>>,
+	/// JSON string or path to JSON file to merge with .millenniumrc
+	#[clap(short, long)]
+	pub config: Option<String>
+}
+
+pub fn command(options: Options) -> Result<()> {
+	// resolve the binary before moving to the project directory
+	let binary = options
+		.binary
+		.canonicalize()
+		.with_context(|| format!("failed to find the application binary `{}`", options.binary.display()))?;
+	let (merge_config, merge_config_path) = read_merge_config(options.config.as_deref())?;
+
+	set_current_dir(millennium_dir()).with_context(|| "failed to change current working directory")?;
+
+	let config = get_config(merge_config.as_deref())?;
+	let config_guard = config.lock().unwrap();
+	let config_ = config_guard.as_ref().unwrap();
+
+	check_bundle_identifier(config_, merge_config_path);
+
+	let mut features = options.features.unwrap_or_default();
+	features.extend(config_.build.features.clone().unwrap_or_default());
+	let interface_options = InterfaceOptions {
+		runner: None,
+		debug: options.debug,
+		target: options.target.clone(),
+		features: Some(features),
+		args: Vec::new(),
+		config: merge_config,
+		no_watch: true
+	};
+
+	let interface = AppInterface::new(config_, options.target.clone())?;
+	let app_settings = interface.app_settings();
+
+	// the bundler looks the main binary up by the product name, which `millennium build` renames it to
+	let target = match &options.target {
+		Some(target) => target.clone(),
+		None => millennium_utils::platform::target_triple()?
+	};
+	let main_binary = app_settings
+		.get_binaries(config_, &target)?
+		.into_iter()
+		.find(|binary| binary.main())
+		.with_context(|| "failed to find the main binary of the application")?;
+	let main_binary_path = place_main_binary(&binary, main_binary.name())?;
+	let out_dir = main_binary_path.parent().unwrap();
+
+	bundle(&interface_options, options.bundles.as_deref(), config_, app_settings, out_dir)
+}
+
+/// Copies `binary` to `name` in its directory, unless that's its name already.
+/// Returns the path of the main binary.
+fn place_main_binary(binary: &Path, name: &str) -> Result<PathBuf> {
+	let main_binary_path = binary.parent().unwrap().join(name);
+	if main_binary_path != binary {
+		info!(action = "Copying"; "{} to {}", binary.display(), main_binary_path.display());
+		fs::copy(binary, &main_binary_path).with_context(|| format!("failed to copy `{}` to `{}`", binary.display(), main_binary_path.display()))?;
+	}
+	Ok(main_binary_path)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use clap::Parser;
+
+	use super::{command, place_main_binary, Options};
+	use crate::build::read_merge_config;
+
+	#[test]
+	fn parses_options() {
+		let options = Options::try_parse_from(["bundle", "target/release/app", "--target", "x86_64-unknown-linux-gnu", "-b", "deb,updater", "-d"]).unwrap();
+		assert_eq!(options.binary, std::path::PathBuf::from("target/release/app"));
+		assert_eq!(options.target.as_deref(), Some("x86_64-unknown-linux-gnu"));
+		assert_eq!(options.bundles, Some(vec!["deb,updater".to_string()]));
+		assert!(options.debug);
+
+		assert!(Options::try_parse_from(["bundle"]).is_err());
+	}
+
+	#[test]
+	fn rejects_missing_binaries() {
+		let options = Options::try_parse_from(["bundle", "/nonexistent/millennium-app"]).unwrap();
+		let error = command(options).unwrap_err();
+		assert!(error.to_string().contains("/nonexistent/millennium-app"));
+	}
+
+	#[test]
+	fn places_the_main_binary() {
+		let dir = std::env::temp_dir().join(format!("millennium-bundle-{}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		let binary = dir.join("app-x86_64");
+		fs::write(&binary, b"binary").unwrap();
+
+		let main_binary = place_main_binary(&binary, "My App").unwrap();
+		assert_eq!(main_binary, dir.join("My App"));
+		assert_eq!(fs::read(&main_binary).unwrap(), b"binary");
+		assert!(binary.exists());
+
+		// a binary which already has the product name is left alone
+		assert_eq!(place_main_binary(&main_binary, "My App").unwrap(), main_binary);
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn reads_merge_configs() {
+		assert_eq!(read_merge_config(None).unwrap(), (None, None));
+		let json = r#"{ "package": { "version": "1.2.0" } }"#;
+		assert_eq!(read_merge_config(Some(json)).unwrap(), (Some(json.to_string()), None));
+
+		let path = std::env::temp_dir().join(format!("millennium-bundle-config-{}.json", std::process::id()));
+		fs::write(&path, json).unwrap();
+		let path_str = path.to_str().unwrap();
+		assert_eq!(read_merge_config(Some(path_str)).unwrap(), (Some(json.to_string()), Some(path_str.to_string())));
+		fs::remove_file(&path).unwrap();
+
+		assert!(read_merge_config(Some("/nonexistent/millennium.json")).is_err());
+	}
+}
@@ -178,11 +178,12 @@ fn command_internal(mut options: Options) -> Result<()> {
 	let mut dev_path = config.lock().unwrap().as_ref().unwrap().build.dev_path.clone();
 	if !options.no_dev_server {
 		if let AppUrl::Url(WindowUrl::App(path)) = &dev_path {
-			use crate::helpers::web_dev_server::{start_dev_server, SERVER_URL};
+			use crate::helpers::web_dev_server::start_dev_server;
 			if path.exists() {
 				let path = path.canonicalize()?;
-				start_dev_server(path);
-				dev_path = AppUrl::Url(WindowUrl::External(SERVER_URL.parse().unwrap()));
+				let dev_server_config = config.lock().unwrap().as_ref().unwrap().build.dev_server.clone();
+				let server_url = start_dev_server(path, &dev_server_config)?;
+				dev_path = AppUrl::Url(WindowUrl::External(server_url.clone()));
 
 				// TODO: in v2, use an env var to pass the url to the app context
 				// or better separate the config passed from the cli internally and
@@ -193,7 +194,7 @@ fn command_internal(mut options: Options) -> Result<()> {
 					c.build.dev_path = dev_path.clone();
 					options.config = Some(serde_json::to_string(&c).unwrap());
 				} else {
-					options.config = Some(format!(r#"{{ "build": {{ "devPath": "{SERVER_URL}" }} }}"#))
+					options.config = Some(format!(r#"{{ "build": {{ "devPath": "{server_url}" }} }}"#))
 				}
 			}
 		}
@@ -248,6 +249,11 @@ fn command_internal(mut options: Options) -> Result<()> {
 		}
 	}
 
+	// the app writes its TypeScript bindings here when it starts; see `Builder::typescript_bindings`
+	if let Some(bindings) = &config.lock().unwrap().as_ref().unwrap().build.bindings {
+		std::env::set_var("MILLENNIUM_BINDINGS_PATH", millennium_path.join(bindings));
+	}
+
 	let exit_on_panic = options.exit_on_panic;
 	let no_watch = options.no_watch;
 	interface.dev(options.into(), move |status, reason| on_dev_exit(status, reason, exit_on_panic, no_watch))
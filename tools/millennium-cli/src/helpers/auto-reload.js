@@ -16,8 +16,22 @@
 	const ws = new WebSocket(url);
 	ws.addEventListener('message', ({ data }) => {
 		const msg = JSON.parse(data);
-		if (msg.reload)
+		if (msg.reload) {
 			window.location.reload();
+		} else if (msg.css) {
+			// swap changed stylesheets in place, anything we can't find (e.g. an `@import`ed file) needs a full reload
+			let swapped = false;
+			for (const link of document.querySelectorAll('link[rel="stylesheet"]')) {
+				const href = new URL(link.href);
+				if (href.host === window.location.host && msg.css.includes(href.pathname)) {
+					href.searchParams.set('t', Date.now());
+					link.href = href.href;
+					swapped = true;
+				}
+			}
+			if (!swapped)
+				window.location.reload();
+		}
 	});
 	ws.addEventListener('close', onClose);
 })();
@@ -16,15 +16,26 @@
 
 use std::{
 	fs::{self, File, OpenOptions},
-	io::{BufReader, BufWriter, Write},
+	io::{self, BufReader, BufWriter, Read, Write},
+	net::TcpListener,
 	path::{Path, PathBuf},
-	str,
+	process::{Command, Stdio},
+	str::{self, FromStr},
+	sync::Arc,
 	time::{SystemTime, UNIX_EPOCH}
 };
 
 use anyhow::Context;
+use axum::{
+	body::Bytes,
+	http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+	response::{IntoResponse, Response},
+	routing::post,
+	Router, Server
+};
 use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
-use minisign::{sign, KeyPair as KP, SecretKey, SecretKeyBox, SignatureBox};
+use blake2::{Blake2b512, Digest};
+use minisign::{sign, KeyPair as KP, PublicKey, PublicKeyBox, SecretKey, SecretKeyBox, SignatureBox};
 
 /// A key pair (`PublicKey` and `SecretKey`).
 #[derive(Clone, Debug)]
@@ -104,8 +115,195 @@ where
 	Ok(fs::read_to_string(sk_path)?)
 }
 
+/// Where the updater signatures come from.
+pub enum SigningBackend {
+	/// A minisign secret key, loaded in memory.
+	SecretKey(SecretKey),
+	/// An Ed25519 key held outside of the build machine. The public key is
+	/// needed to fill in the key id of the signature.
+	External { signer: ExternalSigner, public_key: PublicKey }
+}
+
+/// Signs data with an Ed25519 key without exposing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalSigner {
+	/// A command, given as `command:<command>`, that reads the data to sign
+	/// from stdin and writes the signature to stdout.
+	Command(String),
+	/// An HTTP(S) service which answers `POST` requests with the signature of
+	/// the body, authenticated with a bearer token.
+	Http { url: String, token: Option<String> }
+}
+
+/// The environment variable holding the bearer token shared by
+/// `millennium signer serve` and the build machines signing through it.
+pub const SIGNER_TOKEN_ENV: &str = "MILLENNIUM_SIGNER_TOKEN";
+
+impl FromStr for ExternalSigner {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> crate::Result<Self> {
+		if let Some(command) = s.strip_prefix("command:") {
+			Ok(Self::Command(command.to_string()))
+		} else if s.starts_with("http://") || s.starts_with("https://") {
+			Ok(Self::Http {
+				url: s.to_string(),
+				token: std::env::var(SIGNER_TOKEN_ENV).ok()
+			})
+		} else {
+			Err(anyhow::anyhow!("unknown signer `{s}`, expected `command:<command>` or an HTTP(S) URL"))
+		}
+	}
+}
+
+impl ExternalSigner {
+	/// Returns the Ed25519 signature of `data`.
+	fn sign(&self, data: &[u8]) -> crate::Result<[u8; 64]> {
+		let signature = match self {
+			Self::Command(command) => {
+				#[cfg(windows)]
+				let mut cmd = Command::new("cmd");
+				#[cfg(windows)]
+				cmd.arg("/S").arg("/C").arg(command);
+				#[cfg(not(windows))]
+				let mut cmd = Command::new("sh");
+				#[cfg(not(windows))]
+				cmd.arg("-c").arg(command);
+
+				let mut child = cmd
+					.stdin(Stdio::piped())
+					.stdout(Stdio::piped())
+					.spawn()
+					.with_context(|| format!("failed to run signing command `{command}`"))?;
+				child.stdin.take().unwrap().write_all(data)?;
+				let output = child.wait_with_output()?;
+				if !output.status.success() {
+					return Err(anyhow::anyhow!("signing command `{command}` failed with {}", output.status));
+				}
+				output.stdout
+			}
+			Self::Http { url, token } => {
+				let mut request = ureq::post(url).set("Content-Type", "application/octet-stream");
+				if let Some(token) = token {
+					request = request.set("Authorization", &format!("Bearer {token}"));
+				}
+				let response = request
+					.send_bytes(data)
+					.with_context(|| format!("failed to reach the signing service at {url}"))?;
+				let mut body = Vec::new();
+				response.into_reader().read_to_end(&mut body)?;
+				body
+			}
+		};
+		decode_signature(&signature)
+	}
+}
+
+/// Serves `signer` on `listener` as a signing service for
+/// [`ExternalSigner::Http`]: `POST` requests to `/` are answered with the base64
+/// encoded signature of their body. This lets build machines sign with a key
+/// which stays on the signing host.
+///
+/// Requests must carry `token` as a bearer token. Without a token, only
+/// loopback addresses can be served, since anyone reaching the service could
+/// get arbitrary data signed.
+pub fn serve_signer(signer: ExternalSigner, listener: TcpListener, token: Option<String>) -> crate::Result<()> {
+	let address = listener.local_addr()?;
+	if token.is_none() && !address.ip().is_loopback() {
+		return Err(anyhow::anyhow!(
+			"refusing to serve the signer on {address} without authentication, set `{SIGNER_TOKEN_ENV}` or listen on a loopback address"
+		));
+	}
+
+	let signer = Arc::new(signer);
+	let token = Arc::new(token);
+	let router = Router::new().route(
+		"/",
+		post(move |headers: HeaderMap, body: Bytes| {
+			let (signer, token) = (signer.clone(), token.clone());
+			async move {
+				if !is_authorized(&headers, token.as_deref()) {
+					return (StatusCode::UNAUTHORIZED, "missing or invalid bearer token").into_response();
+				}
+				sign_request(signer, body).await
+			}
+		})
+	);
+	listener.set_nonblocking(true)?;
+	let runtime = tokio::runtime::Builder::new_current_thread().enable_io().build()?;
+	let _guard = runtime.enter();
+	let server = Server::from_tcp(listener)?;
+	runtime.block_on(server.serve(router.into_make_service()))?;
+	Ok(())
+}
+
+/// Whether the request carries the expected bearer token, if any.
+fn is_authorized(headers: &HeaderMap, token: Option<&str>) -> bool {
+	let token = match token {
+		Some(token) => token,
+		None => return true
+	};
+	let provided = headers
+		.get(AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Bearer "))
+		.unwrap_or_default();
+	// compare in constant time so the token can't be guessed byte by byte
+	provided.len() == token.len() && provided.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+async fn sign_request(signer: Arc<ExternalSigner>, body: Bytes) -> Response {
+	match tokio::task::spawn_blocking(move || signer.sign(&body)).await {
+		Ok(Ok(signature)) => BASE64_STANDARD.encode(signature).into_response(),
+		Ok(Err(e)) => {
+			log::error!("Failed to sign a request: {:#}", e);
+			(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+		}
+		Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+	}
+}
+
+/// Accepts a raw 64 byte Ed25519 signature or its base64 encoding.
+fn decode_signature(signature: &[u8]) -> crate::Result<[u8; 64]> {
+	let signature = if signature.len() == 64 {
+		signature.to_vec()
+	} else {
+		BASE64_STANDARD
+			.decode(str::from_utf8(signature)?.trim())
+			.with_context(|| "the signer returned an invalid signature")?
+	};
+	signature
+		.try_into()
+		.map_err(|s: Vec<u8>| anyhow::anyhow!("expected a 64 byte Ed25519 signature, got {} bytes", s.len()))
+}
+
+/// Builds a prehashed minisign signature, signing with `signer`.
+fn sign_external(signer: &ExternalSigner, public_key: &PublicKey, data_path: &Path, trusted_comment: &str) -> crate::Result<SignatureBox> {
+	let mut hasher = Blake2b512::new();
+	io::copy(&mut open_data_file(data_path)?, &mut hasher)?;
+	let signature = signer.sign(&hasher.finalize())?;
+
+	// the global signature covers the trusted comment too
+	let mut global_data = signature.to_vec();
+	global_data.extend_from_slice(trusted_comment.as_bytes());
+	let global_signature = signer.sign(&global_data)?;
+
+	let mut signature_bytes = b"ED".to_vec();
+	signature_bytes.extend_from_slice(public_key.keynum());
+	signature_bytes.extend_from_slice(&signature);
+	let signature_box = format!(
+		"untrusted comment: signature from Millennium secret key\n{}\ntrusted comment: {trusted_comment}\n{}\n",
+		BASE64_STANDARD.encode(signature_bytes),
+		BASE64_STANDARD.encode(global_signature)
+	);
+	let signature_box = SignatureBox::from_string(&signature_box)?;
+	minisign::verify(public_key, &signature_box, open_data_file(data_path)?, true, false, false)
+		.with_context(|| "the external signer's key does not match the updater public key")?;
+	Ok(signature_box)
+}
+
 /// Sign files
-pub fn sign_file<P>(secret_key: &SecretKey, bin_path: P) -> crate::Result<(PathBuf, SignatureBox)>
+pub fn sign_file<P>(backend: &SigningBackend, bin_path: P) -> crate::Result<(PathBuf, SignatureBox)>
 where
 	P: AsRef<Path>
 {
@@ -116,14 +314,17 @@ where
 	extension.push(".sig");
 	let signature_path = bin_path.with_extension(extension);
 
-	let mut signature_box_writer = create_file(&signature_path)?;
-
 	let trusted_comment = format!("timestamp:{}\tfile:{}", unix_timestamp(), bin_path.file_name().unwrap().to_string_lossy());
 
-	let data_reader = open_data_file(bin_path)?;
-
-	let signature_box = sign(None, secret_key, data_reader, Some(trusted_comment.as_str()), Some("signature from Millennium secret key"))?;
+	let signature_box = match backend {
+		SigningBackend::SecretKey(secret_key) => {
+			let data_reader = open_data_file(bin_path)?;
+			sign(None, secret_key, data_reader, Some(trusted_comment.as_str()), Some("signature from Millennium secret key"))?
+		}
+		SigningBackend::External { signer, public_key } => sign_external(signer, public_key, bin_path, &trusted_comment)?
+	};
 
+	let mut signature_box_writer = create_file(&signature_path)?;
 	let encoded_signature = BASE64_STANDARD.encode(signature_box.to_string());
 	signature_box_writer.write_all(encoded_signature.as_bytes())?;
 	signature_box_writer.flush()?;
@@ -140,6 +341,13 @@ pub fn secret_key(private_key: String, password: Option<String>) -> crate::Resul
 	Ok(sk)
 }
 
+/// Gets the updater public key from its base64 encoding.
+pub fn public_key(public_key: &str) -> crate::Result<PublicKey> {
+	let decoded = decode_key(public_key.to_string())?;
+	let pk_box = PublicKeyBox::from_string(&decoded).with_context(|| "failed to load updater public key")?;
+	Ok(pk_box.into_public_key()?)
+}
+
 fn unix_timestamp() -> u64 {
 	let start = SystemTime::now();
 	let since_the_epoch = start.duration_since(UNIX_EPOCH).expect("system clock is incorrect");
@@ -157,3 +365,160 @@ where
 		.map_err(|e| minisign::PError::new(minisign::ErrorKind::Io, e))?;
 	Ok(BufReader::new(file))
 }
+
+#[cfg(test)]
+mod tests {
+	use std::path::PathBuf;
+	#[cfg(not(windows))]
+	use std::{fs, net::TcpListener, path::Path, process::Command};
+
+	use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
+	#[cfg(not(windows))]
+	use minisign::{PublicKey, SignatureBox};
+
+	use super::{decode_signature, ExternalSigner, SIGNER_TOKEN_ENV};
+	#[cfg(not(windows))]
+	use super::{public_key, serve_signer, sign_file, SigningBackend};
+
+	#[cfg(not(windows))]
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("millennium-signer-{}-{name}", std::process::id()));
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	/// A new Ed25519 key in `dir` signing through `openssl`, or `None` if it
+	/// isn't installed.
+	#[cfg(not(windows))]
+	fn openssl_signer(dir: &Path, name: &str) -> Option<(ExternalSigner, PublicKey)> {
+		let key = dir.join(format!("{name}.pem"));
+		let status = Command::new("openssl")
+			.args(["genpkey", "-algorithm", "ed25519", "-out"])
+			.arg(&key)
+			.status()
+			.ok()?;
+		assert!(status.success());
+		let der = Command::new("openssl")
+			.args(["pkey", "-pubout", "-outform", "DER", "-in"])
+			.arg(&key)
+			.output()
+			.unwrap()
+			.stdout;
+
+		// the raw key ends the DER encoding, and any key id will do
+		let mut key_bytes = b"Ed".to_vec();
+		key_bytes.extend_from_slice(&[7; 8]);
+		key_bytes.extend_from_slice(&der[der.len() - 32..]);
+		let key_box = format!("untrusted comment: test key\n{}\n", BASE64_STANDARD.encode(key_bytes));
+		let public_key = public_key(&BASE64_STANDARD.encode(key_box)).unwrap();
+
+		// `pkeyutl` can't sign Ed25519 data from a pipe
+		let command = format!("f=$(mktemp) && cat > \"$f\" && openssl pkeyutl -sign -rawin -inkey '{}' -in \"$f\"; s=$?; rm -f \"$f\"; exit $s", key.display());
+		Some((ExternalSigner::Command(command), public_key))
+	}
+
+	#[cfg(not(windows))]
+	fn assert_signed(public_key: &PublicKey, path: &Path, signature_path: &Path) {
+		let signature = BASE64_STANDARD.decode(fs::read(signature_path).unwrap()).unwrap();
+		let signature = SignatureBox::from_string(std::str::from_utf8(&signature).unwrap()).unwrap();
+		minisign::verify(public_key, &signature, fs::File::open(path).unwrap(), true, false, false).unwrap();
+	}
+
+	#[test]
+	fn parses_signers() {
+		assert_eq!("command:sign --raw".parse::<ExternalSigner>().unwrap(), ExternalSigner::Command("sign --raw".into()));
+		assert_eq!(
+			"https://signer.example.com/sign".parse::<ExternalSigner>().unwrap(),
+			ExternalSigner::Http {
+				url: "https://signer.example.com/sign".into(),
+				token: std::env::var(SIGNER_TOKEN_ENV).ok()
+			}
+		);
+		assert!("/path/to/key".parse::<ExternalSigner>().is_err());
+	}
+
+	#[test]
+	fn decodes_signatures() {
+		let signature = [3; 64];
+		assert_eq!(decode_signature(&signature).unwrap(), signature);
+		let encoded = format!("{}\n", BASE64_STANDARD.encode(signature));
+		assert_eq!(decode_signature(encoded.as_bytes()).unwrap(), signature);
+		assert!(decode_signature(BASE64_STANDARD.encode([3; 32]).as_bytes()).is_err());
+		assert!(decode_signature(b"not a signature").is_err());
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn signs_with_commands() {
+		let dir = temp_dir("command");
+		let (signer, public_key) = match openssl_signer(&dir, "key") {
+			Some(signer) => signer,
+			None => return
+		};
+		let path = dir.join("app.tar.gz");
+		fs::write(&path, b"update").unwrap();
+
+		let backend = SigningBackend::External {
+			signer,
+			public_key: public_key.clone()
+		};
+		let (signature_path, _) = sign_file(&backend, &path).unwrap();
+		assert_signed(&public_key, &path, &signature_path);
+
+		// signatures by another key are rejected
+		let (other_signer, _) = openssl_signer(&dir, "other").unwrap();
+		let backend = SigningBackend::External { signer: other_signer, public_key };
+		assert!(sign_file(&backend, &path).is_err());
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn signs_with_signing_services() {
+		let dir = temp_dir("http");
+		let (signer, public_key) = match openssl_signer(&dir, "key") {
+			Some(signer) => signer,
+			None => return
+		};
+		let path = dir.join("app.tar.gz");
+		fs::write(&path, b"update").unwrap();
+
+		let token = Some("secret".to_string());
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/", listener.local_addr().unwrap());
+		let served_token = token.clone();
+		std::thread::spawn(move || serve_signer(signer, listener, served_token));
+		let backend = SigningBackend::External {
+			signer: ExternalSigner::Http { url: url.clone(), token },
+			public_key: public_key.clone()
+		};
+		let (signature_path, _) = sign_file(&backend, &path).unwrap();
+		assert_signed(&public_key, &path, &signature_path);
+
+		// requests without the right token are rejected
+		for token in [None, Some("wrong".to_string())] {
+			let backend = SigningBackend::External {
+				signer: ExternalSigner::Http { url: url.clone(), token },
+				public_key: public_key.clone()
+			};
+			assert!(sign_file(&backend, &path).is_err());
+		}
+
+		// failures of the served signer are reported
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}/", listener.local_addr().unwrap());
+		std::thread::spawn(move || serve_signer(ExternalSigner::Command("exit 1".into()), listener, None));
+		let backend = SigningBackend::External {
+			signer: ExternalSigner::Http { url, token: None },
+			public_key
+		};
+		assert!(sign_file(&backend, &path).is_err());
+
+		// only loopback addresses can be served without a token
+		let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+		assert!(serve_signer(ExternalSigner::Command("exit 1".into()), listener, None).is_err());
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
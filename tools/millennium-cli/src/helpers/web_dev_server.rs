@@ -1,50 +1,87 @@
 use std::{
-	net::SocketAddr,
-	path::{Path, PathBuf},
-	str::FromStr,
+	io::Read,
+	net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, ToSocketAddrs},
+	path::{Component, Path, PathBuf},
 	sync::{mpsc::sync_channel, Arc},
 	thread,
 	time::Duration
 };
 
+use anyhow::Context;
 use axum::{
+	body::Body,
 	extract::{ws::WebSocket, WebSocketUpgrade},
-	http::{header::CONTENT_TYPE, Request, StatusCode},
-	response::IntoResponse,
-	routing::get,
+	http::{
+		header::{ACCEPT, CONNECTION, CONTENT_LENGTH, CONTENT_TYPE, HOST, TRANSFER_ENCODING},
+		Request, StatusCode
+	},
+	response::{IntoResponse, Response},
+	routing::{any, get},
 	Router, Server
 };
 use html5ever::{namespace_url, ns, LocalName, QualName};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use kuchiki::{traits::TendrilSink, NodeRef};
-use millennium_utils::mime_type::MimeType;
+use millennium_utils::{
+	config::{DevServerConfig, DevServerProxy},
+	mime_type::MimeType
+};
 use notify::RecursiveMode;
 use notify_debouncer_mini::new_debouncer;
 use tokio::sync::broadcast::{channel, Sender};
+use url::Url;
 
 const AUTO_RELOAD_SCRIPT: &str = include_str!("./auto-reload.js");
-pub const SERVER_URL: &str = "http://127.0.0.1:40874";
 
 struct State {
 	serve_dir: PathBuf,
-	tx: Sender<()>
+	config: DevServerConfig,
+	tx: Sender<String>
 }
 
-pub fn start_dev_server<P: AsRef<Path>>(path: P) {
+/// Starts serving `path` with the given configuration.
+/// Returns the URL the server can be reached at.
+pub fn start_dev_server<P: AsRef<Path>>(path: P, config: &DevServerConfig) -> crate::Result<Url> {
 	let serve_dir = path.as_ref().to_path_buf();
+	let addr = (config.host.as_str(), config.port)
+		.to_socket_addrs()
+		.with_context(|| format!("Invalid dev server address {}:{}", config.host, config.port))?
+		.next()
+		.with_context(|| format!("Dev server host {} did not resolve to any address", config.host))?;
+	// bind before returning, so an address already in use is reported
+	let listener = TcpListener::bind(addr).with_context(|| format!("Failed to bind the dev server to {addr}"))?;
+	listener.set_nonblocking(true)?;
+	let addr = listener.local_addr()?;
+	let url_addr = match addr.ip() {
+		IpAddr::V4(ip) if ip.is_unspecified() => SocketAddr::new(Ipv4Addr::LOCALHOST.into(), addr.port()),
+		IpAddr::V6(ip) if ip.is_unspecified() => SocketAddr::new(Ipv6Addr::LOCALHOST.into(), addr.port()),
+		_ => addr
+	};
+	let url = Url::parse(&format!("http://{url_addr}"))?;
+
+	let mut ignore = GitignoreBuilder::new(&serve_dir);
+	for pattern in &config.ignore {
+		ignore
+			.add_line(None, pattern)
+			.with_context(|| format!("Invalid dev server ignore pattern `{pattern}`"))?;
+	}
+	let ignore = ignore.build()?;
 
+	let config = config.clone();
 	std::thread::spawn(move || {
 		tokio::runtime::Builder::new_current_thread()
 			.enable_io()
 			.build()
 			.unwrap()
 			.block_on(async move {
-				let (tx, _) = channel(1);
+				let (tx, _) = channel(16);
 
 				let tokio_tx = tx.clone();
 				let serve_dir_ = serve_dir.clone();
+				let debounce = Duration::from_millis(config.debounce);
 				thread::spawn(move || {
 					let (tx, rx) = sync_channel(1);
-					let mut watcher = new_debouncer(Duration::from_secs(1), None, move |r| {
+					let mut watcher = new_debouncer(debounce, None, move |r| {
 						if let Ok(events) = r {
 							tx.send(events).unwrap()
 						}
@@ -54,18 +91,21 @@ pub fn start_dev_server<P: AsRef<Path>>(path: P) {
 					watcher.watcher().watch(&serve_dir_, RecursiveMode::Recursive).unwrap();
 
 					loop {
-						if rx.recv().is_ok() {
-							let _ = tokio_tx.send(());
+						if let Ok(events) = rx.recv() {
+							let paths: Vec<PathBuf> = events.into_iter().map(|event| event.path).collect();
+							if let Some(message) = reload_message(&serve_dir_, &ignore, &paths) {
+								let _ = tokio_tx.send(message);
+							}
 						}
 					}
 				});
 
-				let state = Arc::new(State { serve_dir, tx });
+				let state = Arc::new(State { serve_dir, config, tx });
 				let router = Router::new()
 					.fallback(
 						Router::new().nest(
 							"/",
-							get({
+							any({
 								let state = state.clone();
 								move |req| handler(req, state)
 							})
@@ -76,25 +116,101 @@ pub fn start_dev_server<P: AsRef<Path>>(path: P) {
 						"/__millennium-live/ws",
 						get(move |ws: WebSocketUpgrade| async move { ws.on_upgrade(|socket| async move { ws_handler(socket, state).await }) })
 					);
-				Server::bind(&SocketAddr::from_str(SERVER_URL.split('/').nth(2).unwrap()).unwrap())
-					.serve(router.into_make_service())
-					.await
-					.unwrap();
+				let server = match Server::from_tcp(listener) {
+					Ok(server) => server,
+					Err(e) => {
+						log::error!("Failed to start the dev server: {}", e);
+						return;
+					}
+				};
+				if let Err(e) = server.serve(router.into_make_service()).await {
+					log::error!("The dev server stopped: {}", e);
+				}
 			})
 	});
+
+	Ok(url)
+}
+
+/// Builds the message sent to the pages after `paths` changed: stylesheets
+/// are swapped in place, anything else reloads the page.
+fn reload_message(serve_dir: &Path, ignore: &Gitignore, paths: &[PathBuf]) -> Option<String> {
+	let paths: Vec<&PathBuf> = paths
+		.iter()
+		.filter(|path| !ignore.matched_path_or_any_parents(path, path.is_dir()).is_ignore())
+		.collect();
+	if paths.is_empty() {
+		return None;
+	}
+
+	let stylesheets: Option<Vec<String>> = paths
+		.iter()
+		.map(|path| {
+			let relative = path.strip_prefix(serve_dir).ok()?;
+			if relative.extension().map_or(false, |ext| ext == "css") {
+				Some(format!("/{}", relative.to_string_lossy().replace('\\', "/")))
+			} else {
+				None
+			}
+		})
+		.collect();
+	Some(match stylesheets {
+		Some(css) => serde_json::json!({ "css": css }).to_string(),
+		None => serde_json::json!({ "reload": true }).to_string()
+	})
+}
+
+/// Whether a request path is handled by a proxy rule, i.e. it is the rule path or below it.
+fn matches_proxy_rule(path: &str, rule: &DevServerProxy) -> bool {
+	let prefix = rule.path.trim_end_matches('/');
+	path.strip_prefix(prefix).map_or(false, |rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Converts a request path to a path relative to the served directory, or `None` if it would escape it.
+fn relative_path(path: &str) -> Option<PathBuf> {
+	let mut relative = PathBuf::new();
+	for component in Path::new(path.trim_start_matches('/')).components() {
+		match component {
+			Component::Normal(component) => relative.push(component),
+			Component::CurDir => {}
+			Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None
+		}
+	}
+	Some(relative)
 }
 
-async fn handler<T>(req: Request<T>, state: Arc<State>) -> impl IntoResponse {
-	let uri = req.uri().to_string();
-	let uri = if uri == "/" { &uri } else { uri.strip_prefix('/').unwrap_or(&uri) };
+async fn handler(req: Request<Body>, state: Arc<State>) -> Response {
+	if let Some(rule) = state.config.proxy.iter().find(|rule| matches_proxy_rule(req.uri().path(), rule)) {
+		return proxy(req, rule).await;
+	}
+
+	let relative = match relative_path(req.uri().path()) {
+		Some(relative) => relative,
+		None => return (StatusCode::FORBIDDEN, [(CONTENT_TYPE, "text/plain".to_string())], vec![]).into_response()
+	};
+	// only page navigations fall back to the SPA entry point, missing assets are 404s
+	let accepts_html = req
+		.headers()
+		.get(ACCEPT)
+		.and_then(|accept| accept.to_str().ok())
+		.map_or(false, |accept| accept.contains("text/html"));
+	let has_extension = relative.extension().is_some();
+	let spa_fallback = state.config.spa_fallback && (accepts_html || !has_extension);
 
-	let file = std::fs::read(state.serve_dir.join(uri))
-		.or_else(|_| std::fs::read(state.serve_dir.join(format!("{}.html", &uri))))
-		.or_else(|_| std::fs::read(state.serve_dir.join(format!("{}/index.html", &uri))))
-		.or_else(|_| std::fs::read(state.serve_dir.join("index.html")));
+	let mut candidates = vec![relative.clone()];
+	if relative.file_name().is_some() {
+		candidates.push(PathBuf::from(format!("{}.html", relative.display())));
+	}
+	candidates.push(relative.join("index.html"));
+	if spa_fallback {
+		candidates.push(PathBuf::from("index.html"));
+	}
+	let file = candidates
+		.into_iter()
+		.find_map(|candidate| std::fs::read(state.serve_dir.join(&candidate)).ok().map(|file| (file, candidate)));
 
-	file.map(|mut f| {
-		let mime_type = MimeType::parse(&f, uri);
+	file.map(|(mut f, uri)| {
+		let mime_type = MimeType::parse(&f, &uri.to_string_lossy());
 		if mime_type == MimeType::Html.to_string() {
 			let mut document = kuchiki::parse_html().one(String::from_utf8_lossy(&f).into_owned());
 			fn with_html_head<F: FnOnce(&NodeRef)>(document: &mut NodeRef, f: F) {
@@ -116,20 +232,136 @@ async fn handler<T>(req: Request<T>, state: Arc<State>) -> impl IntoResponse {
 			f = document.to_string().as_bytes().to_vec();
 		}
 
-		(StatusCode::OK, [(CONTENT_TYPE, mime_type)], f)
+		(StatusCode::OK, [(CONTENT_TYPE, mime_type)], f).into_response()
 	})
-	.unwrap_or_else(|_| (StatusCode::NOT_FOUND, [(CONTENT_TYPE, "text/plain".into())], vec![]))
+	.unwrap_or_else(|| (StatusCode::NOT_FOUND, [(CONTENT_TYPE, "text/plain".to_string())], vec![]).into_response())
+}
+
+/// Forwards the request to the target of `rule`.
+async fn proxy(req: Request<Body>, rule: &DevServerProxy) -> Response {
+	let path_and_query = req.uri().path_and_query().map_or("/", |p| p.as_str());
+	let path = match &rule.rewrite {
+		Some(rewrite) => format!("{rewrite}{}", &path_and_query[rule.path.trim_end_matches('/').len()..]),
+		None => path_and_query.to_string()
+	};
+	let url = format!("{}/{}", rule.target.as_str().trim_end_matches('/'), path.trim_start_matches('/'));
+	let method = req.method().to_string();
+	let headers: Vec<(String, String)> = req
+		.headers()
+		.iter()
+		.filter(|(name, _)| *name != HOST && *name != CONNECTION && *name != CONTENT_LENGTH)
+		.filter_map(|(name, value)| value.to_str().ok().map(|value| (name.to_string(), value.to_string())))
+		.collect();
+	let body = match hyper::body::to_bytes(req.into_body()).await {
+		Ok(body) => body,
+		Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response()
+	};
+
+	let response = tokio::task::spawn_blocking(move || {
+		let agent = ureq::AgentBuilder::new().redirects(0).build();
+		let mut request = agent.request(&method, &url);
+		for (name, value) in &headers {
+			request = request.set(name, value);
+		}
+		let response = match request.send_bytes(&body) {
+			Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+			Err(e) => return Err(format!("Failed to proxy {url}: {e}"))
+		};
+
+		let status = response.status();
+		let headers: Vec<(String, String)> = response
+			.headers_names()
+			.into_iter()
+			.flat_map(|name| {
+				response
+					.all(&name)
+					.into_iter()
+					.map(|value| (name.clone(), value.to_string()))
+					.collect::<Vec<_>>()
+			})
+			.collect();
+		let mut body = Vec::new();
+		response.into_reader().read_to_end(&mut body).map_err(|e| e.to_string())?;
+		Ok((status, headers, body))
+	})
+	.await;
+
+	match response {
+		Ok(Ok((status, headers, body))) => {
+			let mut builder = Response::builder().status(status);
+			for (name, value) in headers {
+				// the body was already read in full
+				if !name.eq_ignore_ascii_case(TRANSFER_ENCODING.as_str()) && !name.eq_ignore_ascii_case(CONNECTION.as_str()) {
+					builder = builder.header(name, value);
+				}
+			}
+			builder
+				.body(axum::body::boxed(Body::from(body)))
+				.unwrap_or_else(|e| (StatusCode::BAD_GATEWAY, e.to_string()).into_response())
+		}
+		Ok(Err(e)) => {
+			log::error!("{}", e);
+			(StatusCode::BAD_GATEWAY, e).into_response()
+		}
+		Err(e) => (StatusCode::BAD_GATEWAY, e.to_string()).into_response()
+	}
 }
 
 async fn ws_handler(mut ws: WebSocket, state: Arc<State>) {
 	let mut rx = state.tx.subscribe();
-	while tokio::select! {
-		_ = ws.recv() => return,
-		fs_reload_event = rx.recv() => fs_reload_event.is_ok(),
-	} {
-		let ws_send = ws.send(axum::extract::ws::Message::Text(r#"{"reload":true}"#.to_owned()));
-		if ws_send.await.is_err() {
+	loop {
+		let message = tokio::select! {
+			_ = ws.recv() => return,
+			message = rx.recv() => match message {
+				Ok(message) => message,
+				Err(_) => break
+			},
+		};
+		if ws.send(axum::extract::ws::Message::Text(message)).await.is_err() {
 			break;
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{net::TcpListener, path::PathBuf};
+
+	use millennium_utils::config::{DevServerConfig, DevServerProxy};
+
+	use super::{matches_proxy_rule, relative_path, start_dev_server};
+
+	#[test]
+	fn reports_addresses_in_use() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let config = DevServerConfig {
+			port: listener.local_addr().unwrap().port(),
+			..Default::default()
+		};
+		assert!(start_dev_server(std::env::temp_dir(), &config).is_err());
+	}
+
+	#[test]
+	fn rejects_paths_escaping_the_served_directory() {
+		assert_eq!(relative_path("/"), Some(PathBuf::new()));
+		assert_eq!(relative_path("/assets/./app.js"), Some(PathBuf::from("assets/app.js")));
+		assert_eq!(relative_path("/../secret"), None);
+		assert_eq!(relative_path("/assets/../../secret"), None);
+	}
+
+	#[test]
+	fn matches_proxy_rules_on_segments() {
+		let rule = DevServerProxy {
+			path: "/api".into(),
+			target: "http://localhost:3000".parse().unwrap(),
+			rewrite: None
+		};
+		assert!(matches_proxy_rule("/api", &rule));
+		assert!(matches_proxy_rule("/api/users", &rule));
+		assert!(!matches_proxy_rule("/apiary", &rule));
+
+		let rule = DevServerProxy { path: "/api/".into(), ..rule };
+		assert!(matches_proxy_rule("/api/users", &rule));
+		assert!(!matches_proxy_rule("/apiary", &rule));
+	}
+}
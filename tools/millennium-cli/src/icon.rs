@@ -1,11 +1,11 @@
 use std::{
 	collections::HashMap,
-	fs::{create_dir_all, File},
+	fs::{self, create_dir_all, File},
 	io::{BufWriter, Write},
 	path::{Path, PathBuf}
 };
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use clap::Parser;
 use icns::{IconFamily, IconType};
 use image::{
@@ -13,9 +13,10 @@ use image::{
 		ico::{IcoEncoder, IcoFrame},
 		png::{CompressionType, FilterType as PngFilterType, PngEncoder}
 	},
-	imageops::FilterType,
-	open, ColorType, DynamicImage, ImageEncoder
+	imageops::{self, FilterType},
+	open, ColorType, DynamicImage, ImageEncoder, Rgba, RgbaImage
 };
+use resvg::{tiny_skia, usvg};
 use serde::Deserialize;
 
 use crate::{helpers::app_paths::millennium_dir, Result};
@@ -37,11 +38,13 @@ impl PngTarget {
 	}
 }
 
+/// The sizes of the Linux hicolor icon theme.
+const HICOLOR_SIZES: [u32; 9] = [16, 22, 24, 32, 48, 64, 128, 256, 512];
+
 #[derive(Debug, Parser)]
 #[clap(about = "Generates various icons for all major platforms")]
 pub struct Options {
-	// TODO: Confirm 1240px
-	/// Path to the source icon (png, 1240x1240px with transparency).
+	/// Path to the source icon: an SVG, or a PNG of at least 1024x1024px with transparency.
 	#[clap(default_value = "./app-icon.png")]
 	input: PathBuf,
 	/// Output directory.
@@ -50,27 +53,124 @@ pub struct Options {
 	output: Option<PathBuf>,
 	/// Custom PNG icon sizes to generate. When set, the default icons are not generated.
 	#[clap(short, long, use_value_delimiter = true)]
-	png: Option<Vec<u32>>
+	png: Option<Vec<u32>>,
+	/// Square images used as-is for their size instead of the source, e.g. a hand-tuned 16x16px PNG.
+	#[clap(long = "override", value_name = "IMAGE")]
+	overrides: Vec<PathBuf>,
+	/// Empty space around the icon, in percent of the icon size on each side.
+	#[clap(long, default_value_t = 0.0)]
+	padding: f32,
+	/// Background color filling the transparent and padded areas, as `#RRGGBB` or `#RRGGBBAA`.
+	#[clap(long, value_parser = parse_color)]
+	background: Option<Rgba<u8>>
 }
 
-pub fn command(options: Options) -> Result<()> {
-	let input = options.input;
-	let out_dir = options.output.unwrap_or_else(|| millennium_dir().join("icons"));
-	let png_icon_sizes = options.png.unwrap_or_default();
-	create_dir_all(&out_dir).context("Can't create output directory")?;
+fn parse_color(color: &str) -> std::result::Result<Rgba<u8>, String> {
+	let hex = color.strip_prefix('#').unwrap_or(color);
+	let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("invalid color `{color}`"));
+	match hex.len() {
+		6 if hex.is_ascii() => Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, 255])),
+		8 if hex.is_ascii() => Ok(Rgba([channel(0)?, channel(2)?, channel(4)?, channel(6)?])),
+		_ => Err(format!("invalid color `{color}`, expected `#RRGGBB` or `#RRGGBBAA`"))
+	}
+}
 
-	let source = open(input).context("Can't read and decode source image")?.into_rgba8();
+enum Image {
+	Raster(DynamicImage),
+	Svg(usvg::Tree)
+}
 
-	let source = DynamicImage::ImageRgba8(source);
+/// The icon source, rendered at every size.
+struct Source {
+	image: Image,
+	overrides: HashMap<u32, DynamicImage>,
+	padding: f32,
+	background: Option<Rgba<u8>>
+}
 
-	if source.height() != source.width() {
-		panic!("Source image must be square");
+impl Source {
+	fn load(options: &Options) -> Result<Self> {
+		let image = if options.input.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("svg")) {
+			let data = fs::read(&options.input).context("Can't read source image")?;
+			let svg_options = usvg::Options {
+				resources_dir: options.input.parent().map(Path::to_path_buf),
+				..Default::default()
+			};
+			Image::Svg(usvg::Tree::from_data(&data, &svg_options).context("Can't parse source SVG")?)
+		} else {
+			Image::Raster(open(&options.input).context("Can't read and decode source image")?)
+		};
+
+		let mut overrides = HashMap::new();
+		for path in &options.overrides {
+			let image = open(path).with_context(|| format!("Can't read and decode override {}", path.display()))?;
+			if image.width() != image.height() {
+				bail!("Override {} must be square", path.display());
+			}
+			overrides.insert(image.width(), image);
+		}
+
+		if !(0.0..50.0).contains(&options.padding) {
+			bail!("Padding must be between 0 and 50 percent");
+		}
+
+		Ok(Self {
+			image,
+			overrides,
+			padding: options.padding,
+			background: options.background
+		})
 	}
 
+	/// Renders the icon at `size`x`size` pixels. Non-square sources are
+	/// centered.
+	fn render(&self, size: u32) -> Result<DynamicImage> {
+		if let Some(image) = self.overrides.get(&size) {
+			return Ok(DynamicImage::ImageRgba8(image.to_rgba8()));
+		}
+
+		let padding = (size as f32 * self.padding / 100.0).round() as u32;
+		let inner = size.saturating_sub(padding * 2).max(1);
+		let mut canvas = RgbaImage::from_pixel(size, size, self.background.unwrap_or(Rgba([0, 0, 0, 0])));
+		match &self.image {
+			Image::Raster(source) => {
+				let image = source.resize(inner, inner, FilterType::Lanczos3);
+				let (x, y) = ((size - image.width()) / 2, (size - image.height()) / 2);
+				imageops::overlay(&mut canvas, &image.to_rgba8(), x as i64, y as i64);
+			}
+			Image::Svg(tree) => {
+				let (width, height) = (tree.size.width() as f32, tree.size.height() as f32);
+				let scale = inner as f32 / width.max(height);
+				let (x, y) = ((size as f32 - width * scale) / 2.0, (size as f32 - height * scale) / 2.0);
+				let mut pixmap = tiny_skia::Pixmap::new(size, size).context("Can't allocate the icon")?;
+				resvg::render(tree, usvg::FitTo::Original, tiny_skia::Transform::from_row(scale, 0.0, 0.0, scale, x, y), pixmap.as_mut())
+					.context("Can't render source SVG")?;
+				let rendered = RgbaImage::from_fn(size, size, |x, y| {
+					let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+					Rgba([pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()])
+				});
+				imageops::overlay(&mut canvas, &rendered, 0, 0);
+			}
+		}
+		Ok(DynamicImage::ImageRgba8(canvas))
+	}
+}
+
+pub fn command(options: Options) -> Result<()> {
+	let out_dir = options.output.clone().unwrap_or_else(|| millennium_dir().join("icons"));
+	create_dir_all(&out_dir).context("Can't create output directory")?;
+
+	let source = Source::load(&options)?;
+	let png_icon_sizes = options.png.unwrap_or_default();
+
 	if png_icon_sizes.is_empty() {
 		appx(&source, &out_dir).context("Failed to generate appx icons")?;
 		icns(&source, &out_dir).context("Failed to generate .icns file")?;
 		ico(&source, &out_dir).context("Failed to generate .ico file")?;
+		hicolor(&source, &options.input, &out_dir).context("Failed to generate the hicolor icon theme")?;
+		if options.output.is_some() {
+			log::info!("Add `{}` to `bundle.icon` to install the hicolor icons with the Linux bundles", out_dir.join("hicolor/*/apps/*").display());
+		}
 
 		let mut png_targets = vec![PngTarget::new(256, "128x128@2x.png"), PngTarget::new(512, "icon.png")];
 		png_targets.extend(
@@ -94,7 +194,7 @@ pub fn command(options: Options) -> Result<()> {
 	Ok(())
 }
 
-fn appx(source: &DynamicImage, out_dir: &Path) -> Result<()> {
+fn appx(source: &Source, out_dir: &Path) -> Result<()> {
 	log::info!(action = "Appx"; "Creating StoreLogo.png");
 	resize_and_save_png(source, 50, &out_dir.join("StoreLogo.png"))?;
 
@@ -109,7 +209,7 @@ fn appx(source: &DynamicImage, out_dir: &Path) -> Result<()> {
 }
 
 // Main target: macOS
-fn icns(source: &DynamicImage, out_dir: &Path) -> Result<()> {
+fn icns(source: &Source, out_dir: &Path) -> Result<()> {
 	log::info!(action = "ICNS"; "Creating icon.icns");
 	let entries: HashMap<String, IcnsEntry> = serde_json::from_slice(include_bytes!("helpers/icns.json")).unwrap();
 
@@ -119,7 +219,7 @@ fn icns(source: &DynamicImage, out_dir: &Path) -> Result<()> {
 		let size = entry.size;
 		let mut buf = Vec::new();
 
-		let image = source.resize_exact(size, size, FilterType::Lanczos3);
+		let image = source.render(size)?;
 
 		write_png(image.as_bytes(), &mut buf, size)?;
 
@@ -139,12 +239,12 @@ fn icns(source: &DynamicImage, out_dir: &Path) -> Result<()> {
 
 // Generate .ico file with layers for the most common sizes.
 // Main target: Windows
-fn ico(source: &DynamicImage, out_dir: &Path) -> Result<()> {
+fn ico(source: &Source, out_dir: &Path) -> Result<()> {
 	log::info!(action = "ICO"; "Creating icon.ico");
 	let mut frames = Vec::new();
 
 	for size in [32, 16, 24, 48, 64, 256] {
-		let image = source.resize_exact(size, size, FilterType::Lanczos3);
+		let image = source.render(size)?;
 
 		// Only the 256px layer can be compressed according to the ico specs.
 		if size == 256 {
@@ -168,7 +268,7 @@ fn ico(source: &DynamicImage, out_dir: &Path) -> Result<()> {
 
 // Generate .png files in 32x32, 128x128, 256x256, 512x512 (icon.png)
 // Main target: Linux
-fn png(source: &DynamicImage, out_dir: &Path, targets: Vec<PngTarget>) -> Result<()> {
+fn png(source: &Source, out_dir: &Path, targets: Vec<PngTarget>) -> Result<()> {
 	for target in targets {
 		log::info!(action = "PNG"; "Creating {}", target.file_name);
 		resize_and_save_png(source, target.size, &out_dir.join(&target.file_name))?;
@@ -177,9 +277,29 @@ fn png(source: &DynamicImage, out_dir: &Path, targets: Vec<PngTarget>) -> Result
 	Ok(())
 }
 
-// Resize image and save it to disk.
-fn resize_and_save_png(source: &DynamicImage, size: u32, file_path: &Path) -> Result<()> {
-	let image = source.resize_exact(size, size, FilterType::Lanczos3);
+// Generate the hicolor icon theme tree, with the source SVG as the scalable icon.
+// Main target: Linux bundles, which install `icons/hicolor` under the name of the main binary
+fn hicolor(source: &Source, input: &Path, out_dir: &Path) -> Result<()> {
+	let hicolor_dir = out_dir.join("hicolor");
+	for size in HICOLOR_SIZES {
+		let dir = hicolor_dir.join(format!("{size}x{size}/apps"));
+		create_dir_all(&dir)?;
+		log::info!(action = "hicolor"; "Creating {size}x{size}/apps/icon.png");
+		resize_and_save_png(source, size, &dir.join("icon.png"))?;
+	}
+	if matches!(source.image, Image::Svg(_)) {
+		let dir = hicolor_dir.join("scalable/apps");
+		create_dir_all(&dir)?;
+		log::info!(action = "hicolor"; "Creating scalable/apps/icon.svg");
+		fs::copy(input, dir.join("icon.svg"))?;
+	}
+
+	Ok(())
+}
+
+// Render image and save it to disk.
+fn resize_and_save_png(source: &Source, size: u32, file_path: &Path) -> Result<()> {
+	let image = source.render(size)?;
 
 	let mut out_file = BufWriter::new(File::create(file_path)?);
 
@@ -194,3 +314,103 @@ fn write_png<W: Write>(image_data: &[u8], w: W, size: u32) -> Result<()> {
 	encoder.write_image(image_data, size, size, ColorType::Rgba8)?;
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+	use resvg::usvg;
+
+	use super::{parse_color, Image, Source};
+
+	const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+	const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+	fn source(image: Image) -> Source {
+		Source {
+			image,
+			overrides: HashMap::new(),
+			padding: 0.0,
+			background: None
+		}
+	}
+
+	fn raster(width: u32, height: u32) -> Image {
+		Image::Raster(DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, RED)))
+	}
+
+	fn is_red(pixel: Rgba<u8>) -> bool {
+		pixel[0] > 250 && pixel[1] < 5 && pixel[2] < 5 && pixel[3] > 250
+	}
+
+	#[test]
+	fn parses_colors() {
+		assert_eq!(parse_color("#ff8000"), Ok(Rgba([255, 128, 0, 255])));
+		assert_eq!(parse_color("FF800080"), Ok(Rgba([255, 128, 0, 128])));
+		assert!(parse_color("#ff80").is_err());
+		assert!(parse_color("#ff8000801").is_err());
+		assert!(parse_color("#gg0000").is_err());
+		// multi-byte characters must not be sliced through
+		assert!(parse_color("#ffé00").is_err());
+	}
+
+	#[test]
+	fn centers_non_square_rasters() {
+		let icon = source(raster(40, 20)).render(8).unwrap();
+		assert_eq!(icon.dimensions(), (8, 8));
+		for x in 0..8 {
+			assert_eq!(icon.get_pixel(x, 1)[3], 0);
+			assert!(is_red(icon.get_pixel(x, 2)));
+			assert!(is_red(icon.get_pixel(x, 5)));
+			assert_eq!(icon.get_pixel(x, 6)[3], 0);
+		}
+
+		let icon = source(raster(20, 40)).render(8).unwrap();
+		for y in 0..8 {
+			assert_eq!(icon.get_pixel(1, y)[3], 0);
+			assert!(is_red(icon.get_pixel(2, y)));
+			assert!(is_red(icon.get_pixel(5, y)));
+			assert_eq!(icon.get_pixel(6, y)[3], 0);
+		}
+	}
+
+	#[test]
+	fn centers_non_square_svgs() {
+		let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><rect width="40" height="20" fill="#ff0000"/></svg>"##;
+		let tree = usvg::Tree::from_data(svg.as_bytes(), &usvg::Options::default()).unwrap();
+		let icon = source(Image::Svg(tree)).render(8).unwrap();
+		assert_eq!(icon.dimensions(), (8, 8));
+		for x in 0..8 {
+			assert_eq!(icon.get_pixel(x, 1)[3], 0);
+			assert!(is_red(icon.get_pixel(x, 2)));
+			assert!(is_red(icon.get_pixel(x, 5)));
+			assert_eq!(icon.get_pixel(x, 6)[3], 0);
+		}
+	}
+
+	#[test]
+	fn pads_and_fills_the_background() {
+		let padded = Source {
+			padding: 25.0,
+			background: Some(BLUE),
+			..source(raster(10, 10))
+		};
+		let icon = padded.render(8).unwrap();
+		assert_eq!(icon.get_pixel(0, 0), BLUE);
+		assert_eq!(icon.get_pixel(1, 6), BLUE);
+		assert!(is_red(icon.get_pixel(2, 2)));
+		assert!(is_red(icon.get_pixel(5, 5)));
+		assert_eq!(icon.get_pixel(6, 6), BLUE);
+	}
+
+	#[test]
+	fn uses_overrides() {
+		let mut overridden = source(raster(10, 10));
+		overridden
+			.overrides
+			.insert(4, DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, BLUE)));
+		assert_eq!(overridden.render(4).unwrap().get_pixel(0, 0), BLUE);
+		assert!(is_red(overridden.render(8).unwrap().get_pixel(0, 0)));
+	}
+}
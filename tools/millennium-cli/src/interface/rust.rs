@@ -39,7 +39,10 @@ use anyhow::Context;
 use heck::ToKebabCase;
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use log::{debug, error, info};
-use millennium_bundler::{AppCategory, BundleBinary, BundleSettings, DebianSettings, MacOsSettings, PackageSettings, UpdaterSettings, WindowsSettings};
+use millennium_bundler::{
+	AppCategory, AppImageSettings, AppStreamSettings, BundleBinary, BundleSettings, DebianSettings, FlatpakSettings, MacOsSettings, PackageSettings,
+	PacmanSettings, UpdaterSettings, WindowsSettings
+};
 use millennium_utils::config::parse::is_configuration_file;
 use notify::RecursiveMode;
 use notify_debouncer_mini::new_debouncer;
@@ -53,7 +56,7 @@ use self::{
 use super::{AppSettings, ExitReason, Interface};
 use crate::helpers::{
 	app_paths::{app_dir, millennium_dir},
-	config::{nsis_settings, reload as reload_config, wix_settings, Config}
+	config::{nsis_settings, reload as reload_config, wix_settings, Allowlist, AllowlistConfig, Config, FlatpakConfig}
 };
 
 #[derive(Debug, Clone)]
@@ -599,7 +602,8 @@ impl AppSettings for RustAppSettings {
 			features,
 			config.millennium.bundle.clone(),
 			config.millennium.system_tray.clone(),
-			config.millennium.updater.clone()
+			config.millennium.updater.clone(),
+			&config.millennium.allowlist
 		)
 	}
 
@@ -806,13 +810,72 @@ pub fn get_workspace_version() -> crate::Result<Option<String>> {
 		.and_then(|p| p.version))
 }
 
+/// Derives the Flatpak sandbox permissions from the APIs the app is allowed to
+/// use, followed by the ones set in the configuration.
+fn flatpak_finish_args(config: &FlatpakConfig, allowlist: &AllowlistConfig, system_tray: bool, updater_active: bool) -> Vec<String> {
+	let features = allowlist.to_features();
+	let all = allowlist.all || features.contains(&"api-all");
+	let enabled = |prefix: &str| all || features.iter().any(|f| f.starts_with(prefix));
+
+	let mut args = Vec::new();
+	if enabled("http-") || updater_active {
+		args.push("--share=network".to_string());
+	}
+	if enabled("fs-") {
+		for path in allowlist.fs.scope.allowed_paths() {
+			let path = path.to_string_lossy();
+			let (base, rest) = path.split_once('/').unwrap_or((&*path, ""));
+			let filesystem = match base {
+				"$HOME" => "home",
+				"$DOWNLOAD" => "xdg-download",
+				"$DOCUMENT" => "xdg-documents",
+				"$PICTURE" => "xdg-pictures",
+				"$VIDEO" => "xdg-videos",
+				"$AUDIO" => "xdg-music",
+				"$DESKTOP" => "xdg-desktop",
+				"" => {
+					// absolute paths are exposed as-is, up to the first glob
+					let path = path.split(['*', '?', '[']).next().unwrap_or_default().trim_end_matches('/');
+					if !path.is_empty() {
+						args.push(format!("--filesystem={path}"));
+					}
+					continue;
+				}
+				_ => continue
+			};
+			let rest = rest.split(['*', '?', '[']).next().unwrap_or_default().trim_end_matches('/');
+			if rest.is_empty() {
+				args.push(format!("--filesystem={filesystem}"));
+			} else {
+				args.push(format!("--filesystem={filesystem}/{rest}"));
+			}
+		}
+	}
+	if enabled("notification-") {
+		args.push("--talk-name=org.freedesktop.Notifications".to_string());
+	}
+	if enabled("secrets-") {
+		args.push("--talk-name=org.freedesktop.secrets".to_string());
+	}
+	if system_tray {
+		args.push("--talk-name=org.kde.StatusNotifierWatcher".to_string());
+	}
+	for arg in &config.finish_args {
+		if !args.contains(arg) {
+			args.push(arg.clone());
+		}
+	}
+	args
+}
+
 #[allow(unused_variables)]
 fn millennium_config_to_bundle_settings(
 	manifest: &Manifest,
 	features: &[String],
 	config: crate::helpers::config::BundleConfig,
 	system_tray_config: Option<crate::helpers::config::SystemTrayConfig>,
-	updater_config: crate::helpers::config::UpdaterConfig
+	updater_config: crate::helpers::config::UpdaterConfig,
+	allowlist: &AllowlistConfig
 ) -> crate::Result<BundleSettings> {
 	let enabled_features = manifest.all_enabled_features(features);
 
@@ -832,6 +895,10 @@ fn millennium_config_to_bundle_settings(
 	let mut resources = config.resources.unwrap_or_default();
 	#[allow(unused_mut)]
 	let mut depends = config.deb.depends.unwrap_or_default();
+	#[allow(unused_mut)]
+	let mut pacman_depends = config.pacman.depends.unwrap_or_default();
+	#[allow(unused_mut)]
+	let mut icon = config.icon;
 
 	#[cfg(target_os = "linux")]
 	{
@@ -839,14 +906,23 @@ fn millennium_config_to_bundle_settings(
 			let tray = std::env::var("MILLENNIUM_TRAY").unwrap_or_else(|_| "ayatana".to_string());
 			if tray == "ayatana" {
 				depends.push("libayatana-appindicator3-1".to_string());
+				pacman_depends.push("libayatana-appindicator".to_string());
 			} else if tray == "gtk" {
 				depends.push("libappindicator3-1".to_string());
+				pacman_depends.push("libappindicator-gtk3".to_string());
 			}
 		}
 
 		// provides `libwebkit2gtk-4.0.so.37` and all `4.0` versions have the -37 package name
 		depends.push("libwebkit2gtk-4.0-37".to_string());
 		depends.push("libgtk-3-0".to_string());
+		pacman_depends.push("webkit2gtk".to_string());
+		pacman_depends.push("gtk3".to_string());
+
+		// install the hicolor icon theme generated by `millennium icon`
+		if millennium_dir().join("icons/hicolor").is_dir() {
+			icon.push("icons/hicolor/*/apps/*".to_string());
+		}
 	}
 
 	#[cfg(windows)]
@@ -881,7 +957,7 @@ fn millennium_config_to_bundle_settings(
 	Ok(BundleSettings {
 		identifier: Some(config.identifier),
 		publisher: config.publisher,
-		icon: Some(config.icon),
+		icon: Some(icon),
 		resources: if resources.is_empty() { None } else { Some(resources) },
 		copyright: config.copyright,
 		category: match config.category {
@@ -898,6 +974,30 @@ fn millennium_config_to_bundle_settings(
 			depends: if depends.is_empty() { None } else { Some(depends) },
 			files: config.deb.files
 		},
+		appstream: AppStreamSettings {
+			summary: config.linux.appstream.summary,
+			description: config.linux.appstream.description,
+			screenshots: config.linux.appstream.screenshots,
+			content_rating: config.linux.appstream.content_rating,
+			project_license: config.linux.appstream.project_license,
+			changelog: config.linux.appstream.changelog
+		},
+		pacman: PacmanSettings {
+			depends: if pacman_depends.is_empty() { None } else { Some(pacman_depends) },
+			optional_depends: config.pacman.optional_depends
+		},
+		appimage: AppImageSettings {
+			bundle_media_framework: config.appimage.bundle_media_framework,
+			runtime: config.appimage.runtime,
+			libraries: config.appimage.libraries,
+			update_information: config.appimage.update_information
+		},
+		flatpak: FlatpakSettings {
+			finish_args: flatpak_finish_args(&config.flatpak, allowlist, system_tray_config.is_some(), updater_config.active),
+			runtime: config.flatpak.runtime,
+			runtime_version: config.flatpak.runtime_version,
+			sdk: config.flatpak.sdk
+		},
 		macos: MacOsSettings {
 			frameworks: config.macos.frameworks,
 			minimum_system_version: config.macos.minimum_system_version,
@@ -17,12 +17,14 @@
 pub use anyhow::Result;
 
 mod build;
+mod bundle;
 mod dev;
 mod helpers;
 mod icon;
 mod info;
 mod init;
 mod interface;
+mod manifest;
 mod plugin;
 mod signer;
 
@@ -77,10 +79,12 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
 	Build(build::Options),
+	Bundle(bundle::Options),
 	Dev(dev::Options),
 	Icon(icon::Options),
 	Info(info::Options),
 	Init(init::Options),
+	Manifest(manifest::Options),
 	Plugin(plugin::Cli),
 	Signer(signer::Cli)
 }
@@ -137,7 +141,7 @@ where
 		.format(|f, record| {
 			let mut is_command_output = false;
 			if let Some(action) = record.key_values().get("action".into()) {
-				let action = action.to_str().unwrap();
+				let action = action.to_borrowed_str().unwrap();
 				is_command_output = action == "Stdout" || action == "Stderr";
 				if !is_command_output {
 					let mut action_style = f.style();
@@ -169,10 +173,12 @@ where
 
 	match cli.command {
 		Commands::Build(options) => build::command(options)?,
+		Commands::Bundle(options) => bundle::command(options)?,
 		Commands::Dev(options) => dev::command(options)?,
 		Commands::Icon(options) => icon::command(options)?,
 		Commands::Info(options) => info::command(options)?,
 		Commands::Init(options) => init::command(options)?,
+		Commands::Manifest(options) => manifest::command(options)?,
 		Commands::Plugin(cli) => plugin::command(cli)?,
 		Commands::Signer(cli) => signer::command(cli)?
 	}
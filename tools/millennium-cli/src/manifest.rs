@@ -0,0 +1,356 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::{Path, PathBuf}
+};
+
+use anyhow::Context;
+use clap::{ArgAction, Parser};
+use log::info;
+use serde::{Deserialize, Serialize};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{helpers::config::get as get_config, Result};
+
+#[derive(Debug, Parser)]
+#[clap(about = "Generate the updater release manifest (`latest.json`) from updater archives")]
+pub struct Options {
+	/// Updater archives to add to the manifest. Their signature is read from the `.sig` file next to them
+	archives: Vec<PathBuf>,
+	/// Target triple the archives were built for, defaults to the host target
+	#[clap(short, long)]
+	target: Option<String>,
+	/// Version of the release, defaults to the version of the merged manifests or `Millennium.toml`
+	#[clap(long)]
+	release_version: Option<String>,
+	/// Download URL template. Can contain `{{version}}`, `{{file}}` (the archive file name), `{{target}}` and
+	/// `{{arch}}`
+	#[clap(short, long)]
+	url: Option<String>,
+	/// Path to a file containing the release notes
+	#[clap(short, long)]
+	notes: Option<PathBuf>,
+	/// TOML or JSON file with the `url` template, per-platform `urls` templates, and `notes` or `notesFile` of the
+	/// release
+	#[clap(short, long)]
+	release: Option<PathBuf>,
+	/// Manifests to merge into the output, e.g. the ones generated by other CI jobs
+	#[clap(short, long, action = ArgAction::Append)]
+	merge: Vec<PathBuf>,
+	/// Write the dynamic format, describing a single platform, instead of the static format with a `platforms` object
+	#[clap(long)]
+	dynamic: bool,
+	/// Where to write the manifest
+	#[clap(short, long, default_value = "latest.json")]
+	output: PathBuf
+}
+
+/// The release file given with `--release`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ReleaseFile {
+	/// Download URL template for all platforms.
+	url: Option<String>,
+	/// Download URL templates by platform, e.g. `linux-x64`.
+	#[serde(default)]
+	urls: HashMap<String, String>,
+	/// Release notes.
+	notes: Option<String>,
+	/// Path to the release notes, relative to the release file.
+	notes_file: Option<PathBuf>
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Platform {
+	url: String,
+	signature: String,
+	/// Other fields, e.g. `with_elevated_task`, which are kept when merging.
+	#[serde(flatten)]
+	extra: serde_json::Map<String, serde_json::Value>
+}
+
+/// The static format of the manifest, which the updater's `RemoteRelease` parses.
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+	#[serde(alias = "name")]
+	version: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	notes: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub_date: Option<String>,
+	platforms: BTreeMap<String, Platform>,
+	/// Other top-level fields, which are kept when merging.
+	#[serde(flatten)]
+	extra: serde_json::Map<String, serde_json::Value>
+}
+
+impl Manifest {
+	/// Merges a manifest generated for other platforms of the same release.
+	fn merge(&mut self, other: Manifest) -> Result<()> {
+		if other.version.trim_start_matches('v') != self.version.trim_start_matches('v') {
+			anyhow::bail!("cannot merge the manifest of version {} into version {}", other.version, self.version);
+		}
+		self.notes = self.notes.take().or(other.notes);
+		for (key, value) in other.extra {
+			match self.extra.get(&key) {
+				Some(existing) if *existing != value => anyhow::bail!("the merged manifests have different values for `{key}`"),
+				Some(_) => {}
+				None => {
+					self.extra.insert(key, value);
+				}
+			}
+		}
+		for (platform, value) in other.platforms {
+			if self.platforms.insert(platform.clone(), value).is_some() {
+				anyhow::bail!("the `{platform}` platform is in several merged manifests");
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The dynamic format of the manifest, for a single platform.
+#[derive(Debug, Serialize)]
+struct DynamicManifest {
+	version: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	notes: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub_date: Option<String>,
+	#[serde(flatten)]
+	platform: Platform,
+	#[serde(flatten)]
+	extra: serde_json::Map<String, serde_json::Value>
+}
+
+/// Returns the `{{target}}` and `{{arch}}` the updater of an app built for
+/// `triple` looks up, e.g. `linux` and `x64`.
+fn updater_platform(triple: &str) -> Result<(&'static str, &'static str)> {
+	let target = if triple.contains("linux") {
+		"linux"
+	} else if triple.contains("apple-darwin") {
+		"darwin"
+	} else if triple.contains("windows") {
+		"windows"
+	} else {
+		anyhow::bail!("the updater does not support the target `{triple}`");
+	};
+	let arch = match triple.split('-').next().unwrap_or_default() {
+		"x86_64" => "x64",
+		"i686" | "i586" => "x86",
+		"aarch64" => "aarch64",
+		arch if arch.starts_with("arm") => "armv7",
+		arch => anyhow::bail!("the updater does not support the architecture `{arch}`")
+	};
+	Ok((target, arch))
+}
+
+fn render_url(template: &str, version: &str, file: &str, target: &str, arch: &str) -> String {
+	template
+		.replace("{{version}}", version)
+		.replace("{{file}}", file)
+		.replace("{{target}}", target)
+		.replace("{{arch}}", arch)
+}
+
+fn read_release_file(path: &Path) -> Result<ReleaseFile> {
+	let contents = fs::read_to_string(path).with_context(|| format!("failed to read release file `{}`", path.display()))?;
+	let mut release: ReleaseFile = if path.extension().map_or(false, |ext| ext == "json") {
+		serde_json::from_str(&contents)?
+	} else {
+		toml::from_str(&contents)?
+	};
+	if let Some(notes_file) = release.notes_file.take() {
+		let notes_file = path.parent().unwrap_or_else(|| Path::new(".")).join(notes_file);
+		release.notes = Some(fs::read_to_string(&notes_file).with_context(|| format!("failed to read release notes `{}`", notes_file.display()))?);
+	}
+	Ok(release)
+}
+
+pub fn command(options: Options) -> Result<()> {
+	let mut release = match &options.release {
+		Some(path) => read_release_file(path)?,
+		None => ReleaseFile::default()
+	};
+	if let Some(url) = options.url {
+		release.url = Some(url);
+	}
+	if let Some(notes) = &options.notes {
+		release.notes = Some(fs::read_to_string(notes).with_context(|| format!("failed to read release notes `{}`", notes.display()))?);
+	}
+
+	let mut merged = Vec::new();
+	for path in &options.merge {
+		let manifest: Manifest = serde_json::from_str(&fs::read_to_string(path).with_context(|| format!("failed to read manifest `{}`", path.display()))?)
+			.with_context(|| format!("`{}` is not a static updater manifest", path.display()))?;
+		merged.push(manifest);
+	}
+
+	let version = match options
+		.release_version
+		.or_else(|| merged.first().map(|manifest| manifest.version.clone()))
+	{
+		Some(version) => version,
+		None => {
+			let config = get_config(None)?;
+			let config_guard = config.lock().unwrap();
+			config_guard
+				.as_ref()
+				.unwrap()
+				.package
+				.version
+				.clone()
+				.with_context(|| "no version found in `Millennium.toml`, pass it with `--release-version`")?
+		}
+	};
+
+	let mut manifest = Manifest {
+		version: version.clone(),
+		notes: release.notes.map(|notes| notes.trim().to_string()),
+		pub_date: Some(OffsetDateTime::now_utc().format(&Rfc3339)?),
+		platforms: BTreeMap::new(),
+		extra: Default::default()
+	};
+	for other in merged {
+		manifest.merge(other)?;
+	}
+
+	if !options.archives.is_empty() {
+		let triple = match options.target {
+			Some(target) => target,
+			None => millennium_utils::platform::target_triple()?
+		};
+		let (target, arch) = updater_platform(&triple)?;
+		let platform = format!("{target}-{arch}");
+		if options.archives.len() > 1 {
+			anyhow::bail!("a `{platform}` release has a single updater archive, but {} were given", options.archives.len());
+		}
+
+		let archive = &options.archives[0];
+		let mut signature_path = archive.clone().into_os_string();
+		signature_path.push(".sig");
+		let signature =
+			fs::read_to_string(&signature_path).with_context(|| format!("failed to read the signature of `{}`, was the archive signed?", archive.display()))?;
+		let template = release
+			.urls
+			.get(&platform)
+			.or(release.url.as_ref())
+			.with_context(|| format!("no download URL for `{platform}`, pass a template with `--url`"))?;
+		let file = archive.file_name().unwrap().to_string_lossy();
+		let value = Platform {
+			url: render_url(template, version.trim_start_matches('v'), &file, target, arch),
+			signature: signature.trim().to_string(),
+			extra: Default::default()
+		};
+		if manifest.platforms.insert(platform.clone(), value).is_some() {
+			anyhow::bail!("the `{platform}` platform is already in a merged manifest");
+		}
+	}
+
+	let json = if options.dynamic {
+		if manifest.platforms.len() != 1 {
+			anyhow::bail!("the dynamic format describes a single platform, but the manifest has {}", manifest.platforms.len());
+		}
+		serde_json::to_string_pretty(&DynamicManifest {
+			version: manifest.version,
+			notes: manifest.notes,
+			pub_date: manifest.pub_date,
+			platform: manifest.platforms.into_values().next().unwrap(),
+			extra: manifest.extra
+		})?
+	} else {
+		serde_json::to_string_pretty(&manifest)?
+	};
+
+	if let Some(parent) = options.output.parent() {
+		fs::create_dir_all(parent)?;
+	}
+	fs::write(&options.output, json)?;
+	info!(action = "Finished"; "updater manifest at {}", options.output.display());
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{render_url, updater_platform, Manifest};
+
+	fn manifest(json: serde_json::Value) -> Manifest {
+		serde_json::from_value(json).unwrap()
+	}
+
+	#[test]
+	fn maps_targets_to_updater_platforms() {
+		assert_eq!(updater_platform("x86_64-unknown-linux-gnu").unwrap(), ("linux", "x64"));
+		assert_eq!(updater_platform("aarch64-apple-darwin").unwrap(), ("darwin", "aarch64"));
+		assert_eq!(updater_platform("i686-pc-windows-msvc").unwrap(), ("windows", "x86"));
+		assert_eq!(updater_platform("armv7-unknown-linux-gnueabihf").unwrap(), ("linux", "armv7"));
+		assert!(updater_platform("wasm32-unknown-unknown").is_err());
+		assert!(updater_platform("riscv64gc-unknown-linux-gnu").is_err());
+	}
+
+	#[test]
+	fn renders_url_templates() {
+		assert_eq!(
+			render_url("https://example.com/{{version}}/{{target}}-{{arch}}/{{file}}", "1.2.0", "app.AppImage.tar.gz", "linux", "x64"),
+			"https://example.com/1.2.0/linux-x64/app.AppImage.tar.gz"
+		);
+		assert_eq!(render_url("https://example.com/latest", "1.2.0", "app.zip", "windows", "x64"), "https://example.com/latest");
+	}
+
+	#[test]
+	fn merges_manifests() {
+		let mut merged = manifest(serde_json::json!({
+			"version": "1.2.0",
+			"platforms": { "linux-x64": { "url": "https://example.com/linux", "signature": "a" } }
+		}));
+		merged
+			.merge(manifest(serde_json::json!({
+				"name": "v1.2.0",
+				"notes": "Fixes",
+				"channel": "beta",
+				"platforms": { "windows-x64": { "url": "https://example.com/windows", "signature": "b", "with_elevated_task": true } }
+			})))
+			.unwrap();
+
+		let json = serde_json::to_value(&merged).unwrap();
+		assert_eq!(json["notes"], "Fixes");
+		assert_eq!(json["channel"], "beta");
+		assert_eq!(json["platforms"]["linux-x64"]["signature"], "a");
+		assert_eq!(json["platforms"]["windows-x64"]["with_elevated_task"], true);
+	}
+
+	#[test]
+	fn rejects_conflicting_merges() {
+		let base = || {
+			manifest(serde_json::json!({
+				"version": "1.2.0",
+				"channel": "beta",
+				"platforms": { "linux-x64": { "url": "https://example.com/linux", "signature": "a" } }
+			}))
+		};
+		let other_version = manifest(serde_json::json!({ "version": "1.3.0", "platforms": {} }));
+		let same_platform = manifest(serde_json::json!({
+			"version": "1.2.0",
+			"platforms": { "linux-x64": { "url": "https://example.com/linux", "signature": "b" } }
+		}));
+		let other_channel = manifest(serde_json::json!({ "version": "1.2.0", "channel": "stable", "platforms": {} }));
+
+		assert!(base().merge(other_version).unwrap_err().to_string().contains("version"));
+		assert!(base().merge(same_platform).unwrap_err().to_string().contains("linux-x64"));
+		assert!(base().merge(other_channel).unwrap_err().to_string().contains("channel"));
+	}
+}
@@ -19,6 +19,7 @@ use clap::{Parser, Subcommand};
 use crate::Result;
 
 mod generate;
+mod serve;
 mod sign;
 
 #[derive(Parser)]
@@ -37,13 +38,15 @@ pub struct Cli {
 #[derive(Subcommand)]
 enum Commands {
 	Sign(sign::Options),
-	Generate(generate::Options)
+	Generate(generate::Options),
+	Serve(serve::Options)
 }
 
 pub fn command(cli: Cli) -> Result<()> {
 	match cli.command {
 		Commands::Sign(options) => sign::command(options)?,
-		Commands::Generate(options) => generate::command(options)?
+		Commands::Generate(options) => generate::command(options)?,
+		Commands::Serve(options) => serve::command(options)?
 	}
 	Ok(())
 }
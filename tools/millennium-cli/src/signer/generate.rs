@@ -57,7 +57,7 @@ pub fn command(options: Options) -> Result<()> {
 	}
 
 	println!(
-		"\nEnvironment variabled used to sign:\n`MILLENNIUM_PRIVATE_KEY`  Path or String of your private key\n`MILLENNIUM_KEY_PASSWORD`  Your private key password (optional)\n\nATTENTION: If you lose your private key OR password, you'll not be able to sign your update package and updates will not work.\n---------------------------\n"
+		"\nEnvironment variabled used to sign:\n`MILLENNIUM_PRIVATE_KEY`  Path or String of your private key\n`MILLENNIUM_KEY_PASSWORD`  Your private key password (optional)\n`MILLENNIUM_SIGNER`  `command:<command>` or a signing service URL to sign with instead of a private key (optional)\n`MILLENNIUM_SIGNER_TOKEN`  The bearer token of the `MILLENNIUM_SIGNER` signing service (optional)\n`MILLENNIUM_SIGNER_PUBLIC_KEY`  The public key of the `MILLENNIUM_SIGNER` key, when it isn't the configured `pubkey` (optional)\n\nATTENTION: If you lose your private key OR password, you'll not be able to sign your update package and updates will not work.\n---------------------------\n"
 	);
 
 	Ok(())
@@ -0,0 +1,52 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::net::TcpListener;
+
+use anyhow::Context;
+use clap::Parser;
+use log::info;
+
+use crate::{
+	helpers::updater_signature::{serve_signer, ExternalSigner, SIGNER_TOKEN_ENV},
+	Result
+};
+
+#[derive(Debug, Parser)]
+#[clap(about = "Serve a signer over HTTP, for use as the `--signer` or `MILLENNIUM_SIGNER` of build machines")]
+pub struct Options {
+	/// The signer holding the key: `command:<command>`. Also read from the `MILLENNIUM_SIGNER`
+	/// environment variable
+	#[clap(short, long)]
+	signer: Option<String>,
+	/// The address to listen on. Addresses other than loopback ones require a bearer token in the
+	/// `MILLENNIUM_SIGNER_TOKEN` environment variable, which build machines then also need to set
+	#[clap(short, long, default_value = "127.0.0.1:8111")]
+	address: String
+}
+
+pub fn command(options: Options) -> Result<()> {
+	let signer = options
+		.signer
+		.or_else(|| std::env::var("MILLENNIUM_SIGNER").ok())
+		.with_context(|| "`--signer` is required")?
+		.parse::<ExternalSigner>()?;
+	let listener = TcpListener::bind(&options.address).with_context(|| format!("failed to listen on {}", options.address))?;
+
+	let token = std::env::var(SIGNER_TOKEN_ENV).ok();
+
+	info!(action = "Serving"; "signatures on http://{}", listener.local_addr()?);
+
+	serve_signer(signer, listener, token)
+}
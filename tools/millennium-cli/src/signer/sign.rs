@@ -21,7 +21,7 @@ use base64::{engine::general_purpose::STANDARD as BASE64_STANDARD, Engine};
 use clap::Parser;
 
 use crate::{
-	helpers::updater_signature::{read_key_from_file, secret_key, sign_file},
+	helpers::updater_signature::{public_key, read_key_from_file, secret_key, sign_file, ExternalSigner, SigningBackend},
 	Result
 };
 
@@ -29,35 +29,59 @@ use crate::{
 #[clap(about = "Sign a file")]
 pub struct Options {
 	/// Load the private key from a file
-	#[clap(short = 'k', long, conflicts_with("private-key-path"))]
+	#[clap(short = 'k', long, conflicts_with_all(["private-key-path", "signer"]))]
 	private_key: Option<String>,
 	/// Load the private key from a string
-	#[clap(short = 'f', long, conflicts_with("private-key"))]
+	#[clap(short = 'f', long, conflicts_with_all(["private-key", "signer"]))]
 	private_key_path: Option<PathBuf>,
 	/// Set private key password when signing
 	#[clap(short, long)]
 	password: Option<String>,
+	/// Sign with a key that never leaves its signer: `command:<command>` or the URL of an HTTP(S)
+	/// signing service, such as `millennium signer serve`. Also read from the `MILLENNIUM_SIGNER` environment variable.
+	/// The service's bearer token is read from the `MILLENNIUM_SIGNER_TOKEN` environment variable
+	#[clap(short, long)]
+	signer: Option<String>,
+	/// The public key, or the path to it, matching the key of `--signer`
+	#[clap(long, requires("signer"))]
+	public_key: Option<String>,
 	/// Sign the specified file
 	file: PathBuf
 }
 
 pub fn command(mut options: Options) -> Result<()> {
-	options.private_key = if let Some(private_key) = options.private_key_path {
-		Some(read_key_from_file(Path::new(&private_key)).expect("Unable to extract private key"))
-	} else {
-		options.private_key
-	};
-	let private_key = if let Some(pk) = options.private_key {
-		pk
+	if options.private_key.is_none() && options.private_key_path.is_none() {
+		options.signer = options.signer.or_else(|| std::env::var("MILLENNIUM_SIGNER").ok());
+	}
+
+	let backend = if let Some(signer) = options.signer {
+		let key = options
+			.public_key
+			.with_context(|| "`--public-key` is required when signing with `--signer`")?;
+		let key = if Path::new(&key).exists() { read_key_from_file(&key)? } else { key };
+		SigningBackend::External {
+			signer: signer.parse::<ExternalSigner>()?,
+			public_key: public_key(key.trim())?
+		}
 	} else {
-		return Err(anyhow::anyhow!("Key generation aborted: Unable to find the private key".to_string(),));
-	};
+		options.private_key = if let Some(private_key) = options.private_key_path {
+			Some(read_key_from_file(&private_key).with_context(|| format!("failed to read the private key from {}", private_key.display()))?)
+		} else {
+			options.private_key
+		};
+		let private_key = if let Some(pk) = options.private_key {
+			pk
+		} else {
+			return Err(anyhow::anyhow!("Key generation aborted: Unable to find the private key".to_string(),));
+		};
 
-	if options.password.is_none() {
-		println!("Signing without password.");
-	}
+		if options.password.is_none() {
+			println!("Signing without password.");
+		}
+		SigningBackend::SecretKey(secret_key(private_key, options.password)?)
+	};
 
-	let (manifest_dir, signature) = sign_file(&secret_key(private_key, options.password)?, options.file).with_context(|| "failed to sign file")?;
+	let (manifest_dir, signature) = sign_file(&backend, options.file).with_context(|| "failed to sign file")?;
 
 	println!(
 		"\nYour file was signed successfully, You can find the signature here:\n{}\n\nPublic signature:\n{}\n\nMake sure to include this into the signature field of your update server.",
@@ -0,0 +1,34 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Debug, Clone, Parser)]
+#[clap(version, about = "WebDriver server to run end-to-end tests against Millennium applications")]
+pub struct Args {
+	/// Port the WebDriver server listens on
+	#[clap(short, long, default_value_t = 4444)]
+	pub port: u16,
+	/// Host the WebDriver server listens on
+	#[clap(long, default_value = "127.0.0.1")]
+	pub host: String,
+	/// Port the native WebDriver server listens on
+	#[clap(long, default_value_t = 4445)]
+	pub native_port: u16,
+	/// Path to the native WebDriver server, defaults to `WebKitWebDriver` on Linux and `msedgedriver` on Windows
+	#[clap(long)]
+	pub native_driver: Option<PathBuf>
+}
@@ -0,0 +1,42 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A WebDriver server for Millennium applications.
+//!
+//! `millennium-driver` sits between WebDriver clients and the native WebDriver server of the platform
+//! (`WebKitWebDriver` on Linux, `msedgedriver` on Windows). It launches the application described by the
+//! `millennium:options` capability with automation enabled.
+
+mod cli;
+mod server;
+mod webdriver;
+
+use clap::Parser;
+use hyper::Client;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+	let args = cli::Args::parse();
+	let client = Client::new();
+	let mut native_driver = webdriver::spawn_native_driver(&args, &client).await?;
+
+	let result = tokio::select! {
+		result = server::run(&args, client) => result,
+		status = native_driver.wait() => Err(anyhow::anyhow!("the native WebDriver server exited with {}", status?)),
+		_ = tokio::signal::ctrl_c() => Ok(())
+	};
+	let _ = native_driver.kill().await;
+	result
+}
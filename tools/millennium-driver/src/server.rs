@@ -0,0 +1,91 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use anyhow::Context;
+use hyper::{
+	client::HttpConnector,
+	header::{CONTENT_LENGTH, CONTENT_TYPE, HOST},
+	service::{make_service_fn, service_fn},
+	Body, Client, Method, Request, Response, Server, StatusCode
+};
+use serde_json::{json, Value};
+
+use crate::{cli::Args, webdriver::map_capabilities};
+
+/// Builds a WebDriver error response.
+fn error_response(status: StatusCode, error: &str, message: String) -> Response<Body> {
+	let body = json!({ "value": { "error": error, "message": message, "stacktrace": "" } });
+	Response::builder()
+		.status(status)
+		.header(CONTENT_TYPE, "application/json; charset=utf-8")
+		.body(Body::from(body.to_string()))
+		.unwrap()
+}
+
+/// Forwards a request to the native WebDriver server, rewriting the
+/// capabilities of new sessions.
+async fn proxy(client: Client<HttpConnector>, native_port: u16, mut req: Request<Body>) -> Response<Body> {
+	if req.method() == Method::POST && req.uri().path().trim_end_matches('/') == "/session" {
+		let body = match hyper::body::to_bytes(req.body_mut()).await {
+			Ok(body) => body,
+			Err(e) => return error_response(StatusCode::BAD_REQUEST, "invalid argument", e.to_string())
+		};
+		let mut json: Value = match serde_json::from_slice(&body) {
+			Ok(json) => json,
+			Err(e) => return error_response(StatusCode::BAD_REQUEST, "invalid argument", e.to_string())
+		};
+		if let Err(e) = map_capabilities(&mut json) {
+			return error_response(StatusCode::BAD_REQUEST, "invalid argument", format!("{e:#}"));
+		}
+		let body = json.to_string();
+		req.headers_mut().insert(CONTENT_LENGTH, body.len().into());
+		*req.body_mut() = Body::from(body);
+	}
+
+	let path_and_query = req.uri().path_and_query().map_or("/", |p| p.as_str());
+	*req.uri_mut() = match format!("http://127.0.0.1:{native_port}{path_and_query}").parse() {
+		Ok(uri) => uri,
+		Err(e) => return error_response(StatusCode::BAD_REQUEST, "invalid argument", e.to_string())
+	};
+	req.headers_mut().remove(HOST);
+
+	client
+		.request(req)
+		.await
+		.unwrap_or_else(|e| error_response(StatusCode::BAD_GATEWAY, "unknown error", format!("the native WebDriver server is unreachable: {e}")))
+}
+
+/// Serves the WebDriver protocol, proxying to the native WebDriver server.
+pub async fn run(args: &Args, client: Client<HttpConnector>) -> anyhow::Result<()> {
+	let address: SocketAddr = format!("{}:{}", args.host, args.port)
+		.parse()
+		.with_context(|| format!("invalid address {}:{}", args.host, args.port))?;
+	let native_port = args.native_port;
+	let service = make_service_fn(move |_| {
+		let client = client.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |req| {
+				let client = client.clone();
+				async move { Ok::<_, Infallible>(proxy(client, native_port, req).await) }
+			}))
+		}
+	});
+
+	let server = Server::try_bind(&address).with_context(|| format!("failed to listen on {address}"))?;
+	log::info!("listening on http://{address}");
+	server.serve(service).await?;
+	Ok(())
+}
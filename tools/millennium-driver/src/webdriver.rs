@@ -0,0 +1,169 @@
+// Copyright 2022 pyke.io
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+	path::PathBuf,
+	process::Stdio,
+	time::{Duration, Instant}
+};
+
+use anyhow::{bail, Context};
+use hyper::{client::HttpConnector, Client, Uri};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::{process::Child, time::sleep};
+
+use crate::cli::Args;
+
+/// The capability describing the application to launch.
+const MILLENNIUM_OPTIONS: &str = "millennium:options";
+
+/// How long the native WebDriver server has to start.
+const NATIVE_DRIVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct MillenniumOptions {
+	/// Path to the application binary.
+	application: PathBuf,
+	/// Arguments the application is launched with.
+	#[serde(default)]
+	args: Vec<String>
+}
+
+impl MillenniumOptions {
+	/// The capability of the native WebDriver server launching the application.
+	#[cfg(not(windows))]
+	fn native_capability(self) -> (&'static str, Value) {
+		("webkitgtk:browserOptions", json!({ "binary": self.application, "args": self.args }))
+	}
+
+	/// The capability of the native WebDriver server launching the application.
+	#[cfg(windows)]
+	fn native_capability(self) -> (&'static str, Value) {
+		("ms:edgeOptions", json!({ "binary": self.application, "args": self.args, "webviewOptions": {} }))
+	}
+}
+
+fn map_capability_set(capabilities: &mut Map<String, Value>) -> anyhow::Result<()> {
+	if let Some(options) = capabilities.remove(MILLENNIUM_OPTIONS) {
+		let mut options: MillenniumOptions = serde_json::from_value(options).with_context(|| format!("invalid `{MILLENNIUM_OPTIONS}` capability"))?;
+		// the native driver launches the application from its own working directory
+		if let Ok(application) = options.application.canonicalize() {
+			options.application = application;
+		}
+		let (name, capability) = options.native_capability();
+		capabilities.insert(name.into(), capability);
+		#[cfg(windows)]
+		capabilities.insert("browserName".into(), "webview2".into());
+	}
+	Ok(())
+}
+
+/// Replaces the `millennium:options` capability of a new session request with
+/// the capability the native WebDriver server launches the application with.
+pub fn map_capabilities(body: &mut Value) -> anyhow::Result<()> {
+	if let Some(capabilities) = body.get_mut("capabilities") {
+		if let Some(always_match) = capabilities.get_mut("alwaysMatch").and_then(Value::as_object_mut) {
+			map_capability_set(always_match)?;
+		}
+		if let Some(first_match) = capabilities.get_mut("firstMatch").and_then(Value::as_array_mut) {
+			for capabilities in first_match.iter_mut().filter_map(Value::as_object_mut) {
+				map_capability_set(capabilities)?;
+			}
+		}
+	}
+	// legacy JSON wire protocol clients
+	if let Some(capabilities) = body.get_mut("desiredCapabilities").and_then(Value::as_object_mut) {
+		map_capability_set(capabilities)?;
+	}
+	Ok(())
+}
+
+fn native_driver_path(args: &Args) -> anyhow::Result<PathBuf> {
+	if let Some(path) = &args.native_driver {
+		return Ok(path.clone());
+	}
+	if cfg!(target_os = "linux") {
+		Ok("WebKitWebDriver".into())
+	} else if cfg!(windows) {
+		Ok("msedgedriver.exe".into())
+	} else {
+		bail!("there is no native WebDriver server for this platform")
+	}
+}
+
+/// Starts the native WebDriver server and waits for it to accept sessions.
+///
+/// Applications launched by the server have automation enabled.
+pub async fn spawn_native_driver(args: &Args, client: &Client<HttpConnector>) -> anyhow::Result<Child> {
+	let path = native_driver_path(args)?;
+	let mut child = tokio::process::Command::new(&path)
+		.arg(format!("--port={}", args.native_port))
+		.env("MILLENNIUM_AUTOMATION", "true")
+		.stdin(Stdio::null())
+		.kill_on_drop(true)
+		.spawn()
+		.with_context(|| format!("failed to start the native WebDriver server `{}`", path.display()))?;
+
+	let status: Uri = format!("http://127.0.0.1:{}/status", args.native_port).parse()?;
+	let started = Instant::now();
+	loop {
+		if let Some(status) = child.try_wait()? {
+			bail!("the native WebDriver server `{}` exited with {status}", path.display());
+		}
+		if client.get(status.clone()).await.map_or(false, |response| response.status().is_success()) {
+			return Ok(child);
+		}
+		if started.elapsed() > NATIVE_DRIVER_TIMEOUT {
+			bail!("the native WebDriver server `{}` did not start in {} seconds", path.display(), NATIVE_DRIVER_TIMEOUT.as_secs());
+		}
+		sleep(Duration::from_millis(100)).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::map_capabilities;
+
+	#[test]
+	fn maps_millennium_options() {
+		let mut body = json!({
+			"capabilities": {
+				"alwaysMatch": {
+					"millennium:options": { "application": "/opt/app/app", "args": ["--e2e"] }
+				},
+				"firstMatch": [{}, { "millennium:options": { "application": "/opt/other/other" } }]
+			}
+		});
+		map_capabilities(&mut body).unwrap();
+
+		let always_match = &body["capabilities"]["alwaysMatch"];
+		assert!(always_match.get("millennium:options").is_none());
+		#[cfg(not(windows))]
+		{
+			assert_eq!(always_match["webkitgtk:browserOptions"], json!({ "binary": "/opt/app/app", "args": ["--e2e"] }));
+			assert_eq!(body["capabilities"]["firstMatch"][1]["webkitgtk:browserOptions"], json!({ "binary": "/opt/other/other", "args": [] }));
+		}
+		assert_eq!(body["capabilities"]["firstMatch"][0], json!({}));
+	}
+
+	#[test]
+	fn rejects_invalid_options() {
+		let mut body = json!({ "capabilities": { "alwaysMatch": { "millennium:options": { "args": [] } } } });
+		assert!(map_capabilities(&mut body).is_err());
+	}
+}